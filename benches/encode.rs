@@ -0,0 +1,92 @@
+//! Throughput of the whole-dict and whole-corpus passes: the full code of every node, and the
+//! encoding of a chat-log-like corpus whose short pieces recur all the time.
+//!
+//! Runs on a generated dict, and on a full schema when `SMART_DICT_BENCH_DIR` points at one like
+//! for the `lookup` bench.
+
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, Criterion};
+use smart_dict::corpus::{self, Alphabet};
+use smart_dict::schema::Schema;
+use smart_dict::trie::Trie;
+
+struct Lcg(u64);
+
+impl Lcg {
+  fn below(&mut self, n: usize) -> usize {
+    self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    (self.0 >> 33) as usize % n
+  }
+}
+
+/// 150k words of 1 to 3 of the first 6k CJK characters, on codes of 1 to 6 of the keys a–z.
+fn generated_trie() -> Box<Trie> {
+  let mut rng = Lcg(0x2545_f491);
+  let mut trie = Box::new(Trie::new());
+  for _ in 0..150_000 {
+    let code: String = (0..1 + rng.below(6)).map(|_| (b'a' + rng.below(26) as u8) as char).collect();
+    let word: String = (0..1 + rng.below(3)).map(|_| char::from_u32(0x4E00 + rng.below(6000) as u32).unwrap()).collect();
+    trie.insert(code, word);
+  }
+  trie
+}
+
+fn schema_trie() -> Option<Box<Trie>> {
+  let dir = std::env::var_os("SMART_DICT_BENCH_DIR")?;
+  let name = std::env::var("SMART_DICT_BENCH_SCHEMA").unwrap_or_else(|_| "xkjd6".to_string());
+  Some(Schema::new(dir, &name).load_trie().expect("can't load the schema"))
+}
+
+/// 20k lines of chat: a few pieces out of a small set of phrases, most of them among the first ones.
+fn chat_log(trie: &Trie) -> Vec<String> {
+  let mut words: Vec<_> = trie.entries().map(|entry| entry.word).collect();
+  words.sort();
+  words.dedup();
+  let mut rng = Lcg(0x9e37_79b9);
+  let phrases: Vec<String> = (0..2000)
+    .map(|_| (0..1 + rng.below(4)).map(|_| words[rng.below(words.len())].as_str()).collect())
+    .collect();
+  (0..20_000)
+    .map(|_| {
+      let pieces: Vec<_> = (0..1 + rng.below(3))
+        .map(|_| {
+          let common = 1 + rng.below(phrases.len());
+          &phrases[rng.below(common)][..]
+        })
+        .collect();
+      pieces.join("，")
+    })
+    .collect()
+}
+
+fn encode(c: &mut Criterion) {
+  let tries = [Some(("generated", generated_trie())), schema_trie().map(|trie| ("schema", trie))];
+  for (name, trie) in tries.into_iter().flatten() {
+    let mut group = c.benchmark_group(name);
+    group.sample_size(10);
+    group.bench_function("full codes by bubbling", |b| b.iter(|| {
+      trie.nodes().map(|node| black_box(node.full_code()).len()).sum::<usize>()
+    }));
+    group.bench_function("full codes by walking", |b| b.iter(|| {
+      trie.walk().map(|(_, code)| black_box(code).len()).sum::<usize>()
+    }));
+
+    let rev_dict = trie.rev_dict();
+    let alphabet = Alphabet::new(&rev_dict);
+    let lines = chat_log(&trie);
+    group.bench_function("corpus segmenting every piece", |b| b.iter(|| {
+      lines.iter()
+        .flat_map(|line| alphabet.pieces(line))
+        .filter_map(|piece| rev_dict.segment(black_box(piece)).ok())
+        .map(|segments| segments.len())
+        .sum::<usize>()
+    }));
+    group.bench_function("corpus with the analyzer's cache", |b| b.iter(|| {
+      corpus::analyze(&rev_dict, black_box(&lines)).commits
+    }));
+    group.finish();
+  }
+}
+
+criterion_group!(benches, encode);
+criterion_main!(benches);
@@ -0,0 +1,62 @@
+//! Latency of looking up the candidates of typed keys, what the IME does on every key.
+//!
+//! Runs on a generated trie whose nodes have around a hundred children, and on a full schema
+//! when `SMART_DICT_BENCH_DIR` points at one, named by `SMART_DICT_BENCH_SCHEMA` (xkjd6 by
+//! default):
+//!
+//! ```text
+//! SMART_DICT_BENCH_DIR=~/AppData/Roaming/Rime cargo bench --bench lookup
+//! ```
+
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, Criterion};
+use smart_dict::schema::Schema;
+use smart_dict::trie::{Completion, Trie};
+
+/// Codes of up to three keys out of the printable ASCII characters, one word each.
+fn wide_trie() -> (Box<Trie>, Vec<String>) {
+  let keys: Vec<_> = ('!'..='~').collect();
+  let mut trie = Box::new(Trie::new());
+  let mut codes = vec![];
+  let mut seed = 0x2545_f491_u64;
+  for &first in &keys {
+    for &second in &keys {
+      for _ in 0..4 {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let third = keys[(seed >> 33) as usize % keys.len()];
+        let code = format!("{first}{second}{third}");
+        trie.insert_weighted(code.clone(), code.to_uppercase(), seed >> 48);
+        codes.push(code);
+      }
+    }
+  }
+  (trie, codes)
+}
+
+fn schema_trie() -> Option<(Box<Trie>, Vec<String>)> {
+  let dir = std::env::var_os("SMART_DICT_BENCH_DIR")?;
+  let name = std::env::var("SMART_DICT_BENCH_SCHEMA").unwrap_or_else(|_| "xkjd6".to_string());
+  let trie = Schema::new(dir, &name).load_trie().expect("can't load the schema");
+  let codes = trie.walk().filter(|(node, _)| !node.words().is_empty()).map(|(_, code)| code).step_by(7).collect();
+  Some((trie, codes))
+}
+
+fn lookup(c: &mut Criterion) {
+  let tries = [Some(("wide", wide_trie())), schema_trie().map(|trie| ("schema", trie))];
+  for (name, (trie, codes)) in tries.into_iter().flatten() {
+    let mut group = c.benchmark_group(name);
+    group.bench_function("node", |b| b.iter(|| {
+      codes.iter().filter(|code| trie.node(black_box(code)).is_some()).count()
+    }));
+    group.bench_function("first page", |b| b.iter(|| {
+      codes.iter()
+        .filter_map(|code| trie.node_through(black_box(code)))
+        .map(|node| node.candidates(Completion::Off).take(5).count())
+        .sum::<usize>()
+    }));
+    group.finish();
+  }
+}
+
+criterion_group!(benches, lookup);
+criterion_main!(benches);
@@ -0,0 +1,23 @@
+//! Entry lines of community tables: parsed with the separator detected from them, and written
+//! back with tabs, a line must read as the same entry.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use smart_dict::dict::{self, LineParser};
+
+fuzz_target!(|text: &str| {
+  let mut parser = LineParser::new();
+  for line in text.lines() {
+    let Some((entry, weight)) = parser.parse_weighted(line) else { continue };
+    let separator = parser.separator();
+    let tabbed = separator.to_tabs(line);
+    separator.restyle(&tabbed);
+    // cells with separators or comments of their own can't be written back as they are
+    if [&entry.word, &entry.code].iter().any(|cell| cell.contains(['\t', '#']) || cell.trim() != cell.as_str() || cell.is_empty()) {
+      continue;
+    }
+    let line = dict::format_line(&entry, Some(weight), None);
+    assert_eq!(Some((entry, weight)), dict::parse_weighted_line(&line));
+  }
+});
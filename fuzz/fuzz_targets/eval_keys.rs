@@ -0,0 +1,26 @@
+//! Keys typed into eval: whatever they are, committing, composing and drawing the candidates
+//! after each of them doesn't panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use smart_dict::preview;
+use smart_dict::trie::{EvalOptions, Trie};
+
+fuzz_target!(|data: (u8, &str)| {
+  let (flags, keys) = data;
+  let mut trie = Trie::new();
+  for (code, word) in [("w", "我"), ("wo", "窝"), ("wi", "我们"), ("n", "你"), ("nh", "你好"), ("xyz", "寻"), (";", "；")] {
+    trie.insert(code.to_string(), word.to_string());
+  }
+  let options = EvalOptions {
+    completion: (flags & 1 == 1).into(),
+    echo: flags & 2 == 2,
+    backspace: (flags & 4 == 4).then_some('<'),
+    escape: (flags & 8 == 8).then_some('\u{1b}'),
+    ..Default::default()
+  };
+  trie.eval_with(keys, &options);
+  trie.compose(keys, &options);
+  preview::frames(&trie, keys, &options, 5);
+});
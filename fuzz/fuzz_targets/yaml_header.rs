@@ -0,0 +1,17 @@
+//! Headers of dict files: splitting one off loses nothing, and reading its YAML, like the tables
+//! it imports, doesn't panic.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use smart_dict::dict::{self, Header, Versioning};
+use smart_dict::yaml;
+
+fuzz_target!(|text: &str| {
+  let (mut header, body) = Header::parse(text);
+  assert_eq!(text, format!("{header}{body}"));
+  dict::import_tables(&header.value());
+  dict::import_tables(&yaml::parse(text));
+  header.bump_version(Versioning::Date, "2024.01.02");
+  header.log("fuzz");
+});
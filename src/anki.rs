@@ -0,0 +1,64 @@
+use std::io::{self, Write};
+use crate::rev_dict::RevDict;
+
+/// A note for Anki: the word on the front, its code and hints on the back.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Card {
+  pub front: String,
+  pub back: String,
+  pub tags: Vec<String>,
+}
+
+/// The card of a word, whose back shows the full code and the code of each character, e.g. `wi (我 w, 们 m)`.
+pub fn card(rev_dict: &RevDict, word: &str, tags: &[String]) -> Option<Card> {
+  let (code, _) = rev_dict.lookup(word)?;
+  let mut back = code.clone();
+  if word.chars().count() > 1 {
+    let parts: Vec<_> = word
+      .chars()
+      .map(|ch| {
+        let ch = ch.to_string();
+        let code = rev_dict.lookup(&ch).map_or("?", |(code, _)| code.as_str());
+        format!("{ch} {code}")
+      })
+      .collect();
+    back = format!("{back} ({})", parts.join(", "));
+  }
+  Some(Card { front: word.to_string(), back, tags: tags.to_vec() })
+}
+
+/// Anki's plain text import with the file headers it understands since 2.1.55, tab separated.
+pub fn write_cards<'a>(mut writer: impl Write, cards: impl IntoIterator<Item=&'a Card>) -> io::Result<()> {
+  writeln!(writer, "#separator:tab")?;
+  writeln!(writer, "#html:false")?;
+  writeln!(writer, "#tags column:3")?;
+  let field = |text: &str| text.replace(['\t', '\n'], " ");
+  for card in cards {
+    writeln!(writer, "{}\t{}\t{}", field(&card.front), field(&card.back), field(&card.tags.join(" ")))?;
+  }
+  writer.flush()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::TrieBuilder;
+
+  #[test]
+  fn test_cards() {
+    let trie = TrieBuilder::from_iter([("w", "我"), ("mk", "们"), ("wi", "我们")]).build();
+    let rev_dict = trie.rev_dict();
+    let tags = vec!["smart-dict".to_string()];
+    let cards: Vec<_> = ["我们", "我", "他"].iter().filter_map(|word| card(&rev_dict, word, &tags)).collect();
+    assert_eq!(2, cards.len());
+    assert_eq!("wi (我 w, 们 mk)", cards[0].back);
+
+    let mut tsv = vec![];
+    write_cards(&mut tsv, &cards).unwrap();
+    assert_eq!(
+      "#separator:tab\n#html:false\n#tags column:3\n我们\twi (我 w, 们 mk)\tsmart-dict\n我\tw\tsmart-dict\n",
+      String::from_utf8(tsv).unwrap()
+    );
+  }
+}
@@ -0,0 +1,164 @@
+//! Dicts read straight from a downloaded bundle: paths going through a `.zip`, e.g.
+//! `release.zip/xkjd6.cizu.dict.yaml`, and `.gz` files. Needs the `archive` feature.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Cursor};
+use std::path::{Path, PathBuf};
+
+fn has_ext(path: &Path, ext: &str) -> bool {
+  path.extension().is_some_and(|e| e.eq_ignore_ascii_case(ext))
+}
+
+/// The `.zip` file a path goes through and the path inside it.
+pub fn split_zip(path: &Path) -> Option<(&Path, PathBuf)> {
+  path
+    .ancestors()
+    .skip(1)
+    .find(|ancestor| has_ext(ancestor, "zip") && ancestor.is_file())
+    .map(|zip| (zip, path.strip_prefix(zip).unwrap().to_path_buf()))
+}
+
+/// Whether the path is read through [`read`] rather than as a plain file.
+pub fn is_archived(path: &Path) -> bool {
+  has_ext(path, "gz") || split_zip(path).is_some()
+}
+
+/// Whether the file exists, in its archive if it's in one.
+pub fn exists(path: &Path) -> bool {
+  match split_zip(path) {
+    Some((zip, inner)) => entry_name(zip, &inner).is_ok_and(|name| name.is_some()),
+    None => path.is_file(),
+  }
+}
+
+/// Opens a file, decompressing it in memory if it's archived.
+pub fn open(path: impl AsRef<Path>) -> io::Result<Box<dyn BufRead + Send>> {
+  let path = path.as_ref();
+  if is_archived(path) {
+    Ok(Box::new(Cursor::new(read(path)?)))
+  } else {
+    Ok(Box::new(BufReader::new(File::open(path)?)))
+  }
+}
+
+pub fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+  let bytes = if is_archived(path.as_ref()) { read(path.as_ref())? } else { std::fs::read(path)? };
+  String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// The content of an archived file, NotFound if the archive lacks it.
+pub fn read(path: &Path) -> io::Result<Vec<u8>> {
+  if let Some((zip, inner)) = split_zip(path) {
+    let name = entry_name(zip, &inner)?
+      .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no {} in {}", inner.display(), zip.display())))?;
+    let bytes = read_zip_entry(zip, &name)?;
+    return if has_ext(path, "gz") { gunzip(&bytes) } else { Ok(bytes) };
+  }
+  gunzip(&std::fs::read(path)?)
+}
+
+/// Bundles often wrap their files in a top directory, so `xkjd6.cizu.dict.yaml` also matches
+/// `xkjd6-v1.2/xkjd6.cizu.dict.yaml`.
+fn find_entry<'a>(names: impl Iterator<Item=&'a str>, inner: &Path) -> Option<String> {
+  let inner = inner.to_string_lossy().replace('\\', "/");
+  let nested = format!("/{inner}");
+  let mut found = None;
+  for name in names {
+    if name == inner {
+      return Some(name.to_string());
+    }
+    if name.ends_with(&nested) && found.as_ref().is_none_or(|found: &String| name.len() < found.len()) {
+      found = Some(name.to_string());
+    }
+  }
+  found
+}
+
+fn entry_name(zip: &Path, inner: &Path) -> io::Result<Option<String>> {
+  Ok(find_entry(zip_names(zip)?.iter().map(String::as_str), inner))
+}
+
+#[cfg(feature = "archive")]
+fn zip_names(zip: &Path) -> io::Result<Vec<String>> {
+  let archive = zip::ZipArchive::new(File::open(zip)?).map_err(io::Error::other)?;
+  Ok(archive.file_names().map(String::from).collect())
+}
+
+#[cfg(feature = "archive")]
+fn read_zip_entry(zip: &Path, name: &str) -> io::Result<Vec<u8>> {
+  use std::io::Read;
+
+  let mut archive = zip::ZipArchive::new(File::open(zip)?).map_err(io::Error::other)?;
+  let mut entry = archive.by_name(name).map_err(io::Error::other)?;
+  let mut bytes = Vec::with_capacity(entry.size() as usize);
+  entry.read_to_end(&mut bytes)?;
+  Ok(bytes)
+}
+
+#[cfg(feature = "archive")]
+fn gunzip(bytes: &[u8]) -> io::Result<Vec<u8>> {
+  use std::io::Read;
+
+  let mut decoded = vec![];
+  flate2::read::MultiGzDecoder::new(bytes).read_to_end(&mut decoded)?;
+  Ok(decoded)
+}
+
+#[cfg(not(feature = "archive"))]
+fn unsupported() -> io::Error {
+  io::Error::new(io::ErrorKind::Unsupported, "smart-dict was built without the archive feature")
+}
+
+#[cfg(not(feature = "archive"))]
+fn zip_names(_zip: &Path) -> io::Result<Vec<String>> {
+  Err(unsupported())
+}
+
+#[cfg(not(feature = "archive"))]
+fn read_zip_entry(_zip: &Path, _name: &str) -> io::Result<Vec<u8>> {
+  Err(unsupported())
+}
+
+#[cfg(not(feature = "archive"))]
+fn gunzip(_bytes: &[u8]) -> io::Result<Vec<u8>> {
+  Err(unsupported())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_find_entry() {
+    let names = ["xkjd6-v1/xkjd6.cizu.dict.yaml", "xkjd6-v1/old/xkjd6.cizu.dict.yaml", "xkjd6.danzi.dict.yaml"];
+    let find = |inner: &str| find_entry(names.into_iter(), Path::new(inner));
+    assert_eq!(Some("xkjd6-v1/xkjd6.cizu.dict.yaml".to_string()), find("xkjd6.cizu.dict.yaml"));
+    assert_eq!(Some("xkjd6.danzi.dict.yaml".to_string()), find("xkjd6.danzi.dict.yaml"));
+    assert_eq!(None, find("cizu.dict.yaml"));
+  }
+
+  #[cfg(feature = "archive")]
+  #[test]
+  fn test_read() {
+    use std::io::Write;
+
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let zip_path = dir.join("release.zip");
+    let mut zip = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+    zip.start_file("release/xkjd6.cizu.dict.yaml", zip::write::SimpleFileOptions::default()).unwrap();
+    zip.write_all("我们\twi\n".as_bytes()).unwrap();
+    zip.finish().unwrap();
+    let gz_path = dir.join("xkjd6.danzi.dict.yaml.gz");
+    let mut gz = flate2::write::GzEncoder::new(File::create(&gz_path).unwrap(), Default::default());
+    gz.write_all("我\tw\n".as_bytes()).unwrap();
+    gz.finish().unwrap();
+
+    let inner = zip_path.join("xkjd6.cizu.dict.yaml");
+    assert!(exists(&inner));
+    assert!(!exists(&zip_path.join("xkjd6.danzi.dict.yaml")));
+    assert_eq!("我们\twi\n", read_to_string(&inner).unwrap());
+    assert_eq!(io::ErrorKind::NotFound, read(&zip_path.join("missing")).unwrap_err().kind());
+    assert_eq!("我\tw\n", read_to_string(&gz_path).unwrap());
+  }
+}
@@ -0,0 +1,81 @@
+use std::collections::{BTreeMap, HashMap};
+use crate::corpus::Alphabet;
+use crate::rev_dict::RevDict;
+use crate::types::Word;
+
+/// How often one dictionary word follows another in a corpus segmented with the shortest codes.
+#[derive(Clone, Debug, Default)]
+pub struct Bigrams(HashMap<(Word, Word), u64>);
+
+impl Bigrams {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  pub fn add_line(&mut self, rev_dict: &RevDict, alphabet: &Alphabet, line: &str) {
+    for piece in alphabet.pieces(line) {
+      let Ok(segments) = rev_dict.segment(piece) else {
+        continue;
+      };
+      for pair in segments.windows(2) {
+        *self.0.entry((pair[0].word.clone(), pair[1].word.clone())).or_default() += 1;
+      }
+    }
+  }
+
+  pub fn count(&self, word: &str, next: &str) -> u64 {
+    self.0.get(&(word.to_string(), next.to_string())).copied().unwrap_or_default()
+  }
+
+  /// For each word, the `top` words most likely to follow it, most frequent first.
+  pub fn associations(&self, top: usize) -> BTreeMap<&Word, Vec<(&Word, u64)>> {
+    let mut table: BTreeMap<_, Vec<_>> = BTreeMap::new();
+    for ((word, next), &count) in &self.0 {
+      table.entry(word).or_default().push((next, count));
+    }
+    for nexts in table.values_mut() {
+      nexts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+      nexts.truncate(top);
+    }
+    table
+  }
+
+  /// Pairs seen at least `min_count` times whose concatenation isn't a word yet, most frequent first.
+  pub fn phrases(&self, rev_dict: &RevDict, min_count: u64) -> Vec<(Word, u64)> {
+    let mut phrases: Vec<_> = self.0
+      .iter()
+      .filter(|(_, &count)| count >= min_count)
+      .map(|((word, next), &count)| (format!("{word}{next}"), count))
+      .filter(|(phrase, _)| rev_dict.lookup(phrase).is_none())
+      .collect();
+    phrases.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    phrases
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::TrieBuilder;
+
+  #[test]
+  fn test_bigrams() {
+    let trie = TrieBuilder::from_iter([("w", "我"), ("wi", "我们"), ("n", "你"), ("fio", "非常"), ("xh", "喜欢")]).build();
+    let rev_dict = trie.rev_dict();
+    let alphabet = Alphabet::new(&rev_dict);
+
+    let mut bigrams = Bigrams::new();
+    for line in ["我非常喜欢你", "我非常 你", "我们非常喜欢"] {
+      bigrams.add_line(&rev_dict, &alphabet, line);
+    }
+
+    assert_eq!(2, bigrams.count("我", "非常"));
+    assert_eq!(0, bigrams.count("非常", "你"));
+    let table = bigrams.associations(1);
+    assert_eq!(vec![(&"喜欢".to_string(), 2)], table[&"非常".to_string()]);
+    assert_eq!(
+      vec![("我非常".to_string(), 2), ("非常喜欢".to_string(), 2)],
+      bigrams.phrases(&rev_dict, 2)
+    );
+  }
+}
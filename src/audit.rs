@@ -0,0 +1,66 @@
+//! What a change to a schema repo does, between the checkout it starts from and the one it
+//! ends at: the lint issues and collisions it adds or removes, for `audit` to report on pull
+//! requests, including as GitHub Actions annotations on the changed lines.
+
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::hash::Hash;
+use crate::lint::Severity;
+
+/// What appeared and disappeared from a base to a head.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Delta<T> {
+  pub added: Vec<T>,
+  pub removed: Vec<T>,
+}
+
+/// The items of `head` not in `base` and the other way around, told apart by `key`,
+/// in the order of their lists.
+pub fn delta<T: Clone, K: Eq + Hash>(base: &[T], head: &[T], key: impl Fn(&T) -> K) -> Delta<T> {
+  let base_keys: HashSet<_> = base.iter().map(&key).collect();
+  let head_keys: HashSet<_> = head.iter().map(&key).collect();
+  Delta {
+    added: head.iter().filter(|item| !base_keys.contains(&key(item))).cloned().collect(),
+    removed: base.iter().filter(|item| !head_keys.contains(&key(item))).cloned().collect(),
+  }
+}
+
+/// Where an annotation points, the file relative to the repository root.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+  pub file: String,
+  /// 1-based.
+  pub line: usize,
+}
+
+/// A GitHub Actions workflow command showing the message on the pull request,
+/// `::error file=a.dict.yaml,line=3,title=lint::message`.
+pub fn github_annotation(severity: Severity, location: Option<&Location>, title: &str, message: &str) -> String {
+  // https://docs.github.com/actions/using-workflows/workflow-commands-for-github-actions
+  let escape_data = |text: &str| text.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A");
+  let escape_property = |text: &str| escape_data(text).replace(':', "%3A").replace(',', "%2C");
+  let mut command = format!("::{severity} ");
+  if let Some(location) = location {
+    let _ = write!(command, "file={},line={},", escape_property(&location.file), location.line);
+  }
+  let _ = write!(command, "title={}::{}", escape_property(title), escape_data(message));
+  command
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_delta() {
+    let delta = delta(&["a", "b", "c"], &["c", "d", "a"], |item| *item);
+    assert_eq!(Delta { added: vec!["d"], removed: vec!["b"] }, delta);
+
+    let location = Location { file: "xkjd6.cizu.dict.yaml".to_string(), line: 12 };
+    assert_eq!(
+      "::error file=xkjd6.cizu.dict.yaml,line=12,title=lint%3A collides::我们\twomf: 100%0Ano",
+      github_annotation(Severity::Error, Some(&location), "lint: collides", "我们\twomf: 100\nno"),
+    );
+    assert_eq!("::warning title=corpus::slower", github_annotation(Severity::Warning, None, "corpus", "slower"));
+  }
+}
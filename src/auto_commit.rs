@@ -0,0 +1,160 @@
+//! Auto-commit policies like the switches of xkjd6, 四码唯一自动上屏 and the like, and how many
+//! of the words of a corpus each one would commit without a key.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::corpus::Alphabet;
+use crate::rev_dict::RevDict;
+use crate::trie::Trie;
+
+/// When the first candidate of a code goes up by itself, `+` joining the rules:
+/// `unique` for the only candidate of a code that no longer code extends, as `Trie::eval` commits
+/// it, `unique:4` for those of codes of 4 keys or more, `full:4` for codes of 4 keys or more whatever
+/// their candidates (满码上屏), `off` for never.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Policy {
+  /// The shortest code whose unique candidate commits itself, `None` if none does.
+  pub unique_from: Option<usize>,
+  /// The shortest code whose first candidate commits itself.
+  pub full_from: Option<usize>,
+}
+
+impl Policy {
+  pub const ENGINE: Self = Self { unique_from: Some(1), full_from: None };
+
+  /// Whether `word`, typed with `len` keys ending at `node`, goes up without a key. The keys
+  /// typed count rather than the full code of `node`, which completions may reach before it ends.
+  pub fn is_auto_commit(&self, node: &Trie, word: &str, len: usize) -> bool {
+    if node.words().first().map(String::as_str) != Some(word) {
+      return false;
+    }
+    let unique = node.words().len() == 1 && node.is_leaf();
+    self.unique_from.is_some_and(|min| unique && len >= min) || self.full_from.is_some_and(|min| len >= min)
+  }
+}
+
+impl FromStr for Policy {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut policy = Policy::default();
+    if s.trim() == "off" {
+      return Ok(policy);
+    }
+    for rule in s.split('+') {
+      let (name, len) = match rule.trim().split_once(':') {
+        Some((name, len)) => (name, len.parse().ok().filter(|&len| len > 0).ok_or_else(|| format!("{len} isn't a code length"))?),
+        None => (rule.trim(), 1),
+      };
+      match name {
+        "unique" => policy.unique_from = Some(len),
+        "full" => policy.full_from = Some(len),
+        _ => return Err(format!("an auto-commit rule is unique[:<len>] or full[:<len>], not {rule}")),
+      }
+    }
+    Ok(policy)
+  }
+}
+
+impl Display for Policy {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let rule = |name: &str, len: usize| if len == 1 { name.to_string() } else { format!("{name}:{len}") };
+    let rules: Vec<_> = [("unique", self.unique_from), ("full", self.full_from)]
+      .into_iter()
+      .filter_map(|(name, len)| Some(rule(name, len?)))
+      .collect();
+    match rules.is_empty() {
+      true => write!(f, "off"),
+      false => write!(f, "{}", rules.join("+")),
+    }
+  }
+}
+
+/// The commits of a corpus under a policy.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tally {
+  pub policy: Policy,
+  pub commits: usize,
+  pub auto_commits: usize,
+}
+
+impl Tally {
+  pub fn manual_commits(&self) -> usize {
+    self.commits - self.auto_commits
+  }
+
+  pub fn auto_commit_rate(&self) -> f64 {
+    match self.commits {
+      0 => 0.0,
+      commits => self.auto_commits as f64 / commits as f64,
+    }
+  }
+}
+
+/// Counts the commits of the words of a corpus, segmented with the shortest codes like
+/// `corpus`, under each of several policies.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+  pub tallies: Vec<Tally>,
+}
+
+impl Comparison {
+  pub fn new(policies: impl IntoIterator<Item=Policy>) -> Self {
+    Self { tallies: policies.into_iter().map(|policy| Tally { policy, ..Default::default() }).collect() }
+  }
+
+  pub fn add_line(&mut self, rev_dict: &RevDict, alphabet: &Alphabet, line: &str) {
+    for piece in alphabet.pieces(&rev_dict.convert(line)) {
+      let Ok(segments) = rev_dict.segment(piece) else { continue };
+      for segment in &segments {
+        for tally in &mut self.tallies {
+          tally.commits += 1;
+          if tally.policy.is_auto_commit(segment.node, &segment.word, segment.word_code().len()) {
+            tally.auto_commits += 1;
+          }
+        }
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_policies() {
+    assert_eq!(Ok(Policy::ENGINE), "unique".parse());
+    assert_eq!(Ok(Policy { unique_from: Some(4), full_from: Some(6) }), "unique:4+full:6".parse());
+    assert_eq!(Ok(Policy::default()), "off".parse());
+    assert!("unique:0".parse::<Policy>().is_err());
+    assert!("single".parse::<Policy>().is_err());
+    for policy in ["off", "unique", "unique:4+full:6", "full:3"] {
+      assert_eq!(policy, policy.parse::<Policy>().unwrap().to_string());
+    }
+
+    let mut trie = Trie::new();
+    for (code, word) in [("w", "我"), ("wi", "我们"), ("fio", "非常"), ("fiox", "非常好"), ("xhd", "喜欢"), ("xhd", "戏"), ("nui", "你的")] {
+      trie.insert(code.to_string(), word.to_string());
+    }
+    let rev_dict = trie.rev_dict();
+    let alphabet = Alphabet::new(&rev_dict);
+    let policies = ["off", "unique", "unique:3", "full:3"].map(|policy| policy.parse().unwrap());
+    let mut comparison = Comparison::new(policies);
+    // 我们 非常 喜欢 你的
+    comparison.add_line(&rev_dict, &alphabet, "我们非常喜欢你的");
+    let counts: Vec<_> = comparison.tallies.iter().map(|tally| (tally.commits, tally.auto_commits)).collect();
+    assert_eq!(vec![(4, 0), (4, 2), (4, 1), (4, 3)], counts);
+    assert_eq!(0.75, comparison.tallies[3].auto_commit_rate());
+    assert_eq!(2, comparison.tallies[1].manual_commits());
+
+    // the keys typed count, not the full code of the node
+    let unique = "unique:4".parse::<Policy>().unwrap();
+    let node = trie.node("nui").unwrap();
+    assert!(!unique.is_auto_commit(node, "你的", 3));
+    assert!(unique.is_auto_commit(node, "你的", 4));
+    assert!(!unique.is_auto_commit(node, "非常", 4));
+  }
+}
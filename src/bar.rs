@@ -0,0 +1,37 @@
+//! Progress bars on stderr for the long operations of the library, see `smart_dict::progress`,
+//! drawn with the `progress` feature and only on a terminal.
+
+use smart_dict::progress::Progress;
+#[cfg(feature = "progress")]
+use smart_dict::progress::Stage;
+
+/// A callback drawing a bar for each stage reported, cleared once done.
+#[cfg(feature = "progress")]
+pub fn bar() -> impl FnMut(Progress) {
+  use indicatif::{ProgressBar, ProgressStyle};
+  let mut bar: Option<(Stage, ProgressBar)> = None;
+  move |progress: Progress| {
+    if bar.as_ref().is_none_or(|(stage, _)| *stage != progress.stage) {
+      let template = match progress.stage {
+        Stage::Load | Stage::Encode => "{msg} {wide_bar} {bytes}/{total_bytes} {eta}",
+        Stage::Index => "{msg} {wide_bar} {pos}/{len}",
+      };
+      let new = ProgressBar::new(progress.total)
+        .with_style(ProgressStyle::with_template(template).unwrap_or_else(|_| ProgressStyle::default_bar()))
+        .with_message(progress.stage.to_string());
+      bar = Some((progress.stage, new));
+    }
+    if let Some((_, bar)) = &bar {
+      bar.set_length(progress.total);
+      bar.set_position(progress.current);
+      if progress.is_done() {
+        bar.finish_and_clear();
+      }
+    }
+  }
+}
+
+#[cfg(not(feature = "progress"))]
+pub fn bar() -> impl FnMut(Progress) {
+  |_| {}
+}
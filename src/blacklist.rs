@@ -0,0 +1,81 @@
+//! Words that should never be loaded, e.g. sensitive words or OCR garbage shipped in upstream tables,
+//! listed one per line in `smart-dict.blacklist`, `/.../` lines being regexes matched against words:
+//!
+//! ```text
+//! # comments and blank lines are ignored
+//! 某敏感词
+//! /[�□]/
+//! ```
+
+use std::collections::HashSet;
+use std::io::{self, BufRead};
+use std::path::Path;
+use regex::Regex;
+use crate::archive;
+use crate::types::Word;
+
+pub const BLACKLIST_FILE: &str = "smart-dict.blacklist";
+
+#[derive(Clone, Debug, Default)]
+pub struct Blacklist {
+  words: HashSet<Word>,
+  patterns: Vec<Regex>,
+}
+
+impl Blacklist {
+  /// Loads the blacklist, or an empty one if the file doesn't exist.
+  pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+    let file = match archive::open(path) {
+      Ok(file) => file,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+      Err(e) => return Err(e),
+    };
+    let mut blacklist = Self::default();
+    for (idx, line) in file.lines().enumerate() {
+      blacklist.add(&line?).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {e}", idx + 1)))?;
+    }
+    Ok(blacklist)
+  }
+
+  /// Adds a line of the file: a word, a `/regex/`, or a comment.
+  pub fn add(&mut self, line: &str) -> Result<(), regex::Error> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+      return Ok(());
+    }
+    match line.strip_prefix('/').and_then(|line| line.strip_suffix('/')).filter(|pattern| !pattern.is_empty()) {
+      Some(pattern) => self.patterns.push(Regex::new(pattern)?),
+      None => {
+        self.words.insert(line.to_string());
+      }
+    }
+    Ok(())
+  }
+
+  pub fn contains(&self, word: &str) -> bool {
+    self.words.contains(word) || self.patterns.iter().any(|pattern| pattern.is_match(word))
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.words.is_empty() && self.patterns.is_empty()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_blacklist() {
+    let mut blacklist = Blacklist::default();
+    assert!(blacklist.is_empty());
+    for line in ["# sensitive", "", "  坏词 ", "/^[a-z]+$/", "/"] {
+      blacklist.add(line).unwrap();
+    }
+    assert!(blacklist.contains("坏词"));
+    assert!(blacklist.contains("abc"));
+    assert!(blacklist.contains("/"));
+    assert!(!blacklist.contains("好词") && !blacklist.contains("abc词"));
+    assert!(blacklist.add("/(/").is_err());
+  }
+}
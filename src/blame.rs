@@ -0,0 +1,123 @@
+//! When each entry line was last changed and by whom, from `git blame` when the Rime directory
+//! is a git repository, for reports like the entries added in the last month.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::process::Command;
+use crate::archive;
+use crate::schema::Schema;
+
+/// The commit a line comes from.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Blame {
+  pub commit: String,
+  pub author: String,
+  /// The author time, in seconds since the epoch.
+  pub time: u64,
+}
+
+impl Blame {
+  /// Whether the line isn't committed yet, git blaming it on a zero commit.
+  pub fn is_uncommitted(&self) -> bool {
+    self.commit.bytes().all(|byte| byte == b'0')
+  }
+}
+
+/// The blame of each line of `git blame --line-porcelain` output.
+pub fn parse_porcelain(text: &str) -> Vec<Blame> {
+  let mut blames = vec![];
+  let mut current = Blame { commit: String::new(), author: String::new(), time: 0 };
+  for line in text.lines() {
+    if line.starts_with('\t') {
+      blames.push(current.clone());
+    } else if let Some(author) = line.strip_prefix("author ") {
+      current.author = author.to_string();
+    } else if let Some(time) = line.strip_prefix("author-time ") {
+      current.time = time.parse().unwrap_or_default();
+    } else if let Some((commit, _)) = line.split_once(' ').filter(|(commit, _)| commit.len() >= 40 && commit.bytes().all(|byte| byte.is_ascii_hexdigit())) {
+      current.commit = commit.to_string();
+    }
+  }
+  blames
+}
+
+/// The blame of each line of the file, `None` if it isn't tracked by git or git isn't installed.
+pub fn blame(path: &Path) -> io::Result<Option<Vec<Blame>>> {
+  let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else { return Ok(None) };
+  let output = match Command::new("git")
+    .args(["blame", "--line-porcelain", "--"])
+    .arg(name)
+    .current_dir(if dir.as_os_str().is_empty() { Path::new(".") } else { dir })
+    .output() {
+    Ok(output) => output,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+    Err(e) => return Err(e),
+  };
+  Ok(output.status.success().then(|| parse_porcelain(&String::from_utf8_lossy(&output.stdout))))
+}
+
+/// The blames of the tables of a schema.
+#[derive(Clone, Debug, Default)]
+pub struct Blames(HashMap<String, Vec<Blame>>);
+
+impl Blames {
+  /// Blames the tables tracked by git, skipping archived ones.
+  pub fn of_schema(schema: &Schema) -> io::Result<Self> {
+    let mut blames = Self::default();
+    for table in schema.tables()? {
+      let path = schema.dict_path(&table);
+      if archive::is_archived(&path) {
+        continue;
+      }
+      if let Some(lines) = blame(&path)? {
+        blames.0.insert(table, lines);
+      }
+    }
+    Ok(blames)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  /// The blame of a 0-based line of a table.
+  pub fn get(&self, table: &str, line: usize) -> Option<&Blame> {
+    self.0.get(table)?.get(line)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_parse_porcelain() {
+    let text = "\
+4e405ea54797f5896433f95fe83ad19bcf752df9 1 1 2
+author 张三
+author-mail <z@example.com>
+author-time 1700000000
+author-tz +0800
+summary baseline
+filename xkjd6.user.dict.yaml
+\t---
+4e405ea54797f5896433f95fe83ad19bcf752df9 2 2
+author 张三
+author-time 1700000000
+filename xkjd6.user.dict.yaml
+\t我们\twomf
+0000000000000000000000000000000000000000 3 3 1
+author Not Committed Yet
+author-time 1710000000
+filename xkjd6.user.dict.yaml
+\tauthor 1\tx
+";
+    let blames = parse_porcelain(text);
+    assert_eq!(3, blames.len());
+    assert_eq!(Blame { commit: "4e405ea54797f5896433f95fe83ad19bcf752df9".to_string(), author: "张三".to_string(), time: 1700000000 }, blames[1]);
+    assert!(blames[2].is_uncommitted() && !blames[0].is_uncommitted());
+    assert_eq!("Not Committed Yet", blames[2].author);
+  }
+}
@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use crate::config::Config;
+use crate::dict;
+use crate::schema::Schema;
+
+/// Entry counts per table recorded by a previous run.
+#[derive(Clone, Debug, Default)]
+pub struct Snapshot(pub BTreeMap<String, usize>);
+
+impl Snapshot {
+  pub fn load(path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+    let file = match File::open(path) {
+      Ok(file) => file,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+      Err(e) => return Err(e),
+    };
+    let mut counts = BTreeMap::new();
+    for line in BufReader::new(file).lines() {
+      let line = line?;
+      if let Some((table, count)) = line.split_once('\t') {
+        if let Ok(count) = count.trim().parse() {
+          counts.insert(table.to_string(), count);
+        }
+      }
+    }
+    Ok(Some(Self(counts)))
+  }
+
+  pub fn write(&self, writer: impl Write) -> io::Result<()> {
+    let mut writer = BufWriter::new(writer);
+    for (table, count) in &self.0 {
+      writeln!(writer, "{table}\t{count}")?;
+    }
+    writer.flush()
+  }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Usage {
+  pub table: String,
+  pub entries: usize,
+  pub budget: Option<usize>,
+  pub previous: Option<usize>,
+}
+
+impl Usage {
+  pub fn ratio(&self) -> Option<f64> {
+    self.budget.map(|budget| self.entries as f64 / budget as f64)
+  }
+
+  pub fn is_over(&self) -> bool {
+    self.budget.is_some_and(|budget| self.entries > budget)
+  }
+
+  pub fn trend(&self) -> Option<isize> {
+    self.previous.map(|prev| self.entries as isize - prev as isize)
+  }
+}
+
+pub fn usage(schema: &Schema, config: &Config, previous: Option<&Snapshot>) -> io::Result<Vec<Usage>> {
+  let mut usages = vec![];
+  for table in schema.tables()? {
+    let entries = dict::count_entries(schema.dict_path(&table))?;
+    usages.push(Usage {
+      budget: config.budgets.get(&table).copied(),
+      previous: previous.and_then(|snapshot| snapshot.0.get(&table).copied()),
+      table,
+      entries,
+    });
+  }
+  Ok(usages)
+}
+
+pub fn snapshot(usages: &[Usage]) -> Snapshot {
+  Snapshot(usages.iter().map(|usage| (usage.table.clone(), usage.entries)).collect())
+}
+
+#[cfg(test)]
+mod test {
+  use std::fs;
+  use super::*;
+
+  #[test]
+  fn test_usage() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let main = "---\nname: xkjd6.extended\nimport_tables:\n  - xkjd6.user\n...\n我\tw\n";
+    fs::write(dir.join("xkjd6.extended.dict.yaml"), main).unwrap();
+    fs::write(dir.join("xkjd6.user.dict.yaml"), "我们\twi\n非常\tfio\n喜欢\txa\n").unwrap();
+    let schema = Schema::new(dir, "xkjd6");
+    let config = Config::parse("budgets:\n  xkjd6.user: 2\n").unwrap();
+
+    let usages = usage(&schema, &config, None).unwrap();
+    let summary: Vec<_> = usages.iter().map(|usage| (usage.table.as_str(), usage.entries, usage.budget, usage.is_over())).collect();
+    assert_eq!(vec![("xkjd6.extended", 1, None, false), ("xkjd6.user", 3, Some(2), true)], summary);
+    assert_eq!((None, Some(1.5)), (usages[0].ratio(), usages[1].ratio()));
+    assert_eq!(None, usages[1].trend());
+
+    let path = dir.join("budget.tsv");
+    snapshot(&usages).write(File::create(&path).unwrap()).unwrap();
+    fs::write(dir.join("xkjd6.user.dict.yaml"), "我们\twi\n").unwrap();
+    let previous = Snapshot::load(&path).unwrap().unwrap();
+    let usages = usage(&schema, &config, Some(&previous)).unwrap();
+    assert_eq!((Some(0), Some(-2)), (usages[0].trend(), usages[1].trend()));
+    assert!(!usages[1].is_over());
+    assert!(Snapshot::load(dir.join("missing.tsv")).unwrap().is_none());
+  }
+}
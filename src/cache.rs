@@ -0,0 +1,238 @@
+//! The caches of `serve`, where clients look the same few codes up over and over, and the
+//! statistics of how well they do. The other commands looking codes up, like `drill` checking each
+//! code typed by hand, look each up too seldom for a cache to pay.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const NIL: usize = usize::MAX;
+
+struct Slot<K, V> {
+  key: K,
+  value: V,
+  /// Towards the most recently used.
+  prev: usize,
+  /// Towards the least recently used.
+  next: usize,
+}
+
+/// Keeps the `capacity` most recently used values, evicting the least recently used one to make
+/// room for a new one.
+pub struct Lru<K, V> {
+  capacity: usize,
+  index: HashMap<K, usize>,
+  slots: Vec<Slot<K, V>>,
+  /// The most recently used slot.
+  head: usize,
+  /// The least recently used slot, the next to go.
+  tail: usize,
+}
+
+impl<K: Hash + Eq + Clone, V> Lru<K, V> {
+  /// A cache of `capacity` values, which keeps none with 0.
+  pub fn new(capacity: usize) -> Self {
+    Self { capacity, index: HashMap::new(), slots: vec![], head: NIL, tail: NIL }
+  }
+
+  pub fn len(&self) -> usize {
+    self.slots.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.slots.is_empty()
+  }
+
+  pub fn capacity(&self) -> usize {
+    self.capacity
+  }
+
+  /// The value of `key`, which becomes the most recently used.
+  pub fn get(&mut self, key: &K) -> Option<&V> {
+    let idx = *self.index.get(key)?;
+    self.detach(idx);
+    self.attach_front(idx);
+    Some(&self.slots[idx].value)
+  }
+
+  /// Stores `value` as the most recently used, returning the key evicted for it if the cache was full.
+  pub fn insert(&mut self, key: K, value: V) -> Option<K> {
+    if self.capacity == 0 {
+      return None;
+    }
+    if let Some(&idx) = self.index.get(&key) {
+      self.slots[idx].value = value;
+      self.detach(idx);
+      self.attach_front(idx);
+      return None;
+    }
+    if self.slots.len() < self.capacity {
+      self.index.insert(key.clone(), self.slots.len());
+      self.slots.push(Slot { key, value, prev: NIL, next: NIL });
+      self.attach_front(self.slots.len() - 1);
+      return None;
+    }
+    // the least recently used slot takes the new value
+    let idx = self.tail;
+    self.detach(idx);
+    let slot = &mut self.slots[idx];
+    let evicted = std::mem::replace(&mut slot.key, key.clone());
+    slot.value = value;
+    self.index.remove(&evicted);
+    self.index.insert(key, idx);
+    self.attach_front(idx);
+    Some(evicted)
+  }
+
+  pub fn clear(&mut self) {
+    self.index.clear();
+    self.slots.clear();
+    self.head = NIL;
+    self.tail = NIL;
+  }
+
+  fn detach(&mut self, idx: usize) {
+    let (prev, next) = (self.slots[idx].prev, self.slots[idx].next);
+    match prev {
+      NIL => self.head = next,
+      prev => self.slots[prev].next = next,
+    }
+    match next {
+      NIL => self.tail = prev,
+      next => self.slots[next].prev = prev,
+    }
+  }
+
+  fn attach_front(&mut self, idx: usize) {
+    self.slots[idx].prev = NIL;
+    self.slots[idx].next = self.head;
+    match self.head {
+      NIL => self.tail = idx,
+      head => self.slots[head].prev = idx,
+    }
+    self.head = idx;
+  }
+}
+
+/// Counts of a two-level cache, a small one of its own per client in front of a larger shared
+/// one, updated from any thread.
+#[derive(Debug, Default)]
+pub struct CacheStats {
+  local_hits: AtomicU64,
+  shared_hits: AtomicU64,
+  misses: AtomicU64,
+  evictions: AtomicU64,
+  invalidations: AtomicU64,
+}
+
+impl CacheStats {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  pub fn local_hit(&self) {
+    self.local_hits.fetch_add(1, Ordering::Relaxed);
+  }
+
+  pub fn shared_hit(&self) {
+    self.shared_hits.fetch_add(1, Ordering::Relaxed);
+  }
+
+  pub fn miss(&self) {
+    self.misses.fetch_add(1, Ordering::Relaxed);
+  }
+
+  pub fn eviction(&self) {
+    self.evictions.fetch_add(1, Ordering::Relaxed);
+  }
+
+  /// The cached values were dropped, the data they came from having changed.
+  pub fn invalidation(&self) {
+    self.invalidations.fetch_add(1, Ordering::Relaxed);
+  }
+
+  pub fn metrics(&self) -> CacheMetrics {
+    CacheMetrics {
+      local_hits: self.local_hits.load(Ordering::Relaxed),
+      shared_hits: self.shared_hits.load(Ordering::Relaxed),
+      misses: self.misses.load(Ordering::Relaxed),
+      evictions: self.evictions.load(Ordering::Relaxed),
+      invalidations: self.invalidations.load(Ordering::Relaxed),
+    }
+  }
+}
+
+/// The counts of `CacheStats` at some point.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheMetrics {
+  pub local_hits: u64,
+  pub shared_hits: u64,
+  pub misses: u64,
+  pub evictions: u64,
+  pub invalidations: u64,
+}
+
+impl CacheMetrics {
+  pub fn lookups(&self) -> u64 {
+    self.local_hits + self.shared_hits + self.misses
+  }
+
+  /// The share of the lookups answered by either level, 0 before any lookup.
+  pub fn hit_rate(&self) -> f64 {
+    match self.lookups() {
+      0 => 0.0,
+      lookups => (self.local_hits + self.shared_hits) as f64 / lookups as f64,
+    }
+  }
+}
+
+/// `name value` pairs separated by tabs, `lookups 5\tlocal_hits 3\t...\thit_rate 0.800`.
+impl Display for CacheMetrics {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "lookups {}\tlocal_hits {}\tshared_hits {}\tmisses {}\tevictions {}\tinvalidations {}\thit_rate {:.3}",
+           self.lookups(), self.local_hits, self.shared_hits, self.misses, self.evictions, self.invalidations, self.hit_rate())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_lru() {
+    let mut lru = Lru::new(2);
+    assert_eq!(None, lru.insert("a", 1));
+    assert_eq!(None, lru.insert("b", 2));
+    assert_eq!(Some(&1), lru.get(&"a"));
+    // b is the least recently used now
+    assert_eq!(Some("b"), lru.insert("c", 3));
+    assert_eq!(None, lru.get(&"b"));
+    assert_eq!(None, lru.insert("a", 10));
+    assert_eq!(Some("c"), lru.insert("d", 4));
+    assert_eq!(Some(&10), lru.get(&"a"));
+    assert_eq!(Some(&4), lru.get(&"d"));
+    assert_eq!(2, lru.len());
+    lru.clear();
+    assert!(lru.is_empty());
+    assert_eq!(None, lru.get(&"a"));
+    assert_eq!(None, lru.insert("e", 5));
+    let mut none = Lru::new(0);
+    assert_eq!(None, none.insert("a", 1));
+    assert_eq!(None, none.get(&"a"));
+    assert!(none.is_empty());
+
+    let stats = CacheStats::new();
+    assert_eq!(0.0, stats.metrics().hit_rate());
+    for _ in 0..3 {
+      stats.local_hit();
+    }
+    stats.shared_hit();
+    stats.miss();
+    stats.invalidation();
+    let metrics = stats.metrics();
+    assert_eq!(0.8, metrics.hit_rate());
+    assert_eq!("lookups 5\tlocal_hits 3\tshared_hits 1\tmisses 1\tevictions 0\tinvalidations 1\thit_rate 0.800", metrics.to_string());
+  }
+}
@@ -0,0 +1,56 @@
+//! Classes of words added by hand, e.g. names, places or tech terms, each with the table they go to,
+//! their default weight and the annotation marking them, read from `smart-dict.yaml`:
+//!
+//! ```yaml
+//! categories:
+//!   tech:
+//!     table: xkjd6.tech
+//!     weight: 100
+//!     note: 专业词
+//! ```
+
+use std::io;
+use crate::dict;
+use crate::trie::Entry;
+use crate::yaml;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Category {
+  pub table: Option<String>,
+  pub weight: Option<u64>,
+  pub note: Option<String>,
+}
+
+impl Category {
+  pub fn parse(name: &str, value: &yaml::Value) -> io::Result<Self> {
+    let field = |key: &str| value.get(key).and_then(yaml::Value::as_str).map(String::from);
+    let weight = field("weight")
+      .map(|weight| weight.parse().map_err(|_| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("weight of category {name} should be a number, not {weight}"),
+      )))
+      .transpose()?;
+    Ok(Self { table: field("table"), weight, note: field("note") })
+  }
+
+  /// The line of an entry of this category, the weight and note given overriding the category's.
+  pub fn line(&self, entry: &Entry, weight: Option<u64>, note: Option<&str>) -> String {
+    dict::format_line(entry, weight.or(self.weight), note.or(self.note.as_deref()))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_category() {
+    let doc = yaml::parse("tech:\n  table: xkjd6.tech\n  weight: 100\n  note: 专业词\nname:\n  weight: many\n");
+    let tech = Category::parse("tech", doc.get("tech").unwrap()).unwrap();
+    assert_eq!(Some("xkjd6.tech"), tech.table.as_deref());
+    let entry = Entry { word: "量子纠缠".to_string(), code: "lzjc".to_string() };
+    assert_eq!("量子纠缠\tlzjc\t100\t# 专业词", tech.line(&entry, None, None));
+    assert_eq!("量子纠缠\tlzjc\t5\t# 物理", tech.line(&entry, Some(5), Some("物理")));
+    assert!(Category::parse("name", doc.get("name").unwrap()).is_err());
+  }
+}
@@ -0,0 +1,173 @@
+//! Drills made from one's own chat history: the phrases one types most that the schema gives no
+//! short code, so that drilling their codes pays off in everyday typing, and how the drills on
+//! them go session after session.
+
+use std::collections::HashMap;
+use crate::corpus::Alphabet;
+use crate::cost::{word_cost, Cost};
+use crate::drill::Score;
+use crate::rev_dict::RevDict;
+use crate::types::Word;
+
+const HEADER: &str = "time\twords\tmisses\tchars\tseconds";
+
+/// The text of a line of an exported chat log, `None` if it's someone else's when `me` is given.
+///
+/// Lines are `[2024-01-02 12:34:56] name: text` or `name：text`, the timestamp being optional.
+/// A line without a sender, like the continuation of a multiline message, is taken whole when
+/// `me` isn't given and skipped otherwise.
+pub fn message<'l>(line: &'l str, me: Option<&str>) -> Option<&'l str> {
+  let rest = line.trim_start_matches(|ch: char| ch.is_ascii_digit() || "-/:.[] ".contains(ch));
+  let sent = rest
+    .find([':', '：'])
+    .map(|idx| (&rest[..idx], rest[idx..].chars().next().map_or(idx, |colon| idx + colon.len_utf8())))
+    .filter(|(sender, _)| !sender.is_empty() && sender.chars().count() <= 32);
+  match (sent, me) {
+    (Some((sender, text)), Some(me)) => (sender.trim() == me).then(|| rest[text..].trim()),
+    (Some((_, text)), None) => Some(rest[text..].trim()),
+    (None, Some(_)) => None,
+    (None, None) => Some(line.trim()),
+  }
+}
+
+/// A word of the dict typed often in the chats.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Phrase {
+  pub word: Word,
+  pub count: u64,
+  pub cost: Cost,
+}
+
+/// Counts the phrases of the messages, segmented with the shortest codes like the corpus.
+#[derive(Clone, Debug, Default)]
+pub struct PhraseCounts(HashMap<Word, u64>);
+
+impl PhraseCounts {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  pub fn add_message(&mut self, rev_dict: &RevDict, alphabet: &Alphabet, text: &str) {
+    for piece in alphabet.pieces(text) {
+      let Ok(segments) = rev_dict.segment(piece) else {
+        continue;
+      };
+      for segment in segments.iter().filter(|segment| segment.word.chars().nth(1).is_some()) {
+        *self.0.entry(segment.word.clone()).or_default() += 1;
+      }
+    }
+  }
+
+  /// The phrases seen at least `min_count` times taking more than `short` keys, most frequent first.
+  pub fn missing_short_codes(&self, rev_dict: &RevDict, short: usize, min_count: u64) -> Vec<Phrase> {
+    let mut phrases: Vec<_> = self.0
+      .iter()
+      .filter(|(_, &count)| count >= min_count)
+      .filter_map(|(word, &count)| {
+        let cost = word_cost(rev_dict, word)?;
+        (cost.keystrokes() > short).then(|| Phrase { word: word.clone(), count, cost })
+      })
+      .collect();
+    phrases.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word)));
+    phrases
+  }
+}
+
+/// How a drill on the chat phrases went.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Session {
+  /// Seconds since the Unix epoch.
+  pub time: u64,
+  pub words: usize,
+  pub misses: usize,
+  /// Characters of the words typed right.
+  pub chars: usize,
+  pub seconds: f64,
+}
+
+impl Session {
+  pub fn new(time: u64, score: &Score) -> Self {
+    Self { time, words: score.words, misses: score.misses.len(), chars: score.chars, seconds: score.elapsed.as_secs_f64() }
+  }
+
+  pub fn accuracy(&self) -> f64 {
+    (self.words - self.misses) as f64 / self.words as f64
+  }
+
+  /// 0 for a session too quick to time, typed from a pipe.
+  pub fn chars_per_minute(&self) -> f64 {
+    if self.seconds > 0.0 { self.chars as f64 / self.seconds * 60.0 } else { 0.0 }
+  }
+
+  fn to_line(&self) -> String {
+    format!("{}\t{}\t{}\t{}\t{:.3}", self.time, self.words, self.misses, self.chars, self.seconds)
+  }
+
+  fn parse(line: &str) -> Option<Self> {
+    let mut cells = line.split('\t');
+    Some(Self {
+      time: cells.next()?.parse().ok()?,
+      words: cells.next()?.parse().ok()?,
+      misses: cells.next()?.parse().ok()?,
+      chars: cells.next()?.parse().ok()?,
+      seconds: cells.next()?.parse().ok()?,
+    }).filter(|session| session.words > 0 && session.misses <= session.words)
+  }
+}
+
+/// The sessions of a log in the order they were appended, skipping lines that don't parse.
+pub fn sessions(log: &str) -> Vec<Session> {
+  log.lines().filter_map(Session::parse).collect()
+}
+
+/// Adds the line of `session` to the text of a log, after the header if it's a new one.
+pub fn append_session(log: &mut String, session: &Session) {
+  if log.is_empty() {
+    log.push_str(HEADER);
+    log.push('\n');
+  }
+  log.push_str(&session.to_line());
+  log.push('\n');
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::Trie;
+
+  #[test]
+  fn test_chat_phrases() {
+    assert_eq!(Some("我们走吧"), message("[2024-01-02 12:34:56] 小明: 我们走吧", Some("小明")));
+    assert_eq!(None, message("2024-01-02 12:35 小红：好的", Some("小明")));
+    assert_eq!(Some("好的"), message("2024-01-02 12:35 小红：好的", None));
+    assert_eq!(Some("接着上一行"), message("接着上一行", None));
+    assert_eq!(None, message("接着上一行", Some("小明")));
+
+    let mut trie = Trie::new();
+    for (code, word) in [("w", "我"), ("mfi", "们"), ("wmz", "我们"), ("fc", "非常"), ("h", "好"), ("hd", "好的"), ("hdo", "后的")] {
+      trie.insert(code.to_string(), word.to_string());
+    }
+    let rev_dict = trie.rev_dict();
+    let alphabet = Alphabet::new(&rev_dict);
+    let mut counts = PhraseCounts::new();
+    for text in ["我们非常好", "我们好的", "非常！我们"] {
+      counts.add_message(&rev_dict, &alphabet, text);
+    }
+    let phrases = counts.missing_short_codes(&rev_dict, 2, 1);
+    let words: Vec<_> = phrases.iter().map(|phrase| (phrase.word.as_str(), phrase.count)).collect();
+    assert_eq!(vec![("我们", 3)], words);
+    assert_eq!(2, counts.missing_short_codes(&rev_dict, 1, 2).len());
+
+    let session = Session { time: 1_700_000_000, words: 10, misses: 2, chars: 16, seconds: 30.0 };
+    assert_eq!(Some(session.clone()), Session::parse(&session.to_line()));
+    assert_eq!(0.8, session.accuracy());
+    assert_eq!(32.0, session.chars_per_minute());
+    assert_eq!(None, Session::parse(HEADER));
+    let mut log = String::new();
+    append_session(&mut log, &session);
+    append_session(&mut log, &session);
+    assert_eq!(vec![session.clone(), session], sessions(&log));
+  }
+}
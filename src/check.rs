@@ -0,0 +1,91 @@
+use std::fmt::Write;
+
+/// The machine-readable result of an analysis run in `--check` mode.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Summary {
+  pub command: String,
+  pub metrics: Vec<(String, f64)>,
+  pub violations: Vec<String>,
+}
+
+impl Summary {
+  pub fn new(command: &str) -> Self {
+    Self {
+      command: command.to_string(),
+      metrics: vec![],
+      violations: vec![],
+    }
+  }
+
+  pub fn metric(&mut self, name: &str, value: impl Into<f64>) {
+    self.metrics.push((name.to_string(), value.into()));
+  }
+
+  pub fn violation(&mut self, msg: impl Into<String>) {
+    self.violations.push(msg.into());
+  }
+
+  pub fn passed(&self) -> bool {
+    self.violations.is_empty()
+  }
+
+  /// Compact single-line JSON, e.g.
+  /// `{"command":"lint","passed":false,"metrics":{"errors":1},"violations":["..."]}`.
+  pub fn to_json(&self) -> String {
+    let mut json = String::new();
+    write!(json, r#"{{"command":{},"passed":{},"metrics":{{"#, quote(&self.command), self.passed()).unwrap();
+    for (i, (name, value)) in self.metrics.iter().enumerate() {
+      if i > 0 {
+        json.push(',');
+      }
+      let value = if value.is_finite() { value.to_string() } else { "null".to_string() };
+      write!(json, "{}:{value}", quote(name)).unwrap();
+    }
+    json.push_str(r#"},"violations":["#);
+    for (i, violation) in self.violations.iter().enumerate() {
+      if i > 0 {
+        json.push(',');
+      }
+      json.push_str(&quote(violation));
+    }
+    json.push_str("]}");
+    json
+  }
+}
+
+/// Quotes a string as a JSON string literal.
+pub fn quote(s: &str) -> String {
+  let mut quoted = String::with_capacity(s.len() + 2);
+  quoted.push('"');
+  for ch in s.chars() {
+    match ch {
+      '"' => quoted.push_str("\\\""),
+      '\\' => quoted.push_str("\\\\"),
+      '\n' => quoted.push_str("\\n"),
+      '\r' => quoted.push_str("\\r"),
+      '\t' => quoted.push_str("\\t"),
+      c if (c as u32) < 0x20 => write!(quoted, "\\u{:04x}", c as u32).unwrap(),
+      c => quoted.push(c),
+    }
+  }
+  quoted.push('"');
+  quoted
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_to_json() {
+    let mut summary = Summary::new("lint");
+    summary.metric("errors", 1);
+    summary.metric("ratio", 0.5);
+    summary.violation("空\t\"x\"");
+    assert_eq!(
+      r#"{"command":"lint","passed":false,"metrics":{"errors":1,"ratio":0.5},"violations":["空\t\"x\""]}"#,
+      summary.to_json()
+    );
+  }
+}
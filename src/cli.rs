@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Options that never take a value.
+pub const SWITCHES: &[&str] = &["check", "save", "dry-run", "yes", "resume", "trace", "no-completion", "echo", "verify", "fix", "blame", "preview", "verbose", "write-back", "ndjson", "help"];
+
+pub struct Args {
+  pub command: Option<String>,
+  pub positional: Vec<String>,
+  options: HashMap<String, Vec<String>>,
+}
+
+impl Args {
+  /// Parses `command [positional...] [--switch] [--option value | --option=value]`.
+  pub fn parse(args: impl Iterator<Item=String>, commands: &[&str]) -> Self {
+    let mut args = args.peekable();
+    let mut parsed = Self {
+      command: None,
+      positional: vec![],
+      options: HashMap::new(),
+    };
+    while let Some(arg) = args.next() {
+      if let Some(name) = arg.strip_prefix("--") {
+        let (name, value) = match name.split_once('=') {
+          Some((name, value)) => (name.to_string(), value.to_string()),
+          None if SWITCHES.contains(&name) => (name.to_string(), String::new()),
+          None => {
+            let value = args.next_if(|next| !next.starts_with("--")).unwrap_or_default();
+            (name.to_string(), value)
+          }
+        };
+        parsed.options.entry(name).or_default().push(value);
+      } else if parsed.command.is_none() && parsed.positional.is_empty() && commands.contains(&arg.as_str()) {
+        parsed.command = Some(arg);
+      } else {
+        parsed.positional.push(arg);
+      }
+    }
+    parsed
+  }
+
+  pub fn switch(&self, name: &str) -> bool {
+    self.options.contains_key(name)
+  }
+
+  pub fn value(&self, name: &str) -> Option<&str> {
+    self.options.get(name).and_then(|values| values.last()).map(String::as_str)
+  }
+
+  /// All the values of an option given several times, e.g. `--part a --part b`.
+  pub fn values(&self, name: &str) -> impl Iterator<Item=&str> {
+    self.options.get(name).into_iter().flatten().map(String::as_str)
+  }
+
+  pub fn parse_value<T: FromStr>(&self, name: &str) -> Result<Option<T>, String> {
+    self.value(name)
+      .map(|value| value
+        .parse()
+        .map_err(|_| format!("invalid value for --{name}: {value:?}")))
+      .transpose()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn parse(line: &str) -> Args {
+    Args::parse(line.split_whitespace().map(String::from), &["budget", "stats"])
+  }
+
+  #[test]
+  fn test_parse() {
+    let args = parse("budget --check --dir D:/Rime --snapshot=a.tsv extra");
+    assert_eq!(Some("budget"), args.command.as_deref());
+    assert!(args.switch("check"));
+    assert_eq!(Some("D:/Rime"), args.value("dir"));
+    assert_eq!(Some("a.tsv"), args.value("snapshot"));
+    assert_eq!(vec!["extra"], args.positional);
+
+    let args = parse("stats --part a --part=b");
+    assert_eq!(vec!["a", "b"], args.values("part").collect::<Vec<_>>());
+
+    let args = parse("D:/Rime");
+    assert_eq!(None, args.command);
+    assert_eq!(vec!["D:/Rime"], args.positional);
+  }
+}
@@ -0,0 +1,36 @@
+//! The system clipboard, with the `clipboard` feature.
+
+#[cfg(feature = "clipboard")]
+pub fn read() -> Result<String, String> {
+  arboard::Clipboard::new()
+    .and_then(|mut clipboard| clipboard.get_text())
+    .map_err(|e| format!("can't read the clipboard: {e}"))
+}
+
+/// Whether `write` blocks until something else is copied: the X11 and Wayland clipboards are
+/// served by the process that set them, their contents are gone once it exits.
+pub const WRITE_WAITS: bool = cfg!(all(unix, not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))));
+
+#[cfg(feature = "clipboard")]
+pub fn write(text: &str) -> Result<(), String> {
+  #[cfg(all(unix, not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))))]
+  let set = |clipboard: &mut arboard::Clipboard| {
+    use arboard::SetExtLinux;
+    clipboard.set().wait().text(text)
+  };
+  #[cfg(not(all(unix, not(any(target_os = "macos", target_os = "android", target_os = "emscripten")))))]
+  let set = |clipboard: &mut arboard::Clipboard| clipboard.set_text(text);
+  arboard::Clipboard::new()
+    .and_then(|mut clipboard| set(&mut clipboard))
+    .map_err(|e| format!("can't write the clipboard: {e}"))
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn read() -> Result<String, String> {
+  Err("built without the clipboard feature".to_string())
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn write(_text: &str) -> Result<(), String> {
+  Err("built without the clipboard feature".to_string())
+}
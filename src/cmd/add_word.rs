@@ -0,0 +1,44 @@
+use std::process::ExitCode;
+use smart_dict::archive;
+use smart_dict::category::Category;
+use smart_dict::dict;
+use smart_dict::trie::Entry;
+use crate::cli::Args;
+use crate::fileman;
+use super::{verified, Context};
+
+/// Appends `<word> <code>` to the table of its `--category` (see `Category`) or `--table`,
+/// with the category's weight and note unless `--weight` or `--note` are given.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let [word, code] = args.positional.as_slice() else {
+    return Err("usage: smart-dict add-word <word> <code> [--category <name>] [--table <table>] [--weight <n>] [--note <text>]".to_string());
+  };
+  let category = match args.value("category") {
+    Some(name) => ctx.config.categories.get(name).cloned().ok_or_else(|| {
+      let known: Vec<_> = ctx.config.categories.keys().map(String::as_str).collect();
+      format!("no category {name} in the config, known: {}", known.join(", "))
+    })?,
+    None => Category::default(),
+  };
+  let table = args.value("table")
+    .or(category.table.as_deref())
+    .ok_or("no table to add the word to, give --table or a category with one")?;
+  let path = ctx.schema.dict_path(table);
+  if !path.is_file() || archive::is_archived(&path) {
+    return Err(format!("{} isn't a table that can be edited", path.display()));
+  }
+
+  let entry = Entry { word: word.clone(), code: code.clone() };
+  let _lock = ctx.lock()?;
+  let existing = dict::read_weighted_entries_with(&path, ctx.schema.line_parser(table))
+    .map_err(|e| format!("can't read {}: {e}", path.display()))?;
+  if existing.iter().any(|(existing, _)| existing == &entry) {
+    return Err(format!("{word} is already on {code} in {table}"));
+  }
+  let separator = ctx.schema.separator(table).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+  let line = separator.restyle(&category.line(&entry, args.parse_value("weight")?, args.value("note")));
+  fileman::append_line(&path, &line).map_err(|e| format!("can't write {}: {e}", path.display()))?;
+  ctx.record_change(args, &path, &format!("add-word: {word} {code}"))?;
+  println!("{line}\t→ {table}");
+  verified(ctx, args).await
+}
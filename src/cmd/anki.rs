@@ -0,0 +1,52 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufWriter};
+use std::process::ExitCode;
+use smart_dict::anki;
+use crate::cli::Args;
+use crate::fileman;
+use super::{drill, Context};
+
+/// Exports Anki cards for the words of `--words` (one per line, first column),
+/// or by default for the words missed in drills, to `--out` or stdout.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let path = args.value("words")
+    .map_or_else(|| drill::misses_path(ctx), Into::into);
+  let text = match fs::read_to_string(&path) {
+    Err(e) if e.kind() == io::ErrorKind::NotFound && args.value("words").is_none() =>
+      return Err("no drill misses yet, run `smart-dict drill` or pass --words".to_string()),
+    text => text.map_err(|e| format!("can't read {}: {e}", path.display()))?,
+  };
+  let mut words: Vec<_> = text
+    .lines()
+    .filter_map(|line| line.split('\t').next())
+    .map(str::trim)
+    .filter(|word| !word.is_empty())
+    .collect();
+  // a word missed in several drills is one card
+  let mut seen = HashSet::new();
+  words.retain(|word| seen.insert(*word));
+
+  let tags: Vec<_> = args.value("tags")
+    .unwrap_or(&ctx.schema.name)
+    .split_whitespace()
+    .map(String::from)
+    .collect();
+  let trie = ctx.load_trie().await?;
+  let rev_dict = ctx.rev_dict(&trie);
+  let mut cards = vec![];
+  for word in words {
+    match anki::card(&rev_dict, word, &tags) {
+      Some(card) => cards.push(card),
+      None => eprintln!("{word} is not in the dictionary, skipped"),
+    }
+  }
+
+  match args.value("out") {
+    Some(path) => fileman::write_with(path, |file| anki::write_cards(BufWriter::new(file), &cards))
+      .map_err(|e| format!("can't write {path}: {e}"))?,
+    None => anki::write_cards(io::stdout().lock(), &cards).map_err(|e| e.to_string())?,
+  }
+  eprintln!("{} cards", cards.len());
+  Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,49 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use smart_dict::archive;
+use smart_dict::patch::Patch;
+use crate::cli::Args;
+use crate::fileman;
+use super::{verified, Context};
+
+/// Applies a patch `diff` wrote to the tables it names. Nothing is written unless every change
+/// applies: an entry to remove or modify must be there and an entry to add must not.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let [patch_path] = args.positional.as_slice() else {
+    return Err("usage: smart-dict apply <patch>".to_string());
+  };
+  let patch: Patch = fs::read_to_string(patch_path)
+    .map_err(|e| format!("can't read {patch_path}: {e}"))?
+    .parse()
+    .map_err(|e| format!("{patch_path}: {e}"))?;
+
+  let _lock = ctx.lock()?;
+  // 同一张表的几段改动依次作用于同一份文本，每张表只写一次
+  let mut tables: Vec<(&str, PathBuf, String, usize)> = vec![];
+  for hunk in &patch.hunks {
+    let idx = match tables.iter().position(|(table, ..)| *table == hunk.table) {
+      Some(idx) => idx,
+      None => {
+        let path = ctx.schema.dict_path(&hunk.table);
+        if !path.is_file() || archive::is_archived(&path) {
+          return Err(format!("{} isn't a table that can be edited", path.display()));
+        }
+        let text = fileman::read(&path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+        let text = String::from_utf8(text).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+        tables.push((&hunk.table, path, text, 0));
+        tables.len() - 1
+      }
+    };
+    let (_, _, text, changes) = &mut tables[idx];
+    *text = hunk.apply(text, ctx.schema.line_parser(&hunk.table))?;
+    *changes += hunk.changes.len();
+  }
+  let name = patch_path.rsplit(['/', '\\']).next().unwrap_or(patch_path);
+  for (table, path, text, changes) in tables {
+    fileman::replace(&path, text).map_err(|e| format!("can't write {}: {e}", path.display()))?;
+    ctx.record_change(args, &path, &format!("apply: {name}"))?;
+    println!("{table}\t{changes} changes");
+  }
+  verified(ctx, args).await
+}
@@ -0,0 +1,56 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::process::ExitCode;
+use smart_dict::assoc::Bigrams;
+use smart_dict::corpus::{self, Alphabet};
+use crate::cli::Args;
+use crate::fileman;
+use super::{corpus_encoding, warn_decode_errors, Context};
+
+/// Counts which words follow which in `--corpus`, writing `word\tnext\tcount\tcode` lines
+/// to `--out` (stdout by default) and reporting frequent pairs worth a single entry.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let corpus_path = args.value("corpus").ok_or("--corpus is required")?;
+  let top = args.parse_value("top")?.unwrap_or(5);
+  let phrases = args.parse_value("phrases")?.unwrap_or(20);
+  let min_count = args.parse_value("min-count")?.unwrap_or(2);
+
+  let trie = ctx.load_trie().await?;
+  let rev_dict = ctx.rev_dict(&trie);
+  let alphabet = Alphabet::new(&rev_dict);
+  let mut bigrams = Bigrams::new();
+  let encoding = corpus_encoding(args, corpus_path)?;
+  let mut decode_errors = 0;
+  File::open(corpus_path)
+    .and_then(|file| corpus::for_each_line(BufReader::new(file), encoding, |line, _, errors| {
+      bigrams.add_line(&rev_dict, &alphabet, line.trim_start_matches('\u{FEFF}'));
+      decode_errors += errors as u64;
+      Ok(())
+    }))
+    .map_err(|e| format!("can't read {corpus_path}: {e}"))?;
+  warn_decode_errors(corpus_path, encoding, decode_errors);
+
+  let write = |writer: &mut dyn Write| {
+    let mut writer = BufWriter::new(writer);
+    for (word, nexts) in bigrams.associations(top) {
+      for (next, count) in nexts {
+        let code = rev_dict.lookup(next).map_or("", |(code, _)| code);
+        writeln!(writer, "{word}\t{next}\t{count}\t{code}")?;
+      }
+    }
+    writer.flush()
+  };
+  match args.value("out") {
+    Some(path) => fileman::write_with(path, write).map_err(|e| format!("can't write {path}: {e}"))?,
+    None => write(&mut io::stdout().lock()).map_err(|e| e.to_string())?,
+  }
+
+  let phrases: Vec<_> = bigrams.phrases(&rev_dict, min_count).into_iter().take(phrases).collect();
+  if !phrases.is_empty() {
+    eprintln!("phrases worth a single entry:");
+    for (phrase, count) in phrases {
+      eprintln!("  {phrase}\t{count}");
+    }
+  }
+  Ok(ExitCode::SUCCESS)
+}
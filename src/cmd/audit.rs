@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use smart_dict::audit::{self, github_annotation, Location};
+use smart_dict::check::Summary;
+use smart_dict::collision::{self, Collision, Provenance};
+use smart_dict::convert::Converter;
+use smart_dict::corpus::CorpusStats;
+use smart_dict::dedup::{Located, Source};
+use smart_dict::lint::{Issue, Severity};
+use smart_dict::trie::Entry;
+use crate::cli::Args;
+use crate::fileman;
+use super::{analyze_corpus, finish, lint, Context, CHECK_FAILED};
+
+/// One checkout of the schema repo, as `audit` sees it.
+struct Side {
+  ctx: Context,
+  issues: Vec<Issue>,
+  /// Not whitelisted.
+  collisions: Vec<Collision>,
+  located: Vec<Located>,
+  corpus: Option<CorpusStats>,
+}
+
+async fn inspect(dir: &str, args: &Args) -> Result<Side, String> {
+  let ctx = Context::open(PathBuf::from(dir), args)?;
+  let trie = ctx.load_trie().await?;
+  let (_, whitelist) = ctx.whitelist(args)?;
+  let issues = lint::issues(&ctx, &trie, &Converter::builtin(), &whitelist);
+  let located = ctx.schema.located_entries().map_err(|e| format!("can't read {dir}: {e}"))?;
+  let provenance = Provenance::new(&located);
+  let collisions = collision::collisions(&trie)
+    .into_iter()
+    .filter(|collision| !whitelist.allows(collision, &provenance))
+    .collect();
+  let corpus = args.value("corpus")
+    .map(|path| analyze_corpus(args, &ctx.rev_dict(&trie), path))
+    .transpose()?;
+  Ok(Side { ctx, issues, collisions, located, corpus })
+}
+
+impl Side {
+  fn count(&self, severity: Severity) -> usize {
+    self.issues.iter().filter(|issue| issue.severity == severity).count()
+  }
+
+  /// Where the entry is in this checkout, the file relative to the checkout.
+  fn location(&self, sources: &HashMap<&Entry, &Source>, entry: &Entry) -> Option<Location> {
+    let source = sources.get(entry)?;
+    let path = self.ctx.schema.dict_path(&source.table);
+    let file = path.strip_prefix(&self.ctx.schema.dir).unwrap_or(&path);
+    Some(Location { file: file.to_string_lossy().replace('\\', "/"), line: source.line + 1 })
+  }
+}
+
+/// Audits a change to a schema repo between the checkouts `--base <dir>` and `--head <dir>`:
+/// the lint issues and the collisions it adds or fixes, and with `--corpus` how the keys per char
+/// change. New lint errors and new collisions fail the audit, as does a rise in keys per char
+/// beyond `--max-regression` if given.
+///
+/// Meant as the one command run on pull requests: `--json <file|->` writes the summary, and
+/// on GitHub Actions the new issues are annotated on the lines adding them.
+pub async fn run(args: &Args) -> Result<ExitCode, String> {
+  let (Some(base_dir), Some(head_dir)) = (args.value("base"), args.value("head")) else {
+    return Err("usage: smart-dict audit --base <base checkout> --head <head checkout> [--corpus <file>]".to_string());
+  };
+  let max_regression: Option<f64> = args.parse_value("max-regression")?;
+  let base = inspect(base_dir, args).await?;
+  let head = inspect(head_dir, args).await?;
+
+  let issues = audit::delta(&base.issues, &head.issues, |issue| issue.to_string());
+  let collisions = audit::delta(&base.collisions, &head.collisions, Collision::key);
+  let new_errors = issues.added.iter().filter(|issue| issue.severity == Severity::Error).count();
+  let new_warnings = issues.added.len() - new_errors;
+
+  let mut summary = Summary::new("audit");
+  summary.metric("lint_errors", head.count(Severity::Error) as f64);
+  summary.metric("lint_warnings", head.count(Severity::Warning) as f64);
+  summary.metric("new_lint_errors", new_errors as f64);
+  summary.metric("new_lint_warnings", new_warnings as f64);
+  summary.metric("fixed_lint_issues", issues.removed.len() as f64);
+  summary.metric("collisions", head.collisions.len() as f64);
+  summary.metric("new_collisions", collisions.added.len() as f64);
+  summary.metric("resolved_collisions", collisions.removed.len() as f64);
+  if new_errors > 0 {
+    summary.violation(format!("{new_errors} new lint errors"));
+  }
+  for collision in &collisions.added {
+    summary.violation(format!("new collision on {}: {}", collision.code, collision.words.join(" ")));
+  }
+  let corpus = base.corpus.as_ref().zip(head.corpus.as_ref());
+  let mut regression = None;
+  if let Some((base_stats, head_stats)) = corpus {
+    let change = head_stats.keys_per_char() - base_stats.keys_per_char();
+    summary.metric("base_keys_per_char", base_stats.keys_per_char());
+    summary.metric("keys_per_char", head_stats.keys_per_char());
+    summary.metric("keys_per_char_change", change);
+    summary.metric("coverage", head_stats.coverage());
+    if let Some(max) = max_regression.filter(|&max| change > max) {
+      let violation = format!("keys per char up by {change:.4}, more than {max}");
+      summary.violation(&violation);
+      regression = Some(violation);
+    }
+  }
+
+  if env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true") {
+    let sources: HashMap<_, _> = head.located.iter().map(|located| (&located.entry, &located.source)).collect();
+    for issue in &issues.added {
+      let location = head.location(&sources, &issue.entry);
+      println!("{}", github_annotation(issue.severity, location.as_ref(), &format!("lint: {}", issue.rule), &issue.to_string()));
+    }
+    for collision in &collisions.added {
+      let location = collision.words.iter().find_map(|word| {
+        head.location(&sources, &Entry { code: collision.code.clone(), word: word.clone() })
+      });
+      let message = format!("{} now shared by {}", collision.code, collision.words.join(" "));
+      println!("{}", github_annotation(Severity::Error, location.as_ref(), "new collision", &message));
+    }
+    if let Some(violation) = &regression {
+      println!("{}", github_annotation(Severity::Error, None, "corpus", violation));
+    }
+  }
+
+  if let Some(path) = args.value("json") {
+    if path == "-" {
+      println!("{}", summary.to_json());
+    } else {
+      fileman::write(path, summary.to_json() + "\n").map_err(|e| format!("can't write {path}: {e}"))?;
+    }
+  }
+  if !args.switch("check") && args.value("json") != Some("-") {
+    println!("audit {base_dir} → {head_dir}");
+    println!("  lint\t{} errors, {} warnings: {new_errors} new errors, {new_warnings} new warnings, {} fixed",
+      head.count(Severity::Error), head.count(Severity::Warning), issues.removed.len());
+    for issue in &issues.added {
+      println!("    + {issue}");
+    }
+    println!("  collisions\t{}: {} new, {} resolved", head.collisions.len(), collisions.added.len(), collisions.removed.len());
+    for (mark, collisions) in [("+", &collisions.added), ("-", &collisions.removed)] {
+      for collision in collisions {
+        println!("    {mark} {}\t{}", collision.code, collision.words.join(" "));
+      }
+    }
+    if let Some((base_stats, head_stats)) = corpus {
+      println!("  corpus\t{:.3} → {:.3} keys per char ({:+.4}), {:.2}% → {:.2}% coverage",
+        base_stats.keys_per_char(), head_stats.keys_per_char(), head_stats.keys_per_char() - base_stats.keys_per_char(),
+        base_stats.coverage() * 100.0, head_stats.coverage() * 100.0);
+    }
+    println!("{}", if summary.passed() { "passed" } else { "failed" });
+  }
+  let code = finish(args, &summary);
+  // unlike the reports, an audit fails on what it finds without `--check`
+  Ok(if summary.passed() { code } else { ExitCode::from(CHECK_FAILED) })
+}
@@ -0,0 +1,47 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::process::ExitCode;
+use smart_dict::auto_commit::{Comparison, Policy};
+use smart_dict::corpus::{self, Alphabet};
+use crate::cli::Args;
+use super::{corpus_encoding, warn_decode_errors, Context};
+
+/// Policies compared unless given with `--policy`.
+const DEFAULT_POLICIES: &[&str] = &["off", "unique", "unique:3", "unique:4", "full:4", "unique:4+full:6"];
+
+/// Counts the words of `--corpus`, typed with their shortest codes, that each auto-commit policy
+/// would commit without a key, see `Policy` for the policies given with `--policy`, as many times
+/// as there are policies to compare.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let corpus_path = args.value("corpus").ok_or("--corpus is required")?;
+  let mut policies: Vec<_> = args.values("policy").collect();
+  if policies.is_empty() {
+    policies = DEFAULT_POLICIES.to_vec();
+  }
+  let policies = policies
+    .into_iter()
+    .map(|policy| policy.parse::<Policy>().map_err(|e| format!("--policy: {e}")))
+    .collect::<Result<Vec<_>, _>>()?;
+
+  let trie = ctx.load_trie().await?;
+  let rev_dict = ctx.rev_dict(&trie);
+  let alphabet = Alphabet::new(&rev_dict);
+  let mut comparison = Comparison::new(policies);
+  let encoding = corpus_encoding(args, corpus_path)?;
+  let mut decode_errors = 0;
+  File::open(corpus_path)
+    .and_then(|file| corpus::for_each_line(BufReader::new(file), encoding, |line, _, errors| {
+      comparison.add_line(&rev_dict, &alphabet, line.trim_start_matches('\u{FEFF}'));
+      decode_errors += errors as u64;
+      Ok(())
+    }))
+    .map_err(|e| format!("can't read {corpus_path}: {e}"))?;
+  warn_decode_errors(corpus_path, encoding, decode_errors);
+
+  println!("{:<20}{:>10}{:>10}{:>10}{:>8}", "policy", "commits", "auto", "manual", "auto%");
+  for tally in &comparison.tallies {
+    println!("{:<20}{:>10}{:>10}{:>10}{:>7.2}%", tally.policy.to_string(), tally.commits, tally.auto_commits,
+             tally.manual_commits(), tally.auto_commit_rate() * 100.0);
+  }
+  Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,43 @@
+use std::process::ExitCode;
+use smart_dict::budget::{self, Snapshot};
+use smart_dict::check::Summary;
+use crate::cli::Args;
+use crate::fileman;
+use super::{finish, Context};
+
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let snapshot_path = args.value("snapshot")
+    .map(Into::into)
+    .unwrap_or_else(|| ctx.state_dir().join("budget.tsv"));
+  let previous = Snapshot::load(&snapshot_path).map_err(|e| e.to_string())?;
+  let usages = budget::usage(&ctx.schema, &ctx.config, previous.as_ref())
+    .map_err(|e| e.to_string())?;
+
+  let mut summary = Summary::new("budget");
+  summary.metric("entries", usages.iter().map(|usage| usage.entries as f64).sum::<f64>());
+  for usage in usages.iter().filter(|usage| usage.is_over()) {
+    summary.violation(format!("{} has {} entries, over its budget of {}",
+                              usage.table, usage.entries, usage.budget.unwrap()));
+  }
+
+  if !args.switch("check") {
+    println!("{:<24}{:>10}{:>10}{:>10}{:>10}", tr!("budget-table"), tr!("budget-entries"), tr!("budget-budget"),
+             tr!("budget-usage"), tr!("budget-trend"));
+    for usage in &usages {
+      let budget = usage.budget.map_or("-".to_string(), |b| b.to_string());
+      let ratio = usage.ratio().map_or("-".to_string(), |r| format!("{:.1}%", r * 100.0));
+      let trend = usage.trend().map_or("-".to_string(), |t| format!("{t:+}"));
+      let mark = if usage.is_over() { format!("  {}", tr!("budget-over")) } else { String::new() };
+      println!("{:<24}{:>10}{budget:>10}{ratio:>10}{trend:>10}{mark}", usage.table, usage.entries);
+    }
+  }
+
+  if args.switch("save") {
+    let _lock = ctx.lock()?;
+    let mut snapshot = vec![];
+    budget::snapshot(&usages).write(&mut snapshot).map_err(|e| e.to_string())?;
+    fileman::write(&snapshot_path, snapshot).map_err(|e| format!("can't write {}: {e}", snapshot_path.display()))?;
+  }
+
+  Ok(finish(args, &summary))
+}
@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+use smart_dict::check::Summary;
+use smart_dict::collision::{self, Baseline, Collision, Provenance, TopK};
+use smart_dict::filter::{self, Row};
+use smart_dict::freq::Frequencies;
+use smart_dict::ndjson::Object;
+use crate::cli::Args;
+use crate::fileman;
+use super::{blames, entry_filter, finish, page, Context};
+
+/// Lists the words sharing a code, but for the whitelisted ones, marking those new since the
+/// baseline; `--filter <expr>` keeps the collisions of which an entry passes it.
+///
+/// The worst come first, by their severity, see `Collision::severity`, weighing the words by
+/// `--freq` if given or else counting each once. `--top <k>` lists only the k worst, picked as
+/// the collisions are streamed from the trie rather than sorting them all; the counts still cover
+/// all of them. `--offset` and `--limit` page through the list the same way.
+///
+/// `--ndjson` writes them as lines like `{"code":"wi","words":["我们","维"],"severity":2,"new":false}`
+/// instead, as the trie is walked, by code, so that none is held for sorting; with `--top`, the
+/// k worst are sorted as above.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let freq = args.value("freq")
+    .map(|path| Frequencies::load(path).map_err(|e| format!("can't read {path}: {e}")))
+    .transpose()?;
+  let top: Option<usize> = args.parse_value("top")?;
+  let page = page(args)?;
+  let streamed = args.switch("ndjson") && top.is_none();
+  let trie = ctx.load_trie().await?;
+  let (_, whitelist) = ctx.whitelist(args)?;
+  let entries = ctx.schema.located_entries().map_err(|e| e.to_string())?;
+  let provenance = Provenance::new(&entries);
+  let expr = entry_filter(args)?;
+  let blames = blames(ctx, args, expr.as_ref())?.unwrap_or_default();
+  let passing: Option<HashSet<_>> = expr.map(|expr| {
+    let candidates = filter::candidates(&entries);
+    entries
+      .iter()
+      .filter(|located| {
+        let blame = blames.get(&located.source.table, located.source.line);
+        expr.matches(&Row::of(located, &candidates).blamed(blame))
+      })
+      .map(|located| (located.entry.code.as_str(), located.entry.word.as_str()))
+      .collect()
+  });
+  let baseline_path = args.value("baseline")
+    .map(Into::into)
+    .unwrap_or_else(|| ctx.state_dir().join("collisions.tsv"));
+  let baseline = Baseline::load(&baseline_path).map_err(|e| e.to_string())?;
+  let is_new = |collision: &Collision| baseline.as_ref().is_some_and(|baseline| baseline.is_new(collision));
+  let weight = |word: &str| freq.as_ref().map_or(1, |freq| freq.get(word));
+
+  let json = |collision: &Collision, severity: f64| Object::new()
+    .str("code", &collision.code)
+    .strs("words", &collision.words)
+    .num("severity", severity)
+    .bool("new", is_new(collision));
+  let mut out = BufWriter::new(io::stdout().lock());
+  let write_error = |e: io::Error| format!("can't write the collisions: {e}");
+
+  let (mut count, mut new_count, mut whitelisted, mut severity) = (0, 0, 0, 0.0);
+  let kept = if streamed { Some(0) } else { [top, page.end()].into_iter().flatten().min() };
+  let mut worst = TopK::new(kept.unwrap_or(usize::MAX));
+  let walk: Box<dyn Iterator<Item=Collision>> = match streamed {
+    true => Box::new(collision::sorted_stream(&trie)),
+    false => Box::new(collision::stream(&trie)),
+  };
+  for collision in walk {
+    if passing.as_ref().is_some_and(|passing| {
+      !collision.words.iter().any(|word| passing.contains(&(collision.code.as_str(), word.as_str())))
+    }) {
+      continue;
+    }
+    if whitelist.allows(&collision, &provenance) {
+      whitelisted += 1;
+      continue;
+    }
+    let collision_severity = collision.severity(weight);
+    if streamed && !args.switch("check") && page.contains(count) {
+      json(&collision, collision_severity).write_line(&mut out).map_err(write_error)?;
+    }
+    count += 1;
+    new_count += is_new(&collision) as usize;
+    severity += collision_severity;
+    worst.push(collision, collision_severity);
+  }
+  let collisions: Vec<_> = page.of(worst.into_sorted_vec().into_iter()).collect();
+  let max: Option<usize> = args.parse_value("max")?;
+
+  let mut summary = Summary::new("collisions");
+  summary.metric("collisions", count as f64);
+  summary.metric("new", new_count as f64);
+  summary.metric("whitelisted", whitelisted as f64);
+  summary.metric("severity", severity);
+  let listed_new = collisions.iter().filter(|(collision, _)| is_new(collision)).count();
+  for (collision, _) in collisions.iter().filter(|(collision, _)| is_new(collision)) {
+    summary.violation(format!("new collision on {}: {}", collision.code, collision.words.join(" ")));
+  }
+  if streamed && new_count > 0 {
+    summary.violation(format!("{new_count} new collisions"));
+  } else if new_count > listed_new {
+    summary.violation(format!("{} more new collisions beyond the top {}", new_count - listed_new, collisions.len()));
+  }
+  if let Some(max) = max.filter(|&max| count > max) {
+    summary.violation(format!("{count} collisions, more than {max}"));
+  }
+
+  if !args.switch("check") && args.switch("ndjson") {
+    for (collision, severity) in &collisions {
+      json(collision, *severity).write_line(&mut out).map_err(write_error)?;
+    }
+  } else if !args.switch("check") {
+    for (collision, severity) in &collisions {
+      let mark = if is_new(collision) { "\tnew" } else { "" };
+      writeln!(out, "{}\t{}\t{severity:.2}{mark}", collision.code, collision.words.join(" ")).map_err(write_error)?;
+    }
+    if page.offset > 0 {
+      let last = page.offset + collisions.len();
+      writeln!(out, "{}", tr!("collisions-page", page.offset + 1, last, count)).map_err(write_error)?;
+    } else if collisions.len() < count {
+      writeln!(out, "{}", tr!("collisions-top", collisions.len(), count)).map_err(write_error)?;
+    }
+    writeln!(out, "{}", tr!("collisions-summary", count, new_count, whitelisted)).map_err(write_error)?;
+  }
+  out.flush().map_err(write_error)?;
+  drop(out);
+
+  if args.switch("save") {
+    let _lock = ctx.lock()?;
+    let mut baseline = vec![];
+    Baseline::write(collision::stream(&trie), &mut baseline).map_err(|e| e.to_string())?;
+    fileman::write(&baseline_path, baseline).map_err(|e| format!("can't write {}: {e}", baseline_path.display()))?;
+  }
+  Ok(finish(args, &summary))
+}
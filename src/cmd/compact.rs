@@ -0,0 +1,58 @@
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use smart_dict::compact::CompactDict;
+use crate::cli::Args;
+use crate::fileman;
+use super::Context;
+use super::profile::format_bytes;
+
+/// Exports the schema's dict to a compact read-only file, by default `.smart-dict/<schema>.sdc`,
+/// or with `--load <file>`, looks up the codes given as arguments in one, listing up to
+/// `--complete <n>` completions of each instead if asked.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  if let Some(path) = args.value("load") {
+    return query(path, args);
+  }
+
+  let trie = ctx.load_trie().await?;
+  let dict = CompactDict::from_trie(&trie);
+  let path = args.value("out")
+    .map_or_else(|| ctx.state_dir().join(format!("{}.sdc", ctx.schema.name)), PathBuf::from);
+  fileman::write_with(&path, |file| {
+    let mut out = BufWriter::new(file);
+    dict.write(&mut out)?;
+    out.flush()
+  }).map_err(|e| format!("can't write {}: {e}", path.display()))?;
+
+  let footprint = trie.footprint();
+  println!("{} nodes in {} became {} states in {}, written to {}",
+           footprint.nodes, format_bytes(footprint.heap_bytes),
+           dict.states(), format_bytes(dict.heap_bytes()), path.display());
+  Ok(ExitCode::SUCCESS)
+}
+
+fn query(path: &str, args: &Args) -> Result<ExitCode, String> {
+  if args.positional.is_empty() {
+    return Err("usage: smart-dict compact --load <file> <code>... [--complete <n>]".to_string());
+  }
+  let complete: Option<usize> = args.parse_value("complete")?;
+  let dict = File::open(path)
+    .and_then(|file| CompactDict::read(&mut BufReader::new(file)))
+    .map_err(|e| format!("can't read {path}: {e}"))?;
+  for code in &args.positional {
+    match complete {
+      Some(limit) => {
+        for (code, word) in dict.complete(code, limit) {
+          println!("{code}\t{word}");
+        }
+      }
+      None => {
+        let words: Vec<_> = dict.get(code).into_iter().map(|(word, _)| word).collect();
+        println!("{code}\t{}", words.join(" "));
+      }
+    }
+  }
+  Ok(ExitCode::SUCCESS)
+}
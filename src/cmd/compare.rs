@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+use std::process::ExitCode;
+use smart_dict::check::Summary;
+use smart_dict::collision;
+use smart_dict::corpus::CorpusStats;
+use smart_dict::schema::Schema;
+use crate::cli::Args;
+use super::{analyze_corpus, finish, Context};
+
+type Column = fn(&Report) -> String;
+
+struct Report {
+  name: String,
+  entries: usize,
+  collisions: usize,
+  corpus: CorpusStats,
+}
+
+async fn evaluate(ctx: &Context, args: &Args, schema: &Schema, corpus_path: &str) -> Result<Report, String> {
+  let trie = schema.load_trie_async().await.map_err(|e| format!("can't load {}: {e}", schema.name))?;
+  let rev_dict = ctx.rev_dict(&trie);
+  let corpus = analyze_corpus(args, &rev_dict, corpus_path)?;
+  Ok(Report {
+    name: schema.name.clone(),
+    entries: trie.entries().count(),
+    collisions: collision::collisions(&trie).len(),
+    corpus,
+  })
+}
+
+/// Compares two schemas on a shared corpus, side by side.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let schema_a = Schema::new(
+    args.value("dir-a").map_or_else(|| ctx.schema.dir.clone(), PathBuf::from),
+    args.value("schema-a").ok_or("--schema-a is required")?,
+  );
+  let schema_b = Schema::new(
+    args.value("dir-b").map_or_else(|| ctx.schema.dir.clone(), PathBuf::from),
+    args.value("schema-b").ok_or("--schema-b is required")?,
+  );
+  let corpus_path = args.value("corpus").ok_or("--corpus is required")?;
+
+  let a = evaluate(ctx, args, &schema_a, corpus_path).await?;
+  let b = evaluate(ctx, args, &schema_b, corpus_path).await?;
+
+  let mut summary = Summary::new("compare");
+  for report in [&a, &b] {
+    summary.metric(&format!("{}.keys_per_char", report.name), report.corpus.keys_per_char());
+    summary.metric(&format!("{}.auto_commit_rate", report.name), report.corpus.auto_commit_rate());
+    summary.metric(&format!("{}.collisions", report.name), report.collisions as f64);
+  }
+
+  if !args.switch("check") {
+    println!("{:<20}{:>16}{:>16}", "", a.name, b.name);
+    let rows: [(&str, Column); 7] = [
+      ("entries", |r| r.entries.to_string()),
+      ("collisions", |r| r.collisions.to_string()),
+      ("chars", |r| r.corpus.chars.to_string()),
+      ("unencoded chars", |r| r.corpus.unencoded.to_string()),
+      ("keystrokes", |r| r.corpus.keystrokes.to_string()),
+      ("keys per char", |r| format!("{:.3}", r.corpus.keys_per_char())),
+      ("auto-commit rate", |r| format!("{:.2}%", r.corpus.auto_commit_rate() * 100.0)),
+    ];
+    for (name, value) in rows {
+      println!("{name:<20}{:>16}{:>16}", value(&a), value(&b));
+    }
+  }
+  Ok(finish(args, &summary))
+}
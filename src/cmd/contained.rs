@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+use std::process::ExitCode;
+use smart_dict::check::Summary;
+use smart_dict::containment;
+use crate::cli::Args;
+use super::{finish, Context};
+
+/// Lists the phrases typed piece by piece with at most `--slack` more keys, 0 by default, than
+/// with their own code, see `containment`, as candidates for deletion.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let slack = args.parse_value("slack")?.unwrap_or(0);
+  let trie = ctx.load_trie().await?;
+  let rev_dict = ctx.rev_dict(&trie);
+  let entries = ctx.schema.located_entries().map_err(|e| e.to_string())?;
+  let sources: HashMap<_, _> = entries
+    .iter()
+    .rev()
+    .map(|located| ((located.entry.word.as_str(), located.entry.code.as_str()), &located.source))
+    .collect();
+  let contained = containment::contained(&rev_dict, slack);
+
+  let mut summary = Summary::new("contained");
+  summary.metric("contained", contained.len() as f64);
+  if args.switch("check") {
+    return Ok(finish(args, &summary));
+  }
+
+  for contained in &contained {
+    let location = sources
+      .get(&(contained.word.as_str(), contained.cost.code.as_str()))
+      .map_or_else(String::new, |source| format!("{}:{}\t", source.table, source.line + 1));
+    let pieces: Vec<_> = contained.pieces.iter().map(|(word, code)| format!("{word} {code}")).collect();
+    println!("{location}{}\t{}\t{} → {} keys: {}",
+      contained.word, contained.cost.code, contained.cost.keystrokes(), contained.piece_keys, pieces.join(" + "));
+  }
+  println!("{}", tr!("contained-summary", contained.len(), slack));
+  Ok(ExitCode::SUCCESS)
+}
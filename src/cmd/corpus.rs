@@ -0,0 +1,111 @@
+use std::fs;
+use std::process::ExitCode;
+use smart_dict::check::Summary;
+use smart_dict::corpus::{self, Checkpoint, CorpusStats};
+use smart_dict::sample::{self, SampleSize};
+use crate::cli::Args;
+use crate::fileman;
+use super::{corpus_encoding, finish, warn_decode_errors, Context};
+
+fn report(lines: u64, stats: &CorpusStats) -> String {
+  tr!("corpus-report", lines, stats.chars, format!("{:.3}", stats.keys_per_char()),
+      format!("{:.2}", stats.auto_commit_rate() * 100.0), format!("{:.2}", stats.coverage() * 100.0))
+}
+
+/// Streams a corpus through the shortest codes, printing a partial report and saving a checkpoint
+/// every `--every` lines so that `--resume` can pick an interrupted run up.
+///
+/// `--sample 1%` or `--sample <lines>` only estimates the keys per char from a sample of the lines,
+/// see `sample`, with `--seed` picking another sample.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let corpus_path = args.value("corpus").ok_or("--corpus is required")?;
+  let every = args.parse_value("every")?.unwrap_or(100_000);
+  let max_keys_per_char: Option<f64> = args.parse_value("max-keys-per-char")?;
+  if let Some(size) = args.parse_value::<SampleSize>("sample")? {
+    return estimate(ctx, args, corpus_path, size, max_keys_per_char).await;
+  }
+  let checkpoint_path = ctx.state_dir().join("corpus.checkpoint");
+
+  // the checkpoint is written all along
+  let _lock = ctx.lock()?;
+  let corpus_len = fs::metadata(corpus_path)
+    .map_err(|e| format!("can't read {corpus_path}: {e}"))?
+    .len();
+  let start = if args.switch("resume") {
+    let checkpoint = Checkpoint::load(&checkpoint_path)
+      .map_err(|e| format!("can't read {}: {e}", checkpoint_path.display()))?
+      .ok_or("no checkpoint to resume from")?;
+    if checkpoint.corpus_len != corpus_len {
+      return Err(tr!("corpus-changed", corpus_path));
+    }
+    checkpoint
+  } else {
+    Checkpoint { corpus_len, ..Default::default() }
+  };
+
+  let trie = ctx.load_trie().await?;
+  let rev_dict = ctx.rev_dict(&trie);
+  let quiet = args.switch("check");
+  let encoding = corpus_encoding(args, corpus_path)?;
+  let done = corpus::analyze_file(&rev_dict, corpus_path, encoding, start, every, |checkpoint| {
+    if !quiet {
+      eprintln!("{}", report(checkpoint.lines, &checkpoint.stats));
+    }
+    // only to resume from, nothing to preview
+    match fileman::is_dry_run() {
+      true => Ok(()),
+      false => checkpoint.save(&checkpoint_path),
+    }
+  }).map_err(|e| format!("can't analyze {corpus_path}: {e}"))?;
+  if checkpoint_path.exists() {
+    fs::remove_file(&checkpoint_path).map_err(|e| e.to_string())?;
+  }
+
+  if !quiet {
+    warn_decode_errors(corpus_path, encoding, done.decode_errors);
+  }
+  let stats = &done.stats;
+  let mut summary = Summary::new("corpus");
+  summary.metric("chars", stats.chars as f64);
+  summary.metric("keys_per_char", stats.keys_per_char());
+  summary.metric("auto_commit_rate", stats.auto_commit_rate());
+  summary.metric("coverage", stats.coverage());
+  summary.metric("decode_errors", done.decode_errors as f64);
+  if let Some(max) = max_keys_per_char.filter(|&max| stats.keys_per_char() > max) {
+    summary.violation(format!("{:.3} keys per char, more than {max}", stats.keys_per_char()));
+  }
+
+  if !quiet {
+    println!("{}", report(done.lines, stats));
+  }
+  Ok(finish(args, &summary))
+}
+
+async fn estimate(ctx: &Context, args: &Args, corpus_path: &str, size: SampleSize, max_keys_per_char: Option<f64>) -> Result<ExitCode, String> {
+  let seed = args.parse_value("seed")?.unwrap_or(0);
+  let trie = ctx.load_trie().await?;
+  let rev_dict = ctx.rev_dict(&trie);
+  let encoding = corpus_encoding(args, corpus_path)?;
+  let estimate = sample::estimate(&rev_dict, corpus_path, encoding, size, seed)
+    .map_err(|e| format!("can't analyze {corpus_path}: {e}"))?;
+
+  let quiet = args.switch("check");
+  if !quiet {
+    warn_decode_errors(corpus_path, encoding, estimate.decode_errors);
+  }
+  let mut summary = Summary::new("corpus");
+  summary.metric("sampled_lines", estimate.sampled_lines as f64);
+  summary.metric("keys_per_char", estimate.keys_per_char);
+  summary.metric("keys_per_char_low", estimate.low);
+  summary.metric("keys_per_char_high", estimate.high);
+  summary.metric("coverage", estimate.stats.coverage());
+  if let Some(max) = max_keys_per_char.filter(|&max| estimate.keys_per_char > max) {
+    summary.violation(format!("{:.3} keys per char, more than {max}", estimate.keys_per_char));
+  }
+
+  if !quiet {
+    println!("{}", tr!("corpus-estimate", format!("{:.3}", estimate.keys_per_char), format!("{:.3}", estimate.low),
+      format!("{:.3}", estimate.high), estimate.sampled_lines, estimate.lines));
+  }
+  Ok(finish(args, &summary))
+}
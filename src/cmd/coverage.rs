@@ -0,0 +1,78 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::process::ExitCode;
+use smart_dict::check::Summary;
+use smart_dict::corpus::{self, Alphabet};
+use smart_dict::coverage::Coverage;
+use crate::cli::Args;
+use super::{corpus_encoding, finish, warn_decode_errors, Context};
+
+/// Reports how much of `--corpus` the dict can type, by characters and by tokens, the share typed
+/// as words of several characters, and the `--top` runs of text it can't type. Each `--pack <dict>`
+/// is compared as well, the schema with the pack's entries added, in the same pass over the corpus.
+///
+/// `--min-coverage <ratio>` fails the check when the schema's character coverage is lower.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let corpus_path = args.value("corpus").ok_or("usage: smart-dict coverage --corpus <file> [--pack <dict>]... [--top <n>]")?;
+  let top = args.parse_value("top")?.unwrap_or(20);
+  let min_coverage: Option<f64> = args.parse_value("min-coverage")?;
+
+  let base = ctx.load_trie().await?;
+  let mut tries = vec![(ctx.schema.name.clone(), base.clone())];
+  for pack in args.values("pack") {
+    let mut trie = base.clone();
+    ctx.schema
+      .load_pack(&mut trie, pack)
+      .map_err(|e| format!("can't read {pack}: {e}"))?;
+    let name = Path::new(pack).file_name().map_or(pack.into(), |name| name.to_string_lossy());
+    tries.push((format!("+ {name}"), trie));
+  }
+  drop(base);
+  let rev_dicts: Vec<_> = tries.iter().map(|(_, trie)| ctx.rev_dict(trie)).collect();
+  let alphabets: Vec<_> = rev_dicts.iter().map(Alphabet::new).collect();
+  let mut coverages = vec![Coverage::default(); tries.len()];
+
+  let encoding = corpus_encoding(args, corpus_path)?;
+  let mut decode_errors = 0;
+  File::open(corpus_path)
+    .and_then(|file| corpus::for_each_line(BufReader::new(file), encoding, |line, _, errors| {
+      let line = line.trim_start_matches('\u{FEFF}');
+      for ((coverage, rev_dict), alphabet) in coverages.iter_mut().zip(&rev_dicts).zip(&alphabets) {
+        coverage.add_line(rev_dict, alphabet, line);
+      }
+      decode_errors += errors as u64;
+      Ok(())
+    }))
+    .map_err(|e| format!("can't read {corpus_path}: {e}"))?;
+
+  let schema = &coverages[0];
+  let mut summary = Summary::new("coverage");
+  summary.metric("char_coverage", schema.char_coverage());
+  summary.metric("token_coverage", schema.token_coverage());
+  summary.metric("word_share", schema.word_share());
+  if let Some(min) = min_coverage.filter(|&min| schema.char_coverage() < min) {
+    summary.violation(format!("{:.4} of the characters covered, less than {min}", schema.char_coverage()));
+  }
+  if args.switch("check") {
+    return Ok(finish(args, &summary));
+  }
+
+  warn_decode_errors(corpus_path, encoding, decode_errors);
+  println!("{:<24}{:>10}{:>10}{:>10}", "dict", "chars%", "tokens%", "words%");
+  for ((name, _), coverage) in tries.iter().zip(&coverages) {
+    println!("{name:<24}{:>9.2}%{:>9.2}%{:>9.2}%", coverage.char_coverage() * 100.0, coverage.token_coverage() * 100.0,
+             coverage.word_share() * 100.0);
+  }
+  for ((name, _), coverage) in tries.iter().zip(&coverages) {
+    let uncovered = coverage.top_uncovered(top);
+    if uncovered.is_empty() {
+      continue;
+    }
+    println!("\n{}", tr!("coverage-uncovered", name));
+    for (token, count) in uncovered {
+      println!("{token}\t{count}");
+    }
+  }
+  Ok(ExitCode::SUCCESS)
+}
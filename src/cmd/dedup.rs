@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::process::ExitCode;
+use smart_dict::check::Summary;
+use smart_dict::dedup;
+use crate::cli::Args;
+use crate::fileman;
+use super::{finish, verified, Context};
+
+/// Lists entries whose word already has a shorter code and offers to delete their lines.
+///
+/// `--dry-run` shows the diffs, `--yes` deletes without asking.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  // held from reading the lines to deleting them
+  let _lock = (!args.switch("dry-run") && !args.switch("check")).then(|| ctx.lock()).transpose()?;
+  let trie = ctx.load_trie().await?;
+  let entries = ctx.schema.located_entries().map_err(|e| e.to_string())?;
+  let redundant = dedup::redundant(&trie, &entries);
+
+  let mut summary = Summary::new("dedup");
+  summary.metric("redundant", redundant.len() as f64);
+  if !redundant.is_empty() {
+    summary.violation(format!("{} redundant entries", redundant.len()));
+  }
+  if args.switch("check") {
+    return Ok(finish(args, &summary));
+  }
+
+  for r in &redundant {
+    println!("{}:{}\t{}\t{}\tkept {}", r.source.table, r.source.line + 1, r.entry.word, r.entry.code, r.kept);
+  }
+  println!("{}", tr!("dedup-summary", redundant.len()));
+  if redundant.is_empty() || !(args.switch("dry-run") || args.switch("yes") || confirm(&tr!("dedup-confirm"))?) {
+    return Ok(ExitCode::SUCCESS);
+  }
+
+  let mut lines: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+  for r in &redundant {
+    lines.entry(&r.source.table).or_default().push(r.source.line);
+  }
+  for (table, mut lines) in lines {
+    lines.sort_unstable();
+    lines.dedup();
+    let count = lines.len();
+    let path = ctx.schema.dict_path(table);
+    fileman::remove_lines(&path, lines.into_iter())
+      .map_err(|e| format!("can't edit {}: {e}", path.display()))?;
+    ctx.record_change(args, &path, &format!("dedup: {count} redundant entries removed"))?;
+  }
+  if !fileman::is_dry_run() {
+    println!("{}", tr!("dedup-deleted"));
+  }
+  verified(ctx, args).await
+}
+
+pub(super) fn confirm(question: &str) -> Result<bool, String> {
+  print!("{question} [y/N] ");
+  io::stdout().flush().map_err(|e| e.to_string())?;
+  let mut answer = String::new();
+  io::stdin().read_line(&mut answer).map_err(|e| e.to_string())?;
+  Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
@@ -0,0 +1,39 @@
+use std::process::ExitCode;
+use smart_dict::check::Summary;
+use smart_dict::density;
+use crate::cli::Args;
+use super::{finish, Context};
+
+/// Reports per prefix depth how many keys and candidates may follow, with the `--hot` busiest prefixes.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let hot = args.parse_value("hot")?.unwrap_or(3);
+  let at = args.parse_value("at")?.unwrap_or(2);
+  let max_avg_candidates: Option<f64> = args.parse_value("max-avg-candidates")?;
+
+  let trie = ctx.load_trie().await?;
+  let depths = density::density(&trie, hot);
+
+  let mut summary = Summary::new("density");
+  for depth in &depths {
+    summary.metric(&format!("depth{}.avg_candidates", depth.depth), depth.avg_candidates);
+    summary.metric(&format!("depth{}.avg_continuations", depth.depth), depth.avg_continuations);
+  }
+  if let Some(max) = max_avg_candidates {
+    for depth in depths.iter().filter(|depth| depth.depth == at && depth.avg_candidates > max) {
+      summary.violation(format!("{:.2} candidates per prefix of {at} keys, more than {max}", depth.avg_candidates));
+    }
+  }
+
+  if !args.switch("check") {
+    println!("depth\tprefixes\tavg next\tmax next\tavg cand\tmax cand\thot");
+    for depth in &depths {
+      let hot: Vec<_> = depth.hot
+        .iter()
+        .map(|prefix| format!("{}({}/{})", prefix.code, prefix.continuations, prefix.candidates))
+        .collect();
+      println!("{}\t{}\t{:.2}\t{}\t{:.2}\t{}\t{}", depth.depth, depth.prefixes, depth.avg_continuations,
+               depth.max_continuations, depth.avg_candidates, depth.max_candidates, hot.join(" "));
+    }
+  }
+  Ok(finish(args, &summary))
+}
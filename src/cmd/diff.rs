@@ -0,0 +1,48 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use smart_dict::archive;
+use smart_dict::check::Summary;
+use smart_dict::patch::{Hunk, Patch};
+use crate::cli::Args;
+use crate::fileman;
+use super::{finish, Context};
+
+/// The text of a table, empty if the schema doesn't have it.
+fn table_text(path: &Path) -> Result<String, String> {
+  match archive::read_to_string(path) {
+    Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+    text => text.map_err(|e| format!("can't read {}: {e}", path.display())),
+  }
+}
+
+/// The changes to the entries of the tables from the checkout `--base <dir>` to `--dir`, as a
+/// patch `apply` takes (see `patch`), written to `--out <file>` or stdout.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let base_dir = args.value("base").ok_or("usage: smart-dict diff --base <Rime directory> [--out <file>]")?;
+  let base = Context::open(PathBuf::from(base_dir), args)?;
+  let tables = ctx.schema.tables().map_err(|e| format!("can't read the tables of {}: {e}", ctx.schema.name))?;
+  let base_tables = base.schema.tables().map_err(|e| format!("can't read the tables of {base_dir}: {e}"))?;
+
+  let mut patch = Patch::default();
+  for table in base_tables.iter().filter(|table| !tables.contains(table)).chain(&tables) {
+    let head = table_text(&ctx.schema.dict_path(table))?;
+    let base = table_text(&base.schema.dict_path(table))?;
+    patch.push(Hunk::between(table, &base, &head, &ctx.schema.line_parser(table)));
+  }
+
+  let mut summary = Summary::new("diff");
+  summary.metric("tables", patch.hunks.len() as f64);
+  summary.metric("changes", patch.len() as f64);
+  if args.switch("check") {
+    return Ok(finish(args, &summary));
+  }
+  match args.value("out") {
+    Some(path) => {
+      fileman::write(path, patch.to_string()).map_err(|e| format!("can't write {path}: {e}"))?;
+      println!("wrote {} changes to {} tables to {path}", patch.len(), patch.hunks.len());
+    }
+    None => print!("{patch}"),
+  }
+  Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,155 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use smart_dict::chat::{self, PhraseCounts, Session};
+use smart_dict::corpus::{for_each_line, Alphabet};
+use smart_dict::drill::{Miss, Rng, Sampler, Score};
+use smart_dict::freq::Frequencies;
+use smart_dict::history::sparkline;
+use smart_dict::rev_dict::RevDict;
+use smart_dict::types::Word;
+use crate::cli::Args;
+use crate::fileman;
+use super::{corpus_encoding, warn_decode_errors, Context};
+
+/// Words typed wrong in drills, as `word\ttyped\texpected` lines, for `smart-dict anki`.
+pub fn misses_path(ctx: &Context) -> PathBuf {
+  ctx.state_dir().join("drill-misses.tsv")
+}
+
+fn save_misses(ctx: &Context, misses: &[Miss]) -> io::Result<()> {
+  let path = misses_path(ctx);
+  let mut log = fileman::read_log(&path)?;
+  for miss in misses {
+    log.push_str(&format!("{}\t{}\t{}\n", miss.word, miss.typed, miss.expected));
+  }
+  fileman::write(&path, log)
+}
+
+/// The drills on chat phrases, as `time\twords\tmisses\tchars\tseconds` lines.
+fn sessions_path(ctx: &Context) -> PathBuf {
+  ctx.state_dir().join("chat-drill.tsv")
+}
+
+/// The phrases of the messages by `--me` in the chat log at `path` taking more than `--short`
+/// keys, 3 by default, weighted by how often they're typed, the `--top` 100 most frequent.
+fn chat_phrases(args: &Args, rev_dict: &RevDict, path: &str) -> Result<Vec<(Word, u64)>, String> {
+  let short = args.parse_value("short")?.unwrap_or(3);
+  let min_count = args.parse_value("min-count")?.unwrap_or(2);
+  let top = args.parse_value("top")?.unwrap_or(100);
+  let me = args.value("me");
+  let encoding = corpus_encoding(args, path)?;
+  let file = File::open(path).map_err(|e| format!("can't read {path}: {e}"))?;
+  let alphabet = Alphabet::new(rev_dict);
+  let mut counts = PhraseCounts::new();
+  let mut decode_errors = 0;
+  for_each_line(BufReader::new(file), encoding, |line, _, errors| {
+    decode_errors += errors as u64;
+    if let Some(text) = chat::message(line, me) {
+      counts.add_message(rev_dict, &alphabet, text);
+    }
+    Ok(())
+  }).map_err(|e| format!("can't read {path}: {e}"))?;
+  warn_decode_errors(path, encoding, decode_errors);
+
+  let mut phrases = counts.missing_short_codes(rev_dict, short, min_count);
+  phrases.truncate(top);
+  println!("{}", tr!("drill-chat-phrases", phrases.len(), path, short));
+  for phrase in phrases.iter().take(10) {
+    println!("  {}\t{}\t{}", phrase.word, phrase.count, phrase.cost.code);
+  }
+  Ok(phrases.into_iter().map(|phrase| (phrase.word, phrase.count)).collect())
+}
+
+/// Appends the session to the chat drills and shows how they went so far.
+fn track(ctx: &Context, score: &Score) -> Result<(), String> {
+  let path = sessions_path(ctx);
+  let time = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+  let mut log = fileman::read_log(&path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+  chat::append_session(&mut log, &Session::new(time, score));
+  fileman::write(&path, &log).map_err(|e| format!("can't save the session: {e}"))?;
+  let sessions = chat::sessions(&log);
+  let accuracy: Vec<_> = sessions.iter().map(|session| session.accuracy() * 100.0).collect();
+  let speed: Vec<_> = sessions.iter().map(Session::chars_per_minute).collect();
+  println!("{}", tr!("drill-chat-progress", sessions.len(),
+                     format!("{:.1}% → {:.1}%", accuracy[0], accuracy[accuracy.len() - 1]), sparkline(&accuracy),
+                     format!("{:.1} → {:.1}", speed[0], speed[speed.len() - 1]), sparkline(&speed)));
+  Ok(())
+}
+
+/// A typing drill: shows `--count` words, sampled by `--freq` or among the `--recent` last entries
+/// of each table, and checks the code typed for each through the trie.
+///
+/// With `--chat <exported chat log>`, drills the phrases one types often there without a short
+/// code, see `chat_phrases`, and keeps track of these sessions to show the progress.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let count = args.parse_value("count")?.unwrap_or(20);
+  let seed = args.parse_value("seed")?
+    .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map_or(1, |time| time.as_nanos() as u64));
+  let recent: Option<usize> = args.parse_value("recent")?;
+  let freq = args.value("freq")
+    .map(|path| Frequencies::load(path).map_err(|e| format!("can't read {path}: {e}")))
+    .transpose()?;
+
+  let trie = ctx.load_trie().await?;
+  let rev_dict = ctx.rev_dict(&trie);
+  let chat_log = args.value("chat");
+  let sampler = if let Some(path) = chat_log {
+    Sampler::new(chat_phrases(args, &rev_dict, path)?)
+  } else if let Some(recent) = recent {
+    let entries = ctx.schema.located_entries().map_err(|e| e.to_string())?;
+    let mut last: Vec<_> = vec![];
+    for table in ctx.schema.tables().map_err(|e| e.to_string())? {
+      let table_entries: Vec<_> = entries.iter().filter(|located| located.source.table == table).collect();
+      last.extend(table_entries.iter().rev().take(recent).map(|located| (located.entry.word.clone(), 1)));
+    }
+    Sampler::new(last)
+  } else {
+    Sampler::new(rev_dict.words().map(|word| {
+      let weight = freq.as_ref().map_or(1, |freq| freq.get(word));
+      (word.clone(), weight)
+    }))
+  };
+  if sampler.is_empty() {
+    return Err(tr!("drill-empty"));
+  }
+
+  println!("{}", tr!("drill-intro"));
+  let mut rng = Rng::new(seed);
+  let mut score = Score::default();
+  let mut lines = io::stdin().lock().lines();
+  for _ in 0..count {
+    let word = sampler.sample(&mut rng).unwrap();
+    print!("{word}: ");
+    io::stdout().flush().map_err(|e| e.to_string())?;
+    let start = Instant::now();
+    let Some(typed) = lines.next().transpose().map_err(|e| e.to_string())? else { break };
+    if typed.is_empty() {
+      break;
+    }
+    if trie.eval(&typed) == *word {
+      score.hit(word, start.elapsed());
+    } else {
+      let expected = rev_dict.shortest(word).map(|codes| codes.concat()).unwrap_or_default();
+      println!("  ✗ {expected:?}");
+      score.miss(Miss { word: word.clone(), typed, expected }, start.elapsed());
+    }
+  }
+
+  if score.words == 0 {
+    return Ok(ExitCode::SUCCESS);
+  }
+  println!("{}", tr!("drill-summary", score.words, format!("{:.1}", score.accuracy() * 100.0),
+                     format!("{:.1}", score.chars_per_minute())));
+  for miss in &score.misses {
+    println!("  {}\t{}", miss.word, tr!("drill-miss", format!("{:?}", miss.typed), format!("{:?}", miss.expected)));
+  }
+  let _lock = ctx.lock()?;
+  save_misses(ctx, &score.misses).map_err(|e| format!("can't save the misses: {e}"))?;
+  if chat_log.is_some() {
+    track(ctx, &score)?;
+  }
+  Ok(ExitCode::SUCCESS)
+}
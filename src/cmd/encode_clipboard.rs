@@ -0,0 +1,32 @@
+use std::process::ExitCode;
+use smart_dict::corpus::Analyzer;
+use crate::cli::Args;
+use crate::clipboard;
+use super::Context;
+
+/// Encodes the text on the clipboard with the shortest codes and prints it with the keys of each
+/// word, see `Analyzer::annotate`, or puts it back on the clipboard with `--write-back`, which on
+/// Linux keeps running until something else is copied, see `clipboard::write`.
+/// Needs the `clipboard` feature.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let text = clipboard::read()?;
+  let trie = ctx.load_trie().await?;
+  let rev_dict = ctx.rev_dict(&trie);
+  let mut analyzer = Analyzer::new(&rev_dict);
+  let annotated: Vec<_> = text.lines().map(|line| analyzer.annotate(line)).collect();
+  let annotated = annotated.join("\n");
+
+  let stats = &analyzer.stats;
+  let per_char = if stats.chars == 0 { 0.0 } else { stats.keys_per_char() };
+  if !args.switch("write-back") {
+    println!("{annotated}");
+  }
+  eprintln!("{}", tr!("encode-clipboard-summary", stats.keystrokes, stats.chars, format!("{per_char:.3}"), stats.unencoded));
+  if args.switch("write-back") {
+    if clipboard::WRITE_WAITS {
+      eprintln!("{}", tr!("encode-clipboard-waiting"));
+    }
+    clipboard::write(&annotated)?;
+  }
+  Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,93 @@
+use std::fs;
+use std::io::{self, Read};
+use std::process::ExitCode;
+use smart_dict::preview::{self, Style};
+use smart_dict::trie::{Completion, EvalOptions};
+use crate::cli::Args;
+use super::Context;
+
+/// Simulates typing each positional code, or the whole `--input` file (`-` for stdin);
+/// `--trace` shows every step's candidates and action, `--echo` passes text that isn't keys through,
+/// `--completion-depth` limits completions to codes that many keys longer and `--no-completion` turns them off.
+/// `--backspace <key>` and `--escape <key>` replay corrections, see `edit_key` for the keys.
+/// `--preview` draws what shows after each key instead, see `preview`, with `--layout vertical`,
+/// `--caret <text>` and `--page-size <n>` candidates, as many as the selection keys by default.
+/// A key given as itself, e.g. `<`, or by the name of the control character logs record:
+/// `bs` or `\b` for backspace (U+0008), `esc` or `\e` for escape (U+001B).
+fn edit_key(option: &str, key: &str) -> Result<char, String> {
+  match key {
+    "bs" | "\\b" => Ok('\u{8}'),
+    "esc" | "\\e" => Ok('\u{1b}'),
+    _ => {
+      let mut chars = key.chars();
+      match (chars.next(), chars.next()) {
+        (Some(key), None) => Ok(key),
+        _ => Err(format!("--{option} should be one key, bs, esc, \\b or \\e, not {key:?}")),
+      }
+    }
+  }
+}
+
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let codes = match args.value("input") {
+    Some("-") => {
+      let mut input = String::new();
+      io::stdin().read_to_string(&mut input).map_err(|e| format!("can't read stdin: {e}"))?;
+      vec![input]
+    }
+    Some(path) => vec![fs::read_to_string(path).map_err(|e| format!("can't read {path}: {e}"))?],
+    None => args.positional.clone(),
+  };
+  if codes.is_empty() {
+    return Err("usage: smart-dict eval <code>... | --input <file> [--trace] [--echo] [--completion-depth <n> | --no-completion] [--backspace <key>] [--escape <key>] [--preview [--layout <horizontal|vertical>] [--caret <text>] [--page-size <n>]]".to_string());
+  }
+  let options = EvalOptions {
+    completion: match args.parse_value("completion-depth")? {
+      _ if args.switch("no-completion") => Completion::Off,
+      Some(depth) => Completion::Depth(depth),
+      None => Completion::ALL,
+    },
+    normalization: ctx.schema.normalization,
+    echo: args.switch("echo"),
+    backspace: args.value("backspace").map(|key| edit_key("backspace", key)).transpose()?,
+    escape: args.value("escape").map(|key| edit_key("escape", key)).transpose()?,
+    select_keys: ctx.config.select_keys.clone(),
+  };
+  let trie = ctx.load_trie().await?;
+
+  if args.switch("preview") {
+    let page_size = args.parse_value("page-size")?.unwrap_or(options.select_keys.len());
+    let mut style = Style { layout: args.parse_value("layout")?.unwrap_or_default(), ..Default::default() };
+    if let Some(caret) = args.value("caret") {
+      style.caret = caret.to_string();
+    }
+    for code in &codes {
+      for (idx, frame) in preview::frames(&trie, code, &options, page_size).iter().enumerate() {
+        println!("{}", style.render(idx + 1, frame));
+      }
+    }
+    return Ok(ExitCode::SUCCESS);
+  }
+  for code in &codes {
+    if !args.switch("trace") {
+      println!("{}", trie.eval_with(code, &options));
+      continue;
+    }
+    println!("{code:?}");
+    let mut output = String::new();
+    for step in trie.trace(code, &options) {
+      // nothing is shown before the first key of a code, then as many candidates as can be selected
+      let shown = if step.node.is_root() { 0 } else { options.select_keys.len() };
+      let candidates: Vec<_> = step.node
+        .candidates(options.completion)
+        .take(shown)
+        .map(String::as_str)
+        .collect();
+      output.push_str(&step.output);
+      println!("  {:<8}{:<8}[{}]\t{}\t{}", format!("{:?}", step.keys), step.node.full_code(),
+               candidates.join(" "), step.action, step.output);
+    }
+    println!("  = {output}");
+  }
+  Ok(ExitCode::SUCCESS)
+}
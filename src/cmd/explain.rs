@@ -0,0 +1,31 @@
+use std::process::ExitCode;
+use smart_dict::explain;
+use smart_dict::migrate::{self, Rules};
+use crate::cli::Args;
+use super::Context;
+
+/// Explains the codes of a word, key by key from the codes of its characters, see `explain`,
+/// or the code the rules would give it if it isn't in the dict.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let word = args.positional.first().ok_or("usage: smart-dict explain <word>")?;
+  let located = ctx.schema.located_entries().map_err(|e| e.to_string())?;
+  let entries: Vec<_> = located.iter().map(|located| located.entry.clone()).collect();
+  let codes = migrate::full_codes(&entries);
+  let rules = Rules::learn(&entries, &codes);
+
+  let mut found = false;
+  for located in located.iter().filter(|located| located.entry.word == *word) {
+    found = true;
+    println!("{}:{}", located.source.table, located.source.line + 1);
+    match explain::explain(&located.entry, &rules, &codes) {
+      Some(explanation) => println!("{explanation}"),
+      None => println!("{} {}: not derived from the codes of its characters by the rules\n", word, located.entry.code),
+    }
+  }
+  if !found {
+    let explanation = explain::derive(word, &rules, &codes)
+      .ok_or_else(|| format!("{word} isn't in the dict, and no rule derives its code"))?;
+    println!("{word} isn't in the dict, by the rules it would be\n{explanation}");
+  }
+  Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,83 @@
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+use smart_dict::collision::{self, Provenance};
+use smart_dict::dict;
+use smart_dict::ndjson::{Object, Page};
+use smart_dict::sheet::{self, Sheet};
+use smart_dict::trie::Trie;
+use crate::cli::Args;
+use crate::fileman;
+use super::{analyze_corpus, page, stats, Context};
+
+/// Writes the stats and collisions reports, and the corpus one with `--corpus`,
+/// as the sheets of one `--xlsx` workbook, or with `--dict`, the merged dict as one table sorted by code.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  if let Some(path) = args.value("dict") {
+    return export_dict(ctx, args, path).await;
+  }
+  let path = args.value("xlsx")
+    .ok_or("usage: smart-dict export --xlsx <file> [--corpus <file>] | --dict <file|-> [--ndjson] [--offset <n>] [--limit <n>]")?;
+  let mut sheets: Vec<Sheet> = vec![stats::sheet(&stats::tables(ctx, args).await?)];
+
+  let trie = ctx.load_trie().await?;
+  let (_, whitelist) = ctx.whitelist(args)?;
+  let provenance = Provenance::new(&ctx.schema.located_entries().map_err(|e| e.to_string())?);
+  let collisions: Vec<_> = collision::collisions(&trie)
+    .into_iter()
+    .filter(|collision| !whitelist.allows(collision, &provenance))
+    .collect();
+  sheets.push(sheet::collisions_sheet(&collisions));
+
+  if let Some(corpus_path) = args.value("corpus") {
+    let rev_dict = ctx.rev_dict(&trie);
+    sheets.push(sheet::corpus_sheet(&analyze_corpus(args, &rev_dict, corpus_path)?));
+  }
+
+  sheet::to_xlsx(&sheets)
+    .and_then(|xlsx| fileman::write(path, xlsx))
+    .map_err(|e| format!("can't write {path}: {e}"))?;
+  let names: Vec<_> = sheets.iter().map(|sheet| sheet.name.as_str()).collect();
+  println!("wrote {} to {path}", names.join(", "));
+  Ok(ExitCode::SUCCESS)
+}
+
+/// Streams the entries to the file, or to stdout for `-`, without sorting them all in memory first;
+/// `--ndjson` writes them as lines like `{"code":"wi","word":"我们","weight":10}`, and `--offset`
+/// and `--limit` only a page of them.
+async fn export_dict(ctx: &Context, args: &Args, path: &str) -> Result<ExitCode, String> {
+  let page = page(args)?;
+  let ndjson = args.switch("ndjson");
+  let trie = ctx.load_trie().await?;
+  let written = if path == "-" {
+    write_entries(&trie, page, ndjson, io::stdout().lock())
+  } else {
+    fileman::write_with(path, |file| write_entries(&trie, page, ndjson, file))
+  };
+  let count = written.map_err(|e| format!("can't write {path}: {e}"))?;
+  if path != "-" {
+    println!("wrote {count} entries to {path}");
+  }
+  Ok(ExitCode::SUCCESS)
+}
+
+fn write_entries(trie: &Trie, page: Page, ndjson: bool, writer: impl Write) -> io::Result<usize> {
+  let mut writer = BufWriter::new(writer);
+  let mut count = 0;
+  for (entry, weight) in page.of(trie.sorted_entries()) {
+    if ndjson {
+      Object::new().str("code", &entry.code).str("word", &entry.word).num("weight", weight as f64).write_line(&mut writer)?;
+    } else {
+      writeln!(writer, "{}", dict::format_line(&entry, Some(weight).filter(|&weight| weight > 0), None))?;
+    }
+    count += 1;
+  }
+  writer.flush()?;
+  Ok(count)
+}
+
+pub(super) fn write_dict(trie: &Trie, writer: impl Write) -> io::Result<usize> {
+  let mut writer = BufWriter::new(writer);
+  let count = trie.write_dict(&mut writer)?;
+  writer.flush()?;
+  Ok(count)
+}
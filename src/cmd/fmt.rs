@@ -0,0 +1,48 @@
+use std::fs;
+use std::process::ExitCode;
+use smart_dict::archive;
+use smart_dict::check::Summary;
+use smart_dict::dict::Header;
+use smart_dict::fmt;
+use crate::cli::Args;
+use crate::fileman;
+use super::{finish, Context};
+
+/// Sorts the entries of the tables given, or of all the schema's, by code, keeping the lines
+/// annotated `# pin` in place, see `fmt`.
+///
+/// `--check` fails on unsorted tables, `--dry-run` shows the diffs.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let tables = match args.positional.is_empty() {
+    true => ctx.schema.tables().map_err(|e| e.to_string())?,
+    false => args.positional.clone(),
+  };
+  let _lock = (!args.switch("dry-run") && !args.switch("check")).then(|| ctx.lock()).transpose()?;
+
+  let mut summary = Summary::new("fmt");
+  let mut unsorted = 0;
+  for table in &tables {
+    let path = ctx.schema.dict_path(table);
+    if archive::is_archived(&path) {
+      eprintln!("{} is archived, skipped", path.display());
+      continue;
+    }
+    let text = fs::read_to_string(&path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+    let (header, body) = Header::parse(&text);
+    let sorted = fmt::sort_body(body, ctx.schema.line_parser(table));
+    if sorted == body {
+      continue;
+    }
+    unsorted += 1;
+    summary.violation(format!("{table} isn't sorted"));
+    if args.switch("check") {
+      continue;
+    }
+    fileman::replace(&path, format!("{header}{sorted}")).map_err(|e| format!("can't write {}: {e}", path.display()))?;
+    if !fileman::is_dry_run() {
+      println!("sorted {table}");
+    }
+  }
+  summary.metric("unsorted", unsorted as f64);
+  Ok(finish(args, &summary))
+}
@@ -0,0 +1,45 @@
+use std::process::ExitCode;
+use regex::Regex;
+use smart_dict::filter::{self, Row};
+use smart_dict::history::format_time;
+use crate::cli::Args;
+use super::{blames, entry_filter, Context};
+
+/// Lists the entries whose word or code matches `<regex>` and whose annotation matches `--note <regex>`,
+/// e.g. `smart-dict grep --note 自造` for the words made up by hand, and which pass `--filter <expr>`.
+/// `--blame` adds the date and author of the commit each line comes from.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let pattern = args.positional.first().map(|p| Regex::new(p)).transpose().map_err(|e| e.to_string())?;
+  let note = note_filter(args)?;
+  let expr = entry_filter(args)?;
+  let entries = ctx.schema.located_entries().map_err(|e| e.to_string())?;
+  let candidates = expr.as_ref().map(|_| filter::candidates(&entries)).unwrap_or_default();
+  let blames = blames(ctx, args, expr.as_ref())?;
+  let mut found = 0;
+  for located in entries {
+    let entry = &located.entry;
+    let blame = blames.as_ref().and_then(|blames| blames.get(&located.source.table, located.source.line));
+    if pattern.as_ref().is_some_and(|p| !p.is_match(&entry.word) && !p.is_match(&entry.code))
+      || !note.as_ref().is_none_or(|note| located.annotation.as_deref().is_some_and(|a| note.is_match(a)))
+      || expr.as_ref().is_some_and(|expr| !expr.matches(&Row::of(&located, &candidates).blamed(blame))) {
+      continue;
+    }
+    let blamed = match blame.filter(|_| args.switch("blame")) {
+      Some(blame) if blame.is_uncommitted() => "\tuncommitted".to_string(),
+      Some(blame) => format!("\t{} {}", &format_time(blame.time)[..10], blame.author),
+      None => String::new(),
+    };
+    let annotation = located.annotation.map(|a| format!("\t# {a}")).unwrap_or_default();
+    println!("{}:{}\t{}\t{}{blamed}{annotation}", located.source.table, located.source.line + 1, entry.word, entry.code);
+    found += 1;
+  }
+  Ok(if found > 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+/// `--note <regex>`, matched against entry annotations.
+pub fn note_filter(args: &Args) -> Result<Option<Regex>, String> {
+  args.value("note")
+    .map(Regex::new)
+    .transpose()
+    .map_err(|e| format!("invalid --note: {e}"))
+}
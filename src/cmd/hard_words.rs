@@ -0,0 +1,47 @@
+use std::process::ExitCode;
+use smart_dict::check::Summary;
+use smart_dict::cost;
+use smart_dict::freq::Frequencies;
+use crate::cli::Args;
+use super::{finish, Context};
+
+/// Among the `--pool` most frequent words, lists the `--top` ones costing the most keystrokes per character.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let freq_path = args.value("freq").ok_or("--freq <word\\tcount file> is required")?;
+  let freq = Frequencies::load(freq_path).map_err(|e| format!("can't read {freq_path}: {e}"))?;
+  let top = args.parse_value("top")?.unwrap_or(50);
+  let pool = args.parse_value("pool")?.unwrap_or(5000);
+  let max_per_char: Option<f64> = args.parse_value("max-per-char")?;
+
+  let trie = ctx.load_trie().await?;
+  let rev_dict = ctx.rev_dict(&trie);
+
+  let mut costs: Vec<_> = freq
+    .ranked()
+    .into_iter()
+    .take(pool)
+    .filter_map(|(word, count)| cost::word_cost(&rev_dict, word).map(|cost| (word, count, cost)))
+    .collect();
+  costs.sort_by(|a, b| b.2.per_char()
+    .total_cmp(&a.2.per_char())
+    .then_with(|| b.1.cmp(&a.1)));
+  costs.truncate(top);
+
+  let mut summary = Summary::new("hard-words");
+  if let Some((_, _, worst)) = costs.first() {
+    summary.metric("worst_per_char", worst.per_char());
+  }
+  if let Some(max) = max_per_char {
+    for (word, _, cost) in costs.iter().filter(|(_, _, cost)| cost.per_char() > max) {
+      summary.violation(format!("{word} takes {:.2} keys per character", cost.per_char()));
+    }
+  }
+
+  if !args.switch("check") {
+    println!("word\tfreq\tcode\tindex\tkeys\tper char");
+    for (word, count, cost) in &costs {
+      println!("{word}\t{count}\t{}\t{}\t{}\t{:.2}", cost.code, cost.index, cost.keystrokes(), cost.per_char());
+    }
+  }
+  Ok(finish(args, &summary))
+}
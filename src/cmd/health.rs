@@ -0,0 +1,79 @@
+use std::process::ExitCode;
+use smart_dict::check::Summary;
+use smart_dict::convert::Converter;
+use smart_dict::cost::word_cost;
+use smart_dict::freq::Frequencies;
+use smart_dict::health::{self, Health, Inputs};
+use smart_dict::lint::Severity;
+use crate::cli::Args;
+use crate::fileman;
+use super::{finish, lint, Context};
+
+/// Scores the dict from 0 to 100 with a breakdown, keystrokes weighted by `--freq` if given.
+/// `--json <file|->` and `--badge <file.svg>` write the score for publishing,
+/// and with `--check`, `--min-score` fails below it.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let freq = args.value("freq")
+    .map(|path| Frequencies::load(path).map_err(|e| format!("can't read {path}: {e}")))
+    .transpose()?;
+  let min_score: Option<f64> = args.parse_value("min-score")?;
+
+  let trie = ctx.load_trie().await?;
+  let rev_dict = ctx.rev_dict(&trie);
+  let (_, whitelist) = ctx.whitelist(args)?;
+  let lint_errors = lint::issues(ctx, &trie, &Converter::builtin(), &whitelist)
+    .iter()
+    .filter(|issue| issue.severity == Severity::Error)
+    .count();
+  let collided = trie
+    .walk()
+    .filter(|(node, _)| node.words().len() > 1)
+    .map(|(node, code)| node.words().iter().filter(|word| !whitelist.allows_word(&code, word)).count())
+    .sum();
+
+  let (mut keystrokes, mut chars) = (0.0, 0.0);
+  for word in rev_dict.words() {
+    let weight = freq.as_ref().map_or(1, |freq| freq.get(word)) as f64;
+    if let Some(cost) = word_cost(&rev_dict, word).filter(|_| weight > 0.0) {
+      keystrokes += cost.keystrokes() as f64 * weight;
+      chars += cost.chars as f64 * weight;
+    }
+  }
+
+  let inputs = Inputs {
+    entries: trie.entries().count(),
+    collided,
+    keystrokes_per_char: keystrokes / chars,
+    dead: health::dead_entries(&trie, ctx.config.select_keys.len()),
+    lint_errors,
+  };
+  let health = Health::new(&inputs);
+  let score = health.score();
+
+  let mut summary = Summary::new("health");
+  summary.metric("score", score);
+  for component in &health.components {
+    summary.metric(component.name, component.value);
+  }
+  if let Some(min) = min_score.filter(|&min| score < min) {
+    summary.violation(format!("health score {score:.0}, less than {min}"));
+  }
+
+  if let Some(path) = args.value("json") {
+    if path == "-" {
+      println!("{}", health.to_json());
+    } else {
+      fileman::write(path, health.to_json() + "\n").map_err(|e| format!("can't write {path}: {e}"))?;
+    }
+  }
+  if let Some(path) = args.value("badge") {
+    fileman::write(path, health.badge_svg("dict health")).map_err(|e| format!("can't write {path}: {e}"))?;
+  }
+  if !args.switch("check") && args.value("json") != Some("-") {
+    println!("health {score:.0}/100");
+    for component in &health.components {
+      println!("  {}\t{:.4}\t{:.0}/{:.0}", component.name, component.value, component.score() * component.weight, component.weight);
+    }
+  }
+  Ok(finish(args, &summary))
+}
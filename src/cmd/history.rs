@@ -0,0 +1,40 @@
+use std::process::ExitCode;
+use smart_dict::history::{self, Record};
+use crate::cli::Args;
+use super::Context;
+
+type Metric = fn(&Record) -> Option<f64>;
+
+const METRICS: [(&str, Metric); 4] = [
+  ("entries", |r| Some(r.entries as f64)),
+  ("collisions", |r| Some(r.collisions as f64)),
+  ("code-len", |r| Some(r.code_len)),
+  ("kpc", |r| r.keys_per_char),
+];
+
+/// Prints the recorded metrics with a sparkline of the `--metric` trend (keys per char by default).
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let path = ctx.state_dir().join("history.tsv");
+  let records = history::load(&path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+  if records.is_empty() {
+    println!("nothing recorded yet, see `smart-dict record`");
+    return Ok(ExitCode::SUCCESS);
+  }
+  let name = args.value("metric").unwrap_or("kpc");
+  let (_, metric) = METRICS
+    .iter()
+    .find(|(metric, _)| *metric == name)
+    .ok_or_else(|| format!("unknown metric {name}, expected one of entries, collisions, code-len, kpc"))?;
+
+  println!("{:<18}{:<12}{:>10}{:>12}{:>10}{:>10}", "time", "label", "entries", "collisions", "code len", "kpc");
+  for record in &records {
+    let kpc = record.keys_per_char.map_or("-".to_string(), |kpc| format!("{kpc:.3}"));
+    println!("{:<18}{:<12}{:>10}{:>12}{:>10.3}{kpc:>10}", history::format_time(record.time), record.label,
+             record.entries, record.collisions, record.code_len);
+  }
+  let values: Vec<_> = records.iter().filter_map(metric).collect();
+  if let (Some(first), Some(last)) = (values.first(), values.last()) {
+    println!("{name}: {} {first:.3} -> {last:.3} ({:+.3})", history::sparkline(&values), last - first);
+  }
+  Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::fs;
+use std::process::ExitCode;
+use smart_dict::archive;
+use smart_dict::audit;
+use smart_dict::check::Summary;
+use smart_dict::collision::{self, Collision, Provenance, Whitelist};
+use smart_dict::dedup::Located;
+use smart_dict::patch::Patch;
+use smart_dict::trie::Trie;
+use crate::cli::Args;
+use crate::fileman;
+use super::{analyze_corpus, finish, Context};
+
+/// The collisions of a trie the whitelist doesn't allow.
+fn collisions(trie: &Trie, whitelist: &Whitelist, located: &[Located]) -> Vec<Collision> {
+  let provenance = Provenance::new(located);
+  collision::collisions(trie)
+    .into_iter()
+    .filter(|collision| !whitelist.allows(collision, &provenance))
+    .collect()
+}
+
+/// Shows what applying the patch `--patch <file>` would do, without writing anything: the entries
+/// and the collisions it adds or resolves, and with `--corpus` how the keystrokes to type the
+/// corpus change. New collisions fail the check, as does a rise in keys per char beyond
+/// `--max-regression` if given.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let patch_path = args.value("patch").ok_or("usage: smart-dict impact --patch <file> [--corpus <file>] [--max-regression <keys>]")?;
+  let max_regression: Option<f64> = args.parse_value("max-regression")?;
+  let patch: Patch = fs::read_to_string(patch_path)
+    .map_err(|e| format!("can't read {patch_path}: {e}"))?
+    .parse()
+    .map_err(|e| format!("{patch_path}: {e}"))?;
+
+  let tables = ctx.schema.tables().map_err(|e| e.to_string())?;
+  let mut patched: HashMap<String, String> = HashMap::new();
+  for hunk in &patch.hunks {
+    let path = ctx.schema.dict_path(&hunk.table);
+    if !tables.contains(&hunk.table) || !path.is_file() || archive::is_archived(&path) {
+      return Err(format!("{} isn't a table of {} that can be edited", hunk.table, ctx.schema.name));
+    }
+    let text = match patched.remove(&hunk.table) {
+      Some(text) => text,
+      None => {
+        let text = fileman::read(&path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+        String::from_utf8(text).map_err(|e| format!("can't read {}: {e}", path.display()))?
+      }
+    };
+    let text = hunk.apply(&text, ctx.schema.line_parser(&hunk.table)).map_err(|e| format!("{}: {e}", hunk.table))?;
+    patched.insert(hunk.table.clone(), text);
+  }
+
+  let before = ctx.load_trie().await?;
+  let after = ctx.schema.load_trie_patched(&patched).map_err(|e| e.to_string())?;
+  let (_, whitelist) = ctx.whitelist(args)?;
+  let located_before = ctx.schema.located_entries().map_err(|e| e.to_string())?;
+  let located_after = ctx.schema.located_entries_patched(&patched).map_err(|e| e.to_string())?;
+  let collisions_before = collisions(&before, &whitelist, &located_before);
+  let collisions_after = collisions(&after, &whitelist, &located_after);
+  let delta = audit::delta(&collisions_before, &collisions_after, Collision::key);
+  let (entries_before, entries_after) = (before.entries().count(), after.entries().count());
+
+  let mut summary = Summary::new("impact");
+  summary.metric("entries", entries_after as f64);
+  summary.metric("entries_change", entries_after as f64 - entries_before as f64);
+  summary.metric("collisions", collisions_after.len() as f64);
+  summary.metric("new_collisions", delta.added.len() as f64);
+  summary.metric("resolved_collisions", delta.removed.len() as f64);
+  for collision in &delta.added {
+    summary.violation(format!("new collision on {}: {}", collision.code, collision.words.join(" ")));
+  }
+  let corpus = match args.value("corpus") {
+    Some(path) => Some((analyze_corpus(args, &ctx.rev_dict(&before), path)?, analyze_corpus(args, &ctx.rev_dict(&after), path)?)),
+    None => None,
+  };
+  if let Some((stats_before, stats_after)) = &corpus {
+    let change = stats_after.keys_per_char() - stats_before.keys_per_char();
+    summary.metric("keystrokes_change", stats_after.keystrokes as f64 - stats_before.keystrokes as f64);
+    summary.metric("base_keys_per_char", stats_before.keys_per_char());
+    summary.metric("keys_per_char", stats_after.keys_per_char());
+    summary.metric("keys_per_char_change", change);
+    summary.metric("coverage", stats_after.coverage());
+    if let Some(max) = max_regression.filter(|&max| change > max) {
+      summary.violation(format!("keys per char up by {change:.4}, more than {max}"));
+    }
+  }
+  if args.switch("check") {
+    return Ok(finish(args, &summary));
+  }
+
+  println!("impact of {patch_path}");
+  for hunk in &patch.hunks {
+    println!("  {}\t{} changes", hunk.table, hunk.changes.len());
+  }
+  println!("  entries\t{entries_before} → {entries_after} ({:+})", entries_after as i64 - entries_before as i64);
+  println!("  collisions\t{} → {}: {} new, {} resolved",
+    collisions_before.len(), collisions_after.len(), delta.added.len(), delta.removed.len());
+  for (mark, collisions) in [("+", &delta.added), ("-", &delta.removed)] {
+    for collision in collisions {
+      println!("    {mark} {}\t{}", collision.code, collision.words.join(" "));
+    }
+  }
+  if let Some((stats_before, stats_after)) = &corpus {
+    println!("  corpus\t{} → {} keystrokes ({:+}), {:.4} → {:.4} keys per char, {:.2}% → {:.2}% coverage",
+      stats_before.keystrokes, stats_after.keystrokes, stats_after.keystrokes as i64 - stats_before.keystrokes as i64,
+      stats_before.keys_per_char(), stats_after.keys_per_char(),
+      stats_before.coverage() * 100.0, stats_after.coverage() * 100.0);
+  }
+  Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,37 @@
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+use smart_dict::freq::{Format, Frequencies};
+use crate::cli::Args;
+use crate::fileman;
+
+/// Merges the positional frequency lists, BLCU lists, jieba dictionaries or `word\tcount` files
+/// in any encoding, into one `word\tcount` table, the most frequent words first, written to `--out`
+/// (stdout by default) for the `--freq` options of the other commands. The format of each list
+/// is detected unless given with `--format <tsv|jieba|blcu>`.
+pub async fn run(args: &Args) -> Result<ExitCode, String> {
+  if args.positional.is_empty() {
+    return Err("usage: smart-dict import-freq <list>... [--format <tsv|jieba|blcu>] [--out <file>]".to_string());
+  }
+  let format: Option<Format> = args.parse_value("format")?;
+  let mut freq = Frequencies::default();
+  for path in &args.positional {
+    let list = Frequencies::load_as(path, format).map_err(|e| format!("can't read {path}: {e}"))?;
+    eprintln!("{path}: {} words", list.0.len());
+    for (word, count) in list.0 {
+      *freq.0.entry(word).or_default() += count;
+    }
+  }
+
+  let write = |writer: &mut dyn Write| {
+    let mut writer = BufWriter::new(writer);
+    for (word, count) in freq.ranked() {
+      writeln!(writer, "{word}\t{count}")?;
+    }
+    writer.flush()
+  };
+  match args.value("out") {
+    Some(path) => fileman::write_with(path, write).map_err(|e| format!("can't write {path}: {e}"))?,
+    None => write(&mut io::stdout().lock()).map_err(|e| e.to_string())?,
+  }
+  Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,42 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::process::ExitCode;
+use smart_dict::keylog::{self, Suggestion};
+use crate::cli::Args;
+use super::Context;
+
+/// Reports on a log of real commits (see `keylog` for the format): the first-candidate rate,
+/// the average code length, the `--top` most corrected words and what the dict could do about them.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let path = args.positional.first().ok_or("usage: smart-dict keylog <log> [--top <n>]")?;
+  let top = args.parse_value("top")?.unwrap_or(20);
+  let commits = File::open(path)
+    .and_then(|file| keylog::read_commits(BufReader::new(file)))
+    .map_err(|e| format!("can't read {path}: {e}"))?;
+
+  let report = keylog::report(&commits, &ctx.config.select_keys);
+  println!("{} commits, {:.2}% first candidate, {:.3} keys per code",
+           report.commits, report.first_candidate_rate * 100.0, report.average_code_len);
+  if !report.corrected.is_empty() {
+    println!("\nmost corrected:");
+    for (word, count) in report.corrected.iter().take(top) {
+      println!("  {word}\t{count}");
+    }
+  }
+
+  let trie = ctx.load_trie().await?;
+  let rev_dict = ctx.rev_dict(&trie);
+  let suggestions = keylog::suggest(&rev_dict, &commits);
+  if !suggestions.is_empty() {
+    println!("\nsuggestions:");
+  }
+  for (word, count, suggestion) in suggestions.iter().take(top) {
+    let advice = match suggestion {
+      Suggestion::ShorterCode { typed, shortest } => format!("typed {typed}, {shortest} is shorter"),
+      Suggestion::Promote { code, index } => format!("candidate {} on {code}, raise its weight or give it its own code", index + 1),
+      Suggestion::Missing { typed } => format!("not in the dict, add it, e.g. on {typed}"),
+    };
+    println!("  {word}\t{count}\t{advice}");
+  }
+  Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,31 @@
+use std::process::ExitCode;
+use smart_dict::keyboard;
+use crate::cli::Args;
+use crate::fileman;
+use super::Context;
+
+/// Shows the keys typed for each word with its shortest codes on a keyboard diagram,
+/// or writes the diagram of the first word as an SVG image to `--svg`.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  if args.positional.is_empty() {
+    return Err("usage: smart-dict keys <word>... [--svg <file>]".to_string());
+  }
+  let trie = ctx.load_trie().await?;
+  let rev_dict = ctx.rev_dict(&trie);
+  for word in &args.positional {
+    let code = rev_dict.shortest(word)?.concat();
+    if let Some(path) = args.value("svg") {
+      fileman::write(path, keyboard::render_svg(word, &code)).map_err(|e| format!("can't write {path}: {e}"))?;
+      println!("wrote {word} {code} to {path}");
+      break;
+    }
+    println!("{word} {code}");
+    print!("{}", keyboard::render_text(&code));
+    let off_board = keyboard::off_board(&code);
+    if !off_board.is_empty() {
+      println!("also {}", off_board.iter().collect::<String>());
+    }
+    println!();
+  }
+  Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,50 @@
+use std::process::ExitCode;
+use smart_dict::check::Summary;
+use smart_dict::freq::Frequencies;
+use smart_dict::learning;
+use crate::cli::Args;
+use super::{analyze_corpus, finish, Context};
+
+const LEVELS: &str = "chars,100,1000,10000,all";
+
+/// Types `--corpus` at each of `--levels` of knowledge, see `learning`, the words ranked by `--freq`
+/// or else the dict's weights, to show what learning more words pays off.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let corpus_path = args.value("corpus").ok_or("--corpus is required")?;
+  let levels = learning::parse_levels(args.value("levels").unwrap_or(LEVELS))?;
+  let freq = args.value("freq")
+    .map(|path| Frequencies::load(path).map_err(|e| format!("can't read {path}: {e}")))
+    .transpose()?;
+
+  let trie = ctx.load_trie().await?;
+  let ranked = learning::ranked_words(&trie, freq.as_ref());
+  let mut summary = Summary::new("learning-curve");
+  let quiet = args.switch("check");
+  if !quiet {
+    println!("level\twords\tkeys per char\tcoverage\tsaved per 100 words");
+  }
+  let mut previous: Option<(usize, f64)> = None;
+  for level in levels {
+    let mut rev_dict = ctx.rev_dict(&trie);
+    let known = learning::known_words(&trie, level, &ranked);
+    let words = known.as_ref().map_or_else(|| rev_dict.words().count(), |known| known.len());
+    if let Some(known) = known {
+      rev_dict.restrict(known);
+    }
+    let stats = analyze_corpus(args, &rev_dict, corpus_path)?;
+    let keys_per_char = stats.keys_per_char();
+    summary.metric(&format!("{}.keys_per_char", level.to_string().replace(' ', "")), keys_per_char);
+
+    // keystrokes per char saved by each hundred words learnt since the previous level
+    let payoff = previous
+      .filter(|&(previous_words, _)| words > previous_words)
+      .map_or_else(String::new, |(previous_words, previous_keys)| {
+        format!("{:.4}", (previous_keys - keys_per_char) / (words - previous_words) as f64 * 100.0)
+      });
+    if !quiet {
+      println!("{level}\t{words}\t{keys_per_char:.3}\t{:.2}%\t{payoff}", stats.coverage() * 100.0);
+    }
+    previous = Some((words, keys_per_char));
+  }
+  Ok(finish(args, &summary))
+}
@@ -0,0 +1,98 @@
+use std::collections::HashSet;
+use std::fs;
+use std::process::ExitCode;
+use smart_dict::archive;
+use smart_dict::check::Summary;
+use smart_dict::convert::Converter;
+use smart_dict::collision::Whitelist;
+use smart_dict::lint::{Blank, Charset, Collides, Issue, Linter, MixedScript, Orphan, ReservedShape, Severity};
+use smart_dict::repair;
+use smart_dict::trie::Trie;
+use crate::cli::Args;
+use crate::fileman;
+use super::{finish, grep, Context};
+
+/// Lints all entries, or with `--note <regex>` only those annotated accordingly.
+///
+/// `--fix` first applies the safe repairs of `repair` to the schema's tables, listing each.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  if args.switch("fix") {
+    fix(ctx, args)?;
+  }
+  let trie = ctx.load_trie().await?;
+  let converter = Converter::builtin();
+  let (_, whitelist) = ctx.whitelist(args)?;
+  let annotated = grep::note_filter(args)?
+    .map(|note| -> Result<HashSet<_>, String> {
+      Ok(ctx.schema.located_entries().map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|located| located.annotation.as_deref().is_some_and(|a| note.is_match(a)))
+        .map(|located| located.entry)
+        .collect())
+    })
+    .transpose()?;
+  let mut issues = issues(ctx, &trie, &converter, &whitelist);
+  if let Some(annotated) = &annotated {
+    issues.retain(|issue| annotated.contains(&issue.entry));
+  }
+
+  let errors = issues.iter().filter(|issue| issue.severity == Severity::Error).count();
+  let warnings = issues.len() - errors;
+  let max_warnings: Option<usize> = args.parse_value("max-warnings")?;
+
+  let mut summary = Summary::new("lint");
+  summary.metric("errors", errors as f64);
+  summary.metric("warnings", warnings as f64);
+  if errors > 0 {
+    summary.violation(format!("{errors} lint errors"));
+  }
+  if let Some(max) = max_warnings.filter(|&max| warnings > max) {
+    summary.violation(format!("{warnings} lint warnings, more than {max}"));
+  }
+
+  if !args.switch("check") {
+    for issue in &issues {
+      println!("{issue}");
+    }
+    println!("{}", tr!("lint-summary", errors, warnings));
+  }
+  Ok(finish(args, &summary))
+}
+
+/// The issues all the rules find, also counted by `health`.
+pub(super) fn issues(ctx: &Context, trie: &Trie, converter: &Converter, whitelist: &Whitelist) -> Vec<Issue> {
+  Linter::new()
+    .rule(Blank)
+    .rule(MixedScript { converter })
+    .rule(Collides { whitelist })
+    .rule(Orphan::new(trie))
+    .rule(ReservedShape { conventions: &ctx.config.conventions })
+    .rule(Charset { allowed: ctx.config.word_chars.as_ref() })
+    .lint(trie)
+}
+
+/// Rewrites the tables needing repairs, printing them as `table:line: fix`.
+fn fix(ctx: &Context, args: &Args) -> Result<(), String> {
+  let _lock = (!args.switch("dry-run")).then(|| ctx.lock()).transpose()?;
+  for table in ctx.schema.tables().map_err(|e| e.to_string())? {
+    let path = ctx.schema.dict_path(&table);
+    if archive::is_archived(&path) {
+      continue;
+    }
+    let text = fs::read_to_string(&path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+    let separator = ctx.schema.separator(&table).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+    let (repaired, fixes) = repair::repair(&text, &separator);
+    if fixes.is_empty() {
+      continue;
+    }
+    fileman::replace(&path, repaired).map_err(|e| format!("can't write {}: {e}", path.display()))?;
+    for fix in &fixes {
+      match fix.line() {
+        Some(line) => println!("{table}:{line}: {fix}"),
+        None => println!("{table}: {fix}"),
+      }
+    }
+    ctx.record_change(args, &path, &format!("lint --fix: {} repairs", fixes.len()))?;
+  }
+  Ok(())
+}
@@ -0,0 +1,43 @@
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+use smart_dict::lookup;
+use crate::cli::Args;
+use super::Context;
+
+/// Looks up the words given as arguments or listed in `--file`, one per line (the first column),
+/// printing `word\tbest_code\talternatives\tneeds_select` lines and the words not found.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let mut words = args.positional.clone();
+  if let Some(path) = args.value("file") {
+    let text = fs::read_to_string(path).map_err(|e| format!("can't read {path}: {e}"))?;
+    words.extend(text
+      .lines()
+      .filter_map(|line| line.split('\t').next())
+      .map(str::trim)
+      .filter(|word| !word.is_empty())
+      .map(String::from));
+  }
+  if words.is_empty() {
+    return Err("usage: smart-dict lookup <word>... | --file <words.txt>".to_string());
+  }
+
+  let trie = ctx.load_trie().await?;
+  let rev_dict = ctx.rev_dict(&trie);
+  let codes = lookup::codes_by_word(&trie);
+  let found = lookup::lookup_all(&rev_dict, &codes, &words);
+
+  let mut out = BufWriter::new(io::stdout().lock());
+  let mut missing = vec![];
+  for (word, lookup) in words.iter().zip(&found) {
+    match lookup {
+      Some(lookup) => writeln!(out, "{}", lookup.to_tsv()).map_err(|e| e.to_string())?,
+      None => missing.push(word.as_str()),
+    }
+  }
+  out.flush().map_err(|e| e.to_string())?;
+  if !missing.is_empty() {
+    eprintln!("{} of {} words not found: {}", missing.len(), words.len(), missing.join(" "));
+  }
+  Ok(ExitCode::SUCCESS)
+}
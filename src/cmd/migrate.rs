@@ -0,0 +1,89 @@
+use std::fs;
+use std::process::ExitCode;
+use smart_dict::check::Summary;
+use smart_dict::migrate::{self, Migration, Rules};
+use smart_dict::{archive, dict};
+use crate::cli::Args;
+use crate::fileman;
+use super::dedup::confirm;
+use super::{finish, verified, Context};
+
+/// Recodes the words of the user table, `<schema>.user` or `--user <table>`, made with characters
+/// whose codes changed from the `--old` single-character table to the `--new` one, see `migrate`,
+/// and lists those needing a manual review.
+///
+/// `--dry-run` shows the diffs, `--yes` rewrites without asking.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let (Some(old_path), Some(new_path)) = (args.value("old"), args.value("new")) else {
+    return Err("usage: smart-dict migrate --old <old danzi table> --new <new danzi table> [--user <table>]".to_string());
+  };
+  let read = |path: &str| dict::read_entries(path).map_err(|e| format!("can't read {path}: {e}"));
+  let old = migrate::full_codes(&read(old_path)?);
+  let new = migrate::full_codes(&read(new_path)?);
+  let changed = migrate::changed_chars(&old, &new);
+
+  let user_table = args.value("user").map_or_else(|| format!("{}.user", ctx.schema.name), String::from);
+  let path = ctx.schema.dict_path(&user_table);
+  if !archive::exists(&path) {
+    return Err(format!("no {user_table} table, name the user table with --user <table>"));
+  }
+  let _lock = (!args.switch("dry-run") && !args.switch("check")).then(|| ctx.lock()).transpose()?;
+  let user = dict::read_annotated_entries_with(&path, ctx.schema.line_parser(&user_table))
+    .map_err(|e| format!("can't read {}: {e}", path.display()))?;
+  let words: Vec<_> = ctx.schema.located_entries().map_err(|e| e.to_string())?.into_iter().map(|located| located.entry).collect();
+  let rules = Rules::learn(&words, &old);
+
+  let mut recoded = vec![];
+  let mut reviews = 0;
+  for (line, entry, _) in &user {
+    if entry.word.chars().count() < 2 || !entry.word.chars().any(|ch| changed.contains(&ch)) {
+      continue;
+    }
+    match migrate::migrate(entry, &rules, &old, &new) {
+      Some(Migration::Recode(code)) => {
+        println!("{user_table}:{}\t{}\t{} → {code}", line + 1, entry.word, entry.code);
+        recoded.push((*line, code));
+      }
+      Some(Migration::Review(reason)) => {
+        println!("{user_table}:{}\t{}\t{}\t# review: {reason}", line + 1, entry.word, entry.code);
+        reviews += 1;
+      }
+      None => {}
+    }
+  }
+
+  let mut summary = Summary::new("migrate");
+  summary.metric("changed_chars", changed.len() as f64);
+  summary.metric("recoded", recoded.len() as f64);
+  summary.metric("review", reviews as f64);
+  if !recoded.is_empty() || reviews > 0 {
+    summary.violation(format!("{} words of {user_table} to migrate", recoded.len() + reviews));
+  }
+  if args.switch("check") {
+    return Ok(finish(args, &summary));
+  }
+  println!("{}", tr!("migrate-summary", recoded.len(), reviews));
+  if recoded.is_empty() || !(args.switch("dry-run") || args.switch("yes") || confirm(&tr!("migrate-confirm"))?) {
+    return Ok(ExitCode::SUCCESS);
+  }
+
+  let text = fs::read_to_string(&path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+  let lines: Vec<_> = text.lines().collect();
+  // 按表原有的分隔符改写，空格分隔的表也一样
+  let separator = ctx.schema.separator(&user_table).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+  let count = recoded.len();
+  let mut replaced = vec![];
+  for (line, code) in recoded {
+    let tabbed = separator.to_tabs(lines.get(line).copied().unwrap_or_default());
+    let mut cells: Vec<_> = tabbed.split('\t').collect();
+    let Some(cell) = cells.get_mut(1) else {
+      return Err(format!("{user_table}:{} isn't an entry line, the table changed meanwhile", line + 1));
+    };
+    *cell = &code;
+    replaced.push((line, separator.restyle(&cells.join("\t"))));
+  }
+  fileman::replace_lines(&path, replaced.into_iter())
+    .map_err(|e| format!("can't edit {}: {e}", path.display()))?;
+  ctx.record_change(args, &path, &format!("migrate: {count} words recoded"))?;
+  verified(ctx, args).await
+}
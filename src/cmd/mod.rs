@@ -0,0 +1,408 @@
+mod add_word;
+mod anki;
+mod apply;
+mod assoc;
+mod audit;
+mod auto_commit;
+mod budget;
+mod collisions;
+mod compact;
+mod compare;
+mod contained;
+mod corpus;
+mod coverage;
+mod dedup;
+mod density;
+mod diff;
+mod drill;
+mod encode_clipboard;
+mod eval;
+mod explain;
+mod export;
+mod fmt;
+mod grep;
+mod hard_words;
+mod health;
+mod history;
+mod impact;
+mod import_freq;
+mod keylog;
+mod keys;
+mod learning_curve;
+mod lint;
+mod lookup;
+mod migrate;
+mod normalize_weights;
+mod profile;
+mod promote;
+mod purge;
+mod record;
+mod serve;
+mod sets;
+mod split;
+mod stats;
+mod tree;
+mod trie_info;
+mod upstream;
+mod verify;
+mod whatif;
+mod whitelist;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Once;
+use std::time::{SystemTime, UNIX_EPOCH};
+use smart_dict::blacklist::{Blacklist, BLACKLIST_FILE};
+use smart_dict::blame::Blames;
+use smart_dict::check::Summary;
+use smart_dict::collision::{Whitelist, WHITELIST_FILE};
+use smart_dict::config::{Config, CONFIG_FILE};
+use smart_dict::convert::{Converter, Script};
+use smart_dict::corpus::{analyze_file_with_progress, CorpusStats};
+use smart_dict::dict::{Header, Versioning};
+use smart_dict::encoding::Encoding;
+use smart_dict::filter::Filter;
+use smart_dict::history::format_time;
+use smart_dict::ndjson::Page;
+use smart_dict::hint::Chunker;
+use smart_dict::rev_dict::RevDict;
+use smart_dict::schema::Schema;
+use smart_dict::trie::Trie;
+use crate::bar::bar;
+use crate::cli::Args;
+use crate::fileman;
+use crate::help;
+
+pub const COMMANDS: &[&str] = &["stats", "budget", "lint", "collisions", "hard-words", "compare", "dedup", "corpus", "density", "eval", "assoc", "whitelist", "record", "history", "drill", "anki", "split", "export", "grep", "verify", "keys", "lookup", "profile", "keylog", "compact", "purge", "promote", "whatif", "add-word", "fmt", "trie-info", "health", "upstream", "learning-curve", "tree", "migrate", "explain", "serve", "audit", "contained", "sets", "diff", "apply", "encode-clipboard", "normalize-weights", "auto-commit", "import-freq", "coverage", "impact", "help", "completions"];
+
+/// Exit code of `--check` runs whose thresholds are violated.
+pub const CHECK_FAILED: u8 = 1;
+
+const SCHEMA: &str = "xkjd6";
+
+pub struct Context {
+  pub schema: Schema,
+  pub config: Config,
+  /// `--chunks <word list>`, the chunks sentences are encoded within, see `hint`.
+  pub chunker: Option<Chunker>,
+  /// `--convert t2s|s2t`, the script corpora are converted to before they're encoded.
+  pub conversion: Option<(Converter, Script)>,
+  /// `--verbose` shows the tables in the order they're loaded.
+  verbose: bool,
+  /// Whether the tables were checked already, once per run, see `check_tables`.
+  tables_checked: Once,
+}
+
+impl Context {
+  fn new(args: &Args) -> Result<Self, String> {
+    let dir = args.value("dir")
+      .map(PathBuf::from)
+      // `smart-dict <dir>` is the legacy form of `smart-dict stats --dir <dir>`
+      .or_else(|| args.command.is_none().then(|| args.positional.first().map(PathBuf::from)).flatten())
+      .map_or_else(get_custom_dir, Ok)?;
+    Self::open(dir, args)
+  }
+
+  /// The context of the Rime directory `dir`, with its own config and blacklist unless given.
+  fn open(dir: PathBuf, args: &Args) -> Result<Self, String> {
+    let config_path = args.value("config")
+      .map_or_else(|| dir.join(CONFIG_FILE), PathBuf::from);
+    let config = Config::load(&config_path)
+      .map_err(|e| format!("can't read {}: {e}", config_path.display()))?;
+    let mut schema = Schema::new(dir, args.value("schema").unwrap_or(SCHEMA));
+    schema.extra_tables = config.extra_tables.clone();
+    schema.secondary_tables = config.secondary_tables.clone();
+    schema.normalization = config.normalization;
+    schema.duplicates = config.duplicates;
+    schema.separators = config.separators.clone();
+    schema.rescaling = config.weights;
+    let blacklist_path = args.value("blacklist")
+      .map_or_else(|| schema.dir.join(BLACKLIST_FILE), PathBuf::from);
+    schema.blacklist = Blacklist::load(&blacklist_path)
+      .map_err(|e| format!("can't read {}: {e}", blacklist_path.display()))?;
+    let crossing_penalty: Option<usize> = args.parse_value("chunk-penalty")?;
+    let chunker = args.value("chunks")
+      .map(|path| Chunker::load(path).map_err(|e| format!("can't read {path}: {e}")))
+      .transpose()?
+      .map(|chunker| match crossing_penalty {
+        Some(keys) => chunker.with_crossing_penalty(keys),
+        None => chunker,
+      });
+    let conversion = conversion(args)?;
+    Ok(Self { schema, config, chunker, conversion, verbose: args.switch("verbose"), tables_checked: Once::new() })
+  }
+
+  /// Where snapshots and other files owned by this tool are kept.
+  pub fn state_dir(&self) -> PathBuf {
+    self.schema.dir.join(".smart-dict")
+  }
+
+  /// Locks the state directory for commands writing dicts or state files, so that two instances
+  /// don't corrupt them; the lock is released when the returned file is dropped.
+  pub fn lock(&self) -> Result<fs::File, String> {
+    let dir = self.state_dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("can't create {}: {e}", dir.display()))?;
+    let path = dir.join("lock");
+    fileman::try_lock(&path)
+      .map_err(|e| format!("can't lock {}: {e}", path.display()))?
+      .ok_or_else(|| format!("another instance of smart-dict is running on {}, try again when it's done", self.schema.dir.display()))
+  }
+
+  /// Loads the schema's trie, with a progress bar.
+  pub async fn load_trie(&self) -> Result<Box<Trie>, String> {
+    self.check_tables();
+    self.schema.load_trie_async_with_progress(bar()).await.map_err(|e| e.to_string())
+  }
+
+  /// Warns about the tables listed twice or importing each other, and shows the load order with
+  /// `--verbose`, the first time it's called. Tables which can't be read are left for loading to report.
+  fn check_tables(&self) {
+    self.tables_checked.call_once(|| {
+      if let Ok(issues) = self.schema.import_issues() {
+        for issue in issues {
+          eprintln!("{}", tr!("tables-issue", issue));
+        }
+      }
+      if self.verbose {
+        if let Ok(tables) = self.schema.tables() {
+          eprintln!("{}", tr!("tables-order", tables.join(" → ")));
+        }
+      }
+    });
+  }
+
+  /// The reverse lookup of the trie, encoding sentences with the configured selection keys.
+  pub fn rev_dict<'a>(&'a self, trie: &'a Trie) -> RevDict<'a> {
+    let mut rev_dict = trie.rev_dict();
+    rev_dict.set_select_keys(self.config.select_keys.clone());
+    if let Some(chunker) = &self.chunker {
+      rev_dict.set_hint(chunker);
+    }
+    if let Some((converter, script)) = &self.conversion {
+      rev_dict.convert_input(converter, *script);
+    }
+    rev_dict
+  }
+
+  /// The whitelist of intentional collisions, `--whitelist` or the one in the Rime directory.
+  pub fn whitelist(&self, args: &Args) -> Result<(PathBuf, Whitelist), String> {
+    let path = args.value("whitelist")
+      .map_or_else(|| self.schema.dir.join(WHITELIST_FILE), PathBuf::from);
+    let whitelist = Whitelist::load(&path)
+      .map_err(|e| format!("can't read {}: {e}", path.display()))?;
+    Ok((path, whitelist))
+  }
+
+  /// After a command changed a dict, bumps its version and logs the change in its header
+  /// if `--bump` or the config's `versioning` asks for it. Plain tables have no header to keep it.
+  pub fn record_change(&self, args: &Args, path: &Path, change: &str) -> Result<(), String> {
+    if fileman::is_dry_run() {
+      return Ok(());
+    }
+    let versioning: Option<Versioning> = args.parse_value("bump")?;
+    let Some(versioning) = versioning.or(self.config.versioning) else { return Ok(()) };
+    let text = fs::read_to_string(path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+    let (mut header, body) = Header::parse(&text);
+    if header.is_empty() {
+      return Ok(());
+    }
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+    let today = format_time(now)[..10].replace('-', ".");
+    let version = header.bump_version(versioning, &today).unwrap_or_else(|| {
+      eprintln!("{}: the version doesn't follow {versioning:?}, not bumped", path.display());
+      header.get("version").unwrap_or_default()
+    });
+    header.log(format!("{version} {change}").trim_start());
+    fileman::replace(path, format!("{header}{body}")).map_err(|e| format!("can't write {}: {e}", path.display()))
+  }
+}
+
+/// After a command wrote dicts: with `--verify`, whether Rime still builds the schema.
+async fn verified(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  if args.switch("verify") && !fileman::is_dry_run() && !verify::verify(ctx).await? {
+    return Ok(ExitCode::from(CHECK_FAILED));
+  }
+  Ok(ExitCode::SUCCESS)
+}
+
+pub async fn run(args: Args) -> Result<ExitCode, String> {
+  // nothing is written, the commands print diffs of the dicts they'd change instead
+  fileman::set_dry_run(args.switch("dry-run"));
+  if args.command.as_deref() == Some("help") || args.switch("help") {
+    return help(&args);
+  }
+  if args.command.as_deref() == Some("completions") {
+    let shell = args.positional.first().map(String::as_str).unwrap_or_default();
+    let script = help::completions(shell).ok_or_else(|| tr!("completions-usage", help::SHELLS.join("|")))?;
+    print!("{script}");
+    return Ok(ExitCode::SUCCESS);
+  }
+  // compares two checkouts rather than working in a Rime directory
+  if args.command.as_deref() == Some("audit") {
+    return audit::run(&args).await;
+  }
+  // converts frequency lists, no schema involved
+  if args.command.as_deref() == Some("import-freq") {
+    return import_freq::run(&args).await;
+  }
+  let ctx = Context::new(&args)?;
+  match args.command.as_deref().unwrap_or("stats") {
+    "stats" => stats::run(&ctx, &args).await,
+    "budget" => budget::run(&ctx, &args).await,
+    "lint" => lint::run(&ctx, &args).await,
+    "collisions" => collisions::run(&ctx, &args).await,
+    "hard-words" => hard_words::run(&ctx, &args).await,
+    "compare" => compare::run(&ctx, &args).await,
+    "dedup" => dedup::run(&ctx, &args).await,
+    "corpus" => corpus::run(&ctx, &args).await,
+    "density" => density::run(&ctx, &args).await,
+    "eval" => eval::run(&ctx, &args).await,
+    "assoc" => assoc::run(&ctx, &args).await,
+    "whitelist" => whitelist::run(&ctx, &args).await,
+    "record" => record::run(&ctx, &args).await,
+    "history" => history::run(&ctx, &args).await,
+    "drill" => drill::run(&ctx, &args).await,
+    "anki" => anki::run(&ctx, &args).await,
+    "split" => split::run(&ctx, &args).await,
+    "export" => export::run(&ctx, &args).await,
+    "grep" => grep::run(&ctx, &args).await,
+    "verify" => verify::run(&ctx, &args).await,
+    "keys" => keys::run(&ctx, &args).await,
+    "lookup" => lookup::run(&ctx, &args).await,
+    "profile" => profile::run(&ctx, &args).await,
+    "keylog" => keylog::run(&ctx, &args).await,
+    "compact" => compact::run(&ctx, &args).await,
+    "purge" => purge::run(&ctx, &args).await,
+    "promote" => promote::run(&ctx, &args).await,
+    "whatif" => whatif::run(&ctx, &args).await,
+    "add-word" => add_word::run(&ctx, &args).await,
+    "fmt" => fmt::run(&ctx, &args).await,
+    "trie-info" => trie_info::run(&ctx, &args).await,
+    "health" => health::run(&ctx, &args).await,
+    "upstream" => upstream::run(&ctx, &args).await,
+    "learning-curve" => learning_curve::run(&ctx, &args).await,
+    "tree" => tree::run(&ctx, &args).await,
+    "migrate" => migrate::run(&ctx, &args).await,
+    "explain" => explain::run(&ctx, &args).await,
+    "serve" => serve::run(&ctx, &args).await,
+    "contained" => contained::run(&ctx, &args).await,
+    "sets" => sets::run(&ctx, &args).await,
+    "diff" => diff::run(&ctx, &args).await,
+    "apply" => apply::run(&ctx, &args).await,
+    "encode-clipboard" => encode_clipboard::run(&ctx, &args).await,
+    "normalize-weights" => normalize_weights::run(&ctx, &args).await,
+    "auto-commit" => auto_commit::run(&ctx, &args).await,
+    "coverage" => coverage::run(&ctx, &args).await,
+    "impact" => impact::run(&ctx, &args).await,
+    command => Err(tr!("unknown-command", command)),
+  }
+}
+
+/// `help [<command>]`, or `<command> --help`.
+fn help(args: &Args) -> Result<ExitCode, String> {
+  let name = match args.command.as_deref() {
+    Some("help") | None => args.positional.first().map(String::as_str),
+    command => command,
+  };
+  match name {
+    Some(name) => println!("{}", help::command_help(help::find(name).ok_or_else(|| tr!("help-unknown", name))?)),
+    None => println!("{}", help::overview()),
+  }
+  Ok(ExitCode::SUCCESS)
+}
+
+/// The encoding of a corpus, `--encoding` or else detected from its start.
+fn corpus_encoding(args: &Args, path: &str) -> Result<Encoding, String> {
+  match args.parse_value("encoding")? {
+    Some(encoding) => Ok(encoding),
+    None => Encoding::detect_file(path).map_err(|e| format!("can't read {path}: {e}")),
+  }
+}
+
+/// Warns about the bytes of a corpus which couldn't be decoded rather than failing on them.
+fn warn_decode_errors(path: &str, encoding: Encoding, errors: u64) {
+  if errors > 0 {
+    eprintln!("{}", tr!("corpus-decode-errors", errors, path, encoding));
+  }
+}
+
+/// Analyzes a whole corpus, see `corpus_encoding`.
+fn analyze_corpus(args: &Args, rev_dict: &RevDict, path: &str) -> Result<CorpusStats, String> {
+  let encoding = corpus_encoding(args, path)?;
+  rev_dict.build_with_progress(bar());
+  let done = analyze_file_with_progress(rev_dict, path, encoding, bar())
+    .map_err(|e| format!("can't read {path}: {e}"))?;
+  warn_decode_errors(path, encoding, done.decode_errors);
+  Ok(done.stats)
+}
+
+/// `--filter <expr>` of the reports, see `filter`.
+fn entry_filter(args: &Args) -> Result<Option<Filter>, String> {
+  args.value("filter")
+    .map(|expr| expr.parse().map_err(|e| format!("invalid --filter: {e}")))
+    .transpose()
+}
+
+/// `--convert t2s|s2t` with the builtin table, or with the OpenCC dictionaries `--opencc <file>`
+/// such as `TSCharacters.txt` and `TSPhrases.txt`, keyed in the script converted from.
+fn conversion(args: &Args) -> Result<Option<(Converter, Script)>, String> {
+  let Some(direction) = args.value("convert") else {
+    return match args.value("opencc") {
+      Some(_) => Err("--opencc needs --convert t2s|s2t".to_string()),
+      None => Ok(None),
+    };
+  };
+  let (from, to) = match direction {
+    "t2s" => (Script::Traditional, Script::Simplified),
+    "s2t" => (Script::Simplified, Script::Traditional),
+    _ => return Err(format!("invalid --convert {direction}, expected t2s or s2t")),
+  };
+  let mut paths = args.values("opencc").peekable();
+  let mut converter = if paths.peek().is_some() { Converter::new() } else { Converter::builtin() };
+  for path in paths {
+    converter.load_opencc(path, from).map_err(|e| format!("can't read {path}: {e}"))?;
+  }
+  Ok(Some((converter, to)))
+}
+
+/// The rows of a report between `--offset` and `--offset` + `--limit`.
+fn page(args: &Args) -> Result<Page, String> {
+  Ok(Page { offset: args.parse_value("offset")?.unwrap_or(0), limit: args.parse_value("limit")? })
+}
+
+/// The commits of the entry lines with `--blame`, or when the filter looks at them.
+fn blames(ctx: &Context, args: &Args, filter: Option<&Filter>) -> Result<Option<Blames>, String> {
+  if !args.switch("blame") && !filter.is_some_and(|filter| filter.uses("age") || filter.uses("author")) {
+    return Ok(None);
+  }
+  let blames = Blames::of_schema(&ctx.schema).map_err(|e| format!("can't run git blame: {e}"))?;
+  if blames.is_empty() {
+    eprintln!("no table of {} is tracked by git, the entries have no age nor author", ctx.schema.dir.display());
+  }
+  Ok(Some(blames))
+}
+
+/// In `--check` mode, prints the summary as JSON and fails if any threshold is violated.
+fn finish(args: &Args, summary: &Summary) -> ExitCode {
+  if !args.switch("check") {
+    return ExitCode::SUCCESS;
+  }
+  println!("{}", summary.to_json());
+  if summary.passed() {
+    ExitCode::SUCCESS
+  } else {
+    ExitCode::from(CHECK_FAILED)
+  }
+}
+
+fn get_custom_dir() -> Result<PathBuf, String> {
+  use std::{
+    env,
+    path::Path,
+  };
+
+  let appdata = env::var("APPDATA")
+    .map_err(|_| tr!("no-appdata"))?;
+  Ok(Path::new(&appdata).join("Rime"))
+}
@@ -0,0 +1,67 @@
+use std::process::ExitCode;
+use smart_dict::archive;
+use smart_dict::check::Summary;
+use smart_dict::weights::{self, Rescaling};
+use crate::cli::Args;
+use crate::fileman;
+use super::{finish, verified, Context};
+
+/// Rewrites the weights of the tables given, or of all the schema's, on one scale, see `weights`:
+/// `--method linear|rank[:<max>]`, else the config's `weights`. Entries without a weight keep
+/// having none.
+///
+/// `--check` fails on tables off the scale, `--dry-run` shows the diffs.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let rescaling: Rescaling = args.parse_value("method")?
+    .or(ctx.config.weights)
+    .ok_or("usage: smart-dict normalize-weights [<table>...] --method <linear|rank>[:<max>], or weights in the config")?;
+  let tables = match args.positional.is_empty() {
+    true => ctx.schema.tables().map_err(|e| e.to_string())?,
+    false => args.positional.clone(),
+  };
+  let _lock = (!args.switch("dry-run") && !args.switch("check")).then(|| ctx.lock()).transpose()?;
+
+  let mut summary = Summary::new("normalize-weights");
+  let mut changed = 0;
+  for table in &tables {
+    let path = ctx.schema.dict_path(table);
+    if archive::is_archived(&path) {
+      eprintln!("{} is archived, skipped", path.display());
+      continue;
+    }
+    let text = fileman::read(&path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+    let text = String::from_utf8(text).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+    let mut parser = ctx.schema.line_parser(table);
+    let weighted: Vec<_> = text
+      .lines()
+      .enumerate()
+      .filter_map(|(idx, line)| parser.parse_weighted(line).map(|(_, weight)| (idx, line, weight)))
+      .collect();
+    let rescaled = rescaling.apply(&weighted.iter().map(|(_, _, weight)| *weight).collect::<Vec<_>>());
+    let lines: Vec<_> = weighted
+      .iter()
+      .zip(rescaled)
+      .filter(|((_, _, old), new)| old != new)
+      .filter_map(|(&(idx, line, old), new)| Some((idx, weights::with_weight(line, old, new)?)))
+      .collect();
+    if lines.is_empty() {
+      continue;
+    }
+    changed += 1;
+    summary.violation(format!("{table}: {} weights off the scale", lines.len()));
+    if args.switch("check") {
+      continue;
+    }
+    let count = lines.len();
+    fileman::replace_lines(&path, lines.into_iter()).map_err(|e| format!("can't write {}: {e}", path.display()))?;
+    ctx.record_change(args, &path, &format!("normalize-weights: {count} weights"))?;
+    if !fileman::is_dry_run() {
+      println!("{table}\t{count} weights rescaled");
+    }
+  }
+  summary.metric("tables_changed", changed as f64);
+  if args.switch("check") {
+    return Ok(finish(args, &summary));
+  }
+  verified(ctx, args).await
+}
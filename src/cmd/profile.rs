@@ -0,0 +1,38 @@
+use std::process::ExitCode;
+use std::time::Instant;
+use smart_dict::trie::Trie;
+use crate::cli::Args;
+use super::Context;
+
+/// Loads the tables one by one, reporting what each adds: entries, trie nodes, approximate heap
+/// bytes (see `Trie::footprint`) and the time taken to read and insert them.
+pub async fn run(ctx: &Context, _args: &Args) -> Result<ExitCode, String> {
+  let mut trie = Box::new(Trie::new());
+  let mut before = trie.footprint();
+  let mut entries_before = 0;
+  println!("{:<24}{:>10}{:>10}{:>12}{:>10}", "table", "entries", "nodes", "heap", "time");
+  for table in ctx.schema.tables().map_err(|e| e.to_string())? {
+    let path = ctx.schema.dict_path(&table);
+    let start = Instant::now();
+    ctx.schema.load_table(&mut trie, &table)
+      .map_err(|e| format!("can't load {}: {e}", path.display()))?;
+    let elapsed = start.elapsed();
+    let after = trie.footprint();
+    let entries = trie.walk().map(|(node, _)| node.words().len()).sum::<usize>();
+    println!("{table:<24}{:>10}{:>10}{:>12}{:>8.1}ms",
+             entries - entries_before, after.nodes - before.nodes,
+             format_bytes(after.heap_bytes.saturating_sub(before.heap_bytes)), elapsed.as_secs_f64() * 1000.0);
+    before = after;
+    entries_before = entries;
+  }
+  println!("{:<24}{entries_before:>10}{:>10}{:>12}", "total", before.nodes, format_bytes(before.heap_bytes));
+  Ok(ExitCode::SUCCESS)
+}
+
+pub fn format_bytes(bytes: usize) -> String {
+  match bytes {
+    0..1024 => format!("{bytes} B"),
+    1024..1048576 => format!("{:.1} KiB", bytes as f64 / 1024.0),
+    _ => format!("{:.1} MiB", bytes as f64 / 1048576.0),
+  }
+}
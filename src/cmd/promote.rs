@@ -0,0 +1,43 @@
+use std::process::ExitCode;
+use smart_dict::dict;
+use smart_dict::freq::Frequencies;
+use smart_dict::promote;
+use smart_dict::trie::Entry;
+use crate::cli::Args;
+use crate::fileman;
+use super::Context;
+
+/// Plans short codes for the `--pool` most frequent words of `--freq`, taking free prefixes of
+/// `--lengths` keys (`2-3` by default) of their codes, and prints the entries to add, or writes
+/// them to `--out`, with the savings on stderr.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let freq_path = args.value("freq").ok_or("--freq <word\\tcount file> is required")?;
+  let freq = Frequencies::load(freq_path).map_err(|e| format!("can't read {freq_path}: {e}"))?;
+  let pool = args.parse_value("pool")?.unwrap_or(5000);
+  let lengths = match args.value("lengths").unwrap_or("2-3").split_once('-') {
+    Some((min, max)) => min.parse().ok().zip(max.parse().ok()),
+    None => args.parse_value("lengths")?.map(|len| (len, len)),
+  };
+  let (min, max) = lengths.filter(|(min, max)| 0 < *min && min <= max)
+    .ok_or("--lengths should be a number of keys or a range like 2-3")?;
+
+  let trie = ctx.load_trie().await?;
+  let rev_dict = ctx.rev_dict(&trie);
+  let plan = promote::plan(&trie, &rev_dict, &freq, min..=max, pool);
+
+  let patch: String = plan
+    .iter()
+    .map(|promotion| {
+      let entry = Entry { word: promotion.word.clone(), code: promotion.code.clone() };
+      let note = format!("was {}, saves {}", promotion.from, promotion.saved);
+      dict::format_line(&entry, None, Some(&note)) + "\n"
+    })
+    .collect();
+  match args.value("out") {
+    Some(path) => fileman::write(path, patch).map_err(|e| format!("can't write {path}: {e}"))?,
+    None => print!("{patch}"),
+  }
+  let saved: u64 = plan.iter().map(|promotion| promotion.saved).sum();
+  eprintln!("{} short codes saving {saved} keystrokes over {freq_path}", plan.len());
+  Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,54 @@
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+use smart_dict::check::Summary;
+use crate::cli::Args;
+use crate::fileman;
+use super::dedup::confirm;
+use super::{finish, verified, Context};
+
+/// Lists the entries of the tables whose word is blacklisted, see `blacklist`, and offers to delete
+/// their lines. `--blacklist <file>` reads another list than the one in the Rime directory.
+///
+/// `--dry-run` shows the diffs, `--yes` deletes without asking.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  if ctx.schema.blacklist.is_empty() {
+    return Err("the blacklist is empty, list words or /regexes/ in smart-dict.blacklist or --blacklist <file>".to_string());
+  }
+  // held from reading the lines to deleting them
+  let _lock = (!args.switch("dry-run") && !args.switch("check")).then(|| ctx.lock()).transpose()?;
+  let entries = ctx.schema.located_entries().map_err(|e| e.to_string())?;
+  let blacklisted: Vec<_> = entries.iter().filter(|located| ctx.schema.blacklist.contains(&located.entry.word)).collect();
+
+  let mut summary = Summary::new("purge");
+  summary.metric("blacklisted", blacklisted.len() as f64);
+  if !blacklisted.is_empty() {
+    summary.violation(format!("{} blacklisted entries", blacklisted.len()));
+  }
+  if args.switch("check") {
+    return Ok(finish(args, &summary));
+  }
+
+  for located in &blacklisted {
+    println!("{}:{}\t{}\t{}", located.source.table, located.source.line + 1, located.entry.word, located.entry.code);
+  }
+  println!("{}", tr!("purge-summary", blacklisted.len()));
+  if blacklisted.is_empty() || !(args.switch("dry-run") || args.switch("yes") || confirm(&tr!("dedup-confirm"))?) {
+    return Ok(ExitCode::SUCCESS);
+  }
+
+  let mut lines: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+  for located in &blacklisted {
+    lines.entry(&located.source.table).or_default().push(located.source.line);
+  }
+  for (table, lines) in lines {
+    let count = lines.len();
+    let path = ctx.schema.dict_path(table);
+    fileman::remove_lines(&path, lines.into_iter())
+      .map_err(|e| format!("can't edit {}: {e}", path.display()))?;
+    ctx.record_change(args, &path, &format!("purge: {count} blacklisted entries removed"))?;
+  }
+  if !fileman::is_dry_run() {
+    println!("{}", tr!("dedup-deleted"));
+  }
+  verified(ctx, args).await
+}
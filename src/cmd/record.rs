@@ -0,0 +1,48 @@
+use std::process::{Command, ExitCode};
+use std::time::{SystemTime, UNIX_EPOCH};
+use smart_dict::collision;
+use smart_dict::freq::Frequencies;
+use smart_dict::history::{self, Record};
+use crate::cli::Args;
+use crate::fileman;
+use super::{analyze_corpus, Context};
+
+/// Appends the current aggregate metrics to the history, labelled with `--label`
+/// or the short commit hash when the Rime directory is a git repository.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let freq = args.value("freq")
+    .map(|path| Frequencies::load(path).map_err(|e| format!("can't read {path}: {e}")))
+    .transpose()?;
+  let trie = ctx.load_trie().await?;
+  let rev_dict = ctx.rev_dict(&trie);
+  let keys_per_char = args.value("corpus")
+    .map(|path| analyze_corpus(args, &rev_dict, path).map(|stats| stats.keys_per_char()))
+    .transpose()?;
+
+  let record = Record {
+    time: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs()),
+    label: args.value("label").map_or_else(|| git_label(ctx), String::from),
+    entries: trie.entries().count(),
+    collisions: collision::collisions(&trie).len(),
+    code_len: history::code_len(&rev_dict, freq.as_ref()),
+    keys_per_char,
+  };
+  let path = ctx.state_dir().join("history.tsv");
+  let _lock = ctx.lock()?;
+  let mut text = fileman::read_log(&path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+  history::append(&mut text, &record);
+  fileman::write(&path, text).map_err(|e| format!("can't write {}: {e}", path.display()))?;
+  println!("recorded {} at {}", record.label, history::format_time(record.time));
+  Ok(ExitCode::SUCCESS)
+}
+
+fn git_label(ctx: &Context) -> String {
+  Command::new("git")
+    .args(["rev-parse", "--short", "HEAD"])
+    .current_dir(&ctx.schema.dir)
+    .output()
+    .ok()
+    .filter(|output| output.status.success())
+    .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    .unwrap_or_else(|| "-".to_string())
+}
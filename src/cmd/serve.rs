@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use smart_dict::cache::{CacheStats, Lru};
+use smart_dict::lookup::{self, Lookup};
+use smart_dict::normalize::Normalization;
+use smart_dict::reload::Stamps;
+use smart_dict::snapshot::{Current, TrieSnapshot};
+use smart_dict::trie::{Completion, Trie};
+use smart_dict::types::{Code, Word};
+use crate::cli::Args;
+use super::Context;
+
+const DEFAULT_ADDR: &str = "127.0.0.1:7878";
+const DEFAULT_CACHE: usize = 4096;
+/// Codes each connection keeps for itself, in front of the shared cache.
+const LOCAL_CACHE: usize = 64;
+
+/// The lookups of every word of the dicts, answered from memory, and the trie they came from,
+/// frozen, with the candidates of the codes asked lately.
+struct Index {
+  lookups: HashMap<Word, Lookup>,
+  trie: TrieSnapshot,
+  candidates: Mutex<Lru<Code, Arc<[Word]>>>,
+}
+
+impl Index {
+  fn new(ctx: &Context, trie: Box<Trie>, cache: usize) -> Self {
+    let rev_dict = ctx.rev_dict(&trie);
+    let codes = lookup::codes_by_word(&trie);
+    let words: Vec<_> = codes.keys().cloned().collect();
+    let lookups = lookup::lookup_all(&rev_dict, &codes, &words)
+      .into_iter()
+      .flatten()
+      .map(|lookup| (lookup.word.clone(), lookup))
+      .collect();
+    drop(rev_dict);
+    Self { lookups, trie: trie.freeze(), candidates: Mutex::new(Lru::new(cache)) }
+  }
+}
+
+/// How candidates are listed, and the counts of the caches across reloads.
+struct Candidates {
+  /// Of the codes asked, like the codes loaded.
+  normalization: Normalization,
+  completion: Completion,
+  page_size: usize,
+  /// Codes each connection keeps for itself, none without a shared cache.
+  local_cache: usize,
+  stats: CacheStats,
+}
+
+impl Candidates {
+  /// The first page of candidates shown after typing `code`, from the connection's own cache,
+  /// the shared one of the index, or the trie.
+  fn get(&self, index: &Index, local: &mut Lru<Code, Arc<[Word]>>, code: &str) -> Arc<[Word]> {
+    let code = self.normalization.keys(code);
+    if let Some(words) = local.get(&code) {
+      self.stats.local_hit();
+      return words.clone();
+    }
+    let mut shared = index.candidates.lock().unwrap();
+    let words = match shared.get(&code) {
+      Some(words) => {
+        self.stats.shared_hit();
+        words.clone()
+      }
+      None => {
+        self.stats.miss();
+        let words: Arc<[Word]> = index.trie
+          .node_through(&code)
+          .map(|node| node.candidates(self.completion).take(self.page_size).cloned().collect())
+          .unwrap_or_default();
+        if shared.insert(code.clone(), words.clone()).is_some() {
+          self.stats.eviction();
+        }
+        words
+      }
+    };
+    drop(shared);
+    local.insert(code, words.clone());
+    words
+  }
+}
+
+/// Serves lookups over TCP on `--addr`, 127.0.0.1:7878 by default: each line sent is a word,
+/// answered by a `word\tbest_code\talternatives\tneeds_select` line like `lookup` prints, with
+/// empty cells if the word isn't in the dicts.
+///
+/// `:candidates <code>` is answered by `code\tcandidates` with the first page of candidates shown
+/// for the code separated by spaces, `--page-size` of them, as many as the selection keys by
+/// default, and without completions with `--no-completion`. They're cached per connection and in
+/// a cache of `--cache` codes, 4096 by default, shared by all; `--cache 0` turns both off. `:metrics`
+/// is answered by the counts of the caches and their hit rate, see `CacheMetrics`.
+///
+/// The dicts are checked every `--interval` milliseconds, 1000 by default, and reloaded when they
+/// changed. Until the new index is built the old one keeps answering, and a request being answered
+/// finishes on the index it started with; a dict which fails to load leaves the old index in place.
+/// The cached candidates go with the old index. Requests take the current index without a lock, so
+/// that swapping a new one in never keeps them waiting.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let addr = args.value("addr").unwrap_or(DEFAULT_ADDR);
+  let interval = Duration::from_millis(args.parse_value("interval")?.unwrap_or(1000));
+  let cache = args.parse_value("cache")?.unwrap_or(DEFAULT_CACHE);
+  let candidates = &Candidates {
+    normalization: ctx.schema.normalization,
+    completion: if args.switch("no-completion") { Completion::Off } else { Completion::ALL },
+    page_size: args.parse_value("page-size")?.unwrap_or(ctx.config.select_keys.len()),
+    local_cache: LOCAL_CACHE.min(cache),
+    stats: CacheStats::new(),
+  };
+  // taken before loading, not to miss a change made meanwhile
+  let stamps = Stamps::of_schema(&ctx.schema).map_err(|e| e.to_string())?;
+  let index = Index::new(ctx, ctx.load_trie().await?, cache);
+  let listener = TcpListener::bind(addr).map_err(|e| format!("can't listen on {addr}: {e}"))?;
+  // the port picked for `:0`
+  let addr = listener.local_addr().map_or_else(|_| addr.to_string(), |addr| addr.to_string());
+  eprintln!("{}", tr!("serve-listening", addr, index.lookups.len()));
+
+  let current = &Current::new(index);
+  thread::scope(|scope| {
+    scope.spawn(move || watch(ctx, current, candidates, stamps, interval, cache));
+    for stream in listener.incoming() {
+      match stream {
+        Ok(stream) => {
+          scope.spawn(move || {
+            let peer = stream.peer_addr().map_or_else(|_| "?".to_string(), |addr| addr.to_string());
+            if let Err(e) = answer(current, candidates, stream) {
+              eprintln!("{peer}: {e}");
+            }
+          });
+        }
+        Err(e) => eprintln!("can't accept a connection: {e}"),
+      }
+    }
+  });
+  Ok(ExitCode::SUCCESS)
+}
+
+/// Rebuilds the index whenever the dicts change, swapping it in once it's ready.
+fn watch(ctx: &Context, current: &Current<Index>, candidates: &Candidates, mut stamps: Stamps, interval: Duration, cache: usize) {
+  loop {
+    thread::sleep(interval);
+    let changed = stamps.changed();
+    if changed.is_empty() {
+      continue;
+    }
+    let names: Vec<_> = changed.iter().map(|path| path.display().to_string()).collect();
+    // the imports may have changed with the main dict
+    let Ok(new_stamps) = Stamps::of_schema(&ctx.schema) else { continue };
+    stamps = new_stamps;
+    match ctx.schema.load_trie() {
+      Ok(trie) => {
+        let index = Index::new(ctx, trie, cache);
+        eprintln!("{}", tr!("serve-reloaded", names.join(", "), index.lookups.len()));
+        current.replace(index);
+        candidates.stats.invalidation();
+      }
+      // likely written halfway, the rest of the write will change the stamps again
+      Err(e) => eprintln!("{}", tr!("serve-reload-failed", names.join(", "), e)),
+    }
+  }
+}
+
+fn answer(current: &Current<Index>, candidates: &Candidates, stream: TcpStream) -> io::Result<()> {
+  let reader = BufReader::new(stream.try_clone()?);
+  let mut out = BufWriter::new(stream);
+  let mut local = Lru::new(candidates.local_cache);
+  let mut local_index = current.load();
+  for line in reader.lines() {
+    let line = line?;
+    let word = line.trim();
+    if word.is_empty() {
+      continue;
+    }
+    // a reload meanwhile doesn't pull the index from under the request
+    let index = current.load();
+    if !Arc::ptr_eq(&index, &local_index) {
+      local.clear();
+      local_index = index.clone();
+    }
+    if word == ":metrics" {
+      writeln!(out, "{}", candidates.stats.metrics())?;
+    } else if let Some(code) = word.strip_prefix(":candidates") {
+      let code = code.trim();
+      writeln!(out, "{code}\t{}", candidates.get(&index, &mut local, code).join(" "))?;
+    } else {
+      match index.lookups.get(word) {
+        Some(lookup) => writeln!(out, "{}", lookup.to_tsv())?,
+        None => writeln!(out, "{word}\t\t\t")?,
+      }
+    }
+    out.flush()?;
+  }
+  Ok(())
+}
@@ -0,0 +1,45 @@
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use smart_dict::check::Summary;
+use crate::cli::Args;
+use crate::fileman;
+use super::export::write_dict;
+use super::{finish, Context};
+
+/// Compares the entries of the schema with those of the same schema in `--other <dir>`, by code
+/// and word: `--op and` keeps the entries in both, `minus` those only here, e.g. the local additions
+/// missing upstream, and `xor` those in only one. The result is written as a table sorted by code
+/// to `--out <file>` or stdout.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let usage = "usage: smart-dict sets --op <and|minus|xor> --other <Rime directory> [--out <file>]";
+  let (Some(op), Some(other_dir)) = (args.value("op"), args.value("other")) else {
+    return Err(usage.to_string());
+  };
+  let other = Context::open(PathBuf::from(other_dir), args)?;
+  let trie = ctx.load_trie().await?;
+  let other_trie = other.load_trie().await?;
+  let result = match op {
+    "and" => trie.intersection(&other_trie),
+    "minus" => trie.difference(&other_trie),
+    "xor" => trie.symmetric_difference(&other_trie),
+    _ => return Err(format!("unknown --op {op}, {usage}")),
+  };
+
+  let mut summary = Summary::new("sets");
+  summary.metric("entries", result.entries().count() as f64);
+  if args.switch("check") {
+    return Ok(finish(args, &summary));
+  }
+  match args.value("out") {
+    Some(path) => {
+      let count = fileman::write_with(path, |file| write_dict(&result, file))
+        .map_err(|e| format!("can't write {path}: {e}"))?;
+      println!("wrote {count} entries to {path}");
+    }
+    None => {
+      write_dict(&result, io::stdout().lock()).map_err(|e| e.to_string())?;
+    }
+  }
+  Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,71 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use smart_dict::dict::{Header, DICT_EXT};
+use smart_dict::split::{self, Part, Predicate};
+use crate::cli::Args;
+use crate::fileman;
+use super::{verified, Context};
+
+fn parse_part(spec: &str) -> Result<Part, String> {
+  let (suffix, predicate) = spec
+    .split_once('=')
+    .ok_or_else(|| format!("--part should be <suffix>=<predicate>, got {spec:?}"))?;
+  let predicate = match predicate.strip_prefix("list:") {
+    Some(path) => {
+      let text = fs::read_to_string(path).map_err(|e| format!("can't read {path}: {e}"))?;
+      Predicate::Category(text
+        .lines()
+        .filter_map(|line| line.split('\t').next())
+        .map(str::trim)
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+        .collect())
+    }
+    None => Predicate::parse(predicate)?,
+  };
+  Ok(Part { suffix: suffix.to_string(), predicate })
+}
+
+/// Splits a table into `<table>.<suffix>` tables, one per `--part <suffix>=<predicate>` where the
+/// predicate is `len:<range>`, `weight:<range>`, `match:<regex>` or `list:<file>`, plus a
+/// `<table>.rest` for the other entries. They're written to `--out-dir`, by default the Rime directory,
+/// next to the `import_tables` replacing the table. `--dry-run` shows the tables as diffs.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let table = args.positional.first().ok_or("which table to split?")?;
+  let parts = args.values("part").map(parse_part).collect::<Result<Vec<_>, _>>()?;
+  if parts.is_empty() {
+    return Err("no --part to split into".to_string());
+  }
+  let _lock = (!args.switch("dry-run")).then(|| ctx.lock()).transpose()?;
+  let path = ctx.schema.dict_path(table);
+  let text = fs::read_to_string(&path).map_err(|e| format!("can't read {}: {e}", path.display()))?;
+  let (header, body) = Header::parse(&text);
+  let groups = split::split(body.lines(), &parts);
+
+  let out_dir = args.value("out-dir").map_or_else(|| ctx.schema.dir.clone(), PathBuf::from);
+  let suffixes = parts.iter().map(|part| part.suffix.as_str()).chain(["rest"]);
+  let mut tables = vec![];
+  for (suffix, lines) in suffixes.zip(&groups) {
+    let name = format!("{table}.{suffix}");
+    println!("{name}\t{} entries", lines.len());
+    if lines.is_empty() {
+      continue;
+    }
+    // the source header, comments and all, under the part's name
+    let mut header = header.clone();
+    header.set("name", &name);
+    let mut content = header.to_string();
+    for line in lines {
+      content.push_str(line);
+      content.push('\n');
+    }
+    let out = out_dir.join(format!("{name}.{DICT_EXT}"));
+    fileman::replace(&out, content).map_err(|e| format!("can't write {}: {e}", out.display()))?;
+    tables.push(name);
+  }
+
+  println!("\nin {}.{DICT_EXT}, instead of {table}:", ctx.schema.main_dict());
+  print!("{}", split::import_snippet(tables.iter().map(String::as_str)));
+  verified(ctx, args).await
+}
@@ -0,0 +1,271 @@
+use std::collections::HashMap;
+use std::io;
+use std::process::ExitCode;
+use futures::{stream, AsyncBufReadExt, AsyncWriteExt, StreamExt, TryStreamExt};
+use smart_dict::blame::Blames;
+use smart_dict::check::Summary;
+use smart_dict::dict;
+use smart_dict::filter::{self, Filter, Row};
+use smart_dict::rt;
+use smart_dict::schema::Schema;
+use smart_dict::sheet::Sheet;
+use smart_dict::text;
+use smart_dict::types::Code;
+use crate::cli::Args;
+use super::{blames, entry_filter, finish, Context};
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Data {
+  name: String,
+  size: usize,
+  entries: usize,
+  /// UTF-8 bytes of the words, 3 per Chinese character.
+  word_len: usize,
+  word_chars: usize,
+  word_graphemes: usize,
+  code_len: usize,
+  /// The bytes of the file by what they hold, see `dict::Breakdown`.
+  header: usize,
+  comments: usize,
+  payload: usize,
+  line_endings: usize,
+}
+
+impl Data {
+  fn sum(&self) -> usize {
+    self.word_len + self.code_len
+  }
+
+  /// Of the entry payload rather than the whole file, so that headers and comments don't count.
+  fn of_payload(&self, len: usize) -> f64 {
+    if self.payload == 0 { 0.0 } else { len as f64 / self.payload as f64 }
+  }
+
+  fn word_ratio(&self) -> f64 {
+    self.of_payload(self.word_len)
+  }
+
+  fn code_ratio(&self) -> f64 {
+    self.of_payload(self.code_len)
+  }
+
+  fn sum_ratio(&self) -> f64 {
+    self.of_payload(self.sum())
+  }
+
+  /// The bytes that aren't entries: the header, comments and line endings.
+  fn overhead_ratio(&self) -> f64 {
+    if self.size == 0 { 0.0 } else { (self.size - self.payload) as f64 / self.size as f64 }
+  }
+
+  fn chars_per_word(&self) -> f64 {
+    self.word_chars as f64 / self.entries as f64
+  }
+
+  fn code_per_char(&self) -> f64 {
+    self.code_len as f64 / self.word_chars as f64
+  }
+}
+
+/// How numbers are written to data.csv.
+struct Format {
+  decimals: usize,
+  decimal_sep: char,
+}
+
+impl Format {
+  fn percent(&self, ratio: f64) -> String {
+    self.number(ratio * 100.0)
+  }
+
+  fn number(&self, number: f64) -> String {
+    let number = format!("{:.*}", self.decimals, number);
+    if self.decimal_sep == '.' {
+      number
+    } else {
+      number.replace('.', &self.decimal_sep.to_string())
+    }
+  }
+
+  /// Spreadsheets reading decimal commas expect semicolons between fields.
+  fn delimiter(&self) -> &'static str {
+    if self.decimal_sep == ',' { ";" } else { "," }
+  }
+}
+
+/// Name for `--columns`, header, and value of a data.csv column.
+type Column = (&'static str, &'static str, fn(&Data, &Format) -> String);
+
+const COLUMNS: [Column; 18] = [
+  ("name", "name", |data, _| data.name.clone()),
+  ("entries", "entries", |data, _| data.entries.to_string()),
+  ("word_len", "word len", |data, _| data.word_len.to_string()),
+  ("word_chars", "word chars", |data, _| data.word_chars.to_string()),
+  ("word_graphemes", "word graphemes", |data, _| data.word_graphemes.to_string()),
+  ("code_len", "code len", |data, _| data.code_len.to_string()),
+  ("sum", "sum", |data, _| data.sum().to_string()),
+  ("word_per", "word per", |data, format| format.percent(data.word_ratio())),
+  ("code_per", "code per", |data, format| format.percent(data.code_ratio())),
+  ("sum_per", "sum per", |data, format| format.percent(data.sum_ratio())),
+  ("chars_per_word", "chars per word", |data, format| format.number(data.chars_per_word())),
+  ("code_per_char", "code per char", |data, format| format.number(data.code_per_char())),
+  ("size", "size", |data, _| data.size.to_string()),
+  ("header", "header", |data, _| data.header.to_string()),
+  ("comments", "comments", |data, _| data.comments.to_string()),
+  ("payload", "payload", |data, _| data.payload.to_string()),
+  ("line_endings", "line endings", |data, _| data.line_endings.to_string()),
+  ("overhead_per", "overhead per", |data, format| format.percent(data.overhead_ratio())),
+];
+
+fn select_columns(names: Option<&str>) -> Result<Vec<&'static Column>, String> {
+  let Some(names) = names else {
+    return Ok(COLUMNS.iter().collect());
+  };
+  names
+    .split(',')
+    .map(|name| COLUMNS
+      .iter()
+      .find(|(id, _, _)| *id == name.trim())
+      .ok_or_else(|| {
+        let ids: Vec<_> = COLUMNS.iter().map(|(id, _, _)| *id).collect();
+        format!("unknown column {name}, expected some of {}", ids.join(","))
+      }))
+    .collect()
+}
+
+/// Tables read at once unless `--jobs` says otherwise.
+const JOBS: usize = 8;
+
+/// Counts the entries of a table, only those passing the filter if any.
+async fn statistic(schema: &Schema, dict_name: &str, filter: Option<(&Filter, &HashMap<Code, usize>, &Blames)>) -> Result<Data, String> {
+  let path = schema.dict_path(dict_name);
+  let error = |e: io::Error| format!("can't read {}: {e}", path.display());
+  let (mut reader, size) = rt::open_with_len(&path).await.map_err(error)?;
+  let mut data = Data {
+    name: dict_name.to_owned(),
+    size: size as usize,
+    entries: 0,
+    word_len: 0,
+    word_chars: 0,
+    word_graphemes: 0,
+    code_len: 0,
+    header: 0,
+    comments: 0,
+    payload: 0,
+    line_endings: 0,
+  };
+  let mut parser = schema.line_parser(dict_name);
+  let mut breakdown = dict::Breakdown::with_parser(schema.line_parser(dict_name));
+  let mut buf = String::new();
+  let mut idx = 0;
+  while reader.read_line(&mut buf).await.map_err(error)? > 0 {
+    idx += 1;
+    breakdown.add_line(&buf);
+    let line = std::mem::take(&mut buf);
+    let line = line.trim_end_matches(['\r', '\n']);
+    // the cells alone, not the weights and annotations after them
+    let Some(entry) = parser.parse(line) else { continue };
+    if let Some((expr, candidates, blames)) = filter {
+      let entry = schema.normalize(dict_name, entry.clone());
+      let row = Row {
+        word: &entry.word,
+        code: &entry.code,
+        dict: dict_name,
+        line: idx,
+        note: dict::split_annotation(line).1,
+        candidates: candidates.get(&entry.code).copied().unwrap_or(1),
+        blame: blames.get(dict_name, idx - 1),
+      };
+      if !expr.matches(&row) {
+        continue;
+      }
+    }
+    data.entries += 1;
+    data.word_len += entry.word.len();
+    data.word_chars += entry.word.chars().count();
+    data.word_graphemes += text::graphemes(&entry.word);
+    data.code_len += entry.code.len();
+  }
+  data.header = breakdown.header();
+  data.comments = breakdown.comments();
+  data.payload = breakdown.payload();
+  data.line_endings = breakdown.line_endings();
+  Ok(data)
+}
+
+/// The statistics of each table, `--jobs` at once, highest sum per first, of the entries passing
+/// `--filter <expr>` if given.
+pub async fn tables(ctx: &Context, args: &Args) -> Result<Vec<Data>, String> {
+  let expr = entry_filter(args)?;
+  let candidates = match &expr {
+    Some(expr) if expr.uses("candidates") => filter::candidates(&ctx.schema.located_entries().map_err(|e| e.to_string())?),
+    _ => HashMap::new(),
+  };
+  let blames = blames(ctx, args, expr.as_ref())?.unwrap_or_default();
+  ctx.check_tables();
+  let import_tables = ctx.schema
+    .import_tables_async()
+    .await
+    .map_err(|e| format!("can't read {:?}: {e}", ctx.schema.dict_path(&ctx.schema.main_dict())))?;
+  // the main dict, first, isn't a table of its own
+  let (mut tables, _) = ctx.schema.plan_tables(import_tables);
+  tables.remove(0);
+
+  let jobs = args.parse_value("jobs")?.unwrap_or(JOBS).max(1);
+  let mut result: Vec<Data> = stream::iter(&tables)
+    .map(|dict_name| statistic(&ctx.schema, dict_name, expr.as_ref().map(|expr| (expr, &candidates, &blames))))
+    .buffer_unordered(jobs)
+    .try_collect()
+    .await?;
+  result.sort_by(|a, b| {
+    b.sum_ratio().partial_cmp(&a.sum_ratio()).unwrap()
+  });
+  Ok(result)
+}
+
+/// The data.csv columns with plain numbers, ratios rather than percents.
+pub fn sheet(result: &[Data]) -> Sheet {
+  let mut sheet = Sheet::new("stats", &COLUMNS.map(|(_, header, _)| header));
+  for data in result {
+    sheet.row([
+      data.name.as_str().into(), data.entries.into(), data.word_len.into(), data.word_chars.into(),
+      data.word_graphemes.into(), data.code_len.into(), data.sum().into(),
+      data.word_ratio().into(), data.code_ratio().into(), data.sum_ratio().into(),
+      data.chars_per_word().into(), data.code_per_char().into(),
+      data.size.into(), data.header.into(), data.comments.into(), data.payload.into(), data.line_endings.into(),
+      data.overhead_ratio().into(),
+    ]);
+  }
+  sheet
+}
+
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let result = tables(ctx, args).await?;
+
+  let max_sum_per: Option<f64> = args.parse_value("max-sum-per")?;
+  let columns = select_columns(args.value("columns"))?;
+  let format = Format {
+    decimals: args.parse_value("decimals")?.unwrap_or(2),
+    decimal_sep: args.parse_value("decimal-sep")?.unwrap_or('.'),
+  };
+  let mut summary = Summary::new("stats");
+  summary.metric("tables", result.len() as f64);
+  if let Some(max) = max_sum_per {
+    for data in result.iter().filter(|data| data.sum_ratio() * 100.0 > max) {
+      summary.violation(format!("{} has a sum per of {:.2}%, more than {max}%", data.name, data.sum_ratio() * 100.0));
+    }
+  }
+
+  let delimiter = format.delimiter();
+  let mut writer = rt::create("data.csv").await.unwrap();
+  let header: Vec<_> = columns.iter().map(|(_, header, _)| *header).collect();
+  writer.write_all(format!("{}\n", header.join(delimiter)).as_bytes()).await.unwrap();
+
+  for x in result {
+    let row: Vec<_> = columns.iter().map(|(_, _, value)| value(&x, &format)).collect();
+    writer.write_all(format!("{}\n", row.join(delimiter)).as_bytes()).await.unwrap();
+  }
+  writer.flush().await.unwrap();
+  Ok(finish(args, &summary))
+}
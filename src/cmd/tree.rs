@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+use smart_dict::collision::Provenance;
+use smart_dict::trie::Trie;
+use crate::cli::Args;
+use super::Context;
+
+struct Printer<'a> {
+  provenance: &'a Provenance,
+  /// Keys shown past the prefix, all by default.
+  depth: usize,
+  /// Words of the subtree by table.
+  tables: BTreeMap<&'a str, usize>,
+}
+
+impl<'a> Printer<'a> {
+  /// The code and its words grouped by the tables they come from, e.g. `wi  cizu: 我们 (3)  user: 为`.
+  fn line(&mut self, node: &Trie, code: &str) -> String {
+    let mut groups: Vec<(String, Vec<String>)> = vec![];
+    for (idx, (word, weight)) in node.words().iter().zip(node.weights()).enumerate() {
+      let mut tables = self.provenance.tables(code, word);
+      // a word loaded again from several tables comes from each in turn
+      let occurrence = node.words()[..idx].iter().filter(|other| *other == word).count();
+      if node.words().iter().filter(|other| *other == word).count() > 1 {
+        tables = tables.get(occurrence..=occurrence).unwrap_or_default();
+      }
+      for table in tables {
+        *self.tables.entry(table).or_default() += 1;
+      }
+      let source = if tables.is_empty() { "?".to_string() } else { tables.join(",") };
+      let word = if *weight > 0 { format!("{word} ({weight})") } else { word.clone() };
+      match groups.iter_mut().find(|(table, _)| *table == source) {
+        Some((_, words)) => words.push(word),
+        None => groups.push((source, vec![word])),
+      }
+    }
+    let mut line = code.to_string();
+    for (source, words) in groups {
+      line.push_str(&format!("  {source}: {}", words.join(" ")));
+    }
+    line
+  }
+
+  /// Prints the children of `node` below the line of `code`, `indent` drawing the branches above.
+  fn children(&mut self, out: &mut impl Write, node: &Trie, code: &str, indent: &str, keys: usize) -> io::Result<()> {
+    let mut children: Vec<_> = node.children().collect();
+    children.sort_unstable_by(|a, b| a.code().cmp(b.code()));
+    let children: Vec<_> = children.into_iter().filter(|child| keys + child.code().len() <= self.depth).collect();
+    for (idx, child) in children.iter().enumerate() {
+      let last = idx + 1 == children.len();
+      let code = format!("{code}{}", child.code());
+      writeln!(out, "{indent}{}{}", if last { "└── " } else { "├── " }, self.line(child, &code))?;
+      let indent = format!("{indent}{}", if last { "    " } else { "│   " });
+      self.children(out, child, &code, &indent, keys + child.code().len())?;
+    }
+    Ok(())
+  }
+}
+
+/// Prints the codes starting with a prefix as a tree, with their words, weights and tables,
+/// `--depth` keys past the prefix at most.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let prefix = args.positional.first().ok_or("usage: smart-dict tree <prefix> [--depth <keys>]")?;
+  let prefix = &ctx.schema.normalization.keys(prefix);
+  let depth = args.parse_value("depth")?.unwrap_or(usize::MAX);
+  let trie = ctx.load_trie().await?;
+  let node = trie.node_through(prefix).ok_or_else(|| format!("no code starts with {prefix}"))?;
+  let provenance = Provenance::new(&ctx.schema.located_entries().map_err(|e| e.to_string())?);
+
+  let mut printer = Printer { provenance: &provenance, depth, tables: BTreeMap::new() };
+  print(&mut BufWriter::new(io::stdout().lock()), &mut printer, node, prefix).map_err(|e| e.to_string())?;
+  Ok(ExitCode::SUCCESS)
+}
+
+fn print(out: &mut impl Write, printer: &mut Printer, node: &Trie, prefix: &str) -> io::Result<()> {
+  let code = node.full_code();
+  writeln!(out, "{}", printer.line(node, &code))?;
+  printer.children(out, node, &code, "", code.len() - prefix.len())?;
+  let tables: Vec<_> = printer.tables.iter().map(|(table, count)| format!("{table} {count}")).collect();
+  if !tables.is_empty() {
+    writeln!(out, "\nby table: {}", tables.join(", "))?;
+  }
+  out.flush()
+}
@@ -0,0 +1,27 @@
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+use crate::cli::Args;
+use super::Context;
+
+/// Prints the shape of the schema's trie, see `Trie::metrics`: sizes, depths, and the histograms
+/// of depths, children and words per node.
+pub async fn run(ctx: &Context, _args: &Args) -> Result<ExitCode, String> {
+  let trie = ctx.load_trie().await?;
+  let metrics = trie.metrics();
+  println!("nodes\t{}", metrics.nodes);
+  println!("words\t{}", metrics.words);
+  println!("depth\tmax {}, avg {:.2}", metrics.max_depth, metrics.avg_depth);
+  println!("code length\tmax {}", metrics.max_code_len);
+  println!("branching\tavg {:.2} among inner nodes", metrics.avg_branching);
+  print_histogram("nodes by depth", &metrics.depths, metrics.nodes);
+  print_histogram("nodes by children", &metrics.branching, metrics.nodes);
+  print_histogram("nodes by words", &metrics.words_per_node, metrics.nodes);
+  Ok(ExitCode::SUCCESS)
+}
+
+fn print_histogram(title: &str, histogram: &BTreeMap<usize, usize>, total: usize) {
+  println!("\n{title}:");
+  for (value, count) in histogram {
+    println!("  {value}\t{count}\t{:.2}%", *count as f64 / total as f64 * 100.0);
+  }
+}
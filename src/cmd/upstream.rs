@@ -0,0 +1,61 @@
+use std::process::ExitCode;
+use smart_dict::check::Summary;
+use smart_dict::dedup::{self, Located, Source};
+use smart_dict::{archive, dict};
+use crate::cli::Args;
+use crate::fileman;
+use super::dedup::confirm;
+use super::{finish, verified, Context};
+
+/// Lists the entries of the user table, `<schema>.user` or `--user <table>`, which the other tables
+/// now have with the same or a shorter code, and offers to delete their lines.
+///
+/// `--dry-run` shows the diffs, `--yes` deletes without asking.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let user_table = args.value("user").map_or_else(|| format!("{}.user", ctx.schema.name), String::from);
+  let path = ctx.schema.dict_path(&user_table);
+  if !archive::exists(&path) {
+    return Err(format!("no {user_table} table, name the user table with --user <table>"));
+  }
+  // held from reading the lines to deleting them
+  let _lock = (!args.switch("dry-run") && !args.switch("check")).then(|| ctx.lock()).transpose()?;
+  let user: Vec<_> = dict::read_annotated_entries(&path)
+    .map_err(|e| format!("can't read {}: {e}", path.display()))?
+    .into_iter()
+    .map(|(line, entry, annotation)| Located {
+      entry: ctx.schema.normalize(&user_table, entry),
+      source: Source { table: user_table.clone(), line },
+      annotation,
+    })
+    .collect();
+  let mut base = ctx.schema.located_entries().map_err(|e| e.to_string())?;
+  base.retain(|located| located.source.table != user_table);
+  let superseded = dedup::superseded(&user, &base);
+
+  let mut summary = Summary::new("upstream");
+  summary.metric("superseded", superseded.len() as f64);
+  if !superseded.is_empty() {
+    summary.violation(format!("{} entries of {user_table} now upstream", superseded.len()));
+  }
+  if args.switch("check") {
+    return Ok(finish(args, &summary));
+  }
+
+  for redundant in &superseded {
+    println!("{}:{}\t{}\t{}\t# upstream: {}", redundant.source.table, redundant.source.line + 1,
+             redundant.entry.word, redundant.entry.code, redundant.kept);
+  }
+  println!("{}", tr!("upstream-summary", superseded.len(), user_table));
+  if superseded.is_empty() || !(args.switch("dry-run") || args.switch("yes") || confirm(&tr!("dedup-confirm"))?) {
+    return Ok(ExitCode::SUCCESS);
+  }
+
+  let count = superseded.len();
+  fileman::remove_lines(&path, superseded.into_iter().map(|redundant| redundant.source.line))
+    .map_err(|e| format!("can't edit {}: {e}", path.display()))?;
+  ctx.record_change(args, &path, &format!("upstream: {count} entries now upstream removed"))?;
+  if !fileman::is_dry_run() {
+    println!("{}", tr!("dedup-deleted"));
+  }
+  verified(ctx, args).await
+}
@@ -0,0 +1,37 @@
+use std::process::ExitCode;
+use smart_dict::lint::{Blank, Linter, Severity};
+use smart_dict::rime::{self, Workspace};
+use crate::cli::Args;
+use super::{Context, CHECK_FAILED};
+
+/// Checks that Rime would still build the schema: first the lint errors, which are quick,
+/// then a deployment by librime into `.smart-dict/rime`, with librime's warnings and errors.
+pub async fn run(ctx: &Context, _args: &Args) -> Result<ExitCode, String> {
+  Ok(if verify(ctx).await? { ExitCode::SUCCESS } else { ExitCode::from(CHECK_FAILED) })
+}
+
+/// Runs both passes, printing what they found; true if the schema deployed.
+pub async fn verify(ctx: &Context) -> Result<bool, String> {
+  let trie = ctx.load_trie().await?;
+  let errors: Vec<_> = Linter::new()
+    .rule(Blank)
+    .lint(&trie)
+    .into_iter()
+    .filter(|issue| issue.severity == Severity::Error)
+    .collect();
+  for issue in &errors {
+    println!("{issue}");
+  }
+  if !errors.is_empty() {
+    println!("{} lint errors, not deploying", errors.len());
+    return Ok(false);
+  }
+
+  let workspace = Workspace::new(&ctx.schema.dir, ctx.state_dir().join("rime"));
+  let deployment = rime::deploy(&workspace, &ctx.schema.name).map_err(|e| format!("can't deploy: {e}"))?;
+  for message in &deployment.messages {
+    println!("librime: {message}");
+  }
+  println!("{} {}", ctx.schema.name, if deployment.ok { "deployed" } else { "failed to deploy" });
+  Ok(deployment.ok)
+}
@@ -0,0 +1,46 @@
+use std::process::ExitCode;
+use smart_dict::trie::{Completion, Entry};
+use smart_dict::whatif;
+use crate::cli::Args;
+use super::{analyze_corpus, Context};
+
+/// Shows what adding `<word> <code>` with `--weight` would do: the words already on the code,
+/// the candidates pushed down on it and its prefixes, the auto-commits broken, and with `--corpus`,
+/// how the keystrokes to type the corpus change.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let [word, code] = args.positional.as_slice() else {
+    return Err("usage: smart-dict whatif <word> <code> [--weight <n>] [--corpus <file>]".to_string());
+  };
+  let entry = ctx.schema.normalization.entry(Entry { word: word.clone(), code: code.clone() });
+  let weight = args.parse_value("weight")?.unwrap_or(0);
+  let before = ctx.load_trie().await?;
+  let mut after = before.clone();
+  after.insert_weighted(entry.code.clone(), entry.word.clone(), weight);
+
+  let impact = whatif::impact(&before, &after, &entry, Completion::ALL, ctx.config.select_keys.len());
+  if impact.occupants.is_empty() {
+    println!("{} is free", entry.code);
+  } else {
+    println!("on {}: {}", entry.code, impact.occupants.join(" "));
+  }
+  if let Some(index) = impact.index {
+    println!("{} would be candidate {} on {}", entry.word, index + 1, entry.code);
+  }
+  for pushed in &impact.pushed {
+    let to = if pushed.to < ctx.config.select_keys.len() { (pushed.to + 1).to_string() } else { "off the list".to_string() };
+    println!("pushed on {}: {} from {} to {to}", pushed.code, pushed.word, pushed.from + 1);
+  }
+  for (code, word) in &impact.broken_auto_commits {
+    println!("{word} would no longer go up by itself on {code}");
+  }
+
+  if let Some(path) = args.value("corpus") {
+    let analyze = |trie| analyze_corpus(args, &ctx.rev_dict(trie), path);
+    let (stats_before, stats_after) = (analyze(&before)?, analyze(&after)?);
+    println!("corpus: {} → {} keystrokes ({:+}), {:.4} → {:.4} keys per character",
+             stats_before.keystrokes, stats_after.keystrokes,
+             stats_after.keystrokes as i64 - stats_before.keystrokes as i64,
+             stats_before.keys_per_char(), stats_after.keys_per_char());
+  }
+  Ok(ExitCode::SUCCESS)
+}
@@ -0,0 +1,78 @@
+use std::process::ExitCode;
+use smart_dict::check::Summary;
+use smart_dict::collision::{self, Provenance};
+use crate::cli::Args;
+use crate::fileman;
+use super::{finish, Context};
+
+const USAGE: &str = "usage: smart-dict whitelist [list | add <code> [<word>...] | remove <code> [<word>...] | prune]";
+
+/// Manages the whitelist of intentional collisions and reports the entries gone stale.
+pub async fn run(ctx: &Context, args: &Args) -> Result<ExitCode, String> {
+  let action = args.positional.first().map_or("list", String::as_str);
+  // held from loading the whitelist to saving it
+  let _lock = (action != "list").then(|| ctx.lock()).transpose()?;
+  let (path, mut whitelist) = ctx.whitelist(args)?;
+  let trie = ctx.load_trie().await?;
+  let collisions = collision::collisions(&trie);
+  let provenance = Provenance::new(&ctx.schema.located_entries().map_err(|e| e.to_string())?);
+
+  let code = args.positional.get(1).map(|code| ctx.schema.normalization.keys(code));
+  let words = args.positional.get(2..).unwrap_or_default();
+  let changed = match (action, code.as_ref()) {
+    ("list", None) => false,
+    ("add", Some(code)) => {
+      let collision = collisions
+        .iter()
+        .find(|collision| &collision.code == code)
+        .ok_or_else(|| format!("no collision on {code}"))?;
+      let mut added = 0;
+      for word in collision.words.iter().filter(|word| words.is_empty() || words.contains(word)) {
+        for table in provenance.tables(code, word) {
+          if whitelist.add(code, word, table) {
+            added += 1;
+          }
+        }
+      }
+      println!("{added} entries added");
+      added > 0
+    }
+    ("remove", Some(code)) => {
+      let removed = whitelist.remove(code, words);
+      println!("{removed} entries removed");
+      removed > 0
+    }
+    ("prune", None) => {
+      let stale: Vec<_> = whitelist.stale(&collisions, &provenance).into_iter().cloned().collect();
+      for (code, word, _) in &stale {
+        whitelist.remove(code, std::slice::from_ref(word));
+      }
+      println!("{} stale entries removed", stale.len());
+      !stale.is_empty()
+    }
+    _ => return Err(USAGE.to_string()),
+  };
+  if changed {
+    let mut text = vec![];
+    whitelist.write(&mut text).map_err(|e| e.to_string())?;
+    fileman::write(&path, text).map_err(|e| format!("can't write {}: {e}", path.display()))?;
+  }
+
+  let stale = whitelist.stale(&collisions, &provenance);
+  let mut summary = Summary::new("whitelist");
+  summary.metric("entries", whitelist.entries().count() as f64);
+  summary.metric("stale", stale.len() as f64);
+  for (code, word, table) in &stale {
+    summary.violation(format!("stale whitelist entry {word} on {code} from {table}"));
+  }
+
+  if !args.switch("check") && action == "list" {
+    for entry in whitelist.entries() {
+      let (code, word, table) = entry;
+      let mark = if stale.contains(&entry) { "\tstale" } else { "" };
+      println!("{code}\t{word}\t{table}{mark}");
+    }
+    println!("{} entries, {} stale", whitelist.entries().count(), stale.len());
+  }
+  Ok(finish(args, &summary))
+}
@@ -0,0 +1,345 @@
+use std::borrow::Borrow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeSet, BinaryHeap, HashMap};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use crate::archive;
+use crate::dedup::Located;
+use crate::trie::{Trie, Walk};
+use crate::types::{Code, Word};
+
+/// Kept in the Rime directory next to the config, since it is edited by hand as well.
+pub const WHITELIST_FILE: &str = "smart-dict.whitelist";
+
+/// Several words sharing one full code.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Collision {
+  pub code: Code,
+  pub words: Vec<Word>,
+}
+
+impl Collision {
+  /// `code\twords`, the words sorted, telling collisions apart across versions of the dicts.
+  pub fn key(&self) -> String {
+    let words: BTreeSet<_> = self.words.iter().collect();
+    let words: Vec<_> = words.into_iter().map(String::as_str).collect();
+    format!("{}\t{}", self.code, words.join(" "))
+  }
+
+  /// How much the collision costs when typing: the frequency of the words needing a selection,
+  /// all but the most frequent, over the length of the code. Frequent words sharing a short code
+  /// come first, rare phrases sharing a long one last.
+  pub fn severity(&self, freq: impl Fn(&str) -> u64) -> f64 {
+    let mut counts: Vec<_> = self.words.iter().map(|word| freq(word)).collect();
+    counts.sort_unstable();
+    counts.pop();
+    counts.iter().sum::<u64>() as f64 / self.code.chars().count().max(1) as f64
+  }
+}
+
+/// Sorts the collisions by descending severity, then by code.
+pub fn sort_by_severity(collisions: &mut [Collision], freq: impl Fn(&str) -> u64) {
+  collisions.sort_by(|a, b| b.severity(&freq).total_cmp(&a.severity(&freq)).then_with(|| a.code.cmp(&b.code)));
+}
+
+pub fn collisions(trie: &Trie) -> Vec<Collision> {
+  let mut collisions: Vec<_> = stream(trie).collect();
+  collisions.sort_by(|a, b| a.code.cmp(&b.code));
+  collisions
+}
+
+/// The collisions in the order the trie is walked, one at a time rather than all in memory.
+pub fn stream(trie: &Trie) -> impl Iterator<Item=Collision> + '_ {
+  trie
+    .walk()
+    .filter(|(node, _)| node.words().len() > 1)
+    .map(|(node, code)| Collision { code, words: node.words().clone() })
+}
+
+/// Like `stream`, by code, so that pages of them stay the same from one run to the next.
+pub fn sorted_stream(trie: &Trie) -> impl Iterator<Item=Collision> + '_ {
+  Walk::sorted(trie)
+    .filter(|(node, _)| node.words().len() > 1)
+    .map(|(node, code)| Collision { code, words: node.words().clone() })
+}
+
+/// A collision ranked by severity, then by code like `sort_by_severity`, the greatest first.
+#[derive(Debug)]
+struct Ranked(f64, Collision);
+
+impl Ord for Ranked {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.0.total_cmp(&other.0).then_with(|| other.1.code.cmp(&self.1.code))
+  }
+}
+
+impl PartialOrd for Ranked {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl PartialEq for Ranked {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for Ranked {}
+
+/// Keeps the `k` most severe collisions of those pushed, holding no more than `k` at once.
+pub struct TopK {
+  k: usize,
+  /// The least severe kept on top, to be dropped first.
+  heap: BinaryHeap<Reverse<Ranked>>,
+}
+
+impl TopK {
+  pub fn new(k: usize) -> Self {
+    Self { k, heap: BinaryHeap::new() }
+  }
+
+  pub fn push(&mut self, collision: Collision, severity: f64) {
+    if self.k == 0 {
+      return;
+    }
+    let ranked = Ranked(severity, collision);
+    if self.heap.len() < self.k {
+      self.heap.push(Reverse(ranked));
+    } else if self.heap.peek().is_some_and(|Reverse(least)| ranked > *least) {
+      self.heap.pop();
+      self.heap.push(Reverse(ranked));
+    }
+  }
+
+  /// The collisions kept with their severities, the most severe first.
+  pub fn into_sorted_vec(self) -> Vec<(Collision, f64)> {
+    // ascending order of `Reverse` is descending severity
+    self.heap.into_sorted_vec().into_iter().map(|Reverse(Ranked(severity, collision))| (collision, severity)).collect()
+  }
+}
+
+/// Collisions recorded by a previous run, used to tell new collisions from known ones.
+pub struct Baseline(BTreeSet<String>);
+
+impl Baseline {
+  pub fn load(path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+    let file = match File::open(path) {
+      Ok(file) => file,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+      Err(e) => return Err(e),
+    };
+    let keys = BufReader::new(file).lines().collect::<io::Result<_>>()?;
+    Ok(Some(Self(keys)))
+  }
+
+  /// Writes the baseline of `collisions`, what [`Self::load`] reads back, sorted so that it doesn't
+  /// change with the order they come in.
+  pub fn write<C: Borrow<Collision>>(collisions: impl IntoIterator<Item=C>, writer: impl Write) -> io::Result<()> {
+    let keys: BTreeSet<_> = collisions.into_iter().map(|collision| collision.borrow().key()).collect();
+    let mut writer = BufWriter::new(writer);
+    for key in keys {
+      writeln!(writer, "{key}")?;
+    }
+    writer.flush()
+  }
+
+  /// A collision is new if its code collided with a different set of words, or not at all, before.
+  pub fn is_new(&self, collision: &Collision) -> bool {
+    !self.0.contains(&collision.key())
+  }
+}
+
+/// The tables each entry was read from.
+#[derive(Clone, Debug, Default)]
+pub struct Provenance(HashMap<(Code, Word), Vec<String>>);
+
+impl Provenance {
+  pub fn new(entries: &[Located]) -> Self {
+    let mut provenance = Self::default();
+    for Located { entry, source, .. } in entries {
+      provenance.0
+        .entry((entry.code.clone(), entry.word.clone()))
+        .or_default()
+        .push(source.table.clone());
+    }
+    provenance
+  }
+
+  pub fn tables(&self, code: &str, word: &str) -> &[String] {
+    self.0
+      .get(&(code.to_string(), word.to_string()))
+      .map_or(&[], Vec::as_slice)
+  }
+}
+
+/// Intentional collisions, e.g. 的/地 on `d`, as lines of `code\tword\ttable`.
+///
+/// An entry stays whitelisted only as long as it comes from the same table,
+/// so the same word added again somewhere else is reported as a new collision.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Whitelist(BTreeSet<(Code, Word, String)>);
+
+impl Whitelist {
+  pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+    let file = match archive::open(path) {
+      Ok(file) => file,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+      Err(e) => return Err(e),
+    };
+    let mut whitelist = Self::default();
+    for line in file.lines() {
+      let line = line?;
+      let line = line.find('#').map_or(line.as_str(), |idx| &line[..idx]);
+      let mut cells = line.split('\t').map(str::trim);
+      if let (Some(code), Some(word), Some(table)) = (cells.next(), cells.next(), cells.next()) {
+        whitelist.add(code, word, table);
+      }
+    }
+    Ok(whitelist)
+  }
+
+  pub fn write(&self, writer: impl Write) -> io::Result<()> {
+    let mut writer = BufWriter::new(writer);
+    for (code, word, table) in &self.0 {
+      writeln!(writer, "{code}\t{word}\t{table}")?;
+    }
+    writer.flush()
+  }
+
+  pub fn add(&mut self, code: &str, word: &str, table: &str) -> bool {
+    self.0.insert((code.to_string(), word.to_string(), table.to_string()))
+  }
+
+  /// Removes the entries of `code`, only those of `words` unless it's empty. Returns how many were removed.
+  pub fn remove(&mut self, code: &str, words: &[String]) -> usize {
+    let len = self.0.len();
+    self.0.retain(|(c, w, _)| c != code || !(words.is_empty() || words.contains(w)));
+    len - self.0.len()
+  }
+
+  pub fn entries(&self) -> impl Iterator<Item=&(Code, Word, String)> {
+    self.0.iter()
+  }
+
+  /// Whether `word` is whitelisted on `code` from any table, for checks that don't know where entries come from.
+  pub fn allows_word(&self, code: &str, word: &str) -> bool {
+    self.0.iter().any(|(c, w, _)| c == code && w == word)
+  }
+
+  /// Whether every entry of the collision is whitelisted from the tables it comes from.
+  pub fn allows(&self, collision: &Collision, provenance: &Provenance) -> bool {
+    collision.words.iter().all(|word| {
+      let tables = provenance.tables(&collision.code, word);
+      !tables.is_empty() && tables.iter().all(|table| {
+        self.0.contains(&(collision.code.clone(), word.clone(), table.clone()))
+      })
+    })
+  }
+
+  /// Entries that no longer take part in any collision, e.g. after one of the words got another code.
+  pub fn stale(&self, collisions: &[Collision], provenance: &Provenance) -> Vec<&(Code, Word, String)> {
+    self.0
+      .iter()
+      .filter(|(code, word, table)| !collisions.iter().any(|collision| {
+        &collision.code == code
+          && collision.words.contains(word)
+          && provenance.tables(code, word).contains(table)
+      }))
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_collisions() {
+    let mut trie = Trie::new();
+    trie.insert("d".to_string(), "的".to_string());
+    trie.insert("d".to_string(), "地".to_string());
+    trie.insert("n".to_string(), "你".to_string());
+    trie.insert("ni".to_string(), "你们".to_string());
+
+    let collisions = collisions(&trie);
+    assert_eq!(vec![Collision { code: "d".to_string(), words: vec!["的".to_string(), "地".to_string()] }], collisions);
+    assert_eq!("d\t地 的", collisions[0].key());
+
+    let mut baseline = vec![];
+    let n = Collision { code: "n".to_string(), words: vec!["你".to_string(), "那".to_string()] };
+    Baseline::write([&n, &collisions[0]], &mut baseline).unwrap();
+    assert_eq!("d\t地 的\nn\t你 那\n", String::from_utf8(baseline).unwrap());
+  }
+
+  #[test]
+  fn test_severity() {
+    let collision = |code: &str, words: &[&str]| Collision { code: code.to_string(), words: words.iter().map(|w| w.to_string()).collect() };
+    let freq = |word: &str| match word {
+      "的" => 1000,
+      "地" => 300,
+      "得" => 100,
+      _ => 1,
+    };
+    let mut collisions = vec![
+      collision("dexuvi", &["得心应手", "得寸进尺"]),
+      collision("de", &["的", "地", "得"]),
+      collision("dd", &["的", "顶顶"]),
+    ];
+    assert_eq!(200.0, collisions[1].severity(freq));
+    sort_by_severity(&mut collisions, freq);
+    let codes: Vec<_> = collisions.iter().map(|collision| collision.code.as_str()).collect();
+    assert_eq!(vec!["de", "dd", "dexuvi"], codes);
+  }
+
+  #[test]
+  fn test_top_k() {
+    let collision = |code: &str| Collision { code: code.to_string(), words: vec!["甲".to_string(), "乙".to_string()] };
+    let mut top = TopK::new(3);
+    for (code, severity) in [("dd", 0.5), ("dexuvi", 0.2), ("de", 200.0), ("aa", 0.5), ("b", 0.1)] {
+      top.push(collision(code), severity);
+    }
+    let kept: Vec<_> = top.into_sorted_vec().into_iter().map(|(collision, severity)| (collision.code, severity)).collect();
+    assert_eq!(vec![("de".to_string(), 200.0), ("aa".to_string(), 0.5), ("dd".to_string(), 0.5)], kept);
+    let mut none = TopK::new(0);
+    none.push(collision("de"), 1.0);
+    assert!(none.into_sorted_vec().is_empty());
+  }
+
+  #[test]
+  fn test_whitelist() {
+    use crate::dedup::Source;
+    use crate::trie::Entry;
+
+    let entries: Vec<_> = [("d", "的", "danzi"), ("d", "地", "danzi"), ("e", "是", "danzi"), ("e", "事", "user")]
+      .into_iter()
+      .enumerate()
+      .map(|(line, (code, word, table))| Located {
+        entry: Entry { code: code.to_string(), word: word.to_string() },
+        source: Source { table: table.to_string(), line },
+        annotation: None,
+      })
+      .collect();
+    let trie: Box<Trie> = entries.iter().map(|located| located.entry.clone()).collect();
+    let collisions = collisions(&trie);
+    let provenance = Provenance::new(&entries);
+
+    let mut whitelist = Whitelist::default();
+    whitelist.add("d", "的", "danzi");
+    whitelist.add("d", "地", "danzi");
+    whitelist.add("e", "是", "danzi");
+    whitelist.add("e", "事", "danzi");
+    whitelist.add("x", "嘻", "danzi");
+    assert!(whitelist.allows(&collisions[0], &provenance));
+    // 事 comes from another table than the whitelisted one
+    assert!(!whitelist.allows(&collisions[1], &provenance));
+    assert!(whitelist.allows_word("e", "事"));
+    assert_eq!(
+      vec![&("e".to_string(), "事".to_string(), "danzi".to_string()), &("x".to_string(), "嘻".to_string(), "danzi".to_string())],
+      whitelist.stale(&collisions, &provenance)
+    );
+    assert_eq!(2, whitelist.remove("e", &[]));
+  }
+}
@@ -0,0 +1,306 @@
+//! A read-only dict in a few flat arrays, for servers and other lookups-only uses: a DAWG of the codes,
+//! where equal subtrees are stored once, with the words of each state as its output.
+//!
+//! Lookups take one binary search among at most a few dozen edges per key, and the whole structure
+//! can be written to a file and loaded back as is.
+
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Read, Write};
+use crate::trie::Trie;
+use crate::types::{Code, Word};
+
+const MAGIC: &[u8; 4] = b"SDC1";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompactDict {
+  /// Edges of state `s`: `edge_labels[edge_offsets[s]..edge_offsets[s + 1]]`, sorted, and their targets.
+  edge_offsets: Vec<u32>,
+  edge_labels: Vec<u8>,
+  edge_targets: Vec<u32>,
+  /// Words of state `s` in candidate order: `outputs[output_offsets[s]..output_offsets[s + 1]]`, with their weights.
+  output_offsets: Vec<u32>,
+  outputs: Vec<u32>,
+  weights: Vec<u64>,
+  /// Word `w` is `text[word_offsets[w]..word_offsets[w + 1]]`.
+  word_offsets: Vec<u32>,
+  text: String,
+  root: u32,
+}
+
+/// A state before it's numbered, what tells equal subtrees apart.
+#[derive(PartialEq, Eq, Hash)]
+struct Signature {
+  outputs: Vec<(u32, u64)>,
+  edges: Vec<(u8, u32)>,
+}
+
+struct Builder<'a> {
+  /// Codes as bytes with their word and weight, sorted by code, keeping the candidate order among equal codes.
+  entries: Vec<(&'a [u8], u32, u64)>,
+  states: HashMap<Signature, u32>,
+  dict: CompactDict,
+}
+
+impl Builder<'_> {
+  /// Builds the state of the entries sharing their first `depth` bytes, its children first.
+  fn state(&mut self, depth: usize, start: usize, end: usize) -> u32 {
+    let mut outputs = vec![];
+    let mut idx = start;
+    while idx < end && self.entries[idx].0.len() == depth {
+      outputs.push((self.entries[idx].1, self.entries[idx].2));
+      idx += 1;
+    }
+    let mut edges = vec![];
+    while idx < end {
+      let label = self.entries[idx].0[depth];
+      let group_end = idx + self.entries[idx..end].partition_point(|(code, _, _)| code[depth] == label);
+      edges.push((label, self.state(depth + 1, idx, group_end)));
+      idx = group_end;
+    }
+
+    let signature = Signature { outputs, edges };
+    if let Some(&state) = self.states.get(&signature) {
+      return state;
+    }
+    let dict = &mut self.dict;
+    let state = dict.edge_offsets.len() as u32 - 1;
+    for &(label, target) in &signature.edges {
+      dict.edge_labels.push(label);
+      dict.edge_targets.push(target);
+    }
+    dict.edge_offsets.push(dict.edge_labels.len() as u32);
+    for &(word, weight) in &signature.outputs {
+      dict.outputs.push(word);
+      dict.weights.push(weight);
+    }
+    dict.output_offsets.push(dict.outputs.len() as u32);
+    self.states.insert(signature, state);
+    state
+  }
+}
+
+impl CompactDict {
+  pub fn from_trie(trie: &Trie) -> Self {
+    let mut dict = Self { edge_offsets: vec![0], output_offsets: vec![0], word_offsets: vec![0], ..Default::default() };
+    let mut ids: HashMap<&Word, u32> = HashMap::new();
+    let mut codes = vec![];
+    for (node, code) in trie.walk() {
+      for (word, &weight) in node.words().iter().zip(node.weights()) {
+        let id = *ids.entry(word).or_insert_with(|| {
+          dict.text.push_str(word);
+          dict.word_offsets.push(dict.text.len() as u32);
+          dict.word_offsets.len() as u32 - 2
+        });
+        codes.push((code.clone(), id, weight));
+      }
+    }
+    codes.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let entries = codes.iter().map(|(code, id, weight)| (code.as_bytes(), *id, *weight)).collect();
+    let mut builder = Builder { entries, states: HashMap::new(), dict };
+    let len = builder.entries.len();
+    builder.dict.root = builder.state(0, 0, len);
+    builder.dict
+  }
+
+  pub fn states(&self) -> usize {
+    self.edge_offsets.len() - 1
+  }
+
+  /// Bytes of the arrays, which is about the size of the file too.
+  pub fn heap_bytes(&self) -> usize {
+    (self.edge_offsets.len() + self.edge_targets.len() + self.output_offsets.len() + self.outputs.len()
+      + self.word_offsets.len()) * size_of::<u32>()
+      + self.weights.len() * size_of::<u64>()
+      + self.edge_labels.len()
+      + self.text.len()
+  }
+
+  fn word(&self, id: u32) -> &str {
+    let id = id as usize;
+    &self.text[self.word_offsets[id] as usize..self.word_offsets[id + 1] as usize]
+  }
+
+  fn range(offsets: &[u32], state: u32) -> std::ops::Range<usize> {
+    offsets[state as usize] as usize..offsets[state as usize + 1] as usize
+  }
+
+  fn state(&self, code: &str) -> Option<u32> {
+    let mut state = self.root;
+    for &key in code.as_bytes() {
+      let edges = Self::range(&self.edge_offsets, state);
+      let idx = self.edge_labels[edges.clone()].binary_search(&key).ok()?;
+      state = self.edge_targets[edges.start + idx];
+    }
+    Some(state)
+  }
+
+  /// The words on exactly `code`, in candidate order, with their weights.
+  pub fn get(&self, code: &str) -> Vec<(&str, u64)> {
+    self.state(code)
+      .map(|state| Self::range(&self.output_offsets, state)
+        .map(|idx| (self.word(self.outputs[idx]), self.weights[idx]))
+        .collect())
+      .unwrap_or_default()
+  }
+
+  /// Up to `limit` entries whose code starts with `prefix`, shorter codes first.
+  pub fn complete(&self, prefix: &str, limit: usize) -> Vec<(Code, &str)> {
+    let mut found = vec![];
+    let Some(state) = self.state(prefix) else { return found };
+    let mut queue = VecDeque::from([(state, prefix.to_string())]);
+    while let Some((state, code)) = queue.pop_front() {
+      for idx in Self::range(&self.output_offsets, state) {
+        if found.len() == limit {
+          return found;
+        }
+        found.push((code.clone(), self.word(self.outputs[idx])));
+      }
+      for idx in Self::range(&self.edge_offsets, state) {
+        let mut code = code.clone();
+        code.push(self.edge_labels[idx] as char);
+        queue.push_back((self.edge_targets[idx], code));
+      }
+    }
+    found
+  }
+
+  pub fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&self.root.to_le_bytes())?;
+    for array in [&self.edge_offsets, &self.edge_targets, &self.output_offsets, &self.outputs, &self.word_offsets] {
+      write_len(writer, array.len())?;
+      array.iter().try_for_each(|n| writer.write_all(&n.to_le_bytes()))?;
+    }
+    write_len(writer, self.weights.len())?;
+    self.weights.iter().try_for_each(|n| writer.write_all(&n.to_le_bytes()))?;
+    for bytes in [&self.edge_labels[..], self.text.as_bytes()] {
+      write_len(writer, bytes.len())?;
+      writer.write_all(bytes)?;
+    }
+    Ok(())
+  }
+
+  pub fn read(reader: &mut impl Read) -> io::Result<Self> {
+    let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+      return Err(invalid("not a compact dict"));
+    }
+    let root = read_u32(reader)?;
+    let mut arrays = vec![];
+    for _ in 0..5 {
+      let len = read_u32(reader)? as usize;
+      arrays.push((0..len).map(|_| read_u32(reader)).collect::<io::Result<Vec<_>>>()?);
+    }
+    let len = read_u32(reader)? as usize;
+    let weights = (0..len).map(|_| read_u64(reader)).collect::<io::Result<Vec<_>>>()?;
+    let edge_labels = read_bytes(reader)?;
+    let text = String::from_utf8(read_bytes(reader)?).map_err(|_| invalid("words aren't UTF-8"))?;
+    let [edge_offsets, edge_targets, output_offsets, outputs, word_offsets] = arrays.try_into().unwrap();
+    let dict = Self { edge_offsets, edge_labels, edge_targets, output_offsets, outputs, weights, word_offsets, text, root };
+    dict.check().then_some(dict).ok_or_else(|| invalid("inconsistent compact dict"))
+  }
+
+  /// Whether every offset and index stays in bounds, so that lookups can't panic on a corrupt file,
+  /// and whether the states are as built: the edges of each sorted by label for the binary search,
+  /// and leading to states numbered before it, so that `complete` can't go around a cycle.
+  fn check(&self) -> bool {
+    let states = self.edge_offsets.len().saturating_sub(1);
+    let monotonic = |offsets: &[u32], len: usize| offsets.first() == Some(&0)
+      && offsets.windows(2).all(|w| w[0] <= w[1])
+      && offsets.last().is_some_and(|&last| last as usize == len);
+    monotonic(&self.edge_offsets, self.edge_labels.len())
+      && self.edge_labels.len() == self.edge_targets.len()
+      && self.output_offsets.len() == states + 1
+      && monotonic(&self.output_offsets, self.outputs.len())
+      && self.outputs.len() == self.weights.len()
+      && monotonic(&self.word_offsets, self.text.len())
+      && self.word_offsets.iter().all(|&offset| self.text.is_char_boundary(offset as usize))
+      && (self.root as usize) < states
+      && self.edge_targets.iter().all(|&target| (target as usize) < states)
+      && self.outputs.iter().all(|&word| (word as usize) + 1 < self.word_offsets.len())
+      && (0..states as u32).all(|state| {
+        let edges = Self::range(&self.edge_offsets, state);
+        self.edge_labels[edges.clone()].windows(2).all(|w| w[0] < w[1])
+          && self.edge_targets[edges].iter().all(|&target| target < state)
+      })
+  }
+}
+
+fn write_len(writer: &mut impl Write, len: usize) -> io::Result<()> {
+  let len = u32::try_from(len).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "dict too large"))?;
+  writer.write_all(&len.to_le_bytes())
+}
+
+fn read_u32(reader: &mut impl Read) -> io::Result<u32> {
+  let mut bytes = [0; 4];
+  reader.read_exact(&mut bytes)?;
+  Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+  let mut bytes = [0; 8];
+  reader.read_exact(&mut bytes)?;
+  Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_bytes(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+  let len = read_u32(reader)? as usize;
+  let mut bytes = vec![0; len];
+  reader.read_exact(&mut bytes)?;
+  Ok(bytes)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::TrieBuilder;
+
+  #[test]
+  fn test_compact() {
+    let trie = TrieBuilder::new()
+      .entry(("n", "你", 0))
+      .entry(("n", "那", 5))
+      .entry(("ni", "你们", 0))
+      .entry(("wi", "我们", 0))
+      .entry(("xhni", "瞎胡闹", 0))
+      .entry(("xhn", "喜欢你", 0))
+      .build();
+    let dict = CompactDict::from_trie(&trie);
+    assert_eq!(trie.node("n").unwrap().words().iter().map(String::as_str).collect::<Vec<_>>(),
+               dict.get("n").iter().map(|(word, _)| *word).collect::<Vec<_>>());
+    assert_eq!(vec![("我们", 0)], dict.get("wi"));
+    assert!(dict.get("x").is_empty() && dict.get("q").is_empty());
+    assert_eq!(vec![("xhn".to_string(), "喜欢你"), ("xhni".to_string(), "瞎胡闹")], dict.complete("x", 10));
+    assert_eq!(3, dict.complete("", 3).len());
+
+    let mut bytes = vec![];
+    dict.write(&mut bytes).unwrap();
+    let loaded = CompactDict::read(&mut bytes.as_slice()).unwrap();
+    assert_eq!(dict, loaded);
+    bytes.truncate(bytes.len() - 1);
+    assert!(CompactDict::read(&mut bytes.as_slice()).is_err());
+
+    let corrupt = |corrupt: fn(&mut CompactDict)| {
+      let mut dict = dict.clone();
+      corrupt(&mut dict);
+      let mut bytes = vec![];
+      dict.write(&mut bytes).unwrap();
+      CompactDict::read(&mut bytes.as_slice()).is_err()
+    };
+    // the root going back to itself, and its edges out of order
+    assert!(corrupt(|dict| *dict.edge_targets.last_mut().unwrap() = dict.root));
+    assert!(corrupt(|dict| dict.edge_labels.reverse()));
+  }
+
+  #[test]
+  fn test_shared_suffixes() {
+    // b and c lead to equal subtrees, stored once
+    let trie = TrieBuilder::from_iter([("ba", "八"), ("ca", "八"), ("b", "不"), ("c", "不")]).build();
+    let dict = CompactDict::from_trie(&trie);
+    assert_eq!(3, dict.states());
+    assert_eq!(vec![("八", 0)], dict.get("ca"));
+  }
+}
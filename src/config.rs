@@ -0,0 +1,190 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use crate::archive;
+use crate::category::Category;
+use crate::dict::{Separator, Versioning};
+use crate::lint::WordChars;
+use crate::normalize::Normalization;
+use crate::schema::Secondary;
+use crate::shape::Conventions;
+use crate::trie::{Duplicates, SelectKeys};
+use crate::weights::Rescaling;
+use crate::yaml;
+
+pub const CONFIG_FILE: &str = "smart-dict.yaml";
+
+/// Settings read from `smart-dict.yaml` in the Rime directory, e.g.
+///
+/// ```yaml
+/// budgets:
+///   xkjd6.user: 2000
+/// extra_tables:
+///   - phrases.tsv
+/// secondary_tables:  # see `Secondary`
+///   easy_en:
+///   xkjd6.symbols:
+///     prefix: "/"
+/// normalize:
+///   lowercase: true
+///   half_width: true
+///   trim: true
+/// conventions:  # see `Conventions`
+///   ...
+/// versioning: date  # or semver
+/// duplicates: ignore  # keep, replace or keep-highest
+/// separators:  # of the tables, tab or spaces are detected otherwise
+///   phrases.txt: "|"
+/// weights: rank  # or linear, then :<max> if not 10000, see `Rescaling`
+/// categories:  # see `Category`
+///   tech:
+///     table: xkjd6.tech
+///     weight: 100
+///     note: 专业词
+/// select_keys:  # the keys picking the first candidate, the second...
+///   - " 1"
+///   - ";2"
+///   - "3"
+/// word_chars:  # what words may be made of, see `WordChars`
+///   - cjk
+///   - cjk-punct
+///   - U+00B7
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Config {
+  /// Maximum number of entries per table.
+  pub budgets: BTreeMap<String, usize>,
+  /// Tables to analyze along with the schema's, see `Schema::extra_tables`.
+  pub extra_tables: Vec<String>,
+  /// English words, symbols and other tables with codes of their own, see `Schema::secondary_tables`.
+  pub secondary_tables: Vec<Secondary>,
+  pub normalization: Normalization,
+  pub conventions: Conventions,
+  /// Bumps the version of dicts changed by commands, which also log the change in their header.
+  pub versioning: Option<Versioning>,
+  /// What loading a word twice on the same code does, see `Schema::duplicates`.
+  pub duplicates: Duplicates,
+  /// See `Schema::separators`.
+  pub separators: BTreeMap<String, Separator>,
+  /// Rescales the weights of the tables as they're loaded, and for `normalize-weights`.
+  pub weights: Option<Rescaling>,
+  /// Which keys select which candidate, for eval and the encoding of sentences.
+  pub select_keys: SelectKeys,
+  /// Classes of words added by `add-word`, by name.
+  pub categories: BTreeMap<String, Category>,
+  /// The characters of words, others being reported by `lint`; any character if not set.
+  pub word_chars: Option<WordChars>,
+}
+
+fn invalid(msg: String) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl Config {
+  /// Loads the config, or the default one if the file doesn't exist.
+  pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+    match archive::read_to_string(path) {
+      Ok(text) => Self::parse(&text),
+      Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Default::default()),
+      Err(e) => Err(e),
+    }
+  }
+
+  pub fn parse(text: &str) -> io::Result<Self> {
+    let doc = yaml::parse(text);
+    let mut config = Self::default();
+    if let Some(budgets) = doc.get("budgets") {
+      for (table, budget) in budgets.entries() {
+        let budget = budget
+          .as_str()
+          .and_then(|s| s.parse().ok())
+          .ok_or_else(|| invalid(format!("budget of {table} should be a number")))?;
+        config.budgets.insert(table.clone(), budget);
+      }
+    }
+    if let Some(tables) = doc.get("extra_tables") {
+      config.extra_tables = tables
+        .as_list()
+        .iter()
+        .filter_map(yaml::Value::as_str)
+        .map(String::from)
+        .collect();
+    }
+    if let Some(tables) = doc.get("secondary_tables") {
+      config.secondary_tables = tables
+        .entries()
+        .iter()
+        .map(|(table, settings)| Secondary {
+          table: table.clone(),
+          prefix: settings.get("prefix").and_then(yaml::Value::as_str).unwrap_or_default().to_string(),
+        })
+        .collect();
+    }
+    if let Some(normalize) = doc.get("normalize") {
+      let flag = |name: &str| match normalize.get(name).and_then(yaml::Value::as_str) {
+        None | Some("false") => Ok(false),
+        Some("true") => Ok(true),
+        Some(other) => Err(invalid(format!("normalize/{name} should be true or false, not {other}"))),
+      };
+      config.normalization = Normalization {
+        lowercase: flag("lowercase")?,
+        half_width: flag("half_width")?,
+        trim: flag("trim")?,
+      };
+    }
+    if let Some(conventions) = doc.get("conventions") {
+      config.conventions = Conventions::parse(conventions)?;
+    }
+    if let Some(versioning) = doc.get("versioning").and_then(yaml::Value::as_str) {
+      config.versioning = Some(versioning.parse().map_err(invalid)?);
+    }
+    if let Some(duplicates) = doc.get("duplicates").and_then(yaml::Value::as_str) {
+      config.duplicates = duplicates.parse().map_err(invalid)?;
+    }
+    if let Some(separators) = doc.get("separators") {
+      for (table, separator) in separators.entries() {
+        let separator = separator.as_str().unwrap_or_default().parse().map_err(|e| invalid(format!("separators/{table}: {e}")))?;
+        config.separators.insert(table.clone(), separator);
+      }
+    }
+    if let Some(weights) = doc.get("weights").and_then(yaml::Value::as_str) {
+      config.weights = Some(weights.parse().map_err(invalid)?);
+    }
+    if let Some(categories) = doc.get("categories") {
+      for (name, category) in categories.entries() {
+        config.categories.insert(name.clone(), Category::parse(name, category)?);
+      }
+    }
+    if let Some(select_keys) = doc.get("select_keys") {
+      let by_index = select_keys
+        .as_list()
+        .iter()
+        .map(|keys| keys.as_str().map(String::from).ok_or_else(|| invalid("select_keys should list strings of keys".to_string())))
+        .collect::<io::Result<_>>()?;
+      config.select_keys = SelectKeys::new(by_index).map_err(|e| invalid(format!("select_keys: {e}")))?;
+    }
+    if let Some(word_chars) = doc.get("word_chars") {
+      let items = word_chars
+        .as_list()
+        .iter()
+        .map(|item| item.as_str().ok_or_else(|| invalid("word_chars should list sets or ranges of characters".to_string())))
+        .collect::<io::Result<Vec<_>>>()?;
+      config.word_chars = Some(WordChars::new(items).map_err(|e| invalid(format!("word_chars: {e}")))?);
+    }
+    Ok(config)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_budgets() {
+    let config = Config::parse("budgets:\n  xkjd6.user: 2000\n  xkjd6.cizu: 50000\n").unwrap();
+    assert_eq!(BTreeMap::from([("xkjd6.cizu".to_string(), 50000), ("xkjd6.user".to_string(), 2000)]), config.budgets);
+    assert!(Config::parse("extra_tables:\n  - phrases.tsv\n").unwrap().budgets.is_empty());
+    let error = Config::parse("budgets:\n  xkjd6.user: lots\n").unwrap_err();
+    assert_eq!("budget of xkjd6.user should be a number", error.to_string());
+  }
+}
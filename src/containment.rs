@@ -0,0 +1,75 @@
+//! Phrases the dict could do without: typed piece by piece with shorter entries, they take no
+//! more keys, or barely more, than with their own code, so that deleting them slims the dict at
+//! little cost to the typist.
+
+use crate::cost::{word_cost, Cost};
+use crate::rev_dict::RevDict;
+use crate::types::{Code, Word};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contained {
+  pub word: Word,
+  /// Typing the phrase with its own code.
+  pub cost: Cost,
+  /// The pieces it's typed with otherwise and their codes, commit keys included.
+  pub pieces: Vec<(Word, Code)>,
+  pub piece_keys: usize,
+}
+
+impl Contained {
+  /// The keys the phrase's own entry saves, negative if the pieces are shorter.
+  pub fn saved(&self) -> isize {
+    self.piece_keys as isize - self.cost.keystrokes() as isize
+  }
+}
+
+/// How the phrase is typed without its own entry, `None` if it can't be or is a single character.
+pub fn containment(rev_dict: &RevDict, word: &str) -> Option<Contained> {
+  if word.chars().count() < 2 {
+    return None;
+  }
+  let cost = word_cost(rev_dict, word)?;
+  let segments = rev_dict.segment_without(word, word).ok()?;
+  let piece_keys = segments
+    .iter()
+    .map(|segment| segment.code.len() + word_cost(rev_dict, &segment.word).map_or(0, |cost| cost.select_keys()))
+    .sum();
+  let pieces = segments.into_iter().map(|segment| (segment.word, segment.code)).collect();
+  Some(Contained { word: word.to_string(), cost, pieces, piece_keys })
+}
+
+/// The phrases whose own entry saves at most `slack` keys, those saving the least first.
+pub fn contained(rev_dict: &RevDict, slack: usize) -> Vec<Contained> {
+  let mut contained: Vec<_> = rev_dict
+    .words()
+    .filter_map(|word| containment(rev_dict, word))
+    .filter(|contained| contained.saved() <= slack as isize)
+    .collect();
+  contained.sort_by(|a, b| a.saved().cmp(&b.saved()).then_with(|| a.word.cmp(&b.word)));
+  contained
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::Trie;
+
+  #[test]
+  fn test_contained() {
+    let mut trie = Trie::new();
+    for (code, word) in [("w", "我"), ("m", "们"), ("wmz", "我们"), ("f", "非"), ("c", "常"), ("fc", "飞船"), ("fcv", "非常"), ("fcb", "非常好"), ("h", "好")] {
+      trie.insert(code.to_string(), word.to_string());
+    }
+    let rev_dict = trie.rev_dict();
+
+    let contained = contained(&rev_dict, 0);
+    let words: Vec<_> = contained.iter().map(|contained| contained.word.as_str()).collect();
+    assert_eq!(vec!["我们", "非常"], words);
+    assert_eq!(-1, contained[0].saved());
+    assert_eq!(vec![("我".to_string(), "w".to_string()), ("们".to_string(), "m".to_string())], contained[0].pieces);
+    // 非常 + 好 takes 4 keys against 3
+    assert_eq!(1, containment(&rev_dict, "非常好").unwrap().saved());
+    assert_eq!(None, containment(&rev_dict, "我"));
+  }
+}
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// Pairs of (simplified, traditional) characters used when no OpenCC table is loaded.
+const BUILTIN_S2T: &str = "\
+万萬 与與 专專 业業 东東 丝絲 两兩 严嚴 个個 临臨 为為 丽麗 举舉 么麼 义義 乐樂 习習 书書 买買 乱亂 争爭 亏虧 亚亞 \
+产產 亲親 亿億 仅僅 从從 仓倉 们們 价價 众眾 优優 会會 伞傘 传傳 伤傷 伦倫 体體 侠俠 侣侶 侦偵 储儲 兑兌 党黨 兰蘭 \
+关關 兴興 养養 兽獸 内內 冈岡 册冊 写寫 军軍 农農 冯馮 决決 况況 冻凍 净淨 减減 凤鳳 凭憑 击擊 刘劉 则則 刚剛 创創 \
+删刪 别別 刹剎 刽劊 剧劇 劝勸 办辦 务務 动動 励勵 劳勞 势勢 区區 医醫 华華 协協 单單 卖賣 卢盧 卫衛 厂廠 压壓 厌厭 \
+县縣 参參 双雙 变變 叙敘 叶葉 号號 叹嘆 吓嚇 吗嗎 听聽 启啟 员員 问問 响響 哑啞 喷噴 团團 园園 围圍 国國 图圖 圆圓 \
+圣聖 场場 坏壞 块塊 坚堅 坛壇 坝壩 报報 垄壟 垒壘 声聲 处處 备備 复復 头頭 夹夾 夺奪 奋奮 奖獎 妇婦 妈媽 孙孫 学學 \
+宁寧 实實 审審 宪憲 宫宮 宽寬 宾賓 对對 寻尋 导導 将將 尔爾 尘塵 尝嘗 层層 属屬 岁歲 岂豈 岛島 帅帥 师師 带帶 帮幫 \
+广廣 庆慶 库庫 应應 废廢 开開 异異 弃棄 张張 弹彈 强強 归歸 当當 录錄 彦彥 彻徹 忆憶 忧憂 怀懷 态態 总總 恋戀 恶惡 \
+悬懸 惊驚 惯慣 愿願 戏戲 战戰 户戶 执執 扩擴 扫掃 扬揚 护護 担擔 拟擬 择擇 挂掛 损損 换換 据據 摄攝 敌敵 数數 断斷 \
+无無 旧舊 时時 显顯 晓曉 暂暫 术術 机機 杀殺 杂雜 权權 条條 来來 杨楊 极極 构構 标標 树樹 样樣 档檔 桥橋 检檢 楼樓 \
+欢歡 欧歐 残殘 毕畢 气氣 汇匯 汉漢 汤湯 沟溝 没沒 沪滬 泪淚 泽澤 洁潔 浅淺 测測 济濟 浓濃 涛濤 润潤 渐漸 温溫 湾灣 \
+满滿 滚滾 灭滅 灯燈 灵靈 灾災 炉爐 点點 炼煉 热熱 爱愛 爷爺 牵牽 状狀 犹猶 独獨 狮獅 猎獵 献獻 环環 现現 电電 画畫 \
+畅暢 疗療 盖蓋 盘盤 矿礦 码碼 础礎 确確 礼禮 祸禍 离離 种種 积積 称稱 稳穩 穷窮 窃竊 竞競 笔筆 笼籠 筑築 签簽 简簡 \
+类類 粮糧 紧緊 纠糾 红紅 约約 级級 纪紀 纯純 纲綱 纳納 纵縱 纷紛 纸紙 线線 练練 组組 细細 织織 终終 经經 结結 给給 \
+络絡 绝絕 统統 继繼 绩績 续續 维維 综綜 绿綠 编編 缘緣 网網 罗羅 罚罰 职職 联聯 肃肅 胜勝 脑腦 脚腳 脸臉 节節 艺藝 \
+苏蘇 茧繭 荣榮 药藥 获獲 营營 虑慮 虽雖 蚁蟻 补補 装裝 见見 观觀 规規 视視 览覽 觉覺 计計 认認 让讓 议議 记記 讲講 \
+许許 论論 设設 访訪 证證 评評 识識 诉訴 词詞 译譯 试試 诗詩 话話 询詢 该該 详詳 语語 误誤 说說 请請 读讀 课課 谁誰 \
+调調 谈談 谢謝 负負 贡貢 财財 责責 败敗 货貨 质質 购購 贵貴 费費 贸貿 资資 赛賽 赞贊 赶趕 趋趨 跃躍 践踐 车車 轨軌 \
+转轉 轮輪 软軟 轻輕 载載 较較 辆輛 辈輩 边邊 达達 过過 运運 还還 这這 进進 远遠 违違 连連 迟遲 适適 选選 递遞 邓鄧 \
+邮郵 邻鄰 郑鄭 酱醬 释釋 针針 钟鐘 钢鋼 钱錢 铁鐵 银銀 错錯 锁鎖 锅鍋 镇鎮 长長 门門 闭閉 闲閒 间間 闻聞 阅閱 队隊 \
+阳陽 阴陰 阵陣 阶階 际際 陆陸 险險 随隨 隐隱 难難 雾霧 静靜 页頁 顶頂 项項 顺順 须須 顾顧 顿頓 预預 领領 频頻 题題 \
+颜顏 风風 飞飛 饭飯 饮飲 馆館 马馬 驱驅 验驗 骑騎 鱼魚 鲜鮮 鸟鳥 鸡雞 鸣鳴 麦麥 黄黃 齐齊 齿齒 龙龍 龟龜";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Script {
+  Simplified,
+  Traditional,
+}
+
+/// An OpenCC-style character/phrase converter between simplified and traditional Chinese.
+#[derive(Default)]
+pub struct Converter {
+  s2t: HashMap<char, char>,
+  t2s: HashMap<char, char>,
+  s2t_phrases: HashMap<String, String>,
+  t2s_phrases: HashMap<String, String>,
+  max_phrase_len: usize,
+}
+
+impl Converter {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  pub fn builtin() -> Self {
+    let mut converter = Self::new();
+    for pair in BUILTIN_S2T.split_whitespace() {
+      let mut chars = pair.chars();
+      if let (Some(s), Some(t)) = (chars.next(), chars.next()) {
+        converter.insert_char(s, t);
+      }
+    }
+    converter
+  }
+
+  pub fn insert_char(&mut self, simplified: char, traditional: char) {
+    if simplified == traditional {
+      return;
+    }
+    self.s2t.entry(simplified).or_insert(traditional);
+    self.t2s.entry(traditional).or_insert(simplified);
+  }
+
+  pub fn insert_phrase(&mut self, simplified: &str, traditional: &str) {
+    self.max_phrase_len = self.max_phrase_len
+      .max(simplified.chars().count())
+      .max(traditional.chars().count());
+    self.s2t_phrases.insert(simplified.to_string(), traditional.to_string());
+    self.t2s_phrases.insert(traditional.to_string(), simplified.to_string());
+  }
+
+  /// Loads an OpenCC dictionary such as `STCharacters.txt` or `STPhrases.txt`,
+  /// where each line is `key\tvalue [alternatives...]` and `from` is the script of the keys.
+  pub fn load_opencc(&mut self, path: impl AsRef<Path>, from: Script) -> io::Result<()> {
+    for line in BufReader::new(File::open(path)?).lines() {
+      let line = line?;
+      let Some((key, values)) = line.split_once('\t') else { continue };
+      let Some(value) = values.split(' ').next() else { continue };
+      let (simplified, traditional) = match from {
+        Script::Simplified => (key, value),
+        Script::Traditional => (value, key),
+      };
+      let mut s_chars = simplified.chars();
+      let mut t_chars = traditional.chars();
+      match (s_chars.next(), s_chars.next(), t_chars.next(), t_chars.next()) {
+        (Some(s), None, Some(t), None) => self.insert_char(s, t),
+        _ => self.insert_phrase(simplified, traditional),
+      }
+    }
+    Ok(())
+  }
+
+  /// Returns the script a character exclusively belongs to,
+  /// or `None` if it is shared by both scripts or unknown to the tables.
+  pub fn script_of(&self, ch: char) -> Option<Script> {
+    match (self.s2t.contains_key(&ch), self.t2s.contains_key(&ch)) {
+      (true, false) => Some(Script::Simplified),
+      (false, true) => Some(Script::Traditional),
+      _ => None,
+    }
+  }
+
+  pub fn is_mixed(&self, text: &str) -> bool {
+    let mut seen = None;
+    for script in text.chars().filter_map(|ch| self.script_of(ch)) {
+      match seen {
+        None => seen = Some(script),
+        Some(prev) if prev != script => return true,
+        _ => {}
+      }
+    }
+    false
+  }
+
+  pub fn convert(&self, text: &str, to: Script) -> String {
+    let (chars, phrases) = match to {
+      Script::Simplified => (&self.t2s, &self.t2s_phrases),
+      Script::Traditional => (&self.s2t, &self.s2t_phrases),
+    };
+    let text: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len() * 3);
+    let mut i = 0;
+    'outer: while i < text.len() {
+      // 最长匹配词组
+      for len in (2..=self.max_phrase_len.min(text.len() - i)).rev() {
+        let phrase: String = text[i..i + len].iter().collect();
+        if let Some(converted) = phrases.get(&phrase) {
+          output.push_str(converted);
+          i += len;
+          continue 'outer;
+        }
+      }
+      output.push(*chars.get(&text[i]).unwrap_or(&text[i]));
+      i += 1;
+    }
+    output
+  }
+
+  pub fn to_simplified(&self, text: &str) -> String {
+    self.convert(text, Script::Simplified)
+  }
+
+  pub fn to_traditional(&self, text: &str) -> String {
+    self.convert(text, Script::Traditional)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_convert() {
+    let mut converter = Converter::builtin();
+    assert_eq!("我們喜歡你", converter.to_traditional("我们喜欢你"));
+    assert_eq!("我们喜欢你", converter.to_simplified("我們喜歡你"));
+
+    converter.insert_phrase("头发", "頭髮");
+    assert_eq!("頭髮很長", converter.to_traditional("头发很长"));
+  }
+
+  #[test]
+  fn test_is_mixed() {
+    let converter = Converter::builtin();
+    assert!(!converter.is_mixed("我们"));
+    assert!(!converter.is_mixed("我們"));
+    assert!(converter.is_mixed("们會"));
+    assert_eq!(None, converter.script_of('我'));
+  }
+}
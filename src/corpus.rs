@@ -0,0 +1,420 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use crate::encoding::Encoding;
+use crate::progress::{Progress, Stage};
+use crate::rev_dict::{RevDict, Segment};
+
+/// Typing statistics of a corpus encoded with the shortest codes.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CorpusStats {
+  /// Characters that could be encoded.
+  pub chars: usize,
+  pub keystrokes: usize,
+  pub commits: usize,
+  pub auto_commits: usize,
+  /// Characters that appear in no word of the dictionary, or in text the dictionary can't produce.
+  pub unencoded: usize,
+}
+
+impl CorpusStats {
+  pub fn keys_per_char(&self) -> f64 {
+    self.keystrokes as f64 / self.chars as f64
+  }
+
+  pub fn auto_commit_rate(&self) -> f64 {
+    self.auto_commits as f64 / self.commits as f64
+  }
+
+  pub fn coverage(&self) -> f64 {
+    self.chars as f64 / (self.chars + self.unencoded) as f64
+  }
+
+  fn add(&mut self, other: &Self) {
+    self.chars += other.chars;
+    self.keystrokes += other.keystrokes;
+    self.commits += other.commits;
+    self.auto_commits += other.auto_commits;
+    self.unencoded += other.unencoded;
+  }
+}
+
+/// The characters some word of a dictionary contains.
+pub struct Alphabet(HashSet<char>);
+
+impl Alphabet {
+  /// Capitals are known as well as their lowercase letters, see `RevDict::get`.
+  pub fn new(rev_dict: &RevDict) -> Self {
+    let chars: HashSet<_> = rev_dict.words().flat_map(|word| word.chars()).collect();
+    let capitals: Vec<_> = chars.iter().filter(|ch| ch.is_ascii_lowercase()).map(char::to_ascii_uppercase).collect();
+    Self(chars.into_iter().chain(capitals).collect())
+  }
+
+  pub fn contains(&self, ch: char) -> bool {
+    self.0.contains(&ch)
+  }
+
+  /// Splits a line at whitespace and characters the dictionary doesn't know.
+  pub fn pieces<'l>(&'l self, line: &'l str) -> impl Iterator<Item=&'l str> {
+    line
+      .split(|ch: char| ch.is_whitespace() || !self.contains(ch))
+      .filter(|piece| !piece.is_empty())
+  }
+}
+
+/// How often `analyze_file_with_progress` reports.
+const PROGRESS_LINES: u64 = 1 << 12;
+
+/// Pieces longer than this are encoded every time, they seldom recur.
+const MAX_CACHED_CHARS: usize = 16;
+/// Pieces remembered at most, the cache starts over when full.
+const CACHE_LEN: usize = 1 << 16;
+
+pub struct Analyzer<'a, 'b> {
+  rev_dict: &'b RevDict<'a>,
+  alphabet: Alphabet,
+  /// The stats of short pieces already encoded, chat logs repeat 好的 and 哈哈哈 all the time.
+  cache: HashMap<String, CorpusStats>,
+  pub stats: CorpusStats,
+}
+
+impl<'a, 'b> Analyzer<'a, 'b> {
+  pub fn new(rev_dict: &'b RevDict<'a>) -> Self {
+    Self {
+      rev_dict,
+      alphabet: Alphabet::new(rev_dict),
+      cache: HashMap::new(),
+      stats: Default::default(),
+    }
+  }
+
+  fn encode(&self, piece: &str) -> CorpusStats {
+    Self::tally(piece, &self.rev_dict.segment(piece))
+  }
+
+  /// The stats of a piece encoded as `segments`.
+  fn tally(piece: &str, segments: &Result<Vec<Segment>, String>) -> CorpusStats {
+    let chars = piece.chars().count();
+    match segments {
+      Ok(segments) => {
+        let mut keystrokes = segments.iter().map(|segment| segment.code.len()).sum::<usize>();
+        if segments.last().is_some_and(|last| !last.is_auto_commit()) {
+          keystrokes += 1;
+        }
+        CorpusStats {
+          chars,
+          keystrokes,
+          commits: segments.len(),
+          auto_commits: segments.iter().filter(|segment| segment.is_auto_commit()).count(),
+          unencoded: 0,
+        }
+      }
+      Err(_) => CorpusStats { unencoded: chars, ..Default::default() },
+    }
+  }
+
+  /// Encodes a line piece by piece, see [`Alphabet::pieces`].
+  pub fn add_line(&mut self, line: &str) {
+    let line = self.rev_dict.convert(line);
+    let line = line.as_ref();
+    for piece in self.alphabet.pieces(line) {
+      if piece.chars().nth(MAX_CACHED_CHARS).is_some() {
+        let stats = self.encode(piece);
+        self.stats.add(&stats);
+        continue;
+      }
+      if let Some(stats) = self.cache.get(piece) {
+        self.stats.add(stats);
+        continue;
+      }
+      let stats = self.encode(piece);
+      self.stats.add(&stats);
+      if self.cache.len() == CACHE_LEN {
+        self.cache.clear();
+      }
+      self.cache.insert(piece.to_string(), stats);
+    }
+    self.stats.unencoded += self.foreign_chars(line);
+  }
+
+  /// The characters of a line outside the dictionary's alphabet, blanks aside.
+  fn foreign_chars(&self, line: &str) -> usize {
+    line.chars().filter(|&ch| !ch.is_whitespace() && !self.alphabet.contains(ch)).count()
+  }
+}
+
+impl Analyzer<'_, '_> {
+  /// Encodes a line like [`Self::add_line`], returning it with the keys of each word after it,
+  /// like `我们(wi)非常(fio)`, blanks shown as `␣`. Text the dictionary can't type stays as it is.
+  pub fn annotate(&mut self, line: &str) -> String {
+    let line = self.rev_dict.convert(line);
+    let line = line.as_ref();
+    let is_piece = |ch: char| !ch.is_whitespace() && self.alphabet.contains(ch);
+    let mut annotated = String::new();
+    let mut rest = line;
+    while !rest.is_empty() {
+      let start = rest.find(is_piece).unwrap_or(rest.len());
+      annotated.push_str(&rest[..start]);
+      rest = &rest[start..];
+      let end = rest.find(|ch: char| !is_piece(ch)).unwrap_or(rest.len());
+      let piece = &rest[..end];
+      if piece.is_empty() {
+        break;
+      }
+      // the segments annotated are the ones counted, rather than encoding the piece again
+      let segments = self.rev_dict.segment(piece);
+      self.stats.add(&Self::tally(piece, &segments));
+      match segments {
+        Ok(segments) => {
+          for segment in segments {
+            annotated.push_str(&format!("{}({})", segment.word, segment.code.replace(' ', "␣")));
+          }
+        }
+        Err(_) => annotated.push_str(piece),
+      }
+      rest = &rest[end..];
+    }
+    self.stats.unencoded += self.foreign_chars(line);
+    annotated
+  }
+}
+
+pub fn analyze<S: AsRef<str>>(rev_dict: &RevDict, lines: impl IntoIterator<Item=S>) -> CorpusStats {
+  let mut analyzer = Analyzer::new(rev_dict);
+  for line in lines {
+    analyzer.add_line(line.as_ref());
+  }
+  analyzer.stats
+}
+
+/// Where a streamed analysis got to, enough to resume it.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Checkpoint {
+  /// Size of the corpus file when the run started, a resumed run refuses a changed file.
+  pub corpus_len: u64,
+  /// Bytes of the corpus already analyzed.
+  pub offset: u64,
+  pub lines: u64,
+  /// Malformed byte sequences read as U+FFFD so far.
+  pub decode_errors: u64,
+  pub stats: CorpusStats,
+}
+
+impl Checkpoint {
+  pub fn load(path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+    let file = match File::open(path) {
+      Ok(file) => file,
+      Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+      Err(e) => return Err(e),
+    };
+    let mut values = BTreeMap::new();
+    for line in BufReader::new(file).lines() {
+      let line = line?;
+      if let Some((key, value)) = line.split_once('\t') {
+        let value: u64 = value.parse()
+          .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, format!("bad checkpoint line {line}")))?;
+        values.insert(key.to_string(), value);
+      }
+    }
+    let get = |key: &str| values.get(key).copied().unwrap_or_default();
+    Ok(Some(Self {
+      corpus_len: get("corpus_len"),
+      offset: get("offset"),
+      lines: get("lines"),
+      decode_errors: get("decode_errors"),
+      stats: CorpusStats {
+        chars: get("chars") as usize,
+        keystrokes: get("keystrokes") as usize,
+        commits: get("commits") as usize,
+        auto_commits: get("auto_commits") as usize,
+        unencoded: get("unencoded") as usize,
+      },
+    }))
+  }
+
+  pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+    if let Some(dir) = path.as_ref().parent() {
+      fs::create_dir_all(dir)?;
+    }
+    let stats = &self.stats;
+    let mut writer = BufWriter::new(File::create(path)?);
+    for (key, value) in [
+      ("corpus_len", self.corpus_len),
+      ("offset", self.offset),
+      ("lines", self.lines),
+      ("decode_errors", self.decode_errors),
+      ("chars", stats.chars as u64),
+      ("keystrokes", stats.keystrokes as u64),
+      ("commits", stats.commits as u64),
+      ("auto_commits", stats.auto_commits as u64),
+      ("unencoded", stats.unencoded as u64),
+    ] {
+      writeln!(writer, "{key}\t{value}")?;
+    }
+    writer.flush()
+  }
+}
+
+/// Analyzes a corpus file in `encoding`, see [`Encoding::detect_file`], line by line from `start` on,
+/// so memory stays bounded by the longest line.
+///
+/// Every `chunk` lines `on_chunk` gets the progress so far, e.g. to report it or save it as a checkpoint.
+pub fn analyze_file(
+  rev_dict: &RevDict,
+  path: impl AsRef<Path>,
+  encoding: Encoding,
+  start: Checkpoint,
+  chunk: u64,
+  mut on_chunk: impl FnMut(&Checkpoint) -> io::Result<()>,
+) -> io::Result<Checkpoint> {
+  let mut file = File::open(path)?;
+  file.seek(SeekFrom::Start(start.offset))?;
+  let mut reader = BufReader::new(file);
+  let mut analyzer = Analyzer::new(rev_dict);
+  analyzer.stats = start.stats.clone();
+  let mut checkpoint = start;
+  if checkpoint.offset == 0 && reader.fill_buf()?.starts_with(encoding.bom()) {
+    reader.consume(encoding.bom().len());
+    checkpoint.offset = encoding.bom().len() as u64;
+  }
+  for_each_line(reader, encoding, |line, len, errors| {
+    analyzer.add_line(line);
+    checkpoint.offset += len as u64;
+    checkpoint.lines += 1;
+    checkpoint.decode_errors += errors as u64;
+    if chunk > 0 && checkpoint.lines.is_multiple_of(chunk) {
+      checkpoint.stats = analyzer.stats.clone();
+      on_chunk(&checkpoint)?;
+    }
+    Ok(())
+  })?;
+  checkpoint.stats = analyzer.stats;
+  Ok(checkpoint)
+}
+
+/// Like [`analyze_file`] from the start, reporting the bytes read every `PROGRESS_LINES` lines.
+pub fn analyze_file_with_progress(
+  rev_dict: &RevDict,
+  path: impl AsRef<Path>,
+  encoding: Encoding,
+  mut progress: impl FnMut(Progress),
+) -> io::Result<Checkpoint> {
+  let total = fs::metadata(path.as_ref())?.len();
+  let done = analyze_file(rev_dict, path, encoding, Checkpoint::default(), PROGRESS_LINES, |checkpoint| {
+    progress(Progress::new(Stage::Encode, checkpoint.offset, total));
+    Ok(())
+  })?;
+  progress(Progress::new(Stage::Encode, total, total));
+  Ok(done)
+}
+
+/// Calls `f` with each line decoded, its length in bytes and the malformed sequences in it,
+/// reusing the buffers.
+pub fn for_each_line(
+  mut reader: impl BufRead,
+  encoding: Encoding,
+  mut f: impl FnMut(&str, usize, usize) -> io::Result<()>,
+) -> io::Result<()> {
+  let mut bytes = Vec::new();
+  let mut line = String::new();
+  loop {
+    bytes.clear();
+    line.clear();
+    let len = encoding.read_line(&mut reader, &mut bytes)?;
+    if len == 0 {
+      return Ok(());
+    }
+    // 语料里偶尔有坏字节，不值得为此中断几个 G 的统计
+    let errors = encoding.decode(&bytes, &mut line);
+    f(&line, len, errors)?;
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::Trie;
+
+  #[test]
+  fn test_analyze() {
+    let mut trie = Trie::new();
+    trie.insert("w".to_string(), "我".to_string());
+    trie.insert("wi".to_string(), "我们".to_string());
+    trie.insert("fio".to_string(), "非常".to_string());
+    let rev_dict = trie.rev_dict();
+
+    let stats = analyze(&rev_dict, ["我们非常 我", "abc非常"]);
+    assert_eq!(CorpusStats {
+      chars: 7,
+      keystrokes: 2 + 3 + 2 + 3,
+      commits: 4,
+      auto_commits: 3,
+      unencoded: 3,
+    }, stats);
+    // repeated pieces come from the cache
+    let mut twice = stats.clone();
+    twice.add(&stats);
+    assert_eq!(twice, analyze(&rev_dict, ["我们非常 我", "abc非常", "我们非常 我", "abc非常"]));
+
+    let mut analyzer = Analyzer::new(&rev_dict);
+    assert_eq!("我们(wi)非常(fio) 我(w)我(w)，abc非常(fio)", analyzer.annotate("我们非常 我我，abc非常"));
+    assert_eq!(analyze(&rev_dict, ["我们非常 我我，abc非常"]), analyzer.stats);
+  }
+
+  #[test]
+  fn test_analyze_file_resume() {
+    let mut trie = Trie::new();
+    trie.insert("w".to_string(), "我".to_string());
+    trie.insert("wi".to_string(), "我们".to_string());
+    trie.insert("fio".to_string(), "非常".to_string());
+    let rev_dict = trie.rev_dict();
+
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let corpus = dir.join("corpus.txt");
+    fs::write(&corpus, "我们非常\n我\nabc非常\n我们\n").unwrap();
+    let whole = analyze_file(&rev_dict, &corpus, Encoding::Utf8, Checkpoint::default(), 0, |_| Ok(())).unwrap();
+
+    // interrupt after the second line, then resume from the saved checkpoint
+    let saved = dir.join("checkpoint");
+    let interrupted = analyze_file(&rev_dict, &corpus, Encoding::Utf8, Checkpoint::default(), 2, |checkpoint| {
+      checkpoint.save(&saved)?;
+      Err(io::Error::new(io::ErrorKind::Interrupted, "stop"))
+    });
+    assert!(interrupted.is_err());
+    let checkpoint = Checkpoint::load(&saved).unwrap().unwrap();
+    assert_eq!(2, checkpoint.lines);
+    let resumed = analyze_file(&rev_dict, &corpus, Encoding::Utf8, checkpoint, 0, |_| Ok(())).unwrap();
+
+    assert_eq!(whole, resumed);
+    assert_eq!(analyze(&rev_dict, ["我们非常", "我", "abc非常", "我们"]), whole.stats);
+  }
+
+  #[test]
+  fn test_analyze_file_encodings() {
+    let mut trie = Trie::new();
+    trie.insert("wi".to_string(), "我们".to_string());
+    trie.insert("fio".to_string(), "非常".to_string());
+    let rev_dict = trie.rev_dict();
+
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let utf16 = dir.join("utf16.txt");
+    let mut bytes = b"\xFF\xFE".to_vec();
+    bytes.extend("我们\n非常\n".encode_utf16().flat_map(u16::to_le_bytes));
+    fs::write(&utf16, bytes).unwrap();
+    // 我们 and 非常 in GBK with a stray byte
+    let gbk = dir.join("gbk.txt");
+    fs::write(&gbk, b"\xCE\xD2\xC3\xC7\n\xB7\xC7\xB3\xA3\xFF\n").unwrap();
+
+    for (path, encoding, lines, errors) in [(&utf16, Encoding::Utf16Le, ["我们", "非常"], 0), (&gbk, Encoding::Gbk, ["我们", "非常\u{FFFD}"], 1)] {
+      assert_eq!(encoding, Encoding::detect_file(path).unwrap());
+      let done = analyze_file(&rev_dict, path, encoding, Checkpoint::default(), 0, |_| Ok(())).unwrap();
+      assert_eq!((analyze(&rev_dict, lines), 2, errors), (done.stats, done.lines, done.decode_errors));
+      assert_eq!(fs::metadata(path).unwrap().len(), done.offset);
+    }
+  }
+}
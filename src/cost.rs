@@ -0,0 +1,68 @@
+use crate::rev_dict::RevDict;
+use crate::types::Code;
+
+/// Keystrokes needed to type a word on its own with its shortest code.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cost {
+  pub code: Code,
+  /// Position in the candidate list, 0 for the first candidate.
+  pub index: usize,
+  pub chars: usize,
+}
+
+impl Cost {
+  /// The first candidate is committed by whatever is typed next,
+  /// any other candidate takes one more key to select.
+  pub fn select_keys(&self) -> usize {
+    if self.index == 0 { 0 } else { 1 }
+  }
+
+  pub fn keystrokes(&self) -> usize {
+    self.code.len() + self.select_keys()
+  }
+
+  pub fn per_char(&self) -> f64 {
+    self.keystrokes() as f64 / self.chars as f64
+  }
+
+  pub fn is_forced_selection(&self) -> bool {
+    self.index > 0
+  }
+}
+
+pub fn word_cost(rev_dict: &RevDict, word: &str) -> Option<Cost> {
+  let (code, node) = rev_dict.lookup(word)?;
+  let index = node
+    .candidates(rev_dict.completion())
+    .position(|candidate| candidate == word)?;
+  Some(Cost {
+    code: code.clone(),
+    index,
+    chars: word.chars().count(),
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::Trie;
+
+  #[test]
+  fn test_word_cost() {
+    let mut trie = Trie::new();
+    trie.insert("fio".to_string(), "非常".to_string());
+    trie.insert("fio".to_string(), "飞船".to_string());
+    trie.insert("fiojp".to_string(), "飞船".to_string());
+    let rev_dict = trie.rev_dict();
+
+    let cost = word_cost(&rev_dict, "非常").unwrap();
+    assert_eq!(3, cost.keystrokes());
+    assert_eq!(1.5, cost.per_char());
+
+    let cost = word_cost(&rev_dict, "飞船").unwrap();
+    assert_eq!("fio", cost.code);
+    assert!(cost.is_forced_selection());
+    assert_eq!(4, cost.keystrokes());
+  }
+}
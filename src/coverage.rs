@@ -0,0 +1,112 @@
+//! How much of a corpus a dictionary can type at all, and how much of it as words of several
+//! characters rather than one character at a time: one number to compare phrase packs by before
+//! adopting one.
+
+use std::collections::HashMap;
+use crate::corpus::Alphabet;
+use crate::rev_dict::RevDict;
+
+/// The coverage of a corpus, its lines split into the words typed with the shortest codes like
+/// `corpus`, and runs of text the dictionary can't type, each such run one uncovered token.
+#[derive(Clone, Debug, Default)]
+pub struct Coverage {
+  /// Characters but blanks.
+  pub chars: usize,
+  /// Characters the dictionary can type.
+  pub covered_chars: usize,
+  /// Characters typed as words of two characters or more.
+  pub word_chars: usize,
+  /// Words typed and runs of text that can't be.
+  pub tokens: usize,
+  pub covered_tokens: usize,
+  /// The runs of text that can't be typed, and how often they occur.
+  uncovered: HashMap<String, usize>,
+}
+
+impl Coverage {
+  pub fn char_coverage(&self) -> f64 {
+    ratio(self.covered_chars, self.chars)
+  }
+
+  pub fn token_coverage(&self) -> f64 {
+    ratio(self.covered_tokens, self.tokens)
+  }
+
+  /// The share of the typed characters that are typed as words of several characters.
+  pub fn word_share(&self) -> f64 {
+    ratio(self.word_chars, self.covered_chars)
+  }
+
+  /// The `n` most frequent runs of text that can't be typed, then in the order of the runs.
+  pub fn top_uncovered(&self, n: usize) -> Vec<(&str, usize)> {
+    let mut uncovered: Vec<_> = self.uncovered.iter().map(|(token, &count)| (token.as_str(), count)).collect();
+    uncovered.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    uncovered.truncate(n);
+    uncovered
+  }
+
+  pub fn add_line(&mut self, rev_dict: &RevDict, alphabet: &Alphabet, line: &str) {
+    let line = rev_dict.convert(line);
+    let mut rest = line.trim_start();
+    while let Some(first) = rest.chars().next() {
+      // 字典里有的字连成一段去切分，没有的字连成一段算作一个未覆盖的词
+      let known = alphabet.contains(first);
+      let end = rest.find(|ch: char| ch.is_whitespace() || alphabet.contains(ch) != known).unwrap_or(rest.len());
+      let run = &rest[..end];
+      let chars = run.chars().count();
+      self.chars += chars;
+      match rev_dict.segment(run).ok().filter(|_| known) {
+        Some(segments) => {
+          self.covered_chars += chars;
+          self.tokens += segments.len();
+          self.covered_tokens += segments.len();
+          self.word_chars += segments
+            .iter()
+            .map(|segment| segment.word.chars().count())
+            .filter(|&len| len > 1)
+            .sum::<usize>();
+        }
+        None => {
+          self.tokens += 1;
+          *self.uncovered.entry(run.to_string()).or_default() += 1;
+        }
+      }
+      rest = rest[end..].trim_start();
+    }
+  }
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+  match whole {
+    0 => 0.0,
+    whole => part as f64 / whole as f64,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::Trie;
+
+  #[test]
+  fn test_coverage() {
+    let mut trie = Trie::new();
+    for (code, word) in [("w", "我"), ("wi", "我们"), ("f", "非"), ("fio", "非常"), ("x", "喜"), ("h", "欢")] {
+      trie.insert(code.to_string(), word.to_string());
+    }
+    let rev_dict = trie.rev_dict();
+    let alphabet = Alphabet::new(&rev_dict);
+    let mut coverage = Coverage::default();
+    // 我们 非常 喜 欢 | 𠮷你 | 我 | ABC | 常非, 常 having no code of its own
+    coverage.add_line(&rev_dict, &alphabet, "我们非常喜欢𠮷你 我ABC 常非");
+    coverage.add_line(&rev_dict, &alphabet, "\t𠮷你\n");
+    assert_eq!(16, coverage.chars);
+    assert_eq!(7, coverage.covered_chars);
+    assert_eq!(4, coverage.word_chars);
+    assert_eq!((9, 5), (coverage.tokens, coverage.covered_tokens));
+    assert_eq!(vec![("𠮷你", 2), ("ABC", 1)], coverage.top_uncovered(2));
+    assert_eq!(5.0 / 9.0, coverage.token_coverage());
+    assert_eq!(4.0 / 7.0, coverage.word_share());
+    assert_eq!(0.0, Coverage::default().char_coverage());
+  }
+}
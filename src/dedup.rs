@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use crate::trie::{Entry, Trie};
+use crate::types::{Code, Word};
+
+/// Where an entry comes from: a table of the schema and the 0-based line index in its file.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Source {
+  pub table: String,
+  pub line: usize,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Located {
+  pub entry: Entry,
+  pub source: Source,
+  /// The comment after the entry, e.g. `来源：自造` in `字词\tzc\t# 来源：自造`.
+  pub annotation: Option<String>,
+}
+
+/// An entry whose word can already be typed with a shorter (or the same) code.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Redundant {
+  pub entry: Entry,
+  pub source: Source,
+  pub kept: Code,
+}
+
+/// Finds entries made redundant by another entry of the same word:
+/// the same code again, or a longer code extending a short one where the word is already the first candidate.
+///
+/// Shorter codes are kept first, ties go to the entry read first.
+pub fn redundant(trie: &Trie, entries: &[Located]) -> Vec<Redundant> {
+  let mut by_word: HashMap<&Word, Vec<&Located>> = HashMap::new();
+  for located in entries {
+    by_word.entry(&located.entry.word).or_default().push(located);
+  }
+
+  let mut redundant = vec![];
+  for (word, mut located) in by_word {
+    if located.len() < 2 {
+      continue;
+    }
+    // stable: the entry read first wins among equal lengths
+    located.sort_by_key(|located| located.entry.code.len());
+    let mut kept: Vec<&Code> = vec![];
+    for located in located {
+      let code = &located.entry.code;
+      let covering = kept.iter().find(|&&short| short == code
+        || code.starts_with(short.as_str()) && trie.candidate_index(short, word, false) == Some(0));
+      match covering {
+        Some(&short) => redundant.push(Redundant {
+          entry: located.entry.clone(),
+          source: located.source.clone(),
+          kept: short.clone(),
+        }),
+        None => kept.push(code),
+      }
+    }
+  }
+  redundant.sort_by(|a, b| (&a.source.table, a.source.line).cmp(&(&b.source.table, b.source.line)));
+  redundant
+}
+
+/// Finds the entries of a user table which the base tables made redundant since: the same word
+/// with the same or a shorter code, which is kept, the shortest one if several.
+pub fn superseded(user: &[Located], base: &[Located]) -> Vec<Redundant> {
+  let mut shortest: HashMap<&Word, &Code> = HashMap::new();
+  for located in base {
+    let code = shortest.entry(&located.entry.word).or_insert(&located.entry.code);
+    if located.entry.code.len() < code.len() {
+      *code = &located.entry.code;
+    }
+  }
+  user
+    .iter()
+    .filter_map(|located| {
+      let kept = shortest.get(&located.entry.word).filter(|kept| kept.len() <= located.entry.code.len())?;
+      Some(Redundant { entry: located.entry.clone(), source: located.source.clone(), kept: (*kept).clone() })
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn located(word: &str, code: &str, table: &str, line: usize) -> Located {
+    Located {
+      entry: Entry { word: word.to_string(), code: code.to_string() },
+      source: Source { table: table.to_string(), line },
+      annotation: None,
+    }
+  }
+
+  #[test]
+  fn test_redundant() {
+    let entries = vec![
+      located("我", "w", "danzi", 0),
+      located("我", "wo", "danzi", 1),
+      located("他", "t", "danzi", 2),
+      located("它", "t", "danzi", 3),
+      located("它", "ta", "danzi", 4),
+      located("我们", "wi", "cizu", 0),
+      located("我们", "wi", "extended", 0),
+    ];
+    let trie: Box<Trie> = entries.iter().map(|located| located.entry.clone()).collect();
+
+    let redundant = redundant(&trie, &entries);
+    let found: Vec<_> = redundant
+      .iter()
+      .map(|r| (r.entry.code.as_str(), r.source.table.as_str(), r.kept.as_str()))
+      .collect();
+    // 它 is only second at `t`, so `ta` stays
+    assert_eq!(vec![("wo", "danzi", "w"), ("wi", "extended", "wi")], found);
+  }
+
+  #[test]
+  fn test_superseded() {
+    let base = vec![located("我们", "wi", "cizu", 0), located("我们", "wim", "cizu", 1), located("飞船", "fio", "cizu", 2)];
+    let user = vec![
+      located("我们", "wimn", "user", 0),
+      located("飞船", "fi", "user", 1),
+      located("喜欢", "xh", "user", 2),
+      located("飞船", "fio", "user", 3),
+    ];
+    let found: Vec<_> = superseded(&user, &base)
+      .iter()
+      .map(|r| (r.source.line, r.kept.clone()))
+      .collect();
+    // a shorter code of one's own stays
+    assert_eq!(vec![(0, "wi".to_string()), (3, "fio".to_string())], found);
+  }
+}
@@ -0,0 +1,95 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use crate::trie::Trie;
+use crate::types::Code;
+
+/// What follows a code prefix: the distinct next keys, and the words typed through it.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Prefix {
+  pub code: Code,
+  pub continuations: usize,
+  pub candidates: usize,
+}
+
+/// Prefixes of one length, e.g. depth 1 for the first key.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Depth {
+  pub depth: usize,
+  pub prefixes: usize,
+  pub avg_continuations: f64,
+  pub max_continuations: usize,
+  pub avg_candidates: f64,
+  pub max_candidates: usize,
+  /// The prefixes with the most candidates, most first.
+  pub hot: Vec<Prefix>,
+}
+
+#[derive(Default)]
+struct Counts {
+  next: HashSet<char>,
+  candidates: usize,
+}
+
+/// Per prefix depth, how many keys and words may follow, keeping the `hot` busiest prefixes.
+pub fn density(trie: &Trie, hot: usize) -> Vec<Depth> {
+  let mut prefixes: HashMap<Code, Counts> = HashMap::new();
+  for entry in trie.entries() {
+    let code = &entry.code;
+    let ends = code.char_indices().map(|(idx, _)| idx).skip(1).chain([code.len()]);
+    for (end, next) in ends.zip(code.chars().skip(1).map(Some).chain([None])) {
+      let counts = prefixes.entry(code[..end].to_string()).or_default();
+      counts.candidates += 1;
+      counts.next.extend(next);
+    }
+  }
+
+  let mut depths: BTreeMap<usize, Vec<Prefix>> = BTreeMap::new();
+  for (code, counts) in prefixes {
+    depths.entry(code.chars().count()).or_default().push(Prefix {
+      code,
+      continuations: counts.next.len(),
+      candidates: counts.candidates,
+    });
+  }
+  depths
+    .into_iter()
+    .map(|(depth, mut prefixes)| {
+      let len = prefixes.len() as f64;
+      prefixes.sort_by(|a, b| b.candidates.cmp(&a.candidates).then_with(|| a.code.cmp(&b.code)));
+      Depth {
+        depth,
+        prefixes: prefixes.len(),
+        avg_continuations: prefixes.iter().map(|p| p.continuations).sum::<usize>() as f64 / len,
+        max_continuations: prefixes.iter().map(|p| p.continuations).max().unwrap_or_default(),
+        avg_candidates: prefixes.iter().map(|p| p.candidates).sum::<usize>() as f64 / len,
+        max_candidates: prefixes.first().map_or(0, |p| p.candidates),
+        hot: prefixes.into_iter().take(hot).collect(),
+      }
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::TrieBuilder;
+
+  #[test]
+  fn test_density() {
+    let trie = TrieBuilder::from_iter([("w", "我"), ("wi", "我们"), ("wo", "窝"), ("wo", "喔"), ("n", "你")]).build();
+
+    let depths = density(&trie, 1);
+    assert_eq!(2, depths.len());
+    assert_eq!(Depth {
+      depth: 1,
+      prefixes: 2,
+      avg_continuations: 1.0,
+      max_continuations: 2,
+      avg_candidates: 2.5,
+      max_candidates: 4,
+      hot: vec![Prefix { code: "w".to_string(), continuations: 2, candidates: 4 }],
+    }, depths[0]);
+    assert_eq!((2, 0, 2), (depths[1].prefixes, depths[1].max_continuations, depths[1].max_candidates));
+  }
+}
@@ -0,0 +1,720 @@
+use std::fmt::{Display, Formatter};
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::str::FromStr;
+#[cfg(feature = "async")]
+use futures::{AsyncBufRead, AsyncBufReadExt, TryStreamExt};
+use crate::archive;
+use crate::trie::Entry;
+use crate::yaml;
+
+pub const DICT_EXT: &str = "dict.yaml";
+
+/// Extensions of headerless `word\tcode[\tweight]` tables.
+pub const PLAIN_EXTS: &[&str] = &["tsv", "txt"];
+
+/// Parses an entry line `word\tcode[\tweight]`, ignoring anything after `#`.
+pub fn parse_line(line: &str) -> Option<Entry> {
+  parse_weighted_line(line).map(|(entry, _)| entry)
+}
+
+/// Splits a line into its content and the annotation after `#`, e.g. `来源：自造`, trimmed.
+pub fn split_annotation(line: &str) -> (&str, Option<&str>) {
+  match line.split_once('#') {
+    Some((content, annotation)) => (content, Some(annotation.trim()).filter(|a| !a.is_empty())),
+    None => (line, None),
+  }
+}
+
+/// Like [`parse_line`], with the weight, 0 when missing or not a number.
+pub fn parse_weighted_line(line: &str) -> Option<(Entry, u64)> {
+  Separator::Tab.parse_weighted(line)
+}
+
+/// How the cells of the entry lines of a table are separated, tabs as Rime expects, or what
+/// community tables use instead, see [`LineParser`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Separator {
+  #[default]
+  Tab,
+  /// Runs of two spaces or more, as in tables aligned in columns, written with this many.
+  Spaces(usize),
+  /// Any other string, e.g. `|`, only given in the config: tables don't say.
+  Custom(String),
+}
+
+impl FromStr for Separator {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "" => Err("a separator can't be empty".to_string()),
+      "tab" | "\t" => Ok(Separator::Tab),
+      "spaces" => Ok(Separator::Spaces(2)),
+      custom => Ok(Separator::Custom(custom.to_string())),
+    }
+  }
+}
+
+impl Separator {
+  /// The separator of the line if it's an entry line telling one: a tab, else a run of spaces.
+  /// Lines starting with a space, like those of YAML lists, tell nothing.
+  pub fn detect(line: &str) -> Option<Self> {
+    let (content, _) = split_annotation(line);
+    let content = content.trim_end();
+    if content.is_empty() || content.starts_with(char::is_whitespace) {
+      return None;
+    }
+    if content.contains('\t') {
+      return Some(Separator::Tab);
+    }
+    let start = content.find("  ")?;
+    let width = content[start..].len() - content[start..].trim_start_matches(' ').len();
+    Some(Separator::Spaces(width))
+  }
+
+  /// The cells of a line, before its annotation.
+  fn cells<'l>(&self, line: &'l str) -> Vec<&'l str> {
+    let (content, _) = split_annotation(line);
+    match self {
+      Separator::Tab => content.split('\t').collect(),
+      Separator::Spaces(_) if content.starts_with(char::is_whitespace) => vec![],
+      // a tab among spaces separates too, as lines added by other tools have them
+      Separator::Spaces(_) => content
+        .split('\t')
+        .flat_map(|part| part.split("  "))
+        .map(str::trim)
+        .filter(|cell| !cell.is_empty())
+        .collect(),
+      Separator::Custom(separator) => content.split(separator.as_str()).map(str::trim).collect(),
+    }
+  }
+
+  /// Parses an entry line `word<separator>code[<separator>weight]`, see [`parse_weighted_line`].
+  pub fn parse_weighted(&self, line: &str) -> Option<(Entry, u64)> {
+    let mut cells = self.cells(line).into_iter();
+    let word = cells.next().map(String::from)?;
+    let code = cells.next().map(String::from)?;
+    let weight = cells.next().and_then(|weight| weight.trim().parse().ok()).unwrap_or(0);
+    Some((Entry { word, code }, weight))
+  }
+
+  /// An entry line of a table separated this way, written with tabs like [`format_line`] does.
+  pub fn to_tabs(&self, line: &str) -> String {
+    if *self == Separator::Tab {
+      return line.to_string();
+    }
+    let mut tabbed = self.cells(line).join("\t");
+    if let Some(idx) = line.find('#') {
+      tabbed.push('\t');
+      tabbed.push_str(&line[idx..]);
+    }
+    tabbed
+  }
+
+  /// A line written with tabs, like [`format_line`] does, separated like the table instead.
+  pub fn restyle(&self, line: &str) -> String {
+    let separator = match self {
+      Separator::Tab => return line.to_string(),
+      Separator::Spaces(width) => " ".repeat(*width),
+      Separator::Custom(separator) => separator.clone(),
+    };
+    match line.find('#') {
+      Some(idx) => format!("{}{}", line[..idx].replace('\t', &separator), &line[idx..]),
+      None => line.replace('\t', &separator),
+    }
+  }
+}
+
+/// Parses the lines of a table in order, skipping its header, and unless given a separator, takes
+/// that of its first entry line so that tables separated by spaces don't read as empty.
+#[derive(Clone, Debug, Default)]
+pub struct LineParser {
+  separator: Option<Separator>,
+  fixed: bool,
+  in_header: bool,
+  /// Whether a line other than a comment or a blank was read.
+  started: bool,
+}
+
+impl LineParser {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  pub fn with_separator(separator: Separator) -> Self {
+    Self { separator: Some(separator), fixed: true, ..Default::default() }
+  }
+
+  /// The separator given or detected so far, tabs if none.
+  pub fn separator(&self) -> Separator {
+    self.separator.clone().unwrap_or_default()
+  }
+
+  pub fn parse(&mut self, line: &str) -> Option<Entry> {
+    self.parse_weighted(line).map(|(entry, _)| entry)
+  }
+
+  pub fn parse_weighted(&mut self, line: &str) -> Option<(Entry, u64)> {
+    let content = line.trim_end();
+    if !self.started && !content.is_empty() && !content.starts_with('#') {
+      self.started = true;
+      self.in_header = content == "---";
+    }
+    if self.in_header {
+      // like `is_header_end`, a tab separated entry ends a header lacking `...`
+      match parse_weighted_line(line) {
+        Some(entry) if !self.fixed || self.separator == Some(Separator::Tab) => {
+          self.in_header = false;
+          self.separator.get_or_insert(Separator::Tab);
+          return Some(entry);
+        }
+        _ => {
+          self.in_header = content != "...";
+          return None;
+        }
+      }
+    }
+    if self.separator.is_none() {
+      self.separator = Separator::detect(line);
+    }
+    self.separator.as_ref().unwrap_or(&Separator::Tab).parse_weighted(line)
+  }
+}
+
+/// The separator of a table, that of its first entry, tabs if it has none.
+pub fn detect_separator(path: impl AsRef<Path>) -> io::Result<Separator> {
+  let mut parser = LineParser::new();
+  for line in archive::open(path)?.lines() {
+    if parser.parse(&line?).is_some() {
+      break;
+    }
+  }
+  Ok(parser.separator())
+}
+
+/// Reads the entries of a dict file, skipping its YAML header.
+pub fn read_entries(path: impl AsRef<Path>) -> io::Result<Vec<Entry>> {
+  read_entries_from(archive::open(path)?)
+}
+
+pub fn read_entries_from(reader: impl BufRead) -> io::Result<Vec<Entry>> {
+  let mut parser = LineParser::new();
+  let mut entries = vec![];
+  for line in reader.lines() {
+    if let Some(entry) = parser.parse(&line?) {
+      entries.push(entry);
+    }
+  }
+  Ok(entries)
+}
+
+/// Like [`read_entries`], with their weights.
+pub fn read_weighted_entries(path: impl AsRef<Path>) -> io::Result<Vec<(Entry, u64)>> {
+  read_weighted_entries_with(path, LineParser::new())
+}
+
+/// Like [`read_weighted_entries`], parsing the lines with `parser`, e.g. one given a separator.
+pub fn read_weighted_entries_with(path: impl AsRef<Path>, parser: LineParser) -> io::Result<Vec<(Entry, u64)>> {
+  read_weighted_entries_from(archive::open(path)?, parser)
+}
+
+/// Like [`read_weighted_entries_with`], from a reader, e.g. a table's text changed in memory.
+pub fn read_weighted_entries_from(reader: impl BufRead, mut parser: LineParser) -> io::Result<Vec<(Entry, u64)>> {
+  let mut entries = vec![];
+  for line in reader.lines() {
+    if let Some(entry) = parser.parse_weighted(&line?) {
+      entries.push(entry);
+    }
+  }
+  Ok(entries)
+}
+
+/// Like [`read_entries`], paired with the 0-based line index of each entry.
+pub fn read_numbered_entries(path: impl AsRef<Path>) -> io::Result<Vec<(usize, Entry)>> {
+  let mut parser = LineParser::new();
+  let mut entries = vec![];
+  for (idx, line) in archive::open(path)?.lines().enumerate() {
+    if let Some(entry) = parser.parse(&line?) {
+      entries.push((idx, entry));
+    }
+  }
+  Ok(entries)
+}
+
+/// Like [`read_numbered_entries`], with the annotation of each entry.
+pub fn read_annotated_entries(path: impl AsRef<Path>) -> io::Result<Vec<(usize, Entry, Option<String>)>> {
+  read_annotated_entries_with(path, LineParser::new())
+}
+
+/// Like [`read_annotated_entries`], parsing the lines with `parser`.
+pub fn read_annotated_entries_with(path: impl AsRef<Path>, parser: LineParser) -> io::Result<Vec<(usize, Entry, Option<String>)>> {
+  read_annotated_entries_from(archive::open(path)?, parser)
+}
+
+/// Like [`read_annotated_entries_with`], from a reader.
+pub fn read_annotated_entries_from(reader: impl BufRead, mut parser: LineParser) -> io::Result<Vec<(usize, Entry, Option<String>)>> {
+  let mut entries = vec![];
+  for (idx, line) in reader.lines().enumerate() {
+    let line = line?;
+    if let Some(entry) = parser.parse(&line) {
+      entries.push((idx, entry, split_annotation(&line).1.map(String::from)));
+    }
+  }
+  Ok(entries)
+}
+
+/// Formats an entry line back, keeping its annotation so that rewriting a table doesn't lose it.
+pub fn format_line(entry: &Entry, weight: Option<u64>, annotation: Option<&str>) -> String {
+  let mut line = format!("{}\t{}", entry.word, entry.code);
+  if let Some(weight) = weight {
+    line.push_str(&format!("\t{weight}"));
+  }
+  if let Some(annotation) = annotation {
+    line.push_str(&format!("\t# {annotation}"));
+  }
+  line
+}
+
+#[cfg(feature = "async")]
+pub async fn read_entries_async(reader: impl AsyncBufRead + Unpin) -> io::Result<Vec<Entry>> {
+  let entries = read_weighted_entries_async(reader).await?;
+  Ok(entries.into_iter().map(|(entry, _)| entry).collect())
+}
+
+#[cfg(feature = "async")]
+pub async fn read_weighted_entries_async(reader: impl AsyncBufRead + Unpin) -> io::Result<Vec<(Entry, u64)>> {
+  read_weighted_entries_async_with(reader, LineParser::new()).await
+}
+
+#[cfg(feature = "async")]
+pub async fn read_weighted_entries_async_with(reader: impl AsyncBufRead + Unpin, mut parser: LineParser) -> io::Result<Vec<(Entry, u64)>> {
+  let mut lines = reader.lines();
+  let mut entries = vec![];
+  while let Some(line) = lines.try_next().await? {
+    if let Some(entry) = parser.parse_weighted(&line) {
+      entries.push(entry);
+    }
+  }
+  Ok(entries)
+}
+
+pub fn count_entries(path: impl AsRef<Path>) -> io::Result<usize> {
+  let mut parser = LineParser::new();
+  let mut count = 0;
+  for line in archive::open(path)?.lines() {
+    if parser.parse(&line?).is_some() {
+      count += 1;
+    }
+  }
+  Ok(count)
+}
+
+/// Where the bytes of a dict file go: its header, its comments, its entries and the line endings,
+/// so that comment banners don't weigh on the ratios of words and codes.
+#[derive(Clone, Debug, Default)]
+pub struct Breakdown {
+  header: usize,
+  comments: usize,
+  payload: usize,
+  line_endings: usize,
+  /// The lines before the end of the header or the first entry, telling which they belong to.
+  pending: usize,
+  in_body: bool,
+  parser: LineParser,
+}
+
+impl Breakdown {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  /// Telling entries with `parser`, e.g. one given the separator of the table.
+  pub fn with_parser(parser: LineParser) -> Self {
+    Self { parser, ..Default::default() }
+  }
+
+  /// Counts a line with its line ending, if any.
+  pub fn add_line(&mut self, line: &str) {
+    let content = line.trim_end_matches(['\r', '\n']);
+    self.line_endings += line.len() - content.len();
+    let is_entry = self.parser.parse(content).is_some();
+    if !self.in_body {
+      if !is_entry {
+        self.pending += content.len();
+        if content.trim_end() == "..." {
+          self.header += std::mem::take(&mut self.pending);
+          self.in_body = true;
+        }
+        return;
+      }
+      // 没有表头的码表，开头的行都是注释
+      self.comments += std::mem::take(&mut self.pending);
+      self.in_body = true;
+    }
+    match is_entry {
+      true => {
+        let (payload, _) = split_annotation(content);
+        self.payload += payload.len();
+        self.comments += content.len() - payload.len();
+      }
+      false => self.comments += content.len(),
+    }
+  }
+
+  /// The YAML header up to `...`, without line endings.
+  pub fn header(&self) -> usize {
+    self.header
+  }
+
+  /// Comment and blank lines, and the annotations after entries.
+  pub fn comments(&self) -> usize {
+    self.comments + self.pending
+  }
+
+  /// The entry lines without their annotations: words, codes, weights and the tabs between.
+  pub fn payload(&self) -> usize {
+    self.payload
+  }
+
+  pub fn line_endings(&self) -> usize {
+    self.line_endings
+  }
+
+  pub fn total(&self) -> usize {
+    self.header + self.comments() + self.payload + self.line_endings
+  }
+}
+
+/// A header ends at `...`, and plain tables have none: they start with an entry right away.
+fn is_header_end(line: &str) -> bool {
+  line.trim_end() == "..." || parse_line(line).is_some()
+}
+
+/// Reads the YAML header of a dict file, i.e. everything before the `...` line.
+/// Plain tables give an empty header.
+pub fn read_header(path: impl AsRef<Path>) -> io::Result<yaml::Value> {
+  let mut header = String::new();
+  for line in archive::open(path)?.lines() {
+    let line = line?;
+    if is_header_end(&line) {
+      break;
+    }
+    header.push_str(&line);
+    header.push('\n');
+  }
+  Ok(yaml::parse(&header))
+}
+
+#[cfg(feature = "async")]
+pub async fn read_header_async(reader: impl AsyncBufRead + Unpin) -> io::Result<yaml::Value> {
+  let mut header = String::new();
+  let mut lines = reader.lines();
+  while let Some(line) = lines.try_next().await? {
+    if is_header_end(&line) {
+      break;
+    }
+    header.push_str(&line);
+    header.push('\n');
+  }
+  Ok(yaml::parse(&header))
+}
+
+/// The header of a dict file kept line by line, so rewriting a file changes nothing but the keys set,
+/// not the order, the comments or the line endings.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Header {
+  /// With their line endings, up to and including `...`.
+  lines: Vec<String>,
+}
+
+impl Header {
+  /// Splits a dict file into its header and the entries after it.
+  pub fn parse(text: &str) -> (Self, &str) {
+    let mut lines = vec![];
+    let mut len = 0;
+    for line in text.split_inclusive('\n') {
+      if parse_line(line.trim_end_matches(['\r', '\n'])).is_some() {
+        break;
+      }
+      lines.push(line.to_string());
+      len += line.len();
+      if line.trim_end() == "..." {
+        break;
+      }
+    }
+    if !lines.iter().any(|line| line.trim_end() == "...") {
+      // plain tables have no header, only comments before their entries
+      return (Self::default(), text);
+    }
+    (Self { lines }, &text[len..])
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.lines.is_empty()
+  }
+
+  pub fn value(&self) -> yaml::Value {
+    yaml::parse(&self.lines.concat())
+  }
+
+  pub fn get(&self, key: &str) -> Option<String> {
+    self.value().get(key).and_then(yaml::Value::as_str).map(String::from)
+  }
+
+  fn find(&self, key: &str) -> Option<usize> {
+    self.lines.iter().position(|line| {
+      line.strip_prefix(key).is_some_and(|rest| rest.starts_with(':'))
+    })
+  }
+
+  /// Sets a top-level key in place, keeping its quotes and trailing comment, or adds it before `...`.
+  pub fn set(&mut self, key: &str, value: &str) {
+    if let Some(idx) = self.find(key) {
+      let line = &self.lines[idx];
+      let content = yaml::strip_comment(line.trim_end_matches(['\r', '\n']));
+      // the spaces before the comment, the comment and the line ending
+      let rest = &line[content.trim_end().len()..];
+      let quoted = content[key.len() + 1..].trim_start().starts_with('"');
+      let value = if quoted { format!("\"{value}\"") } else { value.to_string() };
+      self.lines[idx] = format!("{key}: {value}{rest}");
+    } else {
+      let newline = self.newline();
+      if self.lines.is_empty() {
+        self.lines = vec![format!("---{newline}"), format!("...{newline}")];
+      }
+      let end = self.lines.len() - 1;
+      self.lines.insert(end, format!("{key}: \"{value}\"{newline}"));
+    }
+  }
+
+  fn newline(&self) -> &'static str {
+    match self.lines.first() {
+      Some(line) if line.ends_with("\r\n") => "\r\n",
+      _ => "\n",
+    }
+  }
+
+  /// Bumps `version:`, returning the new one, or `None` when the current one doesn't follow the scheme.
+  pub fn bump_version(&mut self, versioning: Versioning, today: &str) -> Option<String> {
+    let version = versioning.next(self.get("version").as_deref(), today)?;
+    self.set("version", &version);
+    Some(version)
+  }
+
+  /// Adds a line on top of the `# changelog:` comment block, which starts right before `...` if missing.
+  pub fn log(&mut self, line: &str) {
+    let newline = self.newline();
+    if self.lines.is_empty() {
+      self.lines = vec![format!("---{newline}"), format!("...{newline}")];
+    }
+    let idx = match self.lines.iter().position(|line| line.trim_end() == CHANGELOG) {
+      Some(idx) => idx + 1,
+      None => {
+        let end = self.lines.len() - 1;
+        self.lines.insert(end, format!("{CHANGELOG}{newline}"));
+        end + 1
+      }
+    };
+    self.lines.insert(idx, format!("#   {line}{newline}"));
+  }
+}
+
+const CHANGELOG: &str = "# changelog:";
+
+/// How to bump the `version:` of a dict when a command changes it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Versioning {
+  /// `2023.10.01`, then `2023.10.01.1` for another change on the same day.
+  Date,
+  /// `1.2.3` to `1.2.4`.
+  Semver,
+}
+
+impl FromStr for Versioning {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "date" => Ok(Self::Date),
+      "semver" => Ok(Self::Semver),
+      _ => Err(format!("versioning should be date or semver, not {s}")),
+    }
+  }
+}
+
+impl Versioning {
+  /// The version after `version`, `today` being formatted like `2023.10.01`.
+  pub fn next(self, version: Option<&str>, today: &str) -> Option<String> {
+    match self {
+      Self::Date => match version.and_then(|version| version.strip_prefix(today)) {
+        Some("") => Some(format!("{today}.1")),
+        Some(count) => Some(format!("{today}.{}", count.strip_prefix('.')?.parse::<u32>().ok()? + 1)),
+        None => Some(today.to_string()),
+      },
+      Self::Semver => {
+        let Some(version) = version else { return Some("0.1.0".to_string()) };
+        let numbers: Vec<u32> = version.split('.').map(|n| n.parse().ok()).collect::<Option<_>>()?;
+        let [major, minor, patch] = numbers[..] else { return None };
+        Some(format!("{major}.{minor}.{}", patch + 1))
+      }
+    }
+  }
+}
+
+impl Display for Header {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    self.lines.iter().try_for_each(|line| f.write_str(line))
+  }
+}
+
+pub fn import_tables(header: &yaml::Value) -> Vec<String> {
+  header
+    .get("import_tables")
+    .map(|tables| tables
+      .as_list()
+      .iter()
+      .filter_map(yaml::Value::as_str)
+      .map(String::from)
+      .collect())
+    .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_parse_line() {
+    assert_eq!(
+      Some(Entry { word: "我们".to_string(), code: "wi".to_string() }),
+      parse_line("我们\twi\t100 # 常用")
+    );
+    assert_eq!(Some(100), parse_weighted_line("我们\twi\t100 # 常用").map(|(_, weight)| weight));
+    assert_eq!(Some(0), parse_weighted_line("我们\twi").map(|(_, weight)| weight));
+    assert_eq!(None, parse_line("# 我们\twi"));
+    assert_eq!(None, parse_line("name: xkjd6"));
+  }
+
+  #[test]
+  fn test_breakdown() {
+    let mut breakdown = Breakdown::new();
+    let text = "# 键道6\r\n---\r\nname: x\r\n...\r\n\r\n我\tw\r\n# 词组\r\n我们\twomf\t100 # 常用";
+    for line in text.split_inclusive('\n') {
+      breakdown.add_line(line);
+    }
+    assert_eq!(text.len(), breakdown.total());
+    assert_eq!("# 键道6---name: x...".len(), breakdown.header());
+    assert_eq!("# 词组# 常用".len(), breakdown.comments());
+    assert_eq!("我\tw我们\twomf\t100 ".len(), breakdown.payload());
+    assert_eq!(14, breakdown.line_endings());
+
+    // a plain table: what precedes the first entry is comments
+    let mut breakdown = Breakdown::new();
+    for line in ["# 自造词\n", "我\tw\n"] {
+      breakdown.add_line(line);
+    }
+    assert_eq!((0, 11, 5, 2), (breakdown.header(), breakdown.comments(), breakdown.payload(), breakdown.line_endings()));
+  }
+
+  #[test]
+  fn test_separator() {
+    assert_eq!(Some(Separator::Tab), Separator::detect("我们\twomf  # 常用"));
+    assert_eq!(Some(Separator::Spaces(3)), Separator::detect("我们   womf  100"));
+    assert_eq!(None, Separator::detect("name: xkjd6"));
+    assert_eq!(None, Separator::detect("  - xkjd6.cizu"));
+    assert_eq!(Ok(Separator::Custom("|".to_string())), "|".parse());
+
+    let mut parser = LineParser::new();
+    let text = "---\nname: xkjd6.user\nsort:  by_weight\n...\n# 词组\n我们   womf   100  # 常用\n测试  ceui";
+    let entries: Vec<_> = text.lines().filter_map(|line| parser.parse_weighted(line)).collect();
+    let entry = |word: &str, code: &str| Entry { word: word.to_string(), code: code.to_string() };
+    assert_eq!(vec![(entry("我们", "womf"), 100), (entry("测试", "ceui"), 0)], entries);
+    assert_eq!(Separator::Spaces(3), parser.separator());
+    assert_eq!(Some(entry("我们", "womf")), parser.parse("我们\twomf"));
+
+    let mut parser = LineParser::with_separator(Separator::Custom("|".to_string()));
+    assert_eq!(Some((entry("我们", "womf"), 100)), parser.parse_weighted("我们 | womf | 100"));
+
+    let spaces = Separator::Spaces(2);
+    assert_eq!("我们  womf  # 常用", spaces.restyle("我们\twomf\t# 常用"));
+    assert_eq!("我们\twomf\t100\t# 常用", Separator::Spaces(3).to_tabs("我们   womf   100  # 常用"));
+  }
+
+  #[test]
+  fn test_annotation() {
+    let line = "字词\tzc\t# 来源：自造";
+    assert_eq!(("字词\tzc\t", Some("来源：自造")), split_annotation(line));
+    assert_eq!(("我\tw", None), split_annotation("我\tw"));
+    assert_eq!(None, split_annotation("我\tw #").1);
+
+    let entry = parse_line(line).unwrap();
+    assert_eq!("zc", entry.code);
+    assert_eq!(line, format_line(&entry, None, split_annotation(line).1));
+    assert_eq!("字词\tzc\t10", format_line(&entry, Some(10), None));
+  }
+
+  #[cfg(feature = "async")]
+  #[test]
+  fn test_async_parity() {
+    let text = "---\nname: test\nimport_tables:\n  - test.cizu\n...\n我\tw\n我们\twi\t100\n";
+    let sync = read_entries_from(io::Cursor::new(text)).unwrap();
+    let async_ = futures::executor::block_on(read_entries_async(futures::io::Cursor::new(text))).unwrap();
+    assert_eq!(2, sync.len());
+    assert_eq!(sync, async_);
+
+    let header = futures::executor::block_on(read_header_async(futures::io::Cursor::new(text))).unwrap();
+    assert_eq!(vec!["test.cizu"], import_tables(&header));
+  }
+
+  #[test]
+  fn test_plain_table() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let path = dir.join("phrases.tsv");
+    std::fs::write(&path, "# 常用短语\n我们\twi\nname: 不是头\t?\n").unwrap();
+
+    let header = read_header(&path).unwrap();
+    let entries = read_entries(&path).unwrap();
+    assert!(import_tables(&header).is_empty());
+    assert_eq!(2, entries.len());
+  }
+
+  #[test]
+  fn test_header_roundtrip() {
+    let text = "# Rime dictionary\r\n---\r\nname: xkjd6.cizu  # 词组\r\nversion: \"Q1\"\r\nsort: original\r\n...\r\n我们\twi\r\n";
+    let (mut header, body) = Header::parse(text);
+    assert_eq!(text, format!("{header}{body}"));
+    assert_eq!(Some("Q1".to_string()), header.get("version"));
+
+    header.set("version", "Q2");
+    header.set("name", "xkjd6.cizu.two");
+    header.set("columns", "text");
+    assert_eq!(
+      "# Rime dictionary\r\n---\r\nname: xkjd6.cizu.two  # 词组\r\nversion: \"Q2\"\r\nsort: original\r\ncolumns: \"text\"\r\n...\r\n",
+      header.to_string()
+    );
+
+    let (header, body) = Header::parse("# 短语\n我们\twi\n");
+    assert!(header.is_empty());
+    assert_eq!("# 短语\n我们\twi\n", body);
+  }
+
+  #[test]
+  fn test_bump_version() {
+    assert_eq!(Some("2023.10.01".to_string()), Versioning::Date.next(Some("Q1"), "2023.10.01"));
+    assert_eq!(Some("2023.10.01.1".to_string()), Versioning::Date.next(Some("2023.10.01"), "2023.10.01"));
+    assert_eq!(Some("2023.10.01.3".to_string()), Versioning::Date.next(Some("2023.10.01.2"), "2023.10.01"));
+    assert_eq!(Some("1.2.4".to_string()), Versioning::Semver.next(Some("1.2.3"), ""));
+    assert_eq!(None, Versioning::Semver.next(Some("Q1"), ""));
+
+    let (mut header, _) = Header::parse("---\nname: test\nversion: \"1.0.0\"\n...\n");
+    assert_eq!(Some("1.0.1".to_string()), header.bump_version(Versioning::Semver, ""));
+    header.log("1.0.1 dedup: 2 entries removed");
+    header.log("1.0.2 dedup: 1 entry removed");
+    assert_eq!(
+      "---\nname: test\nversion: \"1.0.1\"\n# changelog:\n#   1.0.2 dedup: 1 entry removed\n#   1.0.1 dedup: 2 entries removed\n...\n",
+      header.to_string()
+    );
+  }
+}
@@ -0,0 +1,201 @@
+//! Line diffs of two versions of a file, printed as unified diffs like `diff -u` does, to preview
+//! the changes a command would make to a dict.
+
+use std::fmt::Write;
+use std::ops::Range;
+
+/// Lines of context around each change.
+pub const CONTEXT: usize = 3;
+/// Beyond this many differing lines the middle of the files is shown replaced as a whole,
+/// the diff would take too long to find.
+const MAX_EDITS: usize = 2_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+  Equal,
+  Delete,
+  Insert,
+}
+
+/// The edit script turning `old` into `new`, shortest by Myers' algorithm, each op taking one
+/// line of `old` (`Equal`, `Delete`) or of `new` (`Insert`).
+fn edits(old: &[&str], new: &[&str]) -> Vec<Op> {
+  // the common start and end cost nothing to skip
+  let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+  let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+  let (a, b) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+  let mut ops = vec![Op::Equal; prefix];
+  ops.extend(middle_edits(a, b).unwrap_or_else(|| [vec![Op::Delete; a.len()], vec![Op::Insert; b.len()]].concat()));
+  ops.extend(vec![Op::Equal; suffix]);
+  ops
+}
+
+fn middle_edits(a: &[&str], b: &[&str]) -> Option<Vec<Op>> {
+  let (n, m) = (a.len() as isize, b.len() as isize);
+  let offset = n + m + 1;
+  let mut v = vec![0isize; 2 * offset as usize + 1];
+  let mut trace = vec![];
+  let max = (n + m).min(MAX_EDITS as isize);
+  'search: {
+    for d in 0..=max {
+      // the furthest points of the previous round, on diagonals -d..=d
+      trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+      for k in (-d..=d).step_by(2) {
+        let idx = (k + offset) as usize;
+        let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) { v[idx + 1] } else { v[idx - 1] + 1 };
+        let mut y = x - k;
+        while x < n && y < m && a[x as usize] == b[y as usize] {
+          x += 1;
+          y += 1;
+        }
+        v[idx] = x;
+        if x >= n && y >= m {
+          break 'search;
+        }
+      }
+    }
+    return None;
+  }
+
+  // walks the trace back from the end
+  let mut ops = vec![];
+  let (mut x, mut y) = (n, m);
+  for (d, v) in trace.iter().enumerate().rev() {
+    let d = d as isize;
+    let k = x - y;
+    let (prev_x, prev_y) = match d {
+      0 => (0, 0),
+      _ => {
+        let prev_k = if k == -d || (k != d && v[(k - 1 + d) as usize] < v[(k + 1 + d) as usize]) { k + 1 } else { k - 1 };
+        let prev_x = v[(prev_k + d) as usize];
+        (prev_x, prev_x - prev_k)
+      }
+    };
+    while x > prev_x && y > prev_y {
+      ops.push(Op::Equal);
+      x -= 1;
+      y -= 1;
+    }
+    if d > 0 {
+      ops.push(if x == prev_x { Op::Insert } else { Op::Delete });
+    }
+    (x, y) = (prev_x, prev_y);
+  }
+  ops.reverse();
+  Some(ops)
+}
+
+/// The unified diff from `old` to `new`, empty if they have the same lines.
+pub fn unified(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+  let old: Vec<_> = old.lines().collect();
+  let new: Vec<_> = new.lines().collect();
+  let ops = edits(&old, &new);
+  // the line in old and new before each op
+  let mut positions = Vec::with_capacity(ops.len() + 1);
+  let (mut x, mut y) = (0, 0);
+  for op in &ops {
+    positions.push((x, y));
+    match op {
+      Op::Equal => (x, y) = (x + 1, y + 1),
+      Op::Delete => x += 1,
+      Op::Insert => y += 1,
+    }
+  }
+  positions.push((x, y));
+
+  let mut out = String::new();
+  for hunk in hunks(&ops) {
+    if out.is_empty() {
+      let _ = writeln!(out, "--- {old_name}\n+++ {new_name}");
+    }
+    let (start, end) = (positions[hunk.start], positions[hunk.end]);
+    let _ = writeln!(out, "@@ -{} +{} @@", span(start.0, end.0), span(start.1, end.1));
+    for (op, &(x, y)) in ops[hunk.clone()].iter().zip(&positions[hunk]) {
+      let _ = match op {
+        Op::Equal => writeln!(out, " {}", old[x]),
+        Op::Delete => writeln!(out, "-{}", old[x]),
+        Op::Insert => writeln!(out, "+{}", new[y]),
+      };
+    }
+  }
+  out
+}
+
+/// The ranges of ops shown together: the changes with `CONTEXT` equal lines around them,
+/// merged when their context overlaps.
+fn hunks(ops: &[Op]) -> Vec<Range<usize>> {
+  let mut hunks: Vec<Range<usize>> = vec![];
+  for (idx, op) in ops.iter().enumerate() {
+    if *op == Op::Equal {
+      continue;
+    }
+    let range = idx.saturating_sub(CONTEXT)..(idx + 1 + CONTEXT).min(ops.len());
+    match hunks.last_mut() {
+      Some(last) if last.end >= range.start => last.end = range.end,
+      _ => hunks.push(range),
+    }
+  }
+  hunks
+}
+
+/// A range of lines as `diff -u` writes it: 1-based start and length, the line before if empty.
+fn span(start: usize, end: usize) -> String {
+  match end - start {
+    1 => format!("{}", start + 1),
+    0 => format!("{start},0"),
+    len => format!("{},{len}", start + 1),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use proptest::collection::vec;
+  use proptest::prelude::*;
+  use super::*;
+
+  #[test]
+  fn test_unified() {
+    assert_eq!("", unified("a\nb\n", "a\nb\n", "a", "b"));
+    let old = "---\nname: t\n...\n我\tw\n你\tn\n他\tt\n的\td\n是\te\n在\tz\n了\tl\n不\tb\n";
+    let new = "---\nname: t\n...\n我\tw\n你\tn\n他\tt\n的\td\n是\te\n在\tz\n了\tl\n不\tbu\n";
+    assert_eq!(
+      "--- a/t\n+++ b/t\n@@ -8,4 +8,4 @@\n 是\te\n 在\tz\n 了\tl\n-不\tb\n+不\tbu\n",
+      unified(old, new, "a/t", "b/t"),
+    );
+    assert_eq!("--- a\n+++ b\n@@ -0,0 +1 @@\n+我\tw\n", unified("", "我\tw\n", "a", "b"));
+    assert_eq!(
+      "--- a\n+++ b\n@@ -1,4 +1,4 @@\n-x\n+y\n a\n b\n c\n@@ -6,4 +6,3 @@\n e\n f\n g\n-h\n",
+      unified("x\na\nb\nc\nd\ne\nf\ng\nh\n", "y\na\nb\nc\nd\ne\nf\ng\n", "a", "b"),
+    );
+  }
+
+  proptest! {
+    #[test]
+    fn test_edits_round_trip(old in vec("[abc]", 0..30), new in vec("[abc]", 0..30)) {
+      let old: Vec<_> = old.iter().map(String::as_str).collect();
+      let new: Vec<_> = new.iter().map(String::as_str).collect();
+      let (mut from, mut to) = (vec![], vec![]);
+      let (mut x, mut y) = (0, 0);
+      for op in edits(&old, &new) {
+        match op {
+          Op::Equal => {
+            prop_assert_eq!(old[x], new[y]);
+            from.push(old[x]);
+            to.push(new[y]);
+            (x, y) = (x + 1, y + 1);
+          }
+          Op::Delete => {
+            from.push(old[x]);
+            x += 1;
+          }
+          Op::Insert => {
+            to.push(new[y]);
+            y += 1;
+          }
+        }
+      }
+      prop_assert_eq!(old, from);
+      prop_assert_eq!(new, to);
+    }
+  }
+}
@@ -0,0 +1,131 @@
+use std::time::Duration;
+use crate::types::{Code, Word};
+
+/// A small xorshift generator, good enough to pick drill words and reproducible with a seed.
+pub struct Rng(u64);
+
+impl Rng {
+  pub fn new(seed: u64) -> Self {
+    // splitmix64 spreads small seeds over all bits, xorshift alone would start with tiny numbers
+    let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    // xorshift gets stuck at 0
+    Self((z ^ (z >> 31)).max(1))
+  }
+
+  pub fn next_u64(&mut self) -> u64 {
+    self.0 ^= self.0 << 13;
+    self.0 ^= self.0 >> 7;
+    self.0 ^= self.0 << 17;
+    self.0
+  }
+
+  /// Uniform in `0..bound`.
+  pub fn below(&mut self, bound: u64) -> u64 {
+    self.next_u64() % bound
+  }
+}
+
+/// Picks items with a probability proportional to their weights.
+pub struct Sampler<T> {
+  items: Vec<T>,
+  /// Running sums of the weights.
+  cumulative: Vec<u64>,
+}
+
+impl<T> Sampler<T> {
+  /// Items weighing 0 are never picked.
+  pub fn new(weighted: impl IntoIterator<Item=(T, u64)>) -> Self {
+    let mut items = vec![];
+    let mut cumulative = vec![];
+    let mut total = 0;
+    for (item, weight) in weighted.into_iter().filter(|(_, weight)| *weight > 0) {
+      total += weight;
+      items.push(item);
+      cumulative.push(total);
+    }
+    Self { items, cumulative }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.items.is_empty()
+  }
+
+  pub fn sample(&self, rng: &mut Rng) -> Option<&T> {
+    let total = *self.cumulative.last()?;
+    let target = rng.below(total);
+    let idx = self.cumulative.partition_point(|&sum| sum <= target);
+    self.items.get(idx)
+  }
+}
+
+/// A word that was typed wrong, with what was typed and what should have been.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Miss {
+  pub word: Word,
+  pub typed: Code,
+  pub expected: Code,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Score {
+  pub words: usize,
+  pub chars: usize,
+  pub elapsed: Duration,
+  pub misses: Vec<Miss>,
+}
+
+impl Score {
+  pub fn hit(&mut self, word: &str, elapsed: Duration) {
+    self.words += 1;
+    self.chars += word.chars().count();
+    self.elapsed += elapsed;
+  }
+
+  pub fn miss(&mut self, miss: Miss, elapsed: Duration) {
+    self.words += 1;
+    self.elapsed += elapsed;
+    self.misses.push(miss);
+  }
+
+  pub fn accuracy(&self) -> f64 {
+    (self.words - self.misses.len()) as f64 / self.words as f64
+  }
+
+  /// Characters of the words typed right per minute.
+  pub fn chars_per_minute(&self) -> f64 {
+    self.chars as f64 / self.elapsed.as_secs_f64() * 60.0
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_sampler() {
+    let sampler = Sampler::new([("的", 3), ("了", 0), ("是", 1)]);
+    let mut rng = Rng::new(42);
+    let mut counts = [0; 2];
+    for _ in 0..4000 {
+      match *sampler.sample(&mut rng).unwrap() {
+        "的" => counts[0] += 1,
+        "是" => counts[1] += 1,
+        word => panic!("{word} weighs nothing"),
+      }
+    }
+    assert!((2700..3300).contains(&counts[0]), "{counts:?}");
+    assert!(Sampler::<&str>::new([]).sample(&mut rng).is_none());
+  }
+
+  #[test]
+  fn test_score() {
+    let mut score = Score::default();
+    score.hit("我们", Duration::from_secs(1));
+    score.miss(Miss { word: "的".to_string(), typed: "f".to_string(), expected: "d".to_string() }, Duration::from_secs(1));
+    assert_eq!(0.5, score.accuracy());
+    assert_eq!(60.0, score.chars_per_minute());
+  }
+}
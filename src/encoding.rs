@@ -0,0 +1,236 @@
+//! Text files in the encodings corpora come in: UTF-8, UTF-16 and GBK, detected from a BOM or
+//! the first bytes unless given, and decoded leniently, counting the malformed sequences.
+
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufRead, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Double-byte GBK (code page 936) to UTF-16: for each lead byte 0x81..=0xFE, one little-endian
+/// code unit per trail byte 0x40..=0xFE but 0x7F, 0 where unmapped. Generated with Python's
+/// `bytes([lead, trail]).decode("gbk")`.
+static GBK: &[u8; 126 * 190 * 2] = include_bytes!("gbk.bin");
+
+/// How much of the start of a file `detect` looks at.
+pub const SNIFF_LEN: usize = 4096;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+  Utf8,
+  Utf16Le,
+  Utf16Be,
+  Gbk,
+}
+
+impl Display for Encoding {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      Encoding::Utf8 => "UTF-8",
+      Encoding::Utf16Le => "UTF-16LE",
+      Encoding::Utf16Be => "UTF-16BE",
+      Encoding::Gbk => "GBK",
+    })
+  }
+}
+
+impl FromStr for Encoding {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.to_ascii_lowercase().replace('_', "-").as_str() {
+      "utf-8" | "utf8" => Ok(Encoding::Utf8),
+      "utf-16" | "utf-16le" | "utf16" | "utf16le" => Ok(Encoding::Utf16Le),
+      "utf-16be" | "utf16be" => Ok(Encoding::Utf16Be),
+      "gbk" | "gb2312" | "cp936" => Ok(Encoding::Gbk),
+      _ => Err(format!("unknown encoding {s}, expected utf-8, utf-16le, utf-16be or gbk")),
+    }
+  }
+}
+
+impl Encoding {
+  /// The encoding of a file from its first bytes and the length of its BOM, if any.
+  /// Without a BOM, zero bytes mostly at odd or even positions mean UTF-16, and bytes which
+  /// aren't UTF-8 mean GBK.
+  pub fn detect(head: &[u8]) -> (Self, usize) {
+    if head.starts_with(b"\xEF\xBB\xBF") {
+      return (Encoding::Utf8, 3);
+    }
+    if head.starts_with(b"\xFF\xFE") {
+      return (Encoding::Utf16Le, 2);
+    }
+    if head.starts_with(b"\xFE\xFF") {
+      return (Encoding::Utf16Be, 2);
+    }
+    let zeros = |parity| head.iter().skip(parity).step_by(2).filter(|&&byte| byte == 0).count();
+    let (even, odd) = (zeros(0), zeros(1));
+    if odd > head.len() / 8 && odd > even * 4 {
+      return (Encoding::Utf16Le, 0);
+    }
+    if even > head.len() / 8 && even > odd * 4 {
+      return (Encoding::Utf16Be, 0);
+    }
+    // a multi-byte character cut off at the end of `head` is still UTF-8
+    match std::str::from_utf8(head) {
+      Err(e) if e.error_len().is_some() => (Encoding::Gbk, 0),
+      _ => (Encoding::Utf8, 0),
+    }
+  }
+
+  pub fn bom(self) -> &'static [u8] {
+    match self {
+      Encoding::Utf8 => b"\xEF\xBB\xBF",
+      Encoding::Utf16Le => b"\xFF\xFE",
+      Encoding::Utf16Be => b"\xFE\xFF",
+      Encoding::Gbk => b"",
+    }
+  }
+
+  /// Detects the encoding of a file from its first `SNIFF_LEN` bytes.
+  pub fn detect_file(path: impl AsRef<Path>) -> io::Result<Self> {
+    let mut head = Vec::with_capacity(SNIFF_LEN);
+    File::open(path)?.take(SNIFF_LEN as u64).read_to_end(&mut head)?;
+    Ok(Self::detect(&head).0)
+  }
+
+  /// Reads a line with its `\n` into `buf`, returning its length in bytes, 0 at the end.
+  pub fn read_line(self, reader: &mut impl BufRead, buf: &mut Vec<u8>) -> io::Result<usize> {
+    let newline: [u8; 2] = match self {
+      Encoding::Utf8 | Encoding::Gbk => return reader.read_until(b'\n', buf),
+      Encoding::Utf16Le => [b'\n', 0],
+      Encoding::Utf16Be => [0, b'\n'],
+    };
+    let start = buf.len();
+    loop {
+      if reader.read_until(b'\n', buf)? == 0 {
+        break;
+      }
+      // a 0x0A byte may be half of a code unit, like in 上 (U+4E0A)
+      if (buf.len() - start) % 2 == 1 {
+        let mut byte = [0];
+        if reader.read(&mut byte)? == 0 {
+          break;
+        }
+        buf.push(byte[0]);
+      }
+      if buf[start..].ends_with(&newline) {
+        break;
+      }
+    }
+    Ok(buf.len() - start)
+  }
+
+  /// Appends the decoded bytes to `out`, with U+FFFD for each malformed sequence,
+  /// and returns how many there were.
+  pub fn decode(self, bytes: &[u8], out: &mut String) -> usize {
+    match self {
+      Encoding::Utf8 => {
+        let mut errors = 0;
+        for chunk in bytes.utf8_chunks() {
+          out.push_str(chunk.valid());
+          if !chunk.invalid().is_empty() {
+            out.push(char::REPLACEMENT_CHARACTER);
+            errors += 1;
+          }
+        }
+        errors
+      }
+      Encoding::Utf16Le | Encoding::Utf16Be => {
+        let units = bytes.chunks(2).map(|pair| match (self, pair) {
+          (Encoding::Utf16Le, [low, high]) => u16::from_le_bytes([*low, *high]),
+          (_, [high, low]) => u16::from_be_bytes([*high, *low]),
+          // a lone byte at the end
+          _ => 0xDC00,
+        });
+        let mut errors = 0;
+        for ch in char::decode_utf16(units) {
+          out.push(ch.unwrap_or_else(|_| {
+            errors += 1;
+            char::REPLACEMENT_CHARACTER
+          }));
+        }
+        errors
+      }
+      Encoding::Gbk => decode_gbk(bytes, out),
+    }
+  }
+}
+
+fn gbk_char(lead: u8, trail: u8) -> Option<char> {
+  if !(0x81..=0xFE).contains(&lead) || !(0x40..=0xFE).contains(&trail) || trail == 0x7F {
+    return None;
+  }
+  let idx = (lead - 0x81) as usize * 190 + (trail - 0x40) as usize - usize::from(trail > 0x7F);
+  let unit = u16::from_le_bytes([GBK[idx * 2], GBK[idx * 2 + 1]]);
+  (unit != 0).then(|| char::from_u32(unit as u32)).flatten()
+}
+
+fn decode_gbk(bytes: &[u8], out: &mut String) -> usize {
+  let mut errors = 0;
+  let mut idx = 0;
+  while idx < bytes.len() {
+    let byte = bytes[idx];
+    if byte < 0x80 {
+      out.push(byte as char);
+      idx += 1;
+    } else if byte == 0x80 {
+      out.push('€');
+      idx += 1;
+    } else if let Some(ch) = bytes.get(idx + 1).and_then(|&trail| gbk_char(byte, trail)) {
+      out.push(ch);
+      idx += 2;
+    } else {
+      out.push(char::REPLACEMENT_CHARACTER);
+      errors += 1;
+      idx += 1;
+    }
+  }
+  errors
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn encode_utf16(text: &str, big_endian: bool) -> Vec<u8> {
+    text.encode_utf16()
+      .flat_map(|unit| if big_endian { unit.to_be_bytes() } else { unit.to_le_bytes() })
+      .collect()
+  }
+
+  fn lines(encoding: Encoding, bytes: &[u8]) -> (Vec<String>, usize) {
+    let mut reader = bytes;
+    let (mut lines, mut errors) = (vec![], 0);
+    let mut buf = vec![];
+    while encoding.read_line(&mut reader, &mut buf).unwrap() > 0 {
+      let mut line = String::new();
+      errors += encoding.decode(&buf, &mut line);
+      lines.push(line);
+      buf.clear();
+    }
+    (lines, errors)
+  }
+
+  #[test]
+  fn test_detect() {
+    assert_eq!((Encoding::Utf8, 3), Encoding::detect("\u{FEFF}我们".as_bytes()));
+    assert_eq!((Encoding::Utf8, 0), Encoding::detect(&"我们".as_bytes()[..5]));
+    assert_eq!((Encoding::Utf16Le, 2), Encoding::detect(b"\xFF\xFEa\0"));
+    assert_eq!((Encoding::Utf16Le, 0), Encoding::detect(&encode_utf16("we 我们\n", false)));
+    assert_eq!((Encoding::Utf16Be, 0), Encoding::detect(&encode_utf16("we 我们\n", true)));
+    // 我们 in GBK
+    assert_eq!((Encoding::Gbk, 0), Encoding::detect(b"\xCE\xD2\xC3\xC7\n"));
+    assert_eq!(Ok(Encoding::Gbk), "CP936".parse());
+    assert!("latin1".parse::<Encoding>().is_err());
+  }
+
+  #[test]
+  fn test_decode() {
+    // 上 is 0x4E0A, with a newline byte in it
+    let text = "上我\n们\n";
+    assert_eq!((vec!["上我\n".to_string(), "们\n".to_string()], 0), lines(Encoding::Utf16Le, &encode_utf16(text, false)));
+    assert_eq!((vec!["上我\n".to_string(), "们\n".to_string()], 0), lines(Encoding::Utf16Be, &encode_utf16(text, true)));
+    assert_eq!((vec!["我们\n".to_string(), "a\u{FFFD}€".to_string()], 1), lines(Encoding::Gbk, b"\xCE\xD2\xC3\xC7\na\xFF\x80"));
+    assert_eq!((vec!["a\u{FFFD}b".to_string()], 1), lines(Encoding::Utf8, b"a\xFFb"));
+  }
+}
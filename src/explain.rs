@@ -0,0 +1,144 @@
+//! Why a word has its code: which key of which character each key of the code is, and what that
+//! key stands for in the character's code, to understand codes rather than memorize them.
+//!
+//! The rules are the ones `migrate` learns from the dict. The parts of a character's code are
+//! xkjd6's: the initial and the final of its pronunciation, then its shape keys.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use crate::migrate::{self, Rules, Template};
+use crate::trie::Entry;
+use crate::types::Code;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Part {
+  /// 声母
+  Initial,
+  /// 韵母
+  Final,
+  /// 形码, 1-based.
+  Shape(usize),
+}
+
+impl Part {
+  /// The part of a character's code its key at 0-based `idx` is.
+  pub fn of(idx: usize) -> Self {
+    match idx {
+      0 => Part::Initial,
+      1 => Part::Final,
+      idx => Part::Shape(idx - 1),
+    }
+  }
+}
+
+impl Display for Part {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Part::Initial => f.write_str("声母"),
+      Part::Final => f.write_str("韵母"),
+      Part::Shape(n) => write!(f, "形码{n}"),
+    }
+  }
+}
+
+/// A key of a word's code, taken from the code of one of its characters.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Key {
+  pub key: char,
+  /// 0-based in the word.
+  pub ch: usize,
+  /// 0-based in the code of the character.
+  pub idx: usize,
+  pub part: Part,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation {
+  pub word: String,
+  pub code: Code,
+  /// The full code of each character, in the reading the code comes from.
+  pub chars: Vec<(char, Code)>,
+  pub template: Template,
+  pub keys: Vec<Key>,
+}
+
+/// How the code of the entry derives from the full codes of its characters, `None` if it doesn't
+/// by the rules, like a short code given by hand.
+pub fn explain(entry: &Entry, rules: &Rules, codes: &HashMap<char, Vec<Code>>) -> Option<Explanation> {
+  let chars: Vec<_> = entry.word.chars().collect();
+  let template = match chars.len() {
+    // a character's code is a prefix of its full code
+    1 => Template((0..entry.code.len()).map(|idx| (0, idx)).collect()),
+    len => rules.get(len, entry.code.len())?.clone(),
+  };
+  let reading = migrate::readings(&entry.word, codes)?
+    .into_iter()
+    .find(|reading| template.apply(reading).as_ref() == Some(&entry.code))?;
+  let keys = template.0
+    .iter()
+    .zip(entry.code.chars())
+    .map(|(&(ch, idx), key)| Key { key, ch, idx, part: Part::of(idx) })
+    .collect();
+  Some(Explanation {
+    word: entry.word.clone(),
+    code: entry.code.clone(),
+    chars: chars.into_iter().zip(reading.into_iter().map(String::from)).collect(),
+    template,
+    keys,
+  })
+}
+
+/// The full code the rules give a word, explained, e.g. for one not in the dict yet.
+pub fn derive(word: &str, rules: &Rules, codes: &HashMap<char, Vec<Code>>) -> Option<Explanation> {
+  let template = rules.full(word.chars().count())?;
+  let reading = migrate::readings(word, codes)?.into_iter().next()?;
+  let code = template.apply(&reading)?;
+  explain(&Entry { word: word.to_string(), code }, rules, codes)
+}
+
+impl Display for Explanation {
+  /// ```text
+  /// 我们 womfxi: A1 A2 B1 B2 A3 B3
+  ///   A 我 wox
+  ///   B 们 mfi
+  ///   w  A1  我 声母
+  ///   ...
+  /// ```
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let name = |idx: usize| (b'A' + idx.min(25) as u8) as char;
+    writeln!(f, "{} {}: {}", self.word, self.code, self.template)?;
+    for (idx, (ch, code)) in self.chars.iter().enumerate() {
+      writeln!(f, "  {} {ch} {code}", name(idx))?;
+    }
+    for key in &self.keys {
+      writeln!(f, "  {}  {}{}  {} {}", key.key, name(key.ch), key.idx + 1, self.chars[key.ch].0, key.part)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_explain() {
+    let entries = |entries: &[(&str, &str)]| -> Vec<Entry> {
+      entries.iter().map(|&(word, code)| Entry { word: word.to_string(), code: code.to_string() }).collect()
+    };
+    let codes = migrate::full_codes(&entries(&[("我", "wox"), ("们", "mfi"), ("非", "fvu"), ("常", "cxy")]));
+    let rules = Rules::learn(&entries(&[("我们", "womfxi"), ("非常", "fvcxuy")]), &codes);
+
+    let explanation = explain(&entries(&[("我们", "womfxi")])[0], &rules, &codes).unwrap();
+    assert_eq!(vec![('我', "wox".to_string()), ('们', "mfi".to_string())], explanation.chars);
+    assert_eq!(Key { key: 'x', ch: 0, idx: 2, part: Part::Shape(1) }, explanation.keys[4]);
+    assert_eq!(Part::Initial, explanation.keys[2].part);
+    assert!(explanation.to_string().starts_with("我们 womfxi: A1 A2 B1 B2 A3 B3\n  A 我 wox\n  B 们 mfi\n  w  A1  我 声母\n"));
+
+    assert_eq!(vec![Part::Initial, Part::Final], explain(&entries(&[("我", "wo")])[0], &rules, &codes).unwrap().keys.iter().map(|key| key.part).collect::<Vec<_>>());
+    assert_eq!(None, explain(&entries(&[("我们", "wm")])[0], &rules, &codes));
+    assert_eq!("fvwoux", derive("非我", &rules, &codes).unwrap().code);
+  }
+}
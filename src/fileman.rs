@@ -1,87 +1,306 @@
-use std::fs::OpenOptions;
+use std::fs::{self, OpenOptions, TryLockError};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::{
-  io::{self, SeekFrom, Seek, BufReader, BufWriter, BufRead, Write},
+  io::{self, SeekFrom, Seek, Read, Write},
   fs::File,
-  path::Path,
+  path::{Path, PathBuf},
+  thread,
+  time::Duration,
 };
 
-fn open_read_and_write(path: impl AsRef<Path>) -> io::Result<File> {
-  OpenOptions::new()
-    .read(true)
-    .write(true)
-    .open(path)
+use smart_dict::diff;
+
+/// Set by `--dry-run`: the functions writing files print a unified diff of each instead.
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// Tries of a file operation while another program holds the file, waiting twice as long each time.
+const ATTEMPTS: u32 = 5;
+const FIRST_WAIT: Duration = Duration::from_millis(100);
+
+/// Whether the error means another program holds the file, e.g. Rime while deploying: on Windows
+/// a sharing or lock violation, or access denied to a file open without delete sharing. Elsewhere
+/// files aren't locked that way, and access denied is for good.
+pub fn is_locked(e: &io::Error) -> bool {
+  // ERROR_SHARING_VIOLATION, ERROR_LOCK_VIOLATION
+  cfg!(windows) && (e.kind() == io::ErrorKind::PermissionDenied || matches!(e.raw_os_error(), Some(32 | 33)))
 }
 
-/// requires: lines are in strict ascending order
-fn remove_lines_sync(mut file: File, mut lines: impl Iterator<Item=usize>) {
-  let mut lines = lines.peekable();
-  let msg = "can't clone file handle";
-  let file1 = file.try_clone().expect(msg);
-  let mut file2 = file.try_clone().expect(msg);
-  let mut reader = BufReader::new(file);
-  let mut writer = BufWriter::new(file1);
-  let mut writer_pos = 0;
-
-  for mut line in reader
-    .lines()
-    .enumerate()
-    .filter_map(|(i, line)| {
-      match lines.peek() {
-        None => None,
-        Some(&next_idx) => {
-          if next_idx == i {
-            lines.next().unwrap();
-            None
-          } else {
-            let line = line.expect("can't read lines");
-            Some(line)
-          }
-        }
+/// Runs `f` until it succeeds or fails otherwise than on a locked file, at most [`ATTEMPTS`] times.
+fn retry<T>(path: &Path, f: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+  retry_with(path, FIRST_WAIT, is_locked, f)
+}
+
+/// [`retry`] with the first wait and what tells a locked file given.
+fn retry_with<T>(path: &Path, first_wait: Duration, is_locked: impl Fn(&io::Error) -> bool, mut f: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+  let mut wait = first_wait;
+  for _ in 1..ATTEMPTS {
+    match f() {
+      Err(e) if is_locked(&e) => {
+        thread::sleep(wait);
+        wait *= 2;
       }
-    }) {
-    line.push_str("\r\n");
+      done => return done,
+    }
+  }
+  f().map_err(|e| match is_locked(&e) {
+    true => io::Error::new(e.kind(), format!(
+      "{e}, {} is in use by another program, e.g. Rime deploying; close Weasel or wait for the deployment and try again",
+      path.display(),
+    )),
+    false => e,
+  })
+}
 
-    // store reader pos
-    let reader_pos = file2.stream_position().expect("can't seek");
-    // prepare to write
-    file2.seek(SeekFrom::Start(writer_pos)).expect("can't seek");
+fn temp_path(path: &Path) -> PathBuf {
+  let mut temp = path.as_os_str().to_owned();
+  temp.push(".smart-dict.tmp");
+  temp.into()
+}
+
+/// Replaces the content of a file by writing it next to it and renaming it over, so that a failure
+/// leaves the file as it was; retried while another program holds the file.
+///
+/// With `--dry-run`, prints the diff instead.
+pub fn replace(path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> io::Result<()> {
+  replace_with(path.as_ref(), |file| file.write_all(content.as_ref()))
+}
 
-    writer.write(line.as_bytes()).expect("can't write lines");
-    // update writer pos
-    writer_pos += line.len() as u64;
-    // restore reader pos
-    file2.seek(SeekFrom::Start(reader_pos)).expect("can't seek");
+fn replace_with<T>(path: &Path, f: impl FnOnce(&mut dyn Write) -> io::Result<T>) -> io::Result<T> {
+  if is_dry_run() {
+    let mut content = vec![];
+    let value = f(&mut content)?;
+    preview(path, &content)?;
+    return Ok(value);
   }
+  let temp = temp_path(path);
+  let written = File::create(&temp).and_then(|mut file| {
+    let value = f(&mut file)?;
+    file.sync_all()?;
+    Ok(value)
+  });
+  let renamed = written.and_then(|value| retry(path, || fs::rename(&temp, path)).map(|_| value));
+  if renamed.is_err() {
+    let _ = fs::remove_file(&temp);
+  }
+  renamed
+}
+
+/// Writes a file a command makes, like an `--out` report or a saved baseline, the way [`replace`]
+/// does, creating its directory if needed.
+///
+/// With `--dry-run`, prints the diff instead.
+pub fn write(path: impl AsRef<Path>, content: impl AsRef<[u8]>) -> io::Result<()> {
+  write_with(path, |file| file.write_all(content.as_ref()))
+}
 
-  file2.set_len(writer_pos).unwrap();
+/// Like [`write`], streaming the content to the file with `f`.
+pub fn write_with<T>(path: impl AsRef<Path>, f: impl FnOnce(&mut dyn Write) -> io::Result<T>) -> io::Result<T> {
+  let path = path.as_ref();
+  if let Some(dir) = path.parent().filter(|_| !is_dry_run()) {
+    fs::create_dir_all(dir)?;
+  }
+  replace_with(path, f)
+}
+
+/// Reads a log a command adds to, like the history, empty while there's none yet.
+pub fn read_log(path: impl AsRef<Path>) -> io::Result<String> {
+  match fs::read_to_string(path) {
+    Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+    text => text,
+  }
+}
+
+/// Reads a file, retried while another program holds it.
+pub fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+  let path = path.as_ref();
+  retry(path, || fs::read(path))
+}
+
+/// Takes an exclusive advisory lock on `path`, created if needed, held until the file is dropped.
+/// `None` when another process holds it.
+pub fn try_lock(path: impl AsRef<Path>) -> io::Result<Option<File>> {
+  let file = OpenOptions::new()
+    .create(true)
+    .truncate(false)
+    .write(true)
+    .open(path)?;
+  match file.try_lock() {
+    Ok(()) => Ok(Some(file)),
+    Err(TryLockError::WouldBlock) => Ok(None),
+    Err(TryLockError::Error(e)) => Err(e),
+  }
+}
+
+pub fn set_dry_run(dry_run: bool) {
+  DRY_RUN.store(dry_run, Ordering::Relaxed);
+}
+
+pub fn is_dry_run() -> bool {
+  DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// Prints the diff from the file, empty if missing, to `content`.
+fn preview(path: &Path, content: &[u8]) -> io::Result<()> {
+  let old = match read(path) {
+    Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+    old => old?,
+  };
+  let name = path.file_name().map_or_else(|| path.display().to_string(), |name| name.to_string_lossy().into_owned());
+  match (std::str::from_utf8(&old), std::str::from_utf8(content)) {
+    (Ok(old), Ok(new)) => print!("{}", diff::unified(old, new, &format!("a/{name}"), &format!("b/{name}"))),
+    _ if old != content => println!("Binary files a/{name} and b/{name} differ"),
+    _ => {}
+  }
+  io::stdout().flush()
+}
+
+/// Appends a line to a text file, after a line break if its last line lacks one.
+pub fn append_line(path: impl AsRef<Path>, line: &str) -> io::Result<()> {
+  let path = path.as_ref();
+  if is_dry_run() {
+    let mut content = read(path)?;
+    if content.last().is_some_and(|&last| last != b'\n') {
+      content.push(b'\n');
+    }
+    content.extend_from_slice(format!("{line}\n").as_bytes());
+    return preview(path, &content);
+  }
+  let mut file = retry(path, || OpenOptions::new().read(true).append(true).open(path))?;
+  let len = file.seek(SeekFrom::End(0))?;
+  let mut needs_break = false;
+  if len > 0 {
+    let mut last = [0];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    needs_break = last[0] != b'\n';
+  }
+  let separator = if needs_break { "\n" } else { "" };
+  file.write_all(format!("{separator}{line}\n").as_bytes())?;
+  file.flush()
+}
+
+/// Removes the lines at the given 0-based indices, keeping the line endings of the others,
+/// see [`replace`].
+///
+/// requires: lines are in strict ascending order
+pub fn remove_lines(path: impl AsRef<Path>, lines: impl Iterator<Item=usize>) -> io::Result<()> {
+  let path = path.as_ref();
+  let content = read(path)?;
+  let mut lines = lines.peekable();
+  let mut kept = Vec::with_capacity(content.len());
+  for (i, line) in content.split_inclusive(|&byte| byte == b'\n').enumerate() {
+    if lines.next_if_eq(&i).is_none() {
+      kept.extend_from_slice(line);
+    }
+  }
+  replace(path, kept)
+}
+
+/// Replaces the lines at the given 0-based indices, keeping their line endings, see [`replace`].
+///
+/// requires: lines are in strict ascending order
+pub fn replace_lines(path: impl AsRef<Path>, lines: impl Iterator<Item=(usize, String)>) -> io::Result<()> {
+  let path = path.as_ref();
+  let content = read(path)?;
+  let mut lines = lines.peekable();
+  let mut replaced = Vec::with_capacity(content.len());
+  for (i, line) in content.split_inclusive(|&byte| byte == b'\n').enumerate() {
+    match lines.next_if(|(idx, _)| *idx == i) {
+      Some((_, text)) => {
+        let content_len = line.len() - line.iter().rev().take_while(|&&byte| byte == b'\n' || byte == b'\r').count();
+        replaced.extend_from_slice(text.as_bytes());
+        replaced.extend_from_slice(&line[content_len..]);
+      }
+      None => replaced.extend_from_slice(line),
+    }
+  }
+  replace(path, replaced)
 }
 
 #[cfg(test)]
 mod test {
-  use std::fs::{File, OpenOptions};
+  use std::fs::File;
   use std::io::{BufWriter, Write};
   use super::*;
 
-  const PATH: &str = r"D:\temp.txt";
-
   #[test]
-  #[ignore]
   fn test() {
-    remove_lines_sync(
-      OpenOptions::new()
-        .read(true)
-        .write(true)
-        .open(PATH)
-        .unwrap(),
-      [0usize, 3, 4999, 10000, 50000, 50001].into_iter(),
-    )
-  }
-
-  fn make() {
-    let mut file = File::create(PATH).unwrap();
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("big.txt");
+    make(&path);
+    let removed = [0usize, 3, 4999, 10000, 50000, 50001];
+    remove_lines(&path, removed.into_iter()).unwrap();
+    let text = fs::read_to_string(&path).unwrap();
+    let expected: Vec<_> = (0..100000).filter(|i| !removed.contains(i)).map(|i| i.to_string()).collect();
+    assert_eq!(expected, text.lines().collect::<Vec<_>>());
+  }
+
+  fn make(path: &Path) {
+    let file = File::create(path).unwrap();
     let mut w = BufWriter::new(file);
     for i in 0..100000 {
       w.write_fmt(format_args!("{i}\r\n")).unwrap();
     }
   }
+
+  #[test]
+  fn test_remove_lines() {
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("fileman.txt");
+    fs::write(&path, "a\r\nb\nc\nd").unwrap();
+    remove_lines(&path, [1, 3].into_iter()).unwrap();
+    assert_eq!("a\r\nc\n", fs::read_to_string(&path).unwrap());
+    append_line(&path, "e").unwrap();
+    assert_eq!("a\r\nc\ne\n", fs::read_to_string(&path).unwrap());
+    assert!(!temp_path(&path).exists());
+  }
+
+  #[test]
+  fn test_write() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let path = dir.join("reports/a.txt");
+    write(&path, "a\n").unwrap();
+    assert_eq!(4, write_with(&path, |file| file.write_all(b"abc\n").map(|_| 4)).unwrap());
+    assert_eq!("abc\n", fs::read_to_string(&path).unwrap());
+    assert!(!temp_path(&path).exists());
+  }
+
+  #[test]
+  fn test_is_locked() {
+    let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+    assert_eq!(cfg!(windows), is_locked(&denied));
+    assert_eq!(cfg!(windows), is_locked(&io::Error::from_raw_os_error(32)));
+    assert!(!is_locked(&io::Error::from(io::ErrorKind::NotFound)));
+  }
+
+  #[test]
+  fn test_retry() {
+    let path = Path::new("xkjd6.user.dict.yaml");
+    let locked = |e: &io::Error| e.kind() == io::ErrorKind::PermissionDenied;
+    // fails with `errors` in turn, then succeeds, counting the attempts
+    let attempts = |errors: &[io::ErrorKind]| {
+      let mut errors = errors.iter();
+      let mut count = 0;
+      let result = retry_with(path, Duration::ZERO, locked, || {
+        count += 1;
+        errors.next().map_or(Ok(()), |&kind| Err(io::Error::from(kind)))
+      });
+      (count, result)
+    };
+
+    let (count, result) = attempts(&[io::ErrorKind::PermissionDenied; 2]);
+    assert_eq!(3, count);
+    assert!(result.is_ok());
+    // another error isn't retried
+    let (count, result) = attempts(&[io::ErrorKind::NotFound]);
+    assert_eq!(1, count);
+    assert_eq!(io::ErrorKind::NotFound, result.unwrap_err().kind());
+    // a file locked all along is given up on, telling who may hold it
+    let (count, result) = attempts(&[io::ErrorKind::PermissionDenied; ATTEMPTS as usize]);
+    assert_eq!(ATTEMPTS, count);
+    let e = result.unwrap_err();
+    assert_eq!(io::ErrorKind::PermissionDenied, e.kind());
+    assert!(e.to_string().contains("xkjd6.user.dict.yaml is in use by another program"), "{e}");
+  }
 }
@@ -0,0 +1,405 @@
+//! Filter expressions slicing the entries a report looks at, like
+//! `code_len<=3 && candidates>1 && dict=="xkjd6.cizu"`.
+//!
+//! An expression compares the fields of an entry with numbers and strings, `~` matching a regex,
+//! combined with `&&`, `||`, `!` and parentheses. The fields are checked when parsing, so that a
+//! typo fails before a long report rather than filtering everything out.
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use regex::Regex;
+use crate::blame::Blame;
+use crate::dedup::Located;
+use crate::types::Code;
+
+/// An entry as a filter sees it.
+#[derive(Clone, Copy, Debug)]
+pub struct Row<'a> {
+  pub word: &'a str,
+  pub code: &'a str,
+  pub dict: &'a str,
+  /// 1-based, as reports print it.
+  pub line: usize,
+  pub note: Option<&'a str>,
+  /// The words on the same code, this one included.
+  pub candidates: usize,
+  /// The commit of the line, for `age` and `author`, unknown without one.
+  pub blame: Option<&'a Blame>,
+}
+
+impl<'a> Row<'a> {
+  pub fn of(located: &'a Located, candidates: &HashMap<Code, usize>) -> Self {
+    Self {
+      word: &located.entry.word,
+      code: &located.entry.code,
+      dict: &located.source.table,
+      line: located.source.line + 1,
+      note: located.annotation.as_deref(),
+      candidates: candidates.get(&located.entry.code).copied().unwrap_or(1),
+      blame: None,
+    }
+  }
+
+  pub fn blamed(self, blame: Option<&'a Blame>) -> Self {
+    Self { blame, ..self }
+  }
+}
+
+/// Days since the line was committed, NaN if unknown so that no comparison holds.
+fn age(row: &Row) -> f64 {
+  let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_secs());
+  row.blame.map_or(f64::NAN, |blame| now.saturating_sub(blame.time) as f64 / 86400.0)
+}
+
+/// The words on each code of the entries, for `candidates`.
+pub fn candidates(entries: &[Located]) -> HashMap<Code, usize> {
+  let mut words: HashMap<&str, Vec<&str>> = HashMap::new();
+  for located in entries {
+    let words = words.entry(&located.entry.code).or_default();
+    if !words.contains(&located.entry.word.as_str()) {
+      words.push(&located.entry.word);
+    }
+  }
+  words.into_iter().map(|(code, words)| (code.to_string(), words.len())).collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Type {
+  Number,
+  Text,
+}
+
+impl Display for Type {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      Type::Number => "number",
+      Type::Text => "text",
+    })
+  }
+}
+
+/// Name, type and value of a field.
+type Field = (&'static str, Type, fn(&Row) -> Value);
+
+const FIELDS: [Field; 10] = [
+  ("word", Type::Text, |row| Value::Text(row.word.to_string())),
+  ("code", Type::Text, |row| Value::Text(row.code.to_string())),
+  ("dict", Type::Text, |row| Value::Text(row.dict.to_string())),
+  ("note", Type::Text, |row| Value::Text(row.note.unwrap_or_default().to_string())),
+  ("line", Type::Number, |row| Value::Number(row.line as f64)),
+  ("word_len", Type::Number, |row| Value::Number(row.word.chars().count() as f64)),
+  ("code_len", Type::Number, |row| Value::Number(row.code.chars().count() as f64)),
+  ("candidates", Type::Number, |row| Value::Number(row.candidates as f64)),
+  ("age", Type::Number, |row| Value::Number(age(row))),
+  ("author", Type::Text, |row| Value::Text(row.blame.map(|blame| blame.author.clone()).unwrap_or_default())),
+];
+
+#[derive(Clone, Debug, PartialEq, PartialOrd)]
+enum Value {
+  Number(f64),
+  Text(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+  Eq,
+  Ne,
+  Lt,
+  Le,
+  Gt,
+  Ge,
+}
+
+#[derive(Clone, Debug)]
+enum Operand {
+  Field(usize),
+  Value(Value),
+}
+
+impl Operand {
+  fn get(&self, row: &Row) -> Value {
+    match self {
+      Operand::Field(idx) => FIELDS[*idx].2(row),
+      Operand::Value(value) => value.clone(),
+    }
+  }
+
+  fn ty(&self) -> Type {
+    match self {
+      Operand::Field(idx) => FIELDS[*idx].1,
+      Operand::Value(Value::Number(_)) => Type::Number,
+      Operand::Value(Value::Text(_)) => Type::Text,
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+  And(Box<Expr>, Box<Expr>),
+  Or(Box<Expr>, Box<Expr>),
+  Not(Box<Expr>),
+  Compare(Operand, Op, Operand),
+  Match(Operand, Regex),
+}
+
+impl Expr {
+  fn eval(&self, row: &Row) -> bool {
+    match self {
+      Expr::And(a, b) => a.eval(row) && b.eval(row),
+      Expr::Or(a, b) => a.eval(row) || b.eval(row),
+      Expr::Not(a) => !a.eval(row),
+      Expr::Compare(a, op, b) => {
+        let (a, b) = (a.get(row), b.get(row));
+        match op {
+          Op::Eq => a == b,
+          Op::Ne => a != b,
+          Op::Lt => a < b,
+          Op::Le => a <= b,
+          Op::Gt => a > b,
+          Op::Ge => a >= b,
+        }
+      }
+      Expr::Match(a, regex) => match a.get(row) {
+        Value::Text(text) => regex.is_match(&text),
+        Value::Number(number) => regex.is_match(&number.to_string()),
+      },
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+  Ident(String),
+  Number(f64),
+  Text(String),
+  /// Operators and parentheses.
+  Punct(&'static str),
+}
+
+const PUNCTS: [&str; 13] = ["&&", "||", "==", "!=", "<=", ">=", "<", ">", "!", "~", "(", ")", "="];
+
+/// The tokens and the 1-based column each starts at.
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, String> {
+  let chars: Vec<_> = text.chars().collect();
+  let mut tokens = vec![];
+  let mut idx = 0;
+  while idx < chars.len() {
+    let start = idx;
+    let ch = chars[idx];
+    if ch.is_whitespace() {
+      idx += 1;
+      continue;
+    }
+    let token = if ch.is_ascii_alphabetic() || ch == '_' {
+      while idx < chars.len() && (chars[idx].is_ascii_alphanumeric() || chars[idx] == '_') {
+        idx += 1;
+      }
+      Token::Ident(chars[start..idx].iter().collect())
+    } else if ch.is_ascii_digit() || ch == '.' {
+      while idx < chars.len() && (chars[idx].is_ascii_digit() || chars[idx] == '.') {
+        idx += 1;
+      }
+      let number: String = chars[start..idx].iter().collect();
+      Token::Number(number.parse().map_err(|_| format!("invalid number {number} at column {}", start + 1))?)
+    } else if ch == '"' {
+      let mut text = String::new();
+      idx += 1;
+      loop {
+        match chars.get(idx) {
+          None => return Err(format!("unterminated string at column {}", start + 1)),
+          Some('"') => break,
+          Some('\\') if idx + 1 < chars.len() => {
+            idx += 1;
+            // `\\` and `\"`, other escapes are kept for regexes
+            if !matches!(chars[idx], '\\' | '"') {
+              text.push('\\');
+            }
+            text.push(chars[idx]);
+          }
+          Some(&ch) => text.push(ch),
+        }
+        idx += 1;
+      }
+      idx += 1;
+      Token::Text(text)
+    } else {
+      let rest: String = chars[idx..chars.len().min(idx + 2)].iter().collect();
+      let punct = PUNCTS
+        .into_iter()
+        .find(|punct| rest.starts_with(punct))
+        .ok_or_else(|| format!("unexpected {ch} at column {}", start + 1))?;
+      idx += punct.len();
+      Token::Punct(punct)
+    };
+    tokens.push((token, start + 1));
+  }
+  Ok(tokens)
+}
+
+struct Parser {
+  tokens: Vec<(Token, usize)>,
+  pos: usize,
+}
+
+impl Parser {
+  fn peek(&self) -> Option<&Token> {
+    self.tokens.get(self.pos).map(|(token, _)| token)
+  }
+
+  fn column(&self) -> String {
+    match self.tokens.get(self.pos) {
+      Some((_, column)) => format!("at column {column}"),
+      None => "at the end".to_string(),
+    }
+  }
+
+  fn eat(&mut self, punct: &str) -> bool {
+    let found = matches!(self.peek(), Some(Token::Punct(p)) if *p == punct);
+    if found {
+      self.pos += 1;
+    }
+    found
+  }
+
+  fn or(&mut self) -> Result<Expr, String> {
+    let mut expr = self.and()?;
+    while self.eat("||") {
+      expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+    }
+    Ok(expr)
+  }
+
+  fn and(&mut self) -> Result<Expr, String> {
+    let mut expr = self.unary()?;
+    while self.eat("&&") {
+      expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+    }
+    Ok(expr)
+  }
+
+  fn unary(&mut self) -> Result<Expr, String> {
+    if self.eat("!") {
+      return Ok(Expr::Not(Box::new(self.unary()?)));
+    }
+    if self.eat("(") {
+      let expr = self.or()?;
+      if !self.eat(")") {
+        return Err(format!("expected ) {}", self.column()));
+      }
+      return Ok(expr);
+    }
+    self.comparison()
+  }
+
+  fn comparison(&mut self) -> Result<Expr, String> {
+    let left = self.operand()?;
+    let column = self.column();
+    if self.eat("~") {
+      let Some(Token::Text(pattern)) = self.peek().cloned() else {
+        return Err(format!("expected a \"regex\" after ~ {}", self.column()));
+      };
+      self.pos += 1;
+      let regex = Regex::new(&pattern).map_err(|e| format!("invalid regex {pattern:?}: {e}"))?;
+      return Ok(Expr::Match(left, regex));
+    }
+    let op = [("==", Op::Eq), ("!=", Op::Ne), ("<=", Op::Le), (">=", Op::Ge), ("<", Op::Lt), (">", Op::Gt)]
+      .into_iter()
+      .find(|(punct, _)| self.eat(punct))
+      .map(|(_, op)| op);
+    let Some(op) = op else {
+      let hint = if self.peek() == Some(&Token::Punct("=")) { ", == compares" } else { "" };
+      return Err(format!("expected a comparison {column}{hint}"));
+    };
+    let right = self.operand()?;
+    if left.ty() != right.ty() {
+      return Err(format!("can't compare a {} with a {} {column}", left.ty(), right.ty()));
+    }
+    Ok(Expr::Compare(left, op, right))
+  }
+
+  fn operand(&mut self) -> Result<Operand, String> {
+    let column = self.column();
+    let operand = match self.peek() {
+      Some(Token::Ident(name)) => FIELDS
+        .iter()
+        .position(|(field, _, _)| field == name)
+        .map(Operand::Field)
+        .ok_or_else(|| {
+          let fields: Vec<_> = FIELDS.iter().map(|(field, _, _)| *field).collect();
+          format!("unknown field {name} {column}, expected one of {}", fields.join(", "))
+        })?,
+      Some(Token::Number(number)) => Operand::Value(Value::Number(*number)),
+      Some(Token::Text(text)) => Operand::Value(Value::Text(text.clone())),
+      _ => return Err(format!("expected a field, a number or a \"string\" {column}")),
+    };
+    self.pos += 1;
+    Ok(operand)
+  }
+}
+
+/// A parsed filter expression.
+#[derive(Clone, Debug)]
+pub struct Filter {
+  text: String,
+  expr: Expr,
+}
+
+impl Filter {
+  pub fn matches(&self, row: &Row) -> bool {
+    self.expr.eval(row)
+  }
+
+  /// Whether the expression looks at `field`, e.g. to skip counting candidates when it doesn't.
+  pub fn uses(&self, field: &str) -> bool {
+    tokenize(&self.text).is_ok_and(|tokens| tokens.iter().any(|(token, _)| *token == Token::Ident(field.to_string())))
+  }
+}
+
+impl FromStr for Filter {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut parser = Parser { tokens: tokenize(s)?, pos: 0 };
+    let expr = parser.or()?;
+    if parser.pos < parser.tokens.len() {
+      return Err(format!("unexpected input {}", parser.column()));
+    }
+    Ok(Self { text: s.to_string(), expr })
+  }
+}
+
+impl Display for Filter {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&self.text)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_filter() {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+    let blame = Blame { commit: "4e405ea5".to_string(), author: "张三".to_string(), time: now - 3 * 86400 };
+    let row = Row { word: "非常", code: "fio", dict: "xkjd6.cizu", line: 12, note: Some("自造"), candidates: 2, blame: Some(&blame) };
+    let matches = |expr: &str| expr.parse::<Filter>().unwrap().matches(&row);
+    assert!(matches(r#"code_len<=3 && candidates>1 && dict=="xkjd6.cizu""#));
+    assert!(!matches(r#"code_len<=3 && candidates>1 && dict=="xkjd6.danzi""#));
+    assert!(matches(r#"word_len == 3 || !(line > 100) && note ~ "^自""#));
+    assert!(!matches(r#"(word_len == 3 || !(line > 100)) && note ~ "^来源""#));
+    assert!(matches(r#"code ~ "^f\w+$" && code < "g""#));
+    assert!(matches(r#"age < 30 && author == "张三""#));
+    let unblamed = Row { blame: None, ..row };
+    assert!(!"age < 30".parse::<Filter>().unwrap().matches(&unblamed) && !"age >= 30".parse::<Filter>().unwrap().matches(&unblamed));
+
+    let error = |expr: &str| expr.parse::<Filter>().unwrap_err();
+    assert_eq!("unknown field len at column 1, expected one of word, code, dict, note, line, word_len, code_len, candidates, age, author", error("len < 3"));
+    assert_eq!("can't compare a text with a number at column 6", error("code > 3"));
+    assert_eq!("expected a comparison at column 6, == compares", error("dict = \"a\""));
+    assert_eq!("expected ) at the end", error("(line > 1"));
+    assert!("line > 1".parse::<Filter>().unwrap().uses("line"));
+  }
+}
@@ -0,0 +1,59 @@
+//! Sorting the entries of a table by code, as `fmt` rewrites them.
+//!
+//! Comment and blank lines split the body into blocks sorted separately, so that sections stay
+//! sections, and entries annotated `# pin`, e.g. ordering hacks relying on the file order, keep
+//! their line. Words sharing a code keep their order, which is their candidate order in Rime.
+
+use crate::dict::{split_annotation, LineParser};
+
+pub const PIN: &str = "pin";
+
+/// Whether the line's annotation starts with [`PIN`], like `我\tw\t# pin 排第一`.
+pub fn is_pinned(line: &str) -> bool {
+  split_annotation(line).1.is_some_and(|annotation| annotation.split_whitespace().next() == Some(PIN))
+}
+
+/// The body of a table with the entries of each block sorted by code, trailing blanks trimmed,
+/// and every line ending kept where it was. The entries are told with `parser`, see `LineParser`.
+pub fn sort_body(body: &str, mut parser: LineParser) -> String {
+  let lines: Vec<_> = body.split_inclusive('\n').collect();
+  let contents: Vec<_> = lines.iter().map(|line| line.trim_end_matches(['\r', '\n']).trim_end()).collect();
+  let codes: Vec<_> = contents.iter().map(|line| parser.parse(line).map(|entry| entry.code)).collect();
+  let mut sorted = contents.clone();
+
+  let mut start = 0;
+  while start < lines.len() {
+    let end = (start..lines.len()).find(|&idx| codes[idx].is_none()).unwrap_or(lines.len());
+    let slots: Vec<_> = (start..end).filter(|&idx| !is_pinned(contents[idx])).collect();
+    let mut movable = slots.clone();
+    movable.sort_by(|&a, &b| codes[a].cmp(&codes[b]));
+    for (slot, idx) in slots.into_iter().zip(movable) {
+      sorted[slot] = contents[idx];
+    }
+    start = end + 1;
+  }
+
+  lines
+    .iter()
+    .zip(sorted)
+    .map(|(line, content)| content.to_string() + &line[line.trim_end_matches(['\r', '\n']).len()..])
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_sort_body() {
+    let body = "# 常用\r\nwo\tw\r\n我\tw \r\n首\tb\t# pin\r\n啊\ta\r\n\r\n# 其他\r\n不\tb\r\n吧\ta";
+    assert_eq!(
+      "# 常用\r\n啊\ta\r\nwo\tw\r\n首\tb\t# pin\r\n我\tw\r\n\r\n# 其他\r\n吧\ta\r\n不\tb",
+      sort_body(body, LineParser::new()),
+    );
+    // tables separated by spaces are sorted too
+    assert_eq!("啊    a\n不  b\n吧  c\n", sort_body("不  b\n吧  c\n啊    a\n", LineParser::new()));
+    assert!(is_pinned("首\tb\t#pin"));
+    assert!(!is_pinned("首\tb\t# pinyin"));
+  }
+}
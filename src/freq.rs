@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use crate::corpus;
+use crate::encoding::Encoding;
+use crate::types::Word;
+
+/// Lines `Format::detect` looks at.
+const SNIFF_LINES: usize = 64;
+
+/// The layouts of the word frequency lists around.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Format {
+  /// `word\tcount`, what the commands write, or `word count`.
+  #[default]
+  Tsv,
+  /// The dictionaries of jieba, `word count tag` separated by spaces, the tag being optional.
+  Jieba,
+  /// The lists of the BLCU corpus center (BCC), `rank\tword\tcount` and maybe more columns, like
+  /// the frequency per million, which are ignored.
+  Blcu,
+}
+
+impl FromStr for Format {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "tsv" => Ok(Format::Tsv),
+      "jieba" => Ok(Format::Jieba),
+      "blcu" => Ok(Format::Blcu),
+      _ => Err(format!("a frequency list is tsv, jieba or blcu, not {s}")),
+    }
+  }
+}
+
+impl Display for Format {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      Format::Tsv => "tsv",
+      Format::Jieba => "jieba",
+      Format::Blcu => "blcu",
+    })
+  }
+}
+
+impl Format {
+  /// The word and count of a line, `None` for blank lines, headers and lines that don't parse.
+  pub fn parse(self, line: &str) -> Option<(&str, u64)> {
+    let line = line.trim_start_matches('\u{FEFF}').trim_end_matches(['\r', '\n']);
+    let count = |cell: &str| cell.trim().parse::<u64>().ok();
+    match self {
+      Format::Tsv => match line.split_once('\t') {
+        Some((word, rest)) => Some((word, count(rest)?)),
+        None => {
+          let mut cells = line.split_whitespace();
+          let (word, rest) = (cells.next()?, count(cells.next()?)?);
+          cells.next().is_none().then_some((word, rest))
+        }
+      },
+      Format::Jieba => {
+        let mut cells = line.split_whitespace();
+        Some((cells.next()?, count(cells.next()?)?))
+      }
+      Format::Blcu => {
+        let mut cells = line.split(['\t', ' ']).filter(|cell| !cell.is_empty());
+        count(cells.next()?)?;
+        Some((cells.next()?, count(cells.next()?)?))
+      }
+    }
+  }
+
+  /// The format most of the first lines of a list parse with, TSV if none stands out. A rank
+  /// before the word tells BLCU lists, spaces and tags jieba dictionaries.
+  pub fn detect<'l>(lines: impl IntoIterator<Item=&'l str>) -> Self {
+    let mut votes: HashMap<Format, usize> = HashMap::new();
+    for line in lines.into_iter().take(SNIFF_LINES) {
+      let cells: Vec<_> = line.split_whitespace().collect();
+      let is_count = |idx: usize| cells.get(idx).is_some_and(|cell| cell.parse::<u64>().is_ok());
+      let format = match cells.len() {
+        3.. if is_count(0) && is_count(2) => Format::Blcu,
+        3 if is_count(1) && !line.contains('\t') => Format::Jieba,
+        2 if is_count(1) => Format::Tsv,
+        _ => continue,
+      };
+      *votes.entry(format).or_default() += 1;
+    }
+    [Format::Blcu, Format::Jieba, Format::Tsv]
+      .into_iter()
+      .max_by_key(|format| votes.get(format).copied().unwrap_or_default())
+      .filter(|format| votes.contains_key(format))
+      .unwrap_or_default()
+  }
+}
+
+/// Word frequencies, e.g. counted from a corpus.
+#[derive(Clone, Debug, Default)]
+pub struct Frequencies(pub HashMap<Word, u64>);
+
+impl Frequencies {
+  /// Loads a list in any of the formats, see `Format::detect`, and encodings, see `Encoding::detect`.
+  pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+    Self::load_as(path, None)
+  }
+
+  /// Loads a list in `format`, detected from its first lines if not given, summing repeated words
+  /// and skipping the lines that don't parse.
+  pub fn load_as(path: impl AsRef<Path>, format: Option<Format>) -> io::Result<Self> {
+    let path = path.as_ref();
+    let encoding = Encoding::detect_file(path)?;
+    let mut freq = Self::default();
+    let mut head = vec![];
+    let mut format = format;
+    corpus::for_each_line(BufReader::new(File::open(path)?), encoding, |line, _, _| {
+      match format {
+        Some(format) => freq.add(format, line),
+        None => {
+          head.push(line.to_string());
+          if head.len() == SNIFF_LINES {
+            let detected = *format.insert(Format::detect(head.iter().map(String::as_str)));
+            head.drain(..).for_each(|line| freq.add(detected, &line));
+          }
+        }
+      }
+      Ok(())
+    })?;
+    let format = format.unwrap_or_else(|| Format::detect(head.iter().map(String::as_str)));
+    head.iter().for_each(|line| freq.add(format, line));
+    Ok(freq)
+  }
+
+  /// Reads a line of a list in `format`.
+  pub fn add(&mut self, format: Format, line: &str) {
+    if let Some((word, count)) = format.parse(line) {
+      *self.0.entry(word.to_string()).or_default() += count;
+    }
+  }
+
+  pub fn get(&self, word: &str) -> u64 {
+    self.0.get(word).copied().unwrap_or_default()
+  }
+
+  /// Words sorted by descending frequency.
+  pub fn ranked(&self) -> Vec<(&Word, u64)> {
+    let mut ranked: Vec<_> = self.0.iter().map(|(word, &count)| (word, count)).collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    ranked
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_formats() {
+    let tsv = ["的\t100", "我们\t 20\r\n", "一 下\t3"];
+    let jieba = ["的 100 uj", "我们 20 r", "AT&T 3 nz"];
+    let blcu = ["序号\t词语\t频次", "1\t的\t100\t1234.5", "2\t我们\t20\t33.1"];
+    assert_eq!(Format::Tsv, Format::detect(tsv));
+    assert_eq!(Format::Jieba, Format::detect(jieba));
+    assert_eq!(Format::Blcu, Format::detect(blcu));
+    assert_eq!(Format::Tsv, Format::detect(["# words"]));
+
+    assert_eq!(Some(("我们", 20)), Format::Tsv.parse(tsv[1]));
+    assert_eq!(Some(("一 下", 3)), Format::Tsv.parse(tsv[2]));
+    assert_eq!(Some(("我们", 20)), Format::Tsv.parse("\u{FEFF}我们 20"));
+    assert_eq!(None, Format::Tsv.parse(jieba[0]));
+    assert_eq!(Some(("AT&T", 3)), Format::Jieba.parse(jieba[2]));
+    assert_eq!(Some(("的", 100)), Format::Jieba.parse("的 100"));
+    assert_eq!(None, Format::Blcu.parse(blcu[0]));
+    assert_eq!(Some(("的", 100)), Format::Blcu.parse(blcu[1]));
+    assert_eq!(Ok(Format::Blcu), "blcu".parse());
+
+    let mut freq = Frequencies::default();
+    for line in jieba.iter().chain(&["的 5 uj"]) {
+      freq.add(Format::Jieba, line);
+    }
+    assert_eq!(105, freq.get("的"));
+    assert_eq!(vec![(&"的".to_string(), 105), (&"我们".to_string(), 20), (&"AT&T".to_string(), 3)], freq.ranked());
+  }
+}
@@ -0,0 +1,139 @@
+//! One 0–100 score summing up a dict, for a badge in a schema's README: the collision rate,
+//! the keystrokes per character, the entries pushed off the first page and the lint errors,
+//! each scored from 0 to 1 and weighted.
+
+use std::fmt::Write;
+use crate::check::quote;
+use crate::trie::Trie;
+
+/// A metric and how it's scored: full marks up to `good`, none from `bad` on, linear in between.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Component {
+  pub name: &'static str,
+  pub value: f64,
+  pub weight: f64,
+  good: f64,
+  bad: f64,
+}
+
+impl Component {
+  pub fn score(&self) -> f64 {
+    if !self.value.is_finite() {
+      return 0.0;
+    }
+    ((self.bad - self.value) / (self.bad - self.good)).clamp(0.0, 1.0)
+  }
+}
+
+/// What goes into the score, counted by the caller.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Inputs {
+  pub entries: usize,
+  /// Entries sharing their code with other words, not counting whitelisted ones.
+  pub collided: usize,
+  /// Keystrokes per character, weighted by frequency if known.
+  pub keystrokes_per_char: f64,
+  pub dead: usize,
+  pub lint_errors: usize,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Health {
+  pub components: Vec<Component>,
+}
+
+impl Health {
+  pub fn new(inputs: &Inputs) -> Self {
+    let share = |count: usize| if inputs.entries == 0 { 0.0 } else { count as f64 / inputs.entries as f64 };
+    let component = |name, value, weight, good, bad| Component { name, value, weight, good, bad };
+    Self {
+      components: vec![
+        component("collision_rate", share(inputs.collided), 30.0, 0.0, 0.2),
+        component("keystrokes_per_char", inputs.keystrokes_per_char, 30.0, 2.0, 4.0),
+        component("dead_rate", share(inputs.dead), 20.0, 0.0, 0.05),
+        component("lint_errors", inputs.lint_errors as f64, 20.0, 0.0, 10.0),
+      ],
+    }
+  }
+
+  pub fn score(&self) -> f64 {
+    let total: f64 = self.components.iter().map(|component| component.weight).sum();
+    self.components.iter().map(|component| component.score() * component.weight).sum::<f64>() / total * 100.0
+  }
+
+  /// Like `{"score":87,"components":{"collision_rate":{"value":0.01,"score":0.95,"weight":30},...}}`.
+  pub fn to_json(&self) -> String {
+    let mut json = format!(r#"{{"score":{},"components":{{"#, self.score().round());
+    for (i, component) in self.components.iter().enumerate() {
+      if i > 0 {
+        json.push(',');
+      }
+      let value = if component.value.is_finite() { component.value.to_string() } else { "null".to_string() };
+      write!(json, r#"{}:{{"value":{value},"score":{},"weight":{}}}"#,
+             quote(component.name), component.score(), component.weight).unwrap();
+    }
+    json.push_str("}}");
+    json
+  }
+
+  /// A flat badge in the usual two-part style, `label | 87`, green to red by the score.
+  pub fn badge_svg(&self, label: &str) -> String {
+    let score = self.score().round();
+    let color = match score as u32 {
+      90.. => "#4c1",
+      75..90 => "#97ca00",
+      50..75 => "#dfb317",
+      _ => "#e05d44",
+    };
+    let value = score.to_string();
+    // close enough to Verdana at 11px for these characters
+    let text_width = |text: &str| text.chars().count() * 7 + 10;
+    let (left, right) = (text_width(label), text_width(&value));
+    let width = left + right;
+    let label = escape_xml(label);
+    format!(r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {value}">
+<title>{label}: {value}</title>
+<rect width="{left}" height="20" fill="#555"/>
+<rect x="{left}" width="{right}" height="20" fill="{color}"/>
+<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+<text x="{}" y="14">{label}</text>
+<text x="{}" y="14">{value}</text>
+</g>
+</svg>
+"##, left / 2, left + right / 2)
+  }
+}
+
+fn escape_xml(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Entries pushed off the first page of their own code by the `page` words before them.
+pub fn dead_entries(trie: &Trie, page: usize) -> usize {
+  trie.nodes().map(|node| node.words().len().saturating_sub(page)).sum()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::TrieBuilder;
+
+  #[test]
+  fn test_health() {
+    let perfect = Health::new(&Inputs { entries: 10, keystrokes_per_char: 1.5, ..Default::default() });
+    assert_eq!(100.0, perfect.score());
+
+    let health = Health::new(&Inputs { entries: 100, collided: 10, keystrokes_per_char: 3.0, dead: 10, lint_errors: 0 });
+    // half of the collisions' and of the keystrokes' marks, none of the dead entries'
+    assert_eq!(50.0, health.score());
+    assert!(health.to_json().starts_with(r#"{"score":50,"components":{"collision_rate":{"value":0.1,"score":0.5,"weight":30}"#));
+    let badge = health.badge_svg("dict <health>");
+    assert!(badge.contains("#dfb317") && badge.contains(">50</text>") && badge.contains("dict &lt;health&gt;"));
+
+    let trie = TrieBuilder::from_iter([("a", "啊"), ("a", "阿"), ("a", "呵"), ("b", "不")]).build();
+    assert_eq!(1, dead_entries(&trie, 2));
+    assert_eq!(0, dead_entries(&trie, 10));
+  }
+}
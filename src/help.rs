@@ -0,0 +1,589 @@
+//! The help of the commands, `smart-dict help [<command>]` or `--help`, and the completion scripts
+//! of the shells, `smart-dict completions <bash|zsh|fish|powershell>`, both made from the table of
+//! the commands below.
+
+use std::fmt::Write;
+use crate::cli::SWITCHES;
+use crate::i18n;
+
+pub struct Command {
+  pub name: &'static str,
+  /// What it does, in English and in Chinese.
+  pub about: (&'static str, &'static str),
+  pub usage: &'static str,
+  /// The options besides the global ones, without their `--`.
+  pub options: &'static [&'static str],
+  pub examples: &'static [&'static str],
+}
+
+/// The options every command reading a schema takes.
+pub const GLOBAL_OPTIONS: &[&str] = &["dir", "schema", "config", "lang", "blacklist", "chunks", "chunk-penalty", "convert", "opencc", "verbose", "dry-run", "help"];
+
+/// The options taking a file or a directory, completed with paths; the values of the others, numbers
+/// and names, are left to type.
+pub const PATH_OPTIONS: &[&str] = &[
+  "dir", "config", "blacklist", "chunks", "opencc", "badge", "base", "baseline", "chat", "corpus", "dict", "dir-a", "dir-b",
+  "file", "freq", "head", "input", "json", "load", "new", "old", "other", "out", "out-dir", "pack", "patch", "snapshot", "svg",
+  "whitelist", "words", "xlsx",
+];
+
+pub const SHELLS: &[&str] = &["bash", "zsh", "fish", "powershell"];
+
+pub const COMMANDS: &[Command] = &[
+  Command {
+    name: "stats",
+    about: ("Writes the statistics of each table to data.csv", "统计各码表，写入 data.csv"),
+    usage: "smart-dict stats [--columns <names>] [--decimals <n>] [--decimal-sep <char>] [--filter <expr>] [--jobs <n>] [--max-sum-per <percent>]",
+    options: &["columns", "decimals", "decimal-sep", "filter", "jobs", "max-sum-per", "check"],
+    examples: &["smart-dict stats", "smart-dict stats --filter 'len >= 4' --decimal-sep ,", "smart-dict stats --check --max-sum-per 5"],
+  },
+  Command {
+    name: "budget",
+    about: ("Compares the entries of each table with its budget in the config", "对照配置中的预算检查各码表的词条数"),
+    usage: "smart-dict budget [--snapshot <file>] [--save] [--check]",
+    options: &["snapshot", "save", "check"],
+    examples: &["smart-dict budget", "smart-dict budget --save", "smart-dict budget --check"],
+  },
+  Command {
+    name: "lint",
+    about: ("Checks the entries for blank words, mixed scripts, collisions, orphans and bad characters", "检查空词、简繁混用、重码、孤词与异常字符"),
+    usage: "smart-dict lint [--note <regex>] [--fix [--dry-run]] [--max-warnings <n>] [--check]",
+    options: &["note", "fix", "bump", "max-warnings", "whitelist", "check"],
+    examples: &["smart-dict lint", "smart-dict lint --fix --dry-run", "smart-dict lint --check --max-warnings 0"],
+  },
+  Command {
+    name: "collisions",
+    about: ("Lists the words sharing a code, the worst first", "列出重码，最严重的在前"),
+    usage: "smart-dict collisions [--freq <file>] [--top <k>] [--offset <n>] [--limit <n>] [--ndjson] [--filter <expr>] [--baseline <file>] [--save] [--check [--max <n>]]",
+    options: &["freq", "top", "offset", "limit", "ndjson", "filter", "blame", "baseline", "save", "max", "whitelist", "check"],
+    examples: &["smart-dict collisions --top 20", "smart-dict collisions --freq freq.tsv --offset 100 --limit 100", "smart-dict collisions --ndjson > collisions.ndjson"],
+  },
+  Command {
+    name: "hard-words",
+    about: ("Lists the frequent words costing the most keys per character", "列出每字击键最多的常用词"),
+    usage: "smart-dict hard-words --freq <file> [--pool <n>] [--top <n>] [--max-per-char <keys>]",
+    options: &["freq", "pool", "top", "max-per-char", "check"],
+    examples: &["smart-dict hard-words --freq freq.tsv --top 50"],
+  },
+  Command {
+    name: "compare",
+    about: ("Compares two schemas on a corpus, side by side", "在同一语料上并排比较两个方案"),
+    usage: "smart-dict compare --schema-a <name> --schema-b <name> [--dir-a <dir>] [--dir-b <dir>] --corpus <file>",
+    options: &["schema-a", "schema-b", "dir-a", "dir-b", "corpus", "encoding", "check"],
+    examples: &["smart-dict compare --schema-a xkjd6 --schema-b xkjd6_test --corpus novel.txt"],
+  },
+  Command {
+    name: "dedup",
+    about: ("Deletes the entries of words which already have a shorter code", "删除已有更短编码的词条"),
+    usage: "smart-dict dedup [--dry-run | --yes] [--check]",
+    options: &["yes", "verify", "bump", "check"],
+    examples: &["smart-dict dedup --dry-run", "smart-dict dedup --yes"],
+  },
+  Command {
+    name: "corpus",
+    about: ("Types a corpus with the shortest codes: keys per character, auto-commits, coverage", "用最短编码打一遍语料：字均码长、自动上屏率、覆盖率"),
+    usage: "smart-dict corpus --corpus <file> [--every <lines>] [--resume] [--sample <percent|lines> [--seed <n>]] [--max-keys-per-char <keys>]",
+    options: &["corpus", "encoding", "every", "resume", "sample", "seed", "max-keys-per-char", "check"],
+    examples: &["smart-dict corpus --corpus novel.txt", "smart-dict corpus --corpus huge.txt --sample 1%", "smart-dict corpus --corpus huge.txt --resume"],
+  },
+  Command {
+    name: "density",
+    about: ("Reports how many keys and candidates follow the prefixes of each length", "按前缀长度统计后续按键与候选的数量"),
+    usage: "smart-dict density [--at <depth>] [--hot <n>] [--max-avg-candidates <n>]",
+    options: &["at", "hot", "max-avg-candidates", "check"],
+    examples: &["smart-dict density --hot 20"],
+  },
+  Command {
+    name: "eval",
+    about: ("Simulates typing codes and prints what gets committed", "模拟输入编码，输出上屏的文字"),
+    usage: "smart-dict eval <code>... | --input <file> [--trace] [--echo] [--completion-depth <n> | --no-completion] [--backspace <key>] [--escape <key>] [--preview [--layout <horizontal|vertical>] [--caret <text>] [--page-size <n>]]",
+    options: &["input", "trace", "echo", "completion-depth", "no-completion", "backspace", "escape", "preview", "layout", "caret", "page-size"],
+    examples: &["smart-dict eval 'wlxhdjej '", "smart-dict eval wi --trace", "smart-dict eval wi --preview --layout vertical"],
+  },
+  Command {
+    name: "assoc",
+    about: ("Counts which words follow which in a corpus, for phrases worth an entry", "统计语料中词与词的接续，找出值得成词的组合"),
+    usage: "smart-dict assoc --corpus <file> [--min-count <n>] [--top <n>] [--phrases <n>] [--out <file>]",
+    options: &["corpus", "encoding", "min-count", "top", "phrases", "out"],
+    examples: &["smart-dict assoc --corpus chat.txt --min-count 5 --out pairs.tsv"],
+  },
+  Command {
+    name: "whitelist",
+    about: ("Manages the whitelist of intended collisions", "管理有意保留的重码白名单"),
+    usage: "smart-dict whitelist [list | add <code> [<word>...] | remove <code> [<word>...] | prune]",
+    options: &["whitelist", "check"],
+    examples: &["smart-dict whitelist add djej 读书 赌输", "smart-dict whitelist prune"],
+  },
+  Command {
+    name: "record",
+    about: ("Appends the current metrics to the history", "把当前指标记入历史"),
+    usage: "smart-dict record [--label <text>] [--corpus <file>] [--freq <file>]",
+    options: &["label", "corpus", "encoding", "freq"],
+    examples: &["smart-dict record --corpus novel.txt", "smart-dict record --label v1.2"],
+  },
+  Command {
+    name: "history",
+    about: ("Prints the recorded metrics with the trend of one", "列出历史指标及其走势"),
+    usage: "smart-dict history [--metric <name>]",
+    options: &["metric"],
+    examples: &["smart-dict history", "smart-dict history --metric collisions"],
+  },
+  Command {
+    name: "drill",
+    about: ("A typing drill of frequent or recent words, or of the phrases of a chat log", "打字练习：常用词、新词或聊天记录中的短语"),
+    usage: "smart-dict drill [--count <n>] [--freq <file> | --recent <n> | --chat <log> [--me <name>]] [--seed <n>]",
+    options: &["count", "freq", "recent", "chat", "me", "min-count", "short", "top", "seed"],
+    examples: &["smart-dict drill --freq freq.tsv --count 20", "smart-dict drill --recent 50", "smart-dict drill --chat wechat.txt --me 我"],
+  },
+  Command {
+    name: "anki",
+    about: ("Exports Anki cards for the words missed in drills or listed in a file", "为练习中打错的词或指定的词导出 Anki 卡片"),
+    usage: "smart-dict anki [--words <file>] [--tags <tags>] [--out <file>]",
+    options: &["words", "tags", "out"],
+    examples: &["smart-dict anki --out cards.txt", "smart-dict anki --words hard.txt --tags 键道"],
+  },
+  Command {
+    name: "split",
+    about: ("Splits a table into several by length, weight, pattern or list", "按词长、权重、正则或列表把码表拆成几个"),
+    usage: "smart-dict split <table> --part <suffix>=<predicate>... [--out-dir <dir>] [--dry-run]",
+    options: &["part", "out-dir", "verify"],
+    examples: &["smart-dict split xkjd6.cizu --part short=len:2 --part long=len:5.. --dry-run"],
+  },
+  Command {
+    name: "export",
+    about: ("Exports the reports to an xlsx workbook, or the merged dict as one table", "把报告导出为 xlsx，或把合并后的词库导出为一个码表"),
+    usage: "smart-dict export --xlsx <file> [--corpus <file>] | --dict <file|-> [--ndjson] [--offset <n>] [--limit <n>]",
+    options: &["xlsx", "corpus", "encoding", "dict", "ndjson", "offset", "limit", "whitelist"],
+    examples: &["smart-dict export --xlsx report.xlsx --corpus novel.txt", "smart-dict export --dict merged.txt", "smart-dict export --dict - --ndjson --limit 1000"],
+  },
+  Command {
+    name: "grep",
+    about: ("Lists the entries whose word, code or note matches", "查找词、编码或注释匹配的词条"),
+    usage: "smart-dict grep [<regex>] [--note <regex>] [--filter <expr>] [--blame]",
+    options: &["note", "filter", "blame"],
+    examples: &["smart-dict grep '^wl'", "smart-dict grep --note 自造", "smart-dict grep --filter 'age < 30d' --blame"],
+  },
+  Command {
+    name: "verify",
+    about: ("Checks that Rime still builds the schema", "检查 Rime 能否部署方案"),
+    usage: "smart-dict verify",
+    options: &[],
+    examples: &["smart-dict verify"],
+  },
+  Command {
+    name: "keys",
+    about: ("Shows the keys of words on a keyboard diagram", "在键盘图上显示词的按键"),
+    usage: "smart-dict keys <word>... [--svg <file>]",
+    options: &["svg"],
+    examples: &["smart-dict keys 我爱 读书", "smart-dict keys 键道 --svg keys.svg"],
+  },
+  Command {
+    name: "lookup",
+    about: ("Looks up the shortest codes of words", "反查词的最短编码"),
+    usage: "smart-dict lookup <word>... | --file <words.txt>",
+    options: &["file"],
+    examples: &["smart-dict lookup 我爱 码农", "smart-dict lookup --file words.txt"],
+  },
+  Command {
+    name: "profile",
+    about: ("Reports what loading each table adds and costs", "逐表报告加载所增加的词条、结点、内存与耗时"),
+    usage: "smart-dict profile",
+    options: &[],
+    examples: &["smart-dict profile"],
+  },
+  Command {
+    name: "keylog",
+    about: ("Reports on a log of real commits", "分析实际上屏记录"),
+    usage: "smart-dict keylog <log> [--top <n>]",
+    options: &["top"],
+    examples: &["smart-dict keylog commits.log --top 30"],
+  },
+  Command {
+    name: "compact",
+    about: ("Exports the dict to a compact read-only file, or looks codes up in one", "导出紧凑只读词库文件，或在其中查码"),
+    usage: "smart-dict compact [--out <file>] | --load <file> <code>... [--complete <n>]",
+    options: &["out", "load", "complete"],
+    examples: &["smart-dict compact", "smart-dict compact --load .smart-dict/xkjd6.sdc wl --complete 10"],
+  },
+  Command {
+    name: "purge",
+    about: ("Deletes the entries of blacklisted words", "删除黑名单中的词"),
+    usage: "smart-dict purge [--blacklist <file>] [--dry-run | --yes] [--check]",
+    options: &["yes", "verify", "bump", "check"],
+    examples: &["smart-dict purge --dry-run"],
+  },
+  Command {
+    name: "promote",
+    about: ("Plans short codes for frequent words", "为常用词规划简码"),
+    usage: "smart-dict promote --freq <file> [--pool <n>] [--lengths <range>] [--out <file>]",
+    options: &["freq", "pool", "lengths", "out"],
+    examples: &["smart-dict promote --freq freq.tsv --pool 2000 --lengths 2-3"],
+  },
+  Command {
+    name: "whatif",
+    about: ("Shows what adding an entry would change", "预览添加一个词条的影响"),
+    usage: "smart-dict whatif <word> <code> [--weight <n>] [--corpus <file>]",
+    options: &["weight", "corpus", "encoding"],
+    examples: &["smart-dict whatif 码农 mnxa", "smart-dict whatif 码农 mn --corpus chat.txt"],
+  },
+  Command {
+    name: "add-word",
+    about: ("Adds an entry to the table of its category", "把词加入其类别的码表"),
+    usage: "smart-dict add-word <word> <code> [--category <name>] [--table <table>] [--weight <n>] [--note <text>] [--dry-run] [--verify]",
+    options: &["category", "table", "weight", "note", "verify", "bump"],
+    examples: &["smart-dict add-word 测试 ceui --table xkjd6.user", "smart-dict add-word 区块链 qkln --category tech --dry-run"],
+  },
+  Command {
+    name: "fmt",
+    about: ("Sorts the entries of the tables by code", "按编码排序码表"),
+    usage: "smart-dict fmt [<table>...] [--dry-run] [--check]",
+    options: &["check"],
+    examples: &["smart-dict fmt", "smart-dict fmt xkjd6.user --dry-run", "smart-dict fmt --check"],
+  },
+  Command {
+    name: "trie-info",
+    about: ("Prints the shape of the trie", "输出字典树的形状"),
+    usage: "smart-dict trie-info",
+    options: &[],
+    examples: &["smart-dict trie-info"],
+  },
+  Command {
+    name: "health",
+    about: ("Scores the dict from 0 to 100 with a breakdown", "给词库打 0 到 100 分并列出各项"),
+    usage: "smart-dict health [--freq <file>] [--json <file|->] [--badge <file.svg>] [--check [--min-score <n>]]",
+    options: &["freq", "json", "badge", "min-score", "whitelist", "check"],
+    examples: &["smart-dict health", "smart-dict health --badge health.svg", "smart-dict health --check --min-score 80"],
+  },
+  Command {
+    name: "upstream",
+    about: ("Deletes the user entries the other tables now have", "删除其他码表已收录的用户词"),
+    usage: "smart-dict upstream [--user <table>] [--dry-run | --yes] [--check]",
+    options: &["user", "yes", "verify", "bump", "check"],
+    examples: &["smart-dict upstream --dry-run"],
+  },
+  Command {
+    name: "learning-curve",
+    about: ("Shows what learning more words pays off", "显示多学词对击键的收益"),
+    usage: "smart-dict learning-curve --corpus <file> [--freq <file>] [--levels <n,...>]",
+    options: &["corpus", "encoding", "freq", "levels", "check"],
+    examples: &["smart-dict learning-curve --corpus novel.txt --levels 500,1000,5000"],
+  },
+  Command {
+    name: "tree",
+    about: ("Prints the codes starting with a prefix as a tree", "以树形列出某前缀下的编码"),
+    usage: "smart-dict tree <prefix> [--depth <keys>]",
+    options: &["depth"],
+    examples: &["smart-dict tree wl --depth 2"],
+  },
+  Command {
+    name: "migrate",
+    about: ("Recodes the user words after the codes of characters changed", "单字编码变更后重新编码用户词"),
+    usage: "smart-dict migrate --old <old danzi table> --new <new danzi table> [--user <table>] [--dry-run | --yes]",
+    options: &["old", "new", "user", "yes", "verify", "bump", "check"],
+    examples: &["smart-dict migrate --old old/xkjd6.danzi.dict.yaml --new xkjd6.danzi.dict.yaml --dry-run"],
+  },
+  Command {
+    name: "explain",
+    about: ("Explains the codes of a word key by key", "逐键解释词的编码"),
+    usage: "smart-dict explain <word>",
+    options: &[],
+    examples: &["smart-dict explain 键道"],
+  },
+  Command {
+    name: "serve",
+    about: ("Serves lookups and candidates over TCP, reloading on changes", "通过 TCP 提供反查与候选，词库改动时自动重载"),
+    usage: "smart-dict serve [--addr <host:port>] [--interval <ms>] [--cache <codes>] [--page-size <n>] [--no-completion]",
+    options: &["addr", "interval", "cache", "page-size", "no-completion"],
+    examples: &["smart-dict serve", "smart-dict serve --addr 0.0.0.0:7878 --cache 10000"],
+  },
+  Command {
+    name: "audit",
+    about: ("Audits a change between two checkouts of a schema repo", "审查方案仓库两个版本之间的改动"),
+    usage: "smart-dict audit --base <base checkout> --head <head checkout> [--corpus <file>] [--max-regression <keys>] [--json <file|->]",
+    options: &["base", "head", "corpus", "encoding", "max-regression", "json", "check"],
+    examples: &["smart-dict audit --base main --head . --corpus novel.txt --json -"],
+  },
+  Command {
+    name: "contained",
+    about: ("Lists the phrases typed as fast piece by piece", "列出拆开打也一样快的词"),
+    usage: "smart-dict contained [--slack <keys>]",
+    options: &["slack", "check"],
+    examples: &["smart-dict contained", "smart-dict contained --slack 1"],
+  },
+  Command {
+    name: "sets",
+    about: ("Intersects or subtracts the entries of two copies of a schema", "求两份方案词条的交集、差集或对称差"),
+    usage: "smart-dict sets --op <and|minus|xor> --other <Rime directory> [--out <file>]",
+    options: &["op", "other", "out", "check"],
+    examples: &["smart-dict sets --op minus --other upstream/ --out local.txt"],
+  },
+  Command {
+    name: "diff",
+    about: ("Writes the changes to the tables since a checkout as a patch", "把相对某一版本的码表改动写成补丁"),
+    usage: "smart-dict diff --base <Rime directory> [--out <file>]",
+    options: &["base", "out", "check"],
+    examples: &["smart-dict diff --base ../rime-main --out changes.patch"],
+  },
+  Command {
+    name: "apply",
+    about: ("Applies a patch written by diff", "应用 diff 生成的补丁"),
+    usage: "smart-dict apply <patch> [--dry-run] [--verify]",
+    options: &["verify", "bump"],
+    examples: &["smart-dict apply changes.patch --dry-run"],
+  },
+  Command {
+    name: "encode-clipboard",
+    about: ("Encodes the text on the clipboard", "给剪贴板中的文字注上编码"),
+    usage: "smart-dict encode-clipboard [--write-back]",
+    options: &["write-back"],
+    examples: &["smart-dict encode-clipboard", "smart-dict encode-clipboard --write-back"],
+  },
+  Command {
+    name: "normalize-weights",
+    about: ("Rescales the weights of the tables to one scale", "把各码表的权重换算到同一尺度"),
+    usage: "smart-dict normalize-weights [<table>...] [--method <linear|rank>[:<max>]] [--dry-run] [--check]",
+    options: &["method", "verify", "bump", "check"],
+    examples: &["smart-dict normalize-weights --method rank:10000 --dry-run"],
+  },
+  Command {
+    name: "auto-commit",
+    about: ("Compares how many words auto-commit policies commit without a key", "比较各自动上屏规则免按键上屏的词数"),
+    usage: "smart-dict auto-commit --corpus <file> [--policy <policy>]...",
+    options: &["corpus", "encoding", "policy"],
+    examples: &["smart-dict auto-commit --corpus chat.txt", "smart-dict auto-commit --corpus chat.txt --policy unique:4 --policy full:4"],
+  },
+  Command {
+    name: "import-freq",
+    about: ("Merges frequency lists into one word and count table", "合并词频表为一张词与频次的表"),
+    usage: "smart-dict import-freq <list>... [--format <tsv|jieba|blcu>] [--out <file>]",
+    options: &["format", "out"],
+    examples: &["smart-dict import-freq blcu.txt jieba.txt --out freq.tsv"],
+  },
+  Command {
+    name: "coverage",
+    about: ("Reports how much of a corpus the dict and candidate phrase packs can type", "统计词库及备选词包对语料的覆盖率"),
+    usage: "smart-dict coverage --corpus <file> [--pack <dict>]... [--top <n>] [--min-coverage <ratio>]",
+    options: &["corpus", "encoding", "pack", "top", "min-coverage", "check"],
+    examples: &["smart-dict coverage --corpus novel.txt", "smart-dict coverage --corpus novel.txt --pack phrases.dict.yaml --top 50"],
+  },
+  Command {
+    name: "impact",
+    about: ("Shows what a patch would change, the collisions and the keystrokes, without applying it", "不应用补丁，预览其对重码与击键的影响"),
+    usage: "smart-dict impact --patch <file> [--corpus <file>] [--max-regression <keys>]",
+    options: &["patch", "corpus", "encoding", "max-regression", "whitelist", "check"],
+    examples: &["smart-dict impact --patch changes.patch", "smart-dict impact --patch changes.patch --corpus novel.txt --check"],
+  },
+  Command {
+    name: "help",
+    about: ("Shows the commands, or the help of one", "列出命令，或显示某个命令的帮助"),
+    usage: "smart-dict help [<command>] | smart-dict <command> --help",
+    options: &[],
+    examples: &["smart-dict help", "smart-dict help collisions", "smart-dict lint --help"],
+  },
+  Command {
+    name: "completions",
+    about: ("Prints the completion script of a shell", "输出 shell 的补全脚本"),
+    usage: "smart-dict completions <bash|zsh|fish|powershell>",
+    options: &[],
+    examples: &[
+      "smart-dict completions bash > /etc/bash_completion.d/smart-dict",
+      "smart-dict completions zsh > \"${fpath[1]}/_smart-dict\"",
+      "smart-dict completions fish > ~/.config/fish/completions/smart-dict.fish",
+      "smart-dict completions powershell >> $PROFILE",
+    ],
+  },
+];
+
+pub fn find(name: &str) -> Option<&'static Command> {
+  COMMANDS.iter().find(|command| command.name == name)
+}
+
+fn about(command: &Command) -> &'static str {
+  i18n::pick(command.about.0, command.about.1)
+}
+
+/// The commands and the global options, then how to get the help of one.
+pub fn overview() -> String {
+  let mut text = format!("{}\n", tr!("help-usage"));
+  let width = COMMANDS.iter().map(|command| command.name.len()).max().unwrap_or(0);
+  for command in COMMANDS {
+    writeln!(text, "  {:<width$}  {}", command.name, about(command)).unwrap();
+  }
+  writeln!(text, "\n{}", tr!("help-global", options(GLOBAL_OPTIONS))).unwrap();
+  write!(text, "{}", tr!("help-more")).unwrap();
+  text
+}
+
+/// The help of a command: what it does, its usage, options and examples.
+pub fn command_help(command: &Command) -> String {
+  let mut text = format!("{} - {}\n\n{}\n", command.name, about(command), command.usage);
+  if !command.options.is_empty() {
+    writeln!(text, "\n{}", tr!("help-options", options(command.options))).unwrap();
+  }
+  if !command.examples.is_empty() {
+    writeln!(text, "\n{}", tr!("help-examples")).unwrap();
+    for example in command.examples {
+      writeln!(text, "  {example}").unwrap();
+    }
+  }
+  text.pop();
+  text
+}
+
+fn options(names: &[&str]) -> String {
+  names.iter().map(|name| format!("--{name}")).collect::<Vec<_>>().join(" ")
+}
+
+/// The completion script for `shell`, one of `SHELLS`.
+pub fn completions(shell: &str) -> Option<String> {
+  match shell {
+    "bash" => Some(bash()),
+    "zsh" => Some(zsh()),
+    "fish" => Some(fish()),
+    "powershell" => Some(powershell()),
+    _ => None,
+  }
+}
+
+fn names() -> String {
+  COMMANDS.iter().map(|command| command.name).collect::<Vec<_>>().join(" ")
+}
+
+fn bash() -> String {
+  let mut script = String::from("# smart-dict completions for bash\n_smart_dict() {\n");
+  script.push_str("  local cur=\"${COMP_WORDS[COMP_CWORD]}\" opts\n");
+  writeln!(script, "  if [[ $COMP_CWORD -eq 1 ]]; then\n    COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n    return\n  fi", names()).unwrap();
+  script.push_str("  case \"${COMP_WORDS[1]}\" in\n");
+  for command in COMMANDS {
+    writeln!(script, "    {}) opts=\"{}\" ;;", command.name, options(command.options)).unwrap();
+  }
+  writeln!(script, "  esac\n  case \"${{COMP_WORDS[1]}}\" in\n    help) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")) ;;", names()).unwrap();
+  writeln!(script, "    completions) COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")) ;;", SHELLS.join(" ")).unwrap();
+  writeln!(script, "    *) [[ $cur == -* ]] && COMPREPLY=($(compgen -W \"$opts {}\" -- \"$cur\")) ;;", options(GLOBAL_OPTIONS)).unwrap();
+  script.push_str("  esac\n}\ncomplete -o default -F _smart_dict smart-dict\n");
+  script
+}
+
+/// An `_arguments` spec, which takes a value unless the option is a switch, a path if it's one.
+fn zsh_option(name: &str) -> String {
+  if SWITCHES.contains(&name) {
+    format!("'--{name}'")
+  } else if PATH_OPTIONS.contains(&name) {
+    format!("'--{name}:{name}:_files'")
+  } else {
+    format!("'--{name}:{name}: '")
+  }
+}
+
+fn zsh() -> String {
+  let mut script = String::from("#compdef smart-dict\n# smart-dict completions for zsh\n_smart_dict() {\n  local -a subcommands\n  subcommands=(\n");
+  for command in COMMANDS {
+    writeln!(script, "    '{}:{}'", command.name, command.about.0.replace('\'', "'\\''")).unwrap();
+  }
+  script.push_str("  )\n  if (( CURRENT == 2 )); then\n    _describe command subcommands\n    return\n  fi\n");
+  let global: Vec<_> = GLOBAL_OPTIONS.iter().map(|name| zsh_option(name)).collect();
+  writeln!(script, "  local -a global\n  global=({})", global.join(" ")).unwrap();
+  script.push_str("  case $words[2] in\n    help) _describe command subcommands ;;\n");
+  writeln!(script, "    completions) compadd {} ;;", SHELLS.join(" ")).unwrap();
+  for command in COMMANDS.iter().filter(|command| !["help", "completions"].contains(&command.name)) {
+    let options: Vec<_> = command.options.iter().map(|name| zsh_option(name)).collect();
+    writeln!(script, "    {}) _arguments $global {} '*:file:_files' ;;", command.name, options.join(" ")).unwrap();
+  }
+  script.push_str("  esac\n}\n_smart_dict \"$@\"\n");
+  script
+}
+
+fn fish() -> String {
+  let mut script = String::from("# smart-dict completions for fish\ncomplete -c smart-dict -f\n");
+  for command in COMMANDS {
+    writeln!(script, "complete -c smart-dict -n __fish_use_subcommand -a {} -d '{}'", command.name, command.about.0.replace('\'', "\\'")).unwrap();
+  }
+  let option = |name: &str| if SWITCHES.contains(&name) {
+    format!("-l {name}")
+  } else if PATH_OPTIONS.contains(&name) {
+    format!("-l {name} -r -F")
+  } else {
+    format!("-l {name} -r")
+  };
+  for name in GLOBAL_OPTIONS {
+    writeln!(script, "complete -c smart-dict {}", option(name)).unwrap();
+  }
+  writeln!(script, "complete -c smart-dict -n '__fish_seen_subcommand_from help' -a '{}'", names()).unwrap();
+  writeln!(script, "complete -c smart-dict -n '__fish_seen_subcommand_from completions' -a '{}'", SHELLS.join(" ")).unwrap();
+  for command in COMMANDS {
+    for name in command.options {
+      writeln!(script, "complete -c smart-dict -n '__fish_seen_subcommand_from {}' {}", command.name, option(name)).unwrap();
+    }
+  }
+  script
+}
+
+fn powershell() -> String {
+  let quote = |text: &str| format!("'{}'", text.replace('\'', "''"));
+  let mut script = String::from("# smart-dict completions for PowerShell\n$smartDictCommands = [ordered]@{\n");
+  for command in COMMANDS {
+    let values = if command.name == "help" {
+      COMMANDS.iter().map(|command| command.name).collect()
+    } else if command.name == "completions" {
+      SHELLS.to_vec()
+    } else {
+      vec![]
+    };
+    let list = |items: Vec<String>| items.join(", ");
+    writeln!(script, "  {} = @{{ About = {}; Options = @({}); Values = @({}) }}", quote(command.name), quote(command.about.0),
+             list(command.options.iter().map(|name| quote(&format!("--{name}"))).collect()),
+             list(values.into_iter().map(quote).collect())).unwrap();
+  }
+  script.push_str("}\n");
+  writeln!(script, "$smartDictGlobal = @({})", GLOBAL_OPTIONS.iter().map(|name| quote(&format!("--{name}"))).collect::<Vec<_>>().join(", ")).unwrap();
+  script.push_str(r#"Register-ArgumentCompleter -Native -CommandName smart-dict -ScriptBlock {
+  param($wordToComplete, $commandAst, $cursorPosition)
+  $words = @($commandAst.CommandElements | Select-Object -Skip 1 | ForEach-Object { $_.ToString() })
+  if ($wordToComplete) { $words = @($words | Select-Object -SkipLast 1) }
+  if ($words.Count -eq 0) {
+    $smartDictCommands.GetEnumerator() | Where-Object { $_.Key -like "$wordToComplete*" } | ForEach-Object {
+      [System.Management.Automation.CompletionResult]::new($_.Key, $_.Key, 'ParameterValue', $_.Value.About)
+    }
+    return
+  }
+  $command = $smartDictCommands[$words[0]]
+  if (-not $command) { return }
+  $candidates = if ($wordToComplete -like '-*') { $command.Options + $smartDictGlobal } else { $command.Values }
+  $candidates | Where-Object { $_ -like "$wordToComplete*" } | ForEach-Object {
+    [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterName', $_)
+  }
+}
+"#);
+  script
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::cmd;
+
+  #[test]
+  fn test_help() {
+    let names: Vec<_> = COMMANDS.iter().map(|command| command.name).collect();
+    assert_eq!(cmd::COMMANDS, names.as_slice());
+    for command in COMMANDS {
+      assert!(command.usage.starts_with(&format!("smart-dict {}", command.name)), "{}", command.name);
+      assert!(command.examples.iter().all(|example| example.starts_with("smart-dict ")), "{}", command.name);
+      assert!(command.options.iter().all(|name| !GLOBAL_OPTIONS.contains(name)), "{}", command.name);
+    }
+    assert!(command_help(find("collisions").unwrap()).contains("smart-dict collisions --top 20"));
+
+    let bash = completions("bash").unwrap();
+    assert!(bash.contains("    collisions) opts=\"--freq --top"));
+    assert!(completions("zsh").unwrap().contains("'--freq:freq:_files' '--top:top: '"));
+    let fish = completions("fish").unwrap();
+    assert!(fish.contains("complete -c smart-dict -n '__fish_seen_subcommand_from collisions' -l ndjson\n"));
+    assert!(fish.contains("complete -c smart-dict -n '__fish_seen_subcommand_from collisions' -l top -r\n"));
+    assert!(fish.contains("complete -c smart-dict -n '__fish_seen_subcommand_from collisions' -l freq -r -F\n"));
+    assert!(fish.contains("complete -c smart-dict -l dir -r -F\n"));
+    let options = || COMMANDS.iter().flat_map(|command| command.options).chain(GLOBAL_OPTIONS);
+    assert!(PATH_OPTIONS.iter().all(|name| options().any(|option| option == name) && !SWITCHES.contains(name)), "{PATH_OPTIONS:?}");
+    assert!(completions("powershell").unwrap().contains("'coverage' = @{ About = 'Reports how much"));
+    assert_eq!(None, completions("cmd"));
+  }
+}
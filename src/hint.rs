@@ -0,0 +1,124 @@
+//! Hints steering how `RevDict::segment` splits a sentence into words, for typists who chunk text
+//! their own way: a tokenizer's boundaries that a word may not cross, or only at a cost.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use crate::types::Word;
+
+/// Consulted by the sentence DP before trying the words of a sentence.
+pub trait SegmenterHint: Sync {
+  /// The byte offsets between the chunks of the sentence, in increasing order, without 0 and
+  /// the sentence's length.
+  fn boundaries(&self, sentence: &str) -> Vec<usize>;
+
+  /// Keys added to the cost of a word crossing `crossed` boundaries, `None` to rule it out.
+  /// A word inside a chunk costs nothing more, words crossing boundaries are ruled out by default.
+  fn penalty(&self, crossed: usize) -> Option<usize> {
+    (crossed == 0).then_some(0)
+  }
+}
+
+/// A tokenizer matching the longest words of a list from left to right, like the user
+/// dictionaries of jieba and other segmenters. Runs of characters no word starts with form a
+/// chunk together.
+#[derive(Clone, Debug, Default)]
+pub struct Chunker {
+  words: HashSet<Word>,
+  /// In characters.
+  max_len: usize,
+  /// Keys a word costs for each boundary it crosses, `None` if it can't cross any.
+  crossing: Option<usize>,
+}
+
+impl Chunker {
+  pub fn new(words: impl IntoIterator<Item=Word>) -> Self {
+    let words: HashSet<_> = words.into_iter().filter(|word| !word.is_empty()).collect();
+    let max_len = words.iter().map(|word| word.chars().count()).max().unwrap_or_default();
+    Self { words, max_len, crossing: None }
+  }
+
+  /// Loads the first column of each line, skipping blank lines and `#` comments.
+  pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+    let mut words = vec![];
+    for line in BufReader::new(File::open(path)?).lines() {
+      let line = line?;
+      if let Some(word) = line.split_whitespace().next().filter(|word| !word.starts_with('#')) {
+        words.push(word.to_string());
+      }
+    }
+    Ok(Self::new(words))
+  }
+
+  /// Lets words cross chunks for `keys` more keys each, so that they're only avoided.
+  pub fn with_crossing_penalty(mut self, keys: usize) -> Self {
+    self.crossing = Some(keys);
+    self
+  }
+}
+
+impl SegmenterHint for Chunker {
+  fn boundaries(&self, sentence: &str) -> Vec<usize> {
+    let offsets: Vec<_> = sentence.char_indices().map(|(idx, _)| idx).chain([sentence.len()]).collect();
+    let mut boundaries = vec![];
+    let mut start = 0;
+    // 未登录的字连成一块，免得把每个生字都切开
+    let mut in_unknown = false;
+    while start + 1 < offsets.len() {
+      let longest = (2..=self.max_len.min(offsets.len() - 1 - start))
+        .rev()
+        .find(|&len| self.words.contains(&sentence[offsets[start]..offsets[start + len]]))
+        .or_else(|| self.words.contains(&sentence[offsets[start]..offsets[start + 1]]).then_some(1));
+      let (len, unknown) = longest.map_or((1, true), |len| (len, false));
+      if start > 0 && !(unknown && in_unknown) {
+        boundaries.push(offsets[start]);
+      }
+      in_unknown = unknown;
+      start += len;
+    }
+    boundaries
+  }
+
+  fn penalty(&self, crossed: usize) -> Option<usize> {
+    match self.crossing {
+      _ if crossed == 0 => Some(0),
+      Some(keys) => Some(keys * crossed),
+      None => None,
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::Trie;
+
+  #[test]
+  fn test_chunker() {
+    let chunker = Chunker::new(["我们", "喜欢", "你", "喜欢你"].map(String::from));
+    let sentence = "我们喜欢你的猫";
+    // 我们|喜欢你|的猫
+    assert_eq!(vec![6, 15], chunker.boundaries(sentence));
+    assert_eq!(Vec::<usize>::new(), chunker.boundaries("猫狗"));
+    assert_eq!(vec![3], chunker.boundaries("猫你"));
+    assert_eq!(None, chunker.penalty(1));
+    assert_eq!(Some(0), chunker.penalty(0));
+    assert_eq!(Some(4), chunker.clone().with_crossing_penalty(2).penalty(2));
+
+    let mut trie = Trie::new();
+    for (code, word) in [("xhua", "喜欢"), ("xhn", "喜欢你"), ("n", "你"), ("d", "的"), ("nui", "你的")] {
+      trie.insert(code.to_string(), word.to_string());
+    }
+    assert_eq!(vec!["xhn", "d"], trie.rev_dict().shortest("喜欢你的").unwrap());
+    let strict = Chunker::new(["喜欢", "你的"].map(String::from));
+    // crossing into 你的 takes 4 + 1 keys against 6 within the chunks
+    let lenient = strict.clone().with_crossing_penalty(1);
+    let avoiding = strict.clone().with_crossing_penalty(3);
+    for (hint, expected) in [(&strict, vec!["xhua", "n", "d"]), (&lenient, vec!["xhn", "d"]), (&avoiding, vec!["xhua", "n", "d"])] {
+      let mut rev_dict = trie.rev_dict();
+      rev_dict.set_hint(hint);
+      assert_eq!(expected, rev_dict.shortest("喜欢你的").unwrap());
+    }
+  }
+}
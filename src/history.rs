@@ -0,0 +1,144 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use crate::freq::Frequencies;
+use crate::rev_dict::RevDict;
+
+const HEADER: &str = "time\tlabel\tentries\tcollisions\tcode_len\tkeys_per_char";
+
+/// Aggregate metrics of the dictionary at some point in time.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+  /// Seconds since the Unix epoch.
+  pub time: u64,
+  pub label: String,
+  pub entries: usize,
+  pub collisions: usize,
+  /// Average length of the shortest codes, weighted by word frequency if known.
+  pub code_len: f64,
+  /// Keystrokes per character on a corpus, if one was given.
+  pub keys_per_char: Option<f64>,
+}
+
+impl Record {
+  fn to_line(&self) -> String {
+    let keys_per_char = self.keys_per_char.map_or(String::new(), |kpc| format!("{kpc:.4}"));
+    format!("{}\t{}\t{}\t{}\t{:.4}\t{keys_per_char}", self.time, self.label, self.entries, self.collisions, self.code_len)
+  }
+
+  fn parse(line: &str) -> Option<Self> {
+    let mut cells = line.split('\t');
+    Some(Self {
+      time: cells.next()?.parse().ok()?,
+      label: cells.next()?.to_string(),
+      entries: cells.next()?.parse().ok()?,
+      collisions: cells.next()?.parse().ok()?,
+      code_len: cells.next()?.parse().ok()?,
+      keys_per_char: cells.next().and_then(|kpc| kpc.parse().ok()),
+    })
+  }
+}
+
+/// Records in the order they were appended, skipping lines that don't parse.
+pub fn load(path: impl AsRef<Path>) -> io::Result<Vec<Record>> {
+  let file = match File::open(path) {
+    Ok(file) => file,
+    Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+    Err(e) => return Err(e),
+  };
+  let mut records = vec![];
+  for line in BufReader::new(file).lines() {
+    if let Some(record) = Record::parse(&line?) {
+      records.push(record);
+    }
+  }
+  Ok(records)
+}
+
+/// Adds the line of `record` to the text of a history, after the header if it's a new one.
+pub fn append(history: &mut String, record: &Record) {
+  if history.is_empty() {
+    history.push_str(HEADER);
+    history.push('\n');
+  }
+  history.push_str(&record.to_line());
+  history.push('\n');
+}
+
+/// Average length of the shortest code of each word, weighted by `freq` when given, 0 without any.
+pub fn code_len(rev_dict: &RevDict, freq: Option<&Frequencies>) -> f64 {
+  let (mut total, mut weights) = (0.0, 0.0);
+  for word in rev_dict.words() {
+    let weight = freq.map_or(1.0, |freq| freq.get(word) as f64);
+    if let Some((code, _)) = rev_dict.lookup(word) {
+      total += code.len() as f64 * weight;
+      weights += weight;
+    }
+  }
+  if weights == 0.0 { 0.0 } else { total / weights }
+}
+
+/// `YYYY-MM-DD hh:mm` in UTC.
+pub fn format_time(secs: u64) -> String {
+  // days to civil date, see http://howardhinnant.github.io/date_algorithms.html
+  let days = (secs / 86400) as i64 + 719468;
+  let era = days.div_euclid(146097);
+  let doe = days.rem_euclid(146097);
+  let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + (month <= 2) as i64;
+  let secs = secs % 86400;
+  format!("{year:04}-{month:02}-{day:02} {:02}:{:02}", secs / 3600, secs % 3600 / 60)
+}
+
+/// A one-line chart of the values, lowest `▁` to highest `█`.
+pub fn sparkline(values: &[f64]) -> String {
+  const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+  let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+  let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+  values
+    .iter()
+    .map(|value| {
+      if max > min {
+        BARS[((value - min) / (max - min) * 7.0).round() as usize]
+      } else {
+        BARS[3]
+      }
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::Trie;
+
+  #[test]
+  fn test_record_line() {
+    let record = Record {
+      time: 1_700_000_000,
+      label: "a1b2c3d".to_string(),
+      entries: 120,
+      collisions: 3,
+      code_len: 2.5,
+      keys_per_char: None,
+    };
+    assert_eq!(Some(record.clone()), Record::parse(&record.to_line()));
+    assert_eq!(None, Record::parse(HEADER));
+    assert_eq!("2023-11-14 22:13", format_time(record.time));
+    assert_eq!("▁█▅", sparkline(&[1.0, 3.0, 2.0]));
+  }
+
+  #[test]
+  fn test_code_len() {
+    let mut trie = Box::new(Trie::new());
+    assert_eq!(0.0, code_len(&trie.rev_dict(), None));
+    trie.insert("w".to_string(), "我".to_string());
+    trie.insert("fio".to_string(), "非常".to_string());
+    assert_eq!(2.0, code_len(&trie.rev_dict(), None));
+  }
+}
@@ -0,0 +1,148 @@
+//! The help, the summaries and the prompts of the CLI in English and Simplified Chinese, picked by
+//! `--lang`, else `SMART_DICT_LANG`, else the locale in `LC_ALL`, `LC_MESSAGES` or `LANG`.
+//! Only what's in `MESSAGES` is translated: the lines listing entries, codes or collisions, the
+//! usage lines of the commands and the text of the errors they return stay English.
+
+use std::env;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Lang {
+  En,
+  ZhCn,
+}
+
+impl Lang {
+  /// `zh`, `zh-CN`, `zh_CN.UTF-8`... are Chinese, anything else English.
+  pub fn parse(tag: &str) -> Self {
+    if tag.to_ascii_lowercase().starts_with("zh") { Lang::ZhCn } else { Lang::En }
+  }
+
+  fn detect(option: Option<&str>) -> Self {
+    option
+      .map(String::from)
+      .or_else(|| ["SMART_DICT_LANG", "LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty())))
+      .map_or(Lang::En, |tag| Lang::parse(&tag))
+  }
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Picks the language once, before any message is shown.
+pub fn init(option: Option<&str>) {
+  LANG.get_or_init(|| Lang::detect(option));
+}
+
+fn lang() -> Lang {
+  *LANG.get_or_init(|| Lang::detect(None))
+}
+
+/// Key, English, Chinese; `{}` are filled in order by [`tr!`].
+const MESSAGES: &[(&str, &str, &str)] = &[
+  ("error", "error: {}", "错误：{}"),
+  ("no-appdata", "can't read APPDATA from env, use --dir to locate the Rime directory", "无法从环境变量读取 APPDATA，请用 --dir 指定 Rime 目录"),
+  ("unknown-command", "unknown command {}", "未知命令 {}"),
+  ("lint-summary", "{} errors, {} warnings", "{} 个错误，{} 个警告"),
+  ("collisions-top", "the {} most severe of {} collisions", "仅列出最严重的 {} 处，共 {} 处重码"),
+  ("help-usage", "usage: smart-dict <command> [<args>] [--dir <Rime directory>] [--schema <name>]\n\ncommands:", "用法：smart-dict <命令> [<参数>] [--dir <Rime 目录>] [--schema <方案名>]\n\n命令："),
+  ("help-global", "global options: {}", "通用选项：{}"),
+  ("help-more", "`smart-dict help <command>` shows the options and examples of a command.", "`smart-dict help <命令>` 显示命令的选项与示例。"),
+  ("help-options", "options: {}", "选项：{}"),
+  ("help-examples", "examples:", "示例："),
+  ("help-unknown", "no command {}, `smart-dict help` lists them", "没有 {} 这个命令，`smart-dict help` 可列出所有命令"),
+  ("completions-usage", "usage: smart-dict completions <{}>", "用法：smart-dict completions <{}>"),
+  ("coverage-uncovered", "the most frequent text {} can't type:", "{} 打不出的高频文本："),
+  ("collisions-page", "collisions {} to {} by severity of {}", "按严重程度列出第 {} 至 {} 处重码，共 {} 处"),
+  ("collisions-summary", "{} collisions, {} new, {} whitelisted", "{} 处重码，{} 处新增，{} 处已列入白名单"),
+  ("budget-table", "table", "码表"),
+  ("budget-entries", "entries", "词条"),
+  ("budget-budget", "budget", "预算"),
+  ("budget-usage", "usage", "用量"),
+  ("budget-trend", "trend", "变化"),
+  ("budget-over", "over budget", "超出预算"),
+  ("corpus-report", "{} lines, {} chars, {} keys per char, {}% auto-commit, {}% coverage",
+   "{} 行，{} 字，字均码长 {}，顶屏率 {}%，覆盖率 {}%"),
+  ("tables-issue", "warning: {}", "警告：{}"),
+  ("tables-order", "tables loaded in order: {}", "码表加载顺序：{}"),
+  ("corpus-decode-errors", "{} malformed byte sequences in {}, read as {}", "{} 处字节无法解码：{}，按 {} 读取"),
+  ("corpus-estimate", "{} keys per char, 95% between {} and {}, from {} of {} lines",
+   "字均码长 {}，95% 置信区间 {} 至 {}，抽样 {} / {} 行"),
+  ("corpus-changed", "{} changed since the checkpoint was saved", "{} 在保存断点后已改变"),
+  ("contained-summary", "{} phrases saving at most {} keys over their pieces", "{} 个词组比拆开打最多省 {} 键"),
+  ("dedup-summary", "{} redundant entries", "{} 个冗余词条"),
+  ("dedup-confirm", "delete them?", "删除这些词条？"),
+  ("dedup-deleted", "deleted", "已删除"),
+  ("purge-summary", "{} blacklisted entries", "{} 个黑名单词条"),
+  ("upstream-summary", "{} entries of {} now upstream", "{} 个 {} 词条已在上游"),
+  ("migrate-summary", "{} words to recode, {} to review", "{} 个词需重新编码，{} 个需人工检查"),
+  ("migrate-confirm", "recode them?", "重新编码这些词？"),
+  ("serve-listening", "listening on {}, {} words", "在 {} 上监听，共 {} 个词"),
+  ("serve-reloaded", "reloaded {}, {} words", "已重新加载 {}，共 {} 个词"),
+  ("serve-reload-failed", "can't reload {}, still serving the old dicts: {}", "无法重新加载 {}，继续使用旧词库：{}"),
+  ("drill-empty", "no words to drill", "没有可练习的词"),
+  ("drill-intro", "type the code of each word, an empty line stops", "输入每个词的编码，空行结束"),
+  ("drill-summary", "{} words, {}% right, {} chars per minute", "{} 个词，正确率 {}%，每分钟 {} 字"),
+  ("drill-miss", "typed {}\texpected {}", "输入 {}\t应为 {}"),
+  ("drill-chat-phrases", "{} phrases you type often in {} take more than {} keys, the most frequent:", "{} 个常用词在 {} 中超过 {} 键，最常用的："),
+  ("drill-chat-progress", "{} chat drills: accuracy {} {}, chars per minute {} {}", "共 {} 次聊天练习：正确率 {} {}，每分钟字数 {} {}"),
+  ("encode-clipboard-summary", "{} keys for {} chars, {} per char, {} chars not in the dict", "{} 键打 {} 字，每字 {} 键，{} 字不在码表中"),
+  ("encode-clipboard-waiting", "on the clipboard until something else is copied, keep this running till then", "已写回剪贴板，复制别的内容之前请勿退出"),
+];
+
+/// The message of `key` in the current language, the key itself if it's missing.
+pub fn message(key: &str) -> &str {
+  MESSAGES
+    .iter()
+    .find(|(k, _, _)| *k == key)
+    .map_or(key, |&(_, en, zh)| match lang() {
+      Lang::En => en,
+      Lang::ZhCn => zh,
+    })
+}
+
+/// `en` or `zh` by the language of the messages.
+pub fn pick<'a>(en: &'a str, zh: &'a str) -> &'a str {
+  match lang() {
+    Lang::En => en,
+    Lang::ZhCn => zh,
+  }
+}
+
+/// Replaces each `{}` of `template` with the next argument.
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+  let mut parts = template.split("{}");
+  let mut filled = parts.next().unwrap_or_default().to_string();
+  for (idx, part) in parts.enumerate() {
+    if let Some(arg) = args.get(idx) {
+      filled.push_str(&arg.to_string());
+    }
+    filled.push_str(part);
+  }
+  filled
+}
+
+/// `tr!("lint-summary", errors, warnings)` is the localized message with its arguments filled in.
+macro_rules! tr {
+  ($key:literal $(, $arg:expr)* $(,)?) => {
+    $crate::i18n::fill($crate::i18n::message($key), &[$(&$arg as &dyn std::fmt::Display),*])
+  };
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_messages() {
+    assert_eq!(Lang::ZhCn, Lang::parse("zh_CN.UTF-8"));
+    assert_eq!(Lang::En, Lang::parse("C.UTF-8"));
+    assert_eq!("3 个错误，1 个警告", fill(MESSAGES[3].2, &[&3, &1]));
+    assert_eq!("missing-key", message("missing-key"));
+    for (key, en, zh) in MESSAGES {
+      assert_eq!(en.matches("{}").count(), zh.matches("{}").count(), "{key}");
+    }
+  }
+}
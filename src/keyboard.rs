@@ -0,0 +1,106 @@
+//! Keyboard diagrams of the keys typed for a word, as text or SVG, for teaching material.
+
+use std::fmt::Write;
+
+/// The QWERTY rows, each shifted right by its offset in quarters of a key.
+const ROWS: [(&str, usize); 3] = [("qwertyuiop", 0), ("asdfghjkl;", 1), ("zxcvbnm,./", 3)];
+
+/// Columns per key in the text diagram.
+const CELL: usize = 5;
+
+/// Pixels per key in the SVG diagram.
+const KEY: usize = 40;
+
+/// The 1-based steps at which each key of `code` is typed, e.g. `[1, 3]` for `w` in `wiw`.
+fn steps(code: &str, key: char) -> Vec<usize> {
+  code.chars().enumerate().filter(|&(_, c)| c == key).map(|(idx, _)| idx + 1).collect()
+}
+
+fn steps_label(steps: &[usize]) -> String {
+  steps.iter().map(usize::to_string).collect::<Vec<_>>().join(",")
+}
+
+/// Keys of `code` missing from the diagram, e.g. `'` or digits.
+pub fn off_board(code: &str) -> Vec<char> {
+  let mut keys: Vec<_> = code.chars().filter(|&c| !ROWS.iter().any(|(row, _)| row.contains(c))).collect();
+  keys.dedup();
+  keys
+}
+
+/// Draws the keyboard with the keys of `code` in brackets and the steps typing them underneath:
+///
+/// ```text
+///   q   [w]   e    r  ...
+///        1
+/// ```
+pub fn render_text(code: &str) -> String {
+  let mut text = String::new();
+  for (row, offset) in ROWS {
+    let indent = " ".repeat(offset * CELL / 4);
+    let mut keys = indent.clone();
+    let mut labels = indent;
+    for key in row.chars() {
+      let steps = steps(code, key);
+      if steps.is_empty() {
+        keys.push_str(&format!("{key:^CELL$}"));
+      } else {
+        keys.push_str(&format!("{:^CELL$}", format!("[{key}]")));
+      }
+      labels.push_str(&format!("{:^CELL$}", steps_label(&steps)));
+    }
+    text.push_str(keys.trim_end());
+    text.push('\n');
+    if !labels.trim().is_empty() {
+      text.push_str(labels.trim_end());
+      text.push('\n');
+    }
+  }
+  text
+}
+
+/// The same diagram as an SVG image, titled with the word and its code.
+pub fn render_svg(word: &str, code: &str) -> String {
+  let (width, height) = (KEY * 11, KEY * 4);
+  let mut svg = String::new();
+  let _ = writeln!(svg, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="sans-serif">"#);
+  let _ = writeln!(svg, r#"  <text x="{}" y="{}" font-size="16">{} {}</text>"#, KEY / 4, KEY / 2, escape(word), escape(code));
+  for (idx, (row, offset)) in ROWS.iter().enumerate() {
+    for (col, key) in row.chars().enumerate() {
+      let x = col * KEY + offset * KEY / 4 + KEY / 8;
+      let y = (idx + 1) * KEY;
+      let steps = steps(code, key);
+      let fill = if steps.is_empty() { "#fff" } else { "#fc6" };
+      let size = KEY - 4;
+      let _ = writeln!(svg, r##"  <rect x="{x}" y="{y}" width="{size}" height="{size}" rx="4" fill="{fill}" stroke="#333"/>"##);
+      let _ = writeln!(svg, r#"  <text x="{}" y="{}" font-size="14">{}</text>"#, x + 6, y + 18, escape(&key.to_string()));
+      if !steps.is_empty() {
+        let _ = writeln!(svg, r#"  <text x="{}" y="{}" font-size="10">{}</text>"#, x + 6, y + size - 4, steps_label(&steps));
+      }
+    }
+  }
+  svg.push_str("</svg>\n");
+  svg
+}
+
+fn escape(text: &str) -> String {
+  text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_render() {
+    let text = render_text("wiw");
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(4, lines.len());
+    assert!(lines[0].contains("[w]") && lines[0].contains("[i]") && !lines[0].contains("[q]"));
+    assert_eq!(lines[0].find("[w]"), lines[1].find("1,3"));
+    assert_eq!(vec!['\''], off_board("a'b"));
+
+    let svg = render_svg("我们", "wi");
+    assert_eq!(2, svg.matches(r##"fill="#fc6""##).count());
+    assert!(svg.contains("我们 wi"));
+  }
+}
@@ -0,0 +1,198 @@
+//! Logs of real typing, one commit per line, e.g. written by a Rime Lua commit notifier:
+//! `time\tkeys\ttext[\tindex]`, like `1700000003\txh{BackSpace}a\t喜欢\t0`.
+//!
+//! The keys are those pressed for the commit, with Rime's names in braces for the others,
+//! and the index, if the logger knows it, is the candidate picked.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+use crate::cost::word_cost;
+use crate::rev_dict::RevDict;
+use crate::trie::SelectKeys;
+use crate::types::{Code, Word};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Key {
+  Char(char),
+  Backspace,
+  Escape,
+  /// Other named keys, e.g. `{Return}`, which don't change the code.
+  Other,
+}
+
+/// Parses `wi{space}{BackSpace}`, `{space}` being a blank like in codes.
+pub fn parse_keys(keys: &str) -> Vec<Key> {
+  let mut parsed = vec![];
+  let mut rest = keys;
+  while let Some(ch) = rest.chars().next() {
+    let named = (ch == '{').then(|| rest[1..].split_once('}')).flatten();
+    match named {
+      Some((name, after)) => {
+        parsed.push(match name {
+          "BackSpace" => Key::Backspace,
+          "Escape" => Key::Escape,
+          "space" => Key::Char(' '),
+          "apostrophe" => Key::Char('\''),
+          _ => Key::Other,
+        });
+        rest = after;
+      }
+      None => {
+        parsed.push(Key::Char(ch));
+        rest = &rest[ch.len_utf8()..];
+      }
+    }
+  }
+  parsed
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Commit {
+  pub time: u64,
+  pub keys: Vec<Key>,
+  pub text: Word,
+  /// The candidate picked, 0 for the first one.
+  pub index: Option<usize>,
+}
+
+impl Commit {
+  pub fn parse(line: &str) -> Option<Self> {
+    if line.starts_with('#') {
+      return None;
+    }
+    let mut cells = line.split('\t');
+    let time = cells.next()?.trim().parse().ok()?;
+    let keys = parse_keys(cells.next()?);
+    let text = cells.next().filter(|text| !text.is_empty())?.to_string();
+    let index = cells.next().and_then(|index| index.trim().parse().ok());
+    Some(Self { time, keys, text, index })
+  }
+
+  /// The code finally typed: the keys after corrections, without the selection keys.
+  pub fn code(&self, select_keys: &SelectKeys) -> Code {
+    let mut code = Code::new();
+    for key in &self.keys {
+      match key {
+        Key::Char(ch) if !select_keys.contains(*ch) => code.push(*ch),
+        Key::Backspace => {
+          code.pop();
+        }
+        Key::Escape => code.clear(),
+        Key::Char(_) | Key::Other => {}
+      }
+    }
+    code
+  }
+
+  pub fn is_corrected(&self) -> bool {
+    self.keys.iter().any(|key| matches!(key, Key::Backspace | Key::Escape))
+  }
+}
+
+pub fn read_commits(reader: impl BufRead) -> io::Result<Vec<Commit>> {
+  let mut commits = vec![];
+  for line in reader.lines() {
+    commits.extend(Commit::parse(&line?));
+  }
+  Ok(commits)
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Report {
+  pub commits: usize,
+  /// Among the commits with a known index, the share picking the first candidate.
+  pub first_candidate_rate: f64,
+  pub average_code_len: f64,
+  /// Words whose commits needed a backspace or an escape, most corrected first.
+  pub corrected: Vec<(Word, usize)>,
+}
+
+pub fn report(commits: &[Commit], select_keys: &SelectKeys) -> Report {
+  let indexed: Vec<_> = commits.iter().filter_map(|commit| commit.index).collect();
+  let first = indexed.iter().filter(|&&index| index == 0).count();
+  let code_len: usize = commits.iter().map(|commit| commit.code(select_keys).len()).sum();
+  let mut corrected: HashMap<&Word, usize> = HashMap::new();
+  for commit in commits.iter().filter(|commit| commit.is_corrected()) {
+    *corrected.entry(&commit.text).or_default() += 1;
+  }
+  let mut corrected: Vec<_> = corrected.into_iter().map(|(word, count)| (word.clone(), count)).collect();
+  corrected.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+  Report {
+    commits: commits.len(),
+    first_candidate_rate: if indexed.is_empty() { 0.0 } else { first as f64 / indexed.len() as f64 },
+    average_code_len: if commits.is_empty() { 0.0 } else { code_len as f64 / commits.len() as f64 },
+    corrected,
+  }
+}
+
+/// What the dict could do about a word typed with trouble.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Suggestion {
+  /// Typed with a longer code than its shortest one.
+  ShorterCode { typed: Code, shortest: Code },
+  /// Not the first candidate on its shortest code, so it always takes a selection key.
+  Promote { code: Code, index: usize },
+  /// Typed as several words, or not at all.
+  Missing { typed: Code },
+}
+
+/// Suggestions for the words corrected or picked past the first candidate, most troublesome first.
+pub fn suggest(rev_dict: &RevDict, commits: &[Commit]) -> Vec<(Word, usize, Suggestion)> {
+  let select_keys = rev_dict.select_keys();
+  let mut troubles: HashMap<&Word, (usize, &Commit)> = HashMap::new();
+  for commit in commits.iter().filter(|commit| commit.is_corrected() || commit.index.is_some_and(|index| index > 0)) {
+    troubles.entry(&commit.text).or_insert((0, commit)).0 += 1;
+  }
+  let mut suggestions: Vec<_> = troubles
+    .into_iter()
+    .filter_map(|(word, (count, commit))| {
+      let typed = commit.code(select_keys);
+      let suggestion = match word_cost(rev_dict, word) {
+        None => Suggestion::Missing { typed },
+        Some(cost) if cost.code.len() < typed.len() => Suggestion::ShorterCode { typed, shortest: cost.code },
+        Some(cost) if cost.index > 0 => Suggestion::Promote { code: cost.code, index: cost.index },
+        Some(_) => return None,
+      };
+      Some((word.clone(), count, suggestion))
+    })
+    .collect();
+  suggestions.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+  suggestions
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::TrieBuilder;
+
+  #[test]
+  fn test_report() {
+    let log = "# time\tkeys\ttext\tindex\n\
+               1\twi{space}\t我们\t0\n\
+               2\txh{BackSpace}a\t喜欢\t0\n\
+               3\tfio'\t飞船\t1\n\
+               4\tfiojp\t飞船\n\
+               5\tzz{Escape}nh\t你好\t0\n";
+    let commits = read_commits(io::Cursor::new(log)).unwrap();
+    assert_eq!(5, commits.len());
+    assert_eq!(vec![Key::Char('x'), Key::Char('h'), Key::Backspace, Key::Char('a')], commits[1].keys);
+    let select_keys = SelectKeys::default();
+    assert_eq!("xa", commits[1].code(&select_keys));
+    assert_eq!("nh", commits[4].code(&select_keys));
+    assert_eq!("wi ", commits[0].code(&SelectKeys::new(vec![";".to_string()]).unwrap()));
+
+    let report = report(&commits, &select_keys);
+    assert_eq!(0.75, report.first_candidate_rate);
+    assert_eq!(14.0 / 5.0, report.average_code_len);
+    assert_eq!(vec![("你好".to_string(), 1), ("喜欢".to_string(), 1)], report.corrected);
+
+    let trie = TrieBuilder::from_iter([("wi", "我们"), ("xa", "喜欢"), ("fio", "非常"), ("fio", "飞船"), ("fiojp", "飞船")]).build();
+    let rev_dict = trie.rev_dict();
+    assert_eq!(vec![
+      ("你好".to_string(), 1, Suggestion::Missing { typed: "nh".to_string() }),
+      ("飞船".to_string(), 1, Suggestion::Promote { code: "fio".to_string(), index: 1 }),
+    ], suggest(&rev_dict, &commits));
+  }
+}
@@ -0,0 +1,115 @@
+//! How typing gets cheaper as one learns more of a schema: the cost of a corpus for someone who
+//! knows only the single characters, then also the most frequent words, then everything.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::freq::Frequencies;
+use crate::trie::Trie;
+use crate::types::Word;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+  /// Only the codes of single characters.
+  Chars,
+  /// The single characters and the codes of this many words, most frequent first.
+  Top(usize),
+  All,
+}
+
+impl Display for Level {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Level::Chars => f.write_str("chars"),
+      Level::Top(n) => write!(f, "top {n}"),
+      Level::All => f.write_str("all"),
+    }
+  }
+}
+
+impl FromStr for Level {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s.trim() {
+      "chars" => Ok(Level::Chars),
+      "all" => Ok(Level::All),
+      n => n.parse().map(Level::Top).map_err(|_| format!("a level is chars, all or a number of words, not {n}")),
+    }
+  }
+}
+
+/// Parses `chars,1000,all`, the levels in the given order.
+pub fn parse_levels(levels: &str) -> Result<Vec<Level>, String> {
+  levels.split(',').map(str::parse).collect()
+}
+
+/// The words of several characters, by descending `freq` if given, else by descending weight in the dict.
+pub fn ranked_words(trie: &Trie, freq: Option<&Frequencies>) -> Vec<Word> {
+  let mut weights: HashMap<&Word, u64> = HashMap::new();
+  for node in trie.nodes() {
+    for (word, &weight) in node.words().iter().zip(node.weights()) {
+      if word.chars().nth(1).is_some() {
+        let best = weights.entry(word).or_default();
+        *best = (*best).max(weight);
+      }
+    }
+  }
+  let mut ranked: Vec<_> = weights
+    .into_iter()
+    .map(|(word, weight)| (freq.map_or(weight, |freq| freq.get(word)), word))
+    .collect();
+  ranked.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+  ranked.into_iter().map(|(_, word)| word.clone()).collect()
+}
+
+/// The words known at a level, `None` for all of them, see `RevDict::restrict`.
+pub fn known_words(trie: &Trie, level: Level, ranked: &[Word]) -> Option<HashSet<Word>> {
+  let top = match level {
+    Level::All => return None,
+    Level::Chars => 0,
+    Level::Top(n) => n,
+  };
+  let mut known: HashSet<Word> = trie
+    .nodes()
+    .flat_map(|node| node.words())
+    .filter(|word| word.chars().nth(1).is_none())
+    .cloned()
+    .collect();
+  known.extend(ranked.iter().take(top).cloned());
+  Some(known)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::corpus;
+  use crate::trie::TrieBuilder;
+
+  #[test]
+  fn test_levels() {
+    let trie = TrieBuilder::new()
+      .entry(("w", "我", 0))
+      .entry(("wi", "我们", 5))
+      .entry(("f", "非", 0))
+      .entry(("cxy", "常", 0))
+      .entry(("fio", "非常", 1))
+      .entry(("im", "们", 0))
+      .build();
+    let ranked = ranked_words(&trie, None);
+    assert_eq!(vec!["我们", "非常"], ranked);
+    let freq = Frequencies([("非常".to_string(), 10)].into());
+    assert_eq!(vec!["非常", "我们"], ranked_words(&trie, Some(&freq)));
+    assert_eq!(vec![Level::Chars, Level::Top(1), Level::All], parse_levels("chars,1,all").unwrap());
+
+    let cost = |level| {
+      let mut rev_dict = trie.rev_dict();
+      if let Some(known) = known_words(&trie, level, &ranked) {
+        rev_dict.restrict(known);
+      }
+      corpus::analyze(&rev_dict, ["我们非常"]).keystrokes
+    };
+    // w_im f cxy, then wi f cxy, then wi fio
+    assert_eq!(vec![8, 6, 5], [Level::Chars, Level::Top(1), Level::All].map(cost).to_vec());
+  }
+}
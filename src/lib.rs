@@ -1,3 +1,63 @@
 pub mod types;
+pub mod text;
 pub mod trie;
+pub mod preview;
 pub mod rev_dict;
+pub mod hint;
+pub mod convert;
+pub mod normalize;
+pub mod lint;
+pub mod shape;
+pub mod yaml;
+pub mod archive;
+pub mod dict;
+pub mod fmt;
+pub mod diff;
+pub mod patch;
+pub mod repair;
+pub mod blacklist;
+pub mod progress;
+pub mod schema;
+pub mod reload;
+pub mod cache;
+pub mod snapshot;
+pub mod category;
+pub mod config;
+pub mod budget;
+pub mod check;
+pub mod ndjson;
+pub mod filter;
+pub mod collision;
+pub mod freq;
+pub mod weights;
+pub mod cost;
+pub mod health;
+pub mod audit;
+pub mod lookup;
+pub mod promote;
+pub mod whatif;
+pub mod compact;
+pub mod encoding;
+pub mod corpus;
+pub mod auto_commit;
+pub mod coverage;
+pub mod sample;
+pub mod learning;
+pub mod migrate;
+pub mod explain;
+pub mod assoc;
+pub mod anki;
+pub mod dedup;
+pub mod containment;
+pub mod density;
+pub mod drill;
+pub mod chat;
+pub mod keylog;
+pub mod split;
+pub mod history;
+pub mod blame;
+pub mod sheet;
+pub mod rime;
+pub mod keyboard;
+#[cfg(any(feature = "async-std", feature = "tokio"))]
+pub mod rt;
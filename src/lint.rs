@@ -0,0 +1,404 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::ops::RangeInclusive;
+use crate::collision::Whitelist;
+use crate::convert::Converter;
+use crate::shape::Conventions;
+use crate::trie::{Entry, Trie};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+  Warning,
+  Error,
+}
+
+impl Display for Severity {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      Severity::Warning => "warning",
+      Severity::Error => "error",
+    })
+  }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Issue {
+  pub entry: Entry,
+  pub severity: Severity,
+  pub rule: &'static str,
+  pub message: String,
+}
+
+impl Display for Issue {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}[{}]: {}\t{}: {}",
+           self.severity, self.rule, self.entry.word, self.entry.code, self.message)
+  }
+}
+
+pub trait Rule {
+  fn name(&self) -> &'static str;
+
+  fn check(&self, trie: &Trie, entry: &Entry) -> Option<(Severity, String)>;
+}
+
+/// Flags entries with an empty word or code, or stray spaces around them.
+pub struct Blank;
+
+impl Rule for Blank {
+  fn name(&self) -> &'static str {
+    "blank"
+  }
+
+  fn check(&self, _trie: &Trie, entry: &Entry) -> Option<(Severity, String)> {
+    if entry.word.is_empty() || entry.code.is_empty() {
+      Some((Severity::Error, "empty word or code".to_string()))
+    } else if entry.word.trim() != entry.word || entry.code.trim() != entry.code {
+      Some((Severity::Error, "leading or trailing whitespace".to_string()))
+    } else {
+      None
+    }
+  }
+}
+
+/// Flags words mixing simplified and traditional characters, e.g. "们會".
+pub struct MixedScript<'a> {
+  pub converter: &'a Converter,
+}
+
+impl Rule for MixedScript<'_> {
+  fn name(&self) -> &'static str {
+    "mixed-script"
+  }
+
+  fn check(&self, _trie: &Trie, entry: &Entry) -> Option<(Severity, String)> {
+    if self.converter.is_mixed(&entry.word) {
+      let suggestion = self.converter.to_simplified(&entry.word);
+      Some((Severity::Warning, format!("mixes simplified and traditional characters, did you mean {suggestion}?")))
+    } else {
+      None
+    }
+  }
+}
+
+/// Flags words sharing their full code with other words, unless the whitelist allows them.
+pub struct Collides<'a> {
+  pub whitelist: &'a Whitelist,
+}
+
+impl Rule for Collides<'_> {
+  fn name(&self) -> &'static str {
+    "collision"
+  }
+
+  fn check(&self, trie: &Trie, entry: &Entry) -> Option<(Severity, String)> {
+    let others: Vec<_> = trie
+      .node(&entry.code)?
+      .words()
+      .iter()
+      .filter(|word| **word != entry.word)
+      .map(String::as_str)
+      .collect();
+    if others.is_empty() || self.whitelist.allows_word(&entry.code, &entry.word) {
+      None
+    } else {
+      Some((Severity::Warning, format!("shares its code with {}", others.join(" "))))
+    }
+  }
+}
+
+/// Flags words on code shapes the schema's conventions reserve for words of other lengths.
+pub struct ReservedShape<'a> {
+  pub conventions: &'a Conventions,
+}
+
+impl Rule for ReservedShape<'_> {
+  fn name(&self) -> &'static str {
+    "reserved-shape"
+  }
+
+  fn check(&self, _trie: &Trie, entry: &Entry) -> Option<(Severity, String)> {
+    let shape = self.conventions.violated(entry)?;
+    let lengths: Vec<_> = shape.lengths.iter().map(ToString::to_string).collect();
+    Some((Severity::Warning, format!("{} is reserved for words of {} characters", shape.pattern, lengths.join(" or "))))
+  }
+}
+
+/// Flags phrases with characters that have no entry of their own, so they can't be typed
+/// character by character; often OCR junk in imported lists.
+pub struct Orphan {
+  chars: HashSet<char>,
+}
+
+impl Orphan {
+  pub fn new(trie: &Trie) -> Self {
+    let chars = trie
+      .entries()
+      .filter_map(|entry| {
+        let mut chars = entry.word.chars();
+        chars.next().filter(|_| chars.next().is_none())
+      })
+      .collect();
+    Self { chars }
+  }
+}
+
+impl Rule for Orphan {
+  fn name(&self) -> &'static str {
+    "orphan"
+  }
+
+  fn check(&self, _trie: &Trie, entry: &Entry) -> Option<(Severity, String)> {
+    // single characters are in `chars` themselves, and English words are spelt, not typed by letter
+    let orphans: String = entry.word.chars().filter(|ch| !ch.is_ascii() && !self.chars.contains(ch)).collect();
+    if orphans.is_empty() {
+      None
+    } else {
+      Some((Severity::Warning, format!("{orphans} can't be typed alone, no single-character entry")))
+    }
+  }
+}
+
+/// The characters words may be made of, `word_chars` in the config: named sets or ranges like
+/// `U+3000..U+303F`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WordChars {
+  ranges: Vec<RangeInclusive<char>>,
+}
+
+impl WordChars {
+  /// The sets are `cjk` for the unified ideographs and 〇, `cjk-ext` for the extensions and the
+  /// compatibility ideographs, `cjk-punct` for 、。「」 and the like, `fullwidth` for ！，（） and
+  /// the other full-width forms, and `ascii` for the printable ASCII characters.
+  pub fn new<'s>(items: impl IntoIterator<Item=&'s str>) -> Result<Self, String> {
+    let mut ranges = vec![];
+    for item in items {
+      let item = item.trim();
+      match item {
+        "cjk" => ranges.extend(['\u{4E00}'..='\u{9FFF}', '〇'..='〇']),
+        "cjk-ext" => ranges.extend([
+          '\u{3400}'..='\u{4DBF}',
+          '\u{20000}'..='\u{2EE5F}',
+          '\u{30000}'..='\u{323AF}',
+          '\u{F900}'..='\u{FAFF}',
+          '\u{2F800}'..='\u{2FA1F}',
+        ]),
+        "cjk-punct" => ranges.push('\u{3000}'..='\u{303F}'),
+        "fullwidth" => ranges.push('\u{FF00}'..='\u{FFEF}'),
+        "ascii" => ranges.push(' '..='~'),
+        _ => {
+          let (start, end) = item.split_once("..").unwrap_or((item, item));
+          let (start, end) = (code_point(start), code_point(end));
+          match (start, end) {
+            (Some(start), Some(end)) if start <= end => ranges.push(start..=end),
+            _ => return Err(format!("{item} is neither cjk, cjk-ext, cjk-punct, fullwidth, ascii nor a range like U+3000..U+303F")),
+          }
+        }
+      }
+    }
+    Ok(Self { ranges })
+  }
+
+  pub fn contains(&self, ch: char) -> bool {
+    self.ranges.iter().any(|range| range.contains(&ch))
+  }
+}
+
+/// `U+00B7` or the character itself.
+fn code_point(text: &str) -> Option<char> {
+  let text = text.trim();
+  match text.strip_prefix("U+").or_else(|| text.strip_prefix("u+")) {
+    Some(hex) => char::from_u32(u32::from_str_radix(hex, 16).ok()?),
+    None => {
+      let mut chars = text.chars();
+      chars.next().filter(|_| chars.next().is_none())
+    }
+  }
+}
+
+/// Zero-width spaces and joiners, direction marks, soft hyphens and BOMs, which can't be seen in
+/// a candidate but make it another word.
+fn is_invisible(ch: char) -> bool {
+  matches!(ch, '\u{AD}' | '\u{200B}'..='\u{200F}' | '\u{202A}'..='\u{202E}' | '\u{2060}'..='\u{2064}' | '\u{FEFF}')
+}
+
+/// The part of the word that looks like text decoded with the wrong encoding: replacement
+/// characters, 锟斤拷 and 烫烫烫 from GBK, or UTF-8 read as Latin-1 like `æˆ‘` for 我.
+fn mojibake(word: &str) -> Option<String> {
+  if word.contains('\u{FFFD}') {
+    return Some("\u{FFFD}".to_string());
+  }
+  if let Some(junk) = ["锟斤拷", "烫烫烫", "屯屯屯"].into_iter().find(|junk| word.contains(junk)) {
+    return Some(junk.to_string());
+  }
+  let is_cp1252 = |ch: char| matches!(ch, '\u{80}'..='\u{BF}' | '€' | '‚' | 'ƒ' | '„' | '…' | '†' | '‡' | 'ˆ' | '‰' | 'Š' | '‹' | 'Œ'
+    | 'Ž' | '‘' | '’' | '“' | '”' | '•' | '–' | '—' | '˜' | '™' | 'š' | '›' | 'œ' | 'ž' | 'Ÿ');
+  let chars: Vec<_> = word.chars().collect();
+  chars
+    .windows(2)
+    .find(|pair| matches!(pair[0], '\u{C2}'..='\u{EF}') && is_cp1252(pair[1]))
+    .map(|pair| pair.iter().collect())
+}
+
+/// Flags words with control or invisible characters or mojibake, which phrase packs scraped from
+/// the web are full of, and with `allowed` given, words with characters outside it.
+pub struct Charset<'a> {
+  pub allowed: Option<&'a WordChars>,
+}
+
+impl Rule for Charset<'_> {
+  fn name(&self) -> &'static str {
+    "charset"
+  }
+
+  fn check(&self, _trie: &Trie, entry: &Entry) -> Option<(Severity, String)> {
+    let word = &entry.word;
+    if let Some(ch) = word.chars().find(|ch| ch.is_control()) {
+      return Some((Severity::Error, format!("control character U+{:04X}", ch as u32)));
+    }
+    if let Some(ch) = word.chars().find(|&ch| is_invisible(ch)) {
+      return Some((Severity::Error, format!("invisible character U+{:04X}", ch as u32)));
+    }
+    if let Some(junk) = mojibake(word) {
+      return Some((Severity::Error, format!("{junk} looks like mojibake")));
+    }
+    let outside: String = word.chars().filter(|&ch| self.allowed.is_some_and(|allowed| !allowed.contains(ch))).collect();
+    if outside.is_empty() {
+      None
+    } else {
+      Some((Severity::Warning, format!("{outside} not in word_chars")))
+    }
+  }
+}
+
+#[derive(Default)]
+pub struct Linter<'a> {
+  rules: Vec<Box<dyn Rule + 'a>>,
+}
+
+impl<'a> Linter<'a> {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  pub fn rule(mut self, rule: impl Rule + 'a) -> Self {
+    self.rules.push(Box::new(rule));
+    self
+  }
+
+  pub fn lint(&self, trie: &Trie) -> Vec<Issue> {
+    let mut issues = vec![];
+    for entry in trie.entries() {
+      for rule in &self.rules {
+        if let Some((severity, message)) = rule.check(trie, &entry) {
+          issues.push(Issue {
+            entry: entry.clone(),
+            severity,
+            rule: rule.name(),
+            message,
+          });
+        }
+      }
+    }
+    issues
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_blank() {
+    let mut trie = Trie::new();
+    trie.insert("wm".to_string(), "我们 ".to_string());
+    trie.insert("w".to_string(), "我".to_string());
+
+    let issues = Linter::new().rule(Blank).lint(&trie);
+    assert_eq!(1, issues.len());
+    assert_eq!(Severity::Error, issues[0].severity);
+  }
+
+  #[test]
+  fn test_mixed_script() {
+    let mut trie = Trie::new();
+    trie.insert("wm".to_string(), "我们".to_string());
+    trie.insert("wmh".to_string(), "我們會".to_string());
+    trie.insert("mh".to_string(), "们會".to_string());
+
+    let converter = Converter::builtin();
+    let issues = Linter::new()
+      .rule(MixedScript { converter: &converter })
+      .lint(&trie);
+    assert_eq!(1, issues.len());
+    assert_eq!("们會", issues[0].entry.word);
+    assert_eq!("mh", issues[0].entry.code);
+  }
+
+  #[test]
+  fn test_collides() {
+    let mut trie = Trie::new();
+    trie.insert("d".to_string(), "的".to_string());
+    trie.insert("d".to_string(), "地".to_string());
+    trie.insert("e".to_string(), "是".to_string());
+    trie.insert("e".to_string(), "事".to_string());
+
+    let mut whitelist = Whitelist::default();
+    whitelist.add("d", "的", "xkjd6.danzi");
+    whitelist.add("d", "地", "xkjd6.danzi");
+    let issues = Linter::new()
+      .rule(Collides { whitelist: &whitelist })
+      .lint(&trie);
+    let mut words: Vec<_> = issues.iter().map(|issue| issue.entry.word.as_str()).collect();
+    words.sort();
+    assert_eq!(vec!["事", "是"], words);
+  }
+
+  #[test]
+  fn test_orphan() {
+    let mut trie = Trie::new();
+    trie.insert("w".to_string(), "我".to_string());
+    trie.insert("mk".to_string(), "们".to_string());
+    trie.insert("wi".to_string(), "我们".to_string());
+    trie.insert("wu".to_string(), "我輩".to_string());
+    trie.insert("xy".to_string(), "兲".to_string());
+
+    let issues = Linter::new().rule(Orphan::new(&trie)).lint(&trie);
+    assert_eq!(1, issues.len());
+    assert_eq!("我輩", issues[0].entry.word);
+    assert!(issues[0].message.starts_with("輩 "));
+  }
+
+  #[test]
+  fn test_charset() {
+    let allowed = WordChars::new(["cjk", "cjk-punct", "U+00B7"]).unwrap();
+    assert!(allowed.contains('我') && allowed.contains('·') && !allowed.contains('é'));
+    assert_eq!(Ok(WordChars { ranges: vec!['a'..='z'] }), WordChars::new(["U+61..z"]));
+    assert!(WordChars::new(["han"]).is_err());
+    assert!(WordChars::new(["z..a"]).is_err());
+
+    let mut trie = Trie::new();
+    for (code, word) in [("wm", "我们"), ("ml", "马丁·路德"), ("wz", "我\u{200B}们"), ("wa", "我\u{7}"), ("jk", "锟斤拷"),
+                         ("wo", "æˆ‘"), ("ca", "café"), ("gbk", "哈\u{FFFD}")] {
+      trie.insert(code.to_string(), word.to_string());
+    }
+    let mut found: Vec<_> = Linter::new()
+      .rule(Charset { allowed: Some(&allowed) })
+      .lint(&trie)
+      .into_iter()
+      .map(|issue| (issue.entry.code, issue.severity, issue.message))
+      .collect();
+    found.sort();
+    assert_eq!(vec![
+      ("ca".to_string(), Severity::Warning, "café not in word_chars".to_string()),
+      ("gbk".to_string(), Severity::Error, "\u{FFFD} looks like mojibake".to_string()),
+      ("jk".to_string(), Severity::Error, "锟斤拷 looks like mojibake".to_string()),
+      ("wa".to_string(), Severity::Error, "control character U+0007".to_string()),
+      ("wo".to_string(), Severity::Error, "æˆ looks like mojibake".to_string()),
+      ("wz".to_string(), Severity::Error, "invisible character U+200B".to_string()),
+    ], found);
+    assert_eq!(5, Linter::new().rule(Charset { allowed: None }).lint(&trie).len());
+  }
+}
@@ -0,0 +1,85 @@
+//! Reverse lookups of many words at once, spread over threads.
+
+use std::collections::HashMap;
+use std::thread;
+use crate::cost::word_cost;
+use crate::rev_dict::RevDict;
+use crate::trie::Trie;
+use crate::types::{Code, Word};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Lookup {
+  pub word: Word,
+  /// The shortest full code, see `RevDict::lookup`.
+  pub best: Code,
+  /// The word's other codes, shortest first.
+  pub alternatives: Vec<Code>,
+  /// Whether the word isn't the first candidate on its best code.
+  pub needs_select: bool,
+}
+
+impl Lookup {
+  /// `word\tbest_code\talternatives\tneeds_select`, the alternatives separated by spaces.
+  pub fn to_tsv(&self) -> String {
+    format!("{}\t{}\t{}\t{}", self.word, self.best, self.alternatives.join(" "), self.needs_select)
+  }
+}
+
+/// All the codes of each word, which `RevDict` doesn't keep.
+pub fn codes_by_word(trie: &Trie) -> HashMap<Word, Vec<Code>> {
+  let mut codes: HashMap<Word, Vec<Code>> = HashMap::new();
+  for entry in trie.entries() {
+    codes.entry(entry.word).or_default().push(entry.code);
+  }
+  for codes in codes.values_mut() {
+    codes.sort_by(|a, b| (a.len(), a).cmp(&(b.len(), b)));
+  }
+  codes
+}
+
+/// Looks the words up on as many threads as there are cores, keeping their order; `None` for words not in the dict.
+pub fn lookup_all(rev_dict: &RevDict, codes: &HashMap<Word, Vec<Code>>, words: &[Word]) -> Vec<Option<Lookup>> {
+  let lookup = |word: &Word| {
+    let cost = word_cost(rev_dict, word)?;
+    let alternatives = codes
+      .get(word)
+      .map(|codes| codes.iter().filter(|&code| *code != cost.code).cloned().collect())
+      .unwrap_or_default();
+    Some(Lookup { word: word.clone(), needs_select: cost.is_forced_selection(), best: cost.code, alternatives })
+  };
+
+  // built before spawning, so that the threads don't race to build it
+  rev_dict.words().next();
+  let threads = thread::available_parallelism().map_or(1, |n| n.get());
+  let chunk_size = words.len().div_ceil(threads).max(1);
+  thread::scope(|scope| {
+    let handles: Vec<_> = words
+      .chunks(chunk_size)
+      .map(|chunk| scope.spawn(move || chunk.iter().map(lookup).collect::<Vec<_>>()))
+      .collect();
+    handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_lookup_all() {
+    let mut trie = Trie::new();
+    trie.insert("fio".to_string(), "非常".to_string());
+    trie.insert("fio".to_string(), "飞船".to_string());
+    trie.insert("fiojp".to_string(), "飞船".to_string());
+    let rev_dict = trie.rev_dict();
+    let codes = codes_by_word(&trie);
+
+    let words: Vec<Word> = ["飞船", "不在", "非常"].iter().map(|w| w.to_string()).collect();
+    let found = lookup_all(&rev_dict, &codes, &words);
+    assert_eq!(3, found.len());
+    assert_eq!("飞船\tfio\tfiojp\ttrue", found[0].as_ref().unwrap().to_tsv());
+    assert_eq!(None, found[1]);
+    assert_eq!("非常\tfio\t\tfalse", found[2].as_ref().unwrap().to_tsv());
+  }
+}
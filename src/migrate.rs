@@ -0,0 +1,230 @@
+//! Migrating words to a new version of a schema's single-character table: the code of a word is
+//! derived from the codes of its characters, so when upstream recodes a character, the words
+//! made with it must be recoded the same way.
+//!
+//! How codes are derived isn't configured but learnt: for each word length and code length, the
+//! most common way the codes of the dict's words pick keys from the codes of their characters,
+//! like `A1 A2 B1 B2 A3 B3` for two-character words in xkjd6.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use crate::trie::Entry;
+use crate::types::Code;
+
+/// Beyond this many ways to derive a code, a word says nothing about the rules.
+const MAX_TEMPLATES: usize = 64;
+
+/// The full codes of each character, one per reading: its codes which aren't a prefix of another,
+/// so not its short codes.
+pub fn full_codes(chars: &[Entry]) -> HashMap<char, Vec<Code>> {
+  let mut codes: HashMap<char, Vec<Code>> = HashMap::new();
+  for entry in chars {
+    let mut word = entry.word.chars();
+    if let (Some(ch), None) = (word.next(), word.next()) {
+      codes.entry(ch).or_default().push(entry.code.clone());
+    }
+  }
+  for codes in codes.values_mut() {
+    let all = codes.clone();
+    codes.retain(|code| !all.iter().any(|other| other.len() > code.len() && other.starts_with(code.as_str())));
+    codes.sort_unstable();
+    codes.dedup();
+  }
+  codes
+}
+
+/// Where each key of a word's code comes from: the index of a character and of a key in its code.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Template(pub Vec<(usize, usize)>);
+
+impl Template {
+  /// The code the template derives from the codes of the characters, `None` if one is too short.
+  pub fn apply(&self, codes: &[&str]) -> Option<Code> {
+    self.0.iter().map(|&(ch, key)| codes.get(ch)?.as_bytes().get(key).map(|&key| key as char)).collect()
+  }
+
+  /// How many different keys and characters the template takes keys from.
+  fn spread(&self) -> (usize, usize) {
+    let keys = self.0.iter().collect::<HashSet<_>>().len();
+    (keys, self.0.iter().map(|&(ch, _)| ch).collect::<HashSet<_>>().len())
+  }
+
+  /// The templates deriving `code` from the codes of the characters.
+  fn explaining(code: &str, codes: &[&str]) -> Vec<Self> {
+    let mut templates = vec![vec![]];
+    for key in code.bytes() {
+      let sources: Vec<_> = codes
+        .iter()
+        .enumerate()
+        .flat_map(|(ch, code)| code.bytes().enumerate().filter(move |&(_, other)| other == key).map(move |(idx, _)| (ch, idx)))
+        .collect();
+      templates = templates
+        .into_iter()
+        .flat_map(|template: Vec<_>| sources.iter().map(move |&source| [template.as_slice(), &[source]].concat()))
+        .collect();
+      if templates.is_empty() || templates.len() > MAX_TEMPLATES {
+        return vec![];
+      }
+    }
+    templates.into_iter().map(Template).collect()
+  }
+}
+
+impl Display for Template {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let keys: Vec<_> = self.0
+      .iter()
+      .map(|&(ch, key)| format!("{}{}", (b'A' + ch.min(25) as u8) as char, key + 1))
+      .collect();
+    f.write_str(&keys.join(" "))
+  }
+}
+
+/// The readings of a word: the combinations of full codes of its characters, `None` if one has none.
+pub(crate) fn readings<'a>(word: &str, codes: &'a HashMap<char, Vec<Code>>) -> Option<Vec<Vec<&'a str>>> {
+  let mut readings = vec![vec![]];
+  for ch in word.chars() {
+    let codes = codes.get(&ch)?;
+    readings = readings
+      .into_iter()
+      .flat_map(|reading: Vec<&str>| codes.iter().map(move |code| [reading.as_slice(), &[code.as_str()]].concat()))
+      .take(MAX_TEMPLATES)
+      .collect();
+  }
+  Some(readings)
+}
+
+/// The template of each word length and code length.
+#[derive(Clone, Debug, Default)]
+pub struct Rules(HashMap<(usize, usize), Template>);
+
+impl Rules {
+  /// Learns from words the most common template of each shape, counting a word once per template
+  /// deriving its code from any of its readings.
+  pub fn learn<'a>(words: impl IntoIterator<Item=&'a Entry>, codes: &HashMap<char, Vec<Code>>) -> Self {
+    let mut counts: HashMap<(usize, usize), HashMap<Template, usize>> = HashMap::new();
+    for entry in words {
+      let len = entry.word.chars().count();
+      let Some(readings) = readings(&entry.word, codes).filter(|_| len > 1) else { continue };
+      let templates: HashSet<_> = readings.iter().flat_map(|reading| Template::explaining(&entry.code, reading)).collect();
+      for template in templates {
+        *counts.entry((len, entry.code.len())).or_default().entry(template).or_default() += 1;
+      }
+    }
+    Self(counts
+      .into_iter()
+      .filter_map(|(shape, templates)| {
+        // among as common ones, those taking more different keys, then in order
+        let best = templates.into_iter().max_by(|a, b| {
+          a.1.cmp(&b.1).then_with(|| a.0.spread().cmp(&b.0.spread())).then_with(|| b.0.0.cmp(&a.0.0))
+        })?;
+        Some((shape, best.0))
+      })
+      .collect())
+  }
+
+  pub fn get(&self, word_len: usize, code_len: usize) -> Option<&Template> {
+    self.0.get(&(word_len, code_len))
+  }
+
+  /// The template of the full codes of words of this length, the longest.
+  pub fn full(&self, word_len: usize) -> Option<&Template> {
+    self.0.iter().filter(|((len, _), _)| *len == word_len).max_by_key(|((_, code_len), _)| *code_len).map(|(_, template)| template)
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Migration {
+  /// The word moves to this code.
+  Recode(Code),
+  /// The word can't be recoded automatically, for this reason.
+  Review(String),
+}
+
+/// The characters whose full codes differ between the tables.
+pub fn changed_chars(old: &HashMap<char, Vec<Code>>, new: &HashMap<char, Vec<Code>>) -> HashSet<char> {
+  old.keys()
+    .chain(new.keys())
+    .filter(|ch| old.get(ch) != new.get(ch))
+    .copied()
+    .collect()
+}
+
+/// How a word made with a changed character migrates, `None` if its code stays.
+pub fn migrate(
+  entry: &Entry,
+  rules: &Rules,
+  old: &HashMap<char, Vec<Code>>,
+  new: &HashMap<char, Vec<Code>>,
+) -> Option<Migration> {
+  let review = |reason: String| Some(Migration::Review(reason));
+  let len = entry.word.chars().count();
+  let Some(template) = rules.get(len, entry.code.len()) else {
+    return review(format!("no rule for {len}-character words on {} keys", entry.code.len()));
+  };
+  let Some(readings) = readings(&entry.word, old) else {
+    return review("a character isn't in the old table".to_string());
+  };
+  let matching: Vec<_> = readings.into_iter().filter(|reading| template.apply(reading).as_ref() == Some(&entry.code)).collect();
+  if matching.is_empty() {
+    return review(format!("{} isn't derived from the old codes by {template}", entry.code));
+  }
+
+  let mut codes = HashSet::new();
+  for reading in matching {
+    let mut recoded = vec![];
+    for (ch, old_code) in entry.word.chars().zip(reading) {
+      let Some(candidates) = new.get(&ch) else {
+        return review(format!("{ch} isn't in the new table"));
+      };
+      // the new code of the same reading, sharing the longest prefix with the old one
+      let shared = |code: &Code| code.bytes().zip(old_code.bytes()).take_while(|(a, b)| a == b).count();
+      let best = candidates.iter().map(shared).max().unwrap_or_default();
+      let mut closest = candidates.iter().filter(|code| shared(code) == best);
+      match (closest.next(), closest.next()) {
+        (Some(code), None) => recoded.push(code.as_str()),
+        _ => return review(format!("{ch} has several new codes like {old_code}")),
+      }
+    }
+    match template.apply(&recoded) {
+      Some(code) => codes.insert(code),
+      None => return review(format!("the new codes of {} are too short for {template}", entry.word)),
+    };
+  }
+  let mut codes = codes.into_iter();
+  match (codes.next(), codes.next()) {
+    (Some(code), None) if code == entry.code => None,
+    (Some(code), None) => Some(Migration::Recode(code)),
+    _ => review("its readings give different new codes".to_string()),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn entries(entries: &[(&str, &str)]) -> Vec<Entry> {
+    entries.iter().map(|&(word, code)| Entry { word: word.to_string(), code: code.to_string() }).collect()
+  }
+
+  #[test]
+  fn test_migrate() {
+    let old = full_codes(&entries(&[("我", "w"), ("我", "wox"), ("们", "mfi"), ("非", "fvu"), ("常", "cxy"), ("行", "xkz"), ("行", "hkz")]));
+    assert_eq!(vec!["wox"], old[&'我']);
+    let words = entries(&[("我们", "womfxi"), ("非常", "fvcxuy"), ("常常", "cxcx")]);
+    let rules = Rules::learn(&words, &old);
+    assert_eq!("A1 A2 B1 B2 A3 B3", rules.get(2, 6).unwrap().to_string());
+    // not A1 A2 A1 A2, though it fits 常常 too
+    assert_eq!("A1 A2 B1 B2", rules.get(2, 4).unwrap().to_string());
+
+    // 们 recoded from mfi to mei, 行 from hkz to hqz or hqa
+    let new = full_codes(&entries(&[("我", "wox"), ("们", "mei"), ("非", "fvu"), ("常", "cxy"), ("行", "xkz"), ("行", "hqz"), ("行", "hqa")]));
+    assert_eq!(HashSet::from(['们', '行']), changed_chars(&old, &new));
+    let migrate = |word: &str, code: &str| migrate(&Entry { word: word.to_string(), code: code.to_string() }, &rules, &old, &new);
+    assert_eq!(Some(Migration::Recode("womexi".to_string())), migrate("我们", "womfxi"));
+    assert_eq!(None, migrate("非常", "fvcxuy"));
+    assert!(matches!(migrate("行们", "hkmfzi"), Some(Migration::Review(_))));
+    assert!(matches!(migrate("我们", "wmfx"), Some(Migration::Review(_))));
+  }
+}
@@ -0,0 +1,126 @@
+//! Reports of hundreds of thousands of rows, the entries of a merged dict or its collisions, as
+//! newline-delimited JSON, one object per row written as soon as it's made, and pages of them
+//! with `--offset` and `--limit`, so that neither the tool nor what reads it holds them all.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use crate::check::quote;
+
+/// A JSON object on one line, its fields in the order they're added, e.g.
+/// `{"code":"wi","words":["我们","维"],"severity":1.5}`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Object {
+  json: String,
+}
+
+impl Object {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  fn key(&mut self, name: &str) {
+    self.json.push(if self.json.is_empty() { '{' } else { ',' });
+    self.json.push_str(&quote(name));
+    self.json.push(':');
+  }
+
+  pub fn str(mut self, name: &str, value: &str) -> Self {
+    self.key(name);
+    self.json.push_str(&quote(value));
+    self
+  }
+
+  /// A number, `null` if it's not finite.
+  pub fn num(mut self, name: &str, value: impl Into<f64>) -> Self {
+    self.key(name);
+    let value = value.into();
+    match value.is_finite() {
+      true => write!(self.json, "{value}").unwrap(),
+      false => self.json.push_str("null"),
+    }
+    self
+  }
+
+  pub fn bool(mut self, name: &str, value: bool) -> Self {
+    self.key(name);
+    write!(self.json, "{value}").unwrap();
+    self
+  }
+
+  pub fn strs<S: AsRef<str>>(mut self, name: &str, values: &[S]) -> Self {
+    self.key(name);
+    self.json.push('[');
+    for (i, value) in values.iter().enumerate() {
+      if i > 0 {
+        self.json.push(',');
+      }
+      self.json.push_str(&quote(value.as_ref()));
+    }
+    self.json.push(']');
+    self
+  }
+
+  pub fn to_json(&self) -> String {
+    match self.json.is_empty() {
+      true => "{}".to_string(),
+      false => format!("{}}}", self.json),
+    }
+  }
+
+  /// Writes the object and the newline ending it.
+  pub fn write_line(&self, writer: &mut impl Write) -> io::Result<()> {
+    writeln!(writer, "{}", self.to_json())
+  }
+}
+
+/// The rows `offset..offset + limit` of a report, all of them by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Page {
+  pub offset: usize,
+  pub limit: Option<usize>,
+}
+
+impl Page {
+  /// The row after the last of the page, `None` if the page runs to the end.
+  pub fn end(&self) -> Option<usize> {
+    self.limit.map(|limit| self.offset.saturating_add(limit))
+  }
+
+  pub fn contains(&self, row: usize) -> bool {
+    row >= self.offset && self.end().is_none_or(|end| row < end)
+  }
+
+  /// The rows of the page, stopping the iterator once past it.
+  pub fn of<I: Iterator>(&self, rows: I) -> impl Iterator<Item=I::Item> {
+    rows.skip(self.offset).take(self.limit.unwrap_or(usize::MAX))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_ndjson() {
+    let object = Object::new()
+      .str("code", "wi")
+      .strs("words", &["我们", "维\t\"x\""])
+      .num("severity", 1.5)
+      .num("ratio", f64::NAN)
+      .bool("new", true);
+    assert_eq!(r#"{"code":"wi","words":["我们","维\t\"x\""],"severity":1.5,"ratio":null,"new":true}"#, object.to_json());
+    assert_eq!("{}", Object::new().to_json());
+    let mut lines = vec![];
+    for count in [1, 2] {
+      Object::new().num("count", count).write_line(&mut lines).unwrap();
+    }
+    assert_eq!("{\"count\":1}\n{\"count\":2}\n", String::from_utf8(lines).unwrap());
+
+    let page = Page { offset: 2, limit: Some(3) };
+    assert_eq!(vec![2, 3, 4], page.of(0..10).collect::<Vec<_>>());
+    assert!(page.contains(4) && !page.contains(5) && !page.contains(1));
+    assert_eq!(Some(5), page.end());
+    assert_eq!(vec![8, 9], Page { offset: 8, limit: None }.of(0..10).collect::<Vec<_>>());
+    assert!(Page::default().contains(usize::MAX));
+  }
+}
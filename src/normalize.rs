@@ -0,0 +1,65 @@
+use crate::trie::Entry;
+use crate::types::Code;
+
+/// How codes are normalized before they reach the trie, e.g. because some tables were typed
+/// with full-width letters. Everything is off by default since some schemas give capitals a meaning.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Normalization {
+  pub lowercase: bool,
+  /// Full-width ASCII such as `ａ` or the ideographic space becomes half-width.
+  pub half_width: bool,
+  /// Strips whitespace around words and codes of dict entries, never around typed keys.
+  pub trim: bool,
+}
+
+impl Normalization {
+  pub fn is_identity(&self) -> bool {
+    *self == Self::default()
+  }
+
+  /// Normalizes typed keys, which keep their whitespace since blanks select candidates.
+  pub fn keys(&self, keys: &str) -> Code {
+    keys
+      .chars()
+      .map(|ch| if self.half_width { to_half_width(ch) } else { ch })
+      .map(|ch| if self.lowercase { ch.to_ascii_lowercase() } else { ch })
+      .collect()
+  }
+
+  pub fn entry(&self, entry: Entry) -> Entry {
+    if self.is_identity() {
+      return entry;
+    }
+    let Entry { word, code } = entry;
+    let code = self.keys(&code);
+    if self.trim {
+      Entry { word: word.trim().to_string(), code: code.trim().to_string() }
+    } else {
+      Entry { word, code }
+    }
+  }
+}
+
+pub fn to_half_width(ch: char) -> char {
+  match ch {
+    '\u{3000}' => ' ',
+    '\u{ff01}'..='\u{ff5e}' => char::from_u32(ch as u32 - 0xfee0).unwrap_or(ch),
+    _ => ch,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_normalize() {
+    let all = Normalization { lowercase: true, half_width: true, trim: true };
+    assert_eq!("wi ", all.keys("ＷＩ\u{3000}"));
+    assert_eq!("Wi", Normalization::default().keys("Wi"));
+    assert_eq!(
+      Entry { word: "我们".to_string(), code: "wi".to_string() },
+      all.entry(Entry { word: " 我们".to_string(), code: "Ｗi ".to_string() })
+    );
+  }
+}
@@ -0,0 +1,258 @@
+//! Dictionary changes as patches that can be reviewed and exchanged instead of whole tables:
+//! the entries each table gains, loses or has changed, e.g.
+//!
+//! ```text
+//! @ xkjd6.user
+//! + 测试  ceui  100
+//! - 非  f
+//! ~ 飞  f => 飞  fw  # 让位给非
+//! ```
+//!
+//! `@ <table>` names the table of the changes below it, `+` appends an entry line, `-` removes an
+//! entry whatever its weight, `~` replaces the line of an entry. Blank lines and `#` comments
+//! are skipped. The cells of the lines are separated by tabs as in the tables.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::dict::{self, format_line, LineParser};
+use crate::trie::Entry;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+  /// The line appended to the table.
+  Add(String),
+  Remove(Entry),
+  /// The entry and the line replacing it.
+  Modify(Entry, String),
+}
+
+impl Display for Change {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Change::Add(line) => write!(f, "+ {line}"),
+      Change::Remove(entry) => write!(f, "- {}", format_line(entry, None, None)),
+      Change::Modify(entry, line) => write!(f, "~ {} => {line}", format_line(entry, None, None)),
+    }
+  }
+}
+
+/// The changes to one table.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hunk {
+  pub table: String,
+  pub changes: Vec<Change>,
+}
+
+/// The entry lines of a table, without headers and comments, written with tabs.
+fn entry_lines(text: &str, mut parser: LineParser) -> Vec<(Entry, String)> {
+  text
+    .lines()
+    .filter_map(|line| {
+      let entry = parser.parse(line)?;
+      Some((entry, parser.separator().to_tabs(line)))
+    })
+    .collect()
+}
+
+impl Hunk {
+  /// The changes turning the entries of `base` into those of `head`, both the text of the table
+  /// read with `parser`. An entry whose line changed, or the one entry of a word replaced by
+  /// another, is modified; the others are removed and added.
+  pub fn between(table: &str, base: &str, head: &str, parser: &LineParser) -> Self {
+    let (base, head) = (entry_lines(base, parser.clone()), entry_lines(head, parser.clone()));
+    let base_lines: HashMap<_, _> = base.iter().map(|(entry, line)| (entry, line)).collect();
+    let head_lines: HashMap<_, _> = head.iter().map(|(entry, line)| (entry, line)).collect();
+    let removed: Vec<_> = base.iter().filter(|(entry, _)| !head_lines.contains_key(entry)).collect();
+    let added: Vec<_> = head.iter().filter(|(entry, _)| !base_lines.contains_key(entry)).collect();
+    let count = |entries: &[&(Entry, String)], word: &str| entries.iter().filter(|(entry, _)| entry.word == word).count();
+
+    let mut changes = vec![];
+    let mut replaced = HashSet::new();
+    let mut seen = HashSet::new();
+    for (entry, line) in &base {
+      if !seen.insert(entry) {
+        continue;
+      }
+      match head_lines.get(entry) {
+        Some(head_line) if *head_line != line => changes.push(Change::Modify(entry.clone(), head_line.to_string())),
+        Some(_) => {}
+        None if count(&removed, &entry.word) == 1 && count(&added, &entry.word) == 1 => {
+          let (new, line) = added.iter().find(|(new, _)| new.word == entry.word).unwrap();
+          replaced.insert(new);
+          changes.push(Change::Modify(entry.clone(), line.to_string()));
+        }
+        None => changes.push(Change::Remove(entry.clone())),
+      }
+    }
+    for (entry, line) in added {
+      if !replaced.contains(entry) && seen.insert(entry) {
+        changes.push(Change::Add(line.to_string()));
+      }
+    }
+    Self { table: table.to_string(), changes }
+  }
+
+  /// The text of the table read with `parser` with the changes applied, keeping the other lines
+  /// and their line endings, and writing the new ones separated like the table. Fails on the first
+  /// change that doesn't fit the table: an entry to remove or modify that isn't there, or one to
+  /// add that already is.
+  pub fn apply(&self, text: &str, mut parser: LineParser) -> Result<String, String> {
+    let mut lines: Vec<_> = text.split_inclusive('\n').map(|line| Some(line.to_string())).collect();
+    let mut index = HashMap::new();
+    for (idx, line) in lines.iter().enumerate() {
+      if let Some(entry) = line.as_deref().and_then(|line| parser.parse(line)).map(trimmed) {
+        index.entry(entry).or_insert(idx);
+      }
+    }
+    let separator = parser.separator();
+    let ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    for change in &self.changes {
+      let conflict = || format!("{}: can't apply {change}", self.table);
+      match change {
+        Change::Add(line) => {
+          let entry = dict::parse_line(line).map(trimmed).ok_or_else(conflict)?;
+          if index.contains_key(&entry) {
+            return Err(conflict());
+          }
+          if let Some(last) = lines.iter_mut().rev().flatten().next().filter(|last| !last.ends_with('\n')) {
+            last.push_str(ending);
+          }
+          index.insert(entry, lines.len());
+          lines.push(Some(format!("{}{ending}", separator.restyle(line))));
+        }
+        Change::Remove(entry) => {
+          let idx = index.remove(&trimmed(entry.clone())).ok_or_else(conflict)?;
+          lines[idx] = None;
+        }
+        Change::Modify(entry, line) => {
+          let new = dict::parse_line(line).map(trimmed).ok_or_else(conflict)?;
+          let entry = trimmed(entry.clone());
+          if new != entry && index.contains_key(&new) {
+            return Err(conflict());
+          }
+          let idx = index.remove(&entry).ok_or_else(conflict)?;
+          let old = lines[idx].take().unwrap_or_default();
+          let content_len = old.trim_end_matches(['\r', '\n']).len();
+          lines[idx] = Some(format!("{}{}", separator.restyle(line), &old[content_len..]));
+          index.insert(new, idx);
+        }
+      }
+    }
+    Ok(lines.into_iter().flatten().collect())
+  }
+}
+
+/// Without the spaces around the cells, so that a patch matches whatever the alignment.
+fn trimmed(entry: Entry) -> Entry {
+  Entry { word: entry.word.trim().to_string(), code: entry.code.trim().to_string() }
+}
+
+/// Changes to the tables of a schema, in the order they're applied.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Patch {
+  pub hunks: Vec<Hunk>,
+}
+
+impl Patch {
+  /// Keeps the hunks with changes.
+  pub fn push(&mut self, hunk: Hunk) {
+    if !hunk.changes.is_empty() {
+      self.hunks.push(hunk);
+    }
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.hunks.is_empty()
+  }
+
+  pub fn len(&self) -> usize {
+    self.hunks.iter().map(|hunk| hunk.changes.len()).sum()
+  }
+}
+
+impl Display for Patch {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    for hunk in &self.hunks {
+      writeln!(f, "@ {}", hunk.table)?;
+      for change in &hunk.changes {
+        writeln!(f, "{change}")?;
+      }
+    }
+    Ok(())
+  }
+}
+
+impl FromStr for Patch {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut patch = Patch::default();
+    for (idx, line) in s.lines().enumerate() {
+      let error = |message: &str| format!("line {}: {message}", idx + 1);
+      let entry = |text: &str| dict::parse_line(text).map(trimmed).ok_or_else(|| error("not an entry line"));
+      if line.trim().is_empty() || line.starts_with('#') {
+        continue;
+      }
+      if let Some(table) = line.strip_prefix("@ ") {
+        patch.hunks.push(Hunk { table: table.trim().to_string(), changes: vec![] });
+        continue;
+      }
+      let (mark, text) = line.split_at_checked(2).filter(|(mark, _)| mark.ends_with(' ')).ok_or_else(|| error("expected +, - or ~ and a space"))?;
+      let change = match mark {
+        "+ " => {
+          entry(text)?;
+          Change::Add(text.to_string())
+        }
+        "- " => Change::Remove(entry(text)?),
+        "~ " => {
+          let (old, new) = text.split_once(" => ").ok_or_else(|| error("expected `old => new`"))?;
+          entry(new)?;
+          Change::Modify(entry(old)?, new.to_string())
+        }
+        _ => return Err(error("expected +, - or ~ and a space")),
+      };
+      patch.hunks.last_mut().ok_or_else(|| error("a change before any `@ <table>`"))?.changes.push(change);
+    }
+    Ok(patch)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_patch() {
+    let base = "---\nname: xkjd6.user\n...\n非\tf\n飞\tf\n测\tce\t10\n希望\twomf\n";
+    let head = "---\nname: xkjd6.user\n...\n飞\tfw\n测\tce\t20\n希望\twomf\n测试\tceui\n";
+    let hunk = Hunk::between("xkjd6.user", base, head, &LineParser::new());
+    let entry = |word: &str, code: &str| Entry { word: word.to_string(), code: code.to_string() };
+    assert_eq!(vec![
+      Change::Remove(entry("非", "f")),
+      Change::Modify(entry("飞", "f"), "飞\tfw".to_string()),
+      Change::Modify(entry("测", "ce"), "测\tce\t20".to_string()),
+      Change::Add("测试\tceui".to_string()),
+    ], hunk.changes);
+    assert_eq!(head, hunk.apply(base, LineParser::new()).unwrap());
+
+    let mut patch = Patch::default();
+    patch.push(hunk.clone());
+    patch.push(Hunk::between("xkjd6.extended", base, base, &LineParser::new()));
+    assert_eq!(1, patch.hunks.len());
+    assert_eq!(4, patch.len());
+    let text = patch.to_string();
+    assert_eq!("@ xkjd6.user\n- 非\tf\n~ 飞\tf => 飞\tfw\n~ 测\tce => 测\tce\t20\n+ 测试\tceui\n", text);
+    assert_eq!(Ok(patch), format!("# from base\n{text}").parse());
+
+    // applied twice, the entries to remove are gone and those to add are there
+    assert_eq!(Err("xkjd6.user: can't apply - 非\tf".to_string()), hunk.apply(head, LineParser::new()));
+    let adding = Hunk { table: "t".to_string(), changes: vec![Change::Add("非\tf".to_string())] };
+    assert!(adding.apply(base, LineParser::new()).is_err());
+    assert_eq!("a\tb\r\n非\tf\r\n", adding.apply("a\tb\r\n", LineParser::new()).unwrap());
+    assert_eq!("a\tb\n非\tf\n", adding.apply("a\tb", LineParser::new()).unwrap());
+    assert_eq!("a   b\n非   f\n", adding.apply("a   b\n", LineParser::new()).unwrap());
+    assert!("+ 非\tf".parse::<Patch>().is_err());
+    assert_eq!(Err("line 2: expected `old => new`".to_string()), "@ t\n~ 非\tf".parse::<Patch>());
+  }
+}
@@ -0,0 +1,134 @@
+//! Plain text frames of what the IME shows while a code is typed, key after key, like Weasel draws
+//! its candidate window: the text committed so far, the composition with a caret, and the numbered
+//! candidates with the first one highlighted. Meant for tutorials and documentation.
+
+use std::fmt::Write;
+use std::str::FromStr;
+use crate::trie::{EvalOptions, Trie};
+use crate::types::Code;
+
+/// How the candidates are laid out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Layout {
+  /// On one line, `[1.我] 2.窝`.
+  #[default]
+  Horizontal,
+  /// One per line, the highlighted one marked with `>`.
+  Vertical,
+}
+
+impl FromStr for Layout {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "horizontal" => Ok(Layout::Horizontal),
+      "vertical" => Ok(Layout::Vertical),
+      _ => Err(format!("a layout is horizontal or vertical, not {s}")),
+    }
+  }
+}
+
+/// What shows after a key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+  pub key: char,
+  pub committed: String,
+  pub composition: Code,
+  /// The first page of candidates of the composition.
+  pub candidates: Vec<String>,
+}
+
+/// The frames after each key of `keys`, with at most `page_size` candidates.
+pub fn frames(trie: &Trie, keys: &str, options: &EvalOptions, page_size: usize) -> Vec<Frame> {
+  let keys = options.normalization.keys(keys);
+  keys
+    .char_indices()
+    .map(|(idx, key)| {
+      let (committed, composition) = trie.compose(&keys[..idx + key.len_utf8()], options);
+      let candidates = match composition.is_empty() {
+        true => vec![],
+        false => trie
+          .node_through(&composition)
+          .map(|node| node.candidates(options.completion).take(page_size).cloned().collect())
+          .unwrap_or_default(),
+      };
+      Frame { key, committed, composition, candidates }
+    })
+    .collect()
+}
+
+/// How frames are drawn.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Style {
+  pub layout: Layout,
+  pub caret: String,
+}
+
+impl Default for Style {
+  fn default() -> Self {
+    Self { layout: Layout::default(), caret: "‸".to_string() }
+  }
+}
+
+impl Style {
+  /// The key, the committed text, the composition and the candidates, each on its own line(s):
+  ///
+  /// ```text
+  /// -- 2 o --
+  /// 你好
+  /// wo‸
+  /// [1.我] 2.窝
+  /// ```
+  pub fn render(&self, number: usize, frame: &Frame) -> String {
+    let key = match frame.key {
+      ' ' => "␣".to_string(),
+      '\u{8}' => "⌫".to_string(),
+      '\u{1b}' => "⎋".to_string(),
+      key => key.to_string(),
+    };
+    let mut text = format!("-- {number} {key} --\n{}\n{}{}\n", frame.committed, frame.composition, self.caret);
+    match self.layout {
+      Layout::Horizontal if !frame.candidates.is_empty() => {
+        let candidates: Vec<_> = frame.candidates
+          .iter()
+          .enumerate()
+          .map(|(idx, word)| if idx == 0 { format!("[1.{word}]") } else { format!("{}.{word}", idx + 1) })
+          .collect();
+        let _ = writeln!(text, "{}", candidates.join(" "));
+      }
+      Layout::Vertical => {
+        for (idx, word) in frame.candidates.iter().enumerate() {
+          let _ = writeln!(text, "{} {}. {word}", if idx == 0 { '>' } else { ' ' }, idx + 1);
+        }
+      }
+      Layout::Horizontal => {}
+    }
+    text
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::TrieBuilder;
+
+  #[test]
+  fn test_frames() {
+    let trie = TrieBuilder::from_iter([("n", "你"), ("n", "那"), ("nh", "你好"), ("nh", "拟合"), ("w", "我"), ("wo", "窝"), ("wo", "握"), ("xyz", "寻")]).build();
+    let options = EvalOptions { backspace: Some('<'), ..Default::default() };
+    let frames = frames(&trie, "nh wo<", &options, 2);
+    let shown: Vec<_> = frames.iter().map(|frame| (frame.committed.as_str(), frame.composition.as_str())).collect();
+    assert_eq!(vec![("", "n"), ("", "nh"), ("你好", ""), ("你好", "w"), ("你好", "wo"), ("你好", "w")], shown);
+    assert_eq!(vec!["你", "那"], frames[0].candidates);
+    assert!(frames[2].candidates.is_empty());
+    // keys only starting a code compose too
+    assert_eq!(("我".to_string(), "xy".to_string()), trie.compose("w xy", &options));
+
+    assert_eq!("-- 5 o --\n你好\nwo‸\n[1.窝] 2.握\n", Style::default().render(5, &frames[4]));
+    let vertical = Style { layout: Layout::Vertical, caret: "|".to_string() };
+    assert_eq!("-- 3 ␣ --\n你好\n|\n", vertical.render(3, &frames[2]));
+    assert_eq!("-- 3 ␣ --\n你好\n‸\n", Style::default().render(3, &frames[2]));
+    assert_eq!("-- 1 n --\n\nn|\n> 1. 你\n  2. 那\n", vertical.render(1, &frames[0]));
+  }
+}
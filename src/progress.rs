@@ -0,0 +1,46 @@
+//! Progress of the long operations, for embedders to draw bars: loading a schema, indexing its
+//! words for reverse lookups and encoding a corpus take seconds to minutes on big dicts.
+//!
+//! The `_with_progress` variants of these take an `FnMut(Progress)`, called from the thread
+//! doing the work, so it should only hand the progress over, e.g. through a channel.
+
+use std::fmt::{Display, Formatter};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Stage {
+  /// Reading the tables of a schema, in bytes.
+  Load,
+  /// Building the reverse lookup of a trie, in nodes.
+  Index,
+  /// Encoding a corpus file, in bytes.
+  Encode,
+}
+
+impl Display for Stage {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.write_str(match self {
+      Stage::Load => "loading",
+      Stage::Index => "indexing",
+      Stage::Encode => "encoding",
+    })
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Progress {
+  pub stage: Stage,
+  pub current: u64,
+  pub total: u64,
+}
+
+impl Progress {
+  pub fn new(stage: Stage, current: u64, total: u64) -> Self {
+    Self { stage, current, total }
+  }
+
+  pub fn is_done(&self) -> bool {
+    self.current >= self.total
+  }
+}
@@ -0,0 +1,89 @@
+//! Plans short codes (简码) for frequent words typed with long ones: each word may take a free
+//! prefix of one of its codes, as long as no more frequent word shows first there today.
+
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+use crate::cost::word_cost;
+use crate::freq::Frequencies;
+use crate::lookup::codes_by_word;
+use crate::rev_dict::RevDict;
+use crate::trie::Trie;
+use crate::types::{Code, Word};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Promotion {
+  pub word: Word,
+  pub code: Code,
+  /// The code typed today, see `word_cost`.
+  pub from: Code,
+  pub freq: u64,
+  /// Keystrokes saved over the frequencies, the frequency times the keys saved per use.
+  pub saved: u64,
+}
+
+/// Whether a word can take `code` without pushing a more frequent one down: the code has no words,
+/// and what shows first there, a completion, is less frequent than `freq` or the word itself.
+fn is_free(trie: &Trie, rev_dict: &RevDict, freq: &Frequencies, code: &str, word: &str, count: u64) -> bool {
+  let Some(node) = trie.node_through(code) else { return true };
+  if node.full_code_len() == code.len() && !node.words().is_empty() {
+    return false;
+  }
+  node.candidates(rev_dict.completion())
+    .next()
+    .is_none_or(|shown| shown == word || freq.get(shown) < count)
+}
+
+/// Assigns codes of `lengths` keys to the `pool` most frequent words, the biggest savings first,
+/// each code and each word at most once.
+pub fn plan(trie: &Trie, rev_dict: &RevDict, freq: &Frequencies, lengths: RangeInclusive<usize>, pool: usize) -> Vec<Promotion> {
+  let codes = codes_by_word(trie);
+  let mut options = vec![];
+  for (word, count) in freq.ranked().into_iter().take(pool) {
+    let Some(cost) = word_cost(rev_dict, word) else { continue };
+    let keystrokes = cost.keystrokes();
+    let prefixes: HashSet<_> = codes[word]
+      .iter()
+      .flat_map(|code| lengths.clone().filter_map(|len| code.get(..len)))
+      .filter(|prefix| prefix.len() < keystrokes && is_free(trie, rev_dict, freq, prefix, word, count))
+      .collect();
+    for prefix in prefixes {
+      options.push(Promotion {
+        word: word.clone(),
+        code: prefix.to_string(),
+        from: cost.code.clone(),
+        freq: count,
+        saved: count * (keystrokes - prefix.len()) as u64,
+      });
+    }
+  }
+  options.sort_by(|a, b| b.saved.cmp(&a.saved).then_with(|| a.code.cmp(&b.code)).then_with(|| a.word.cmp(&b.word)));
+
+  let mut taken_words = HashSet::new();
+  let mut taken_codes = HashSet::new();
+  options.retain(|option| !taken_codes.contains(&option.code) && !taken_words.contains(&option.word)
+    && taken_codes.insert(option.code.clone()) && taken_words.insert(option.word.clone()));
+  options
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::TrieBuilder;
+
+  #[test]
+  fn test_plan() {
+    let trie = TrieBuilder::from_iter([
+      ("fio", "非常"), ("fio", "飞船"), ("fiojp", "飞船"), ("xhni", "瞎胡闹"), ("xhnv", "喜欢你"), ("wi", "我们"),
+    ]).build();
+    let rev_dict = trie.rev_dict();
+    let freq = Frequencies([("非常", 100), ("飞船", 50), ("喜欢你", 30), ("瞎胡闹", 40), ("我们", 1000)]
+      .map(|(word, count)| (word.to_string(), count))
+      .into());
+    let plan = plan(&trie, &rev_dict, &freq, 2..=3, 10);
+    let found: Vec<_> = plan.iter().map(|p| (p.word.as_str(), p.code.as_str(), p.saved)).collect();
+    // 非常 shows first on fi, so 飞船 can't take it, and fio has words already;
+    // 瞎胡闹 shows first on xh and xhn, so 喜欢你 can't take them; 我们 is short enough
+    assert_eq!(vec![("非常", "fi", 100), ("瞎胡闹", "xh", 80)], found);
+  }
+}
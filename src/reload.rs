@@ -0,0 +1,63 @@
+//! Noticing that the dicts of a schema changed on disk, for long-running processes like `serve`
+//! to reload them. Polling the modification times is enough for files edited by hand or deployed
+//! by a sync tool, and needs no platform watcher.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use crate::archive;
+use crate::schema::Schema;
+
+/// The modification time and length of each file, `None` for one missing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stamps(Vec<(PathBuf, Option<(SystemTime, u64)>)>);
+
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+  // an archived dict changes with its archive
+  let file = archive::split_zip(path).map_or(path, |(zip, _)| zip);
+  let metadata = fs::metadata(file).ok()?;
+  Some((metadata.modified().ok()?, metadata.len()))
+}
+
+impl Stamps {
+  pub fn new(paths: impl IntoIterator<Item=PathBuf>) -> Self {
+    Self(paths.into_iter().map(|path| {
+      let stamp = stamp(&path);
+      (path, stamp)
+    }).collect())
+  }
+
+  /// The stamps of the tables the schema loads, the main dict's imports as they are now.
+  pub fn of_schema(schema: &Schema) -> io::Result<Self> {
+    Ok(Self::new(schema.tables()?.iter().map(|table| schema.dict_path(table))))
+  }
+
+  /// The files changed since the stamps were taken.
+  pub fn changed(&self) -> Vec<&Path> {
+    self.0
+      .iter()
+      .filter(|(path, old)| stamp(path) != *old)
+      .map(|(path, _)| path.as_path())
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_changed() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let (a, b) = (dir.join("a.txt"), dir.join("b.txt"));
+    fs::write(&a, "我\tw\n").unwrap();
+    let stamps = Stamps::new([a.clone(), b.clone()]);
+    assert!(stamps.changed().is_empty());
+
+    fs::write(&a, "我\tw\n们\tm\n").unwrap();
+    fs::write(&b, "").unwrap();
+    assert_eq!(vec![a.as_path(), b.as_path()], stamps.changed());
+  }
+}
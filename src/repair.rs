@@ -0,0 +1,153 @@
+//! Safe automatic repairs of a table, as `lint --fix` applies them: nothing an entry means
+//! changes, only how it's written.
+
+use std::fmt::{Display, Formatter};
+use crate::dict::{parse_line, split_annotation, Header, Separator};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Fix {
+  /// A byte order mark stripped, Rime reads it as part of the first line.
+  Bom,
+  /// This many `\r\n` or `\r` line endings made `\n`.
+  LineEndings(usize),
+  /// The entry on this line, 1-based, had its cells separated otherwise than the table, now like it.
+  Separator(usize),
+  /// The line, empty word or empty code, removed.
+  Empty(usize, String),
+}
+
+impl Display for Fix {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Fix::Bom => f.write_str("BOM stripped"),
+      Fix::LineEndings(count) => write!(f, "{count} line endings made \\n"),
+      Fix::Separator(_) => f.write_str("cells separated like the rest of the table"),
+      Fix::Empty(_, text) => write!(f, "removed {text:?}, without word or code"),
+    }
+  }
+}
+
+impl Fix {
+  /// The line fixed, 1-based, `None` for fixes of the whole file.
+  pub fn line(&self) -> Option<usize> {
+    match self {
+      Fix::Bom | Fix::LineEndings(_) => None,
+      Fix::Separator(line) | Fix::Empty(line, _) => Some(*line),
+    }
+  }
+}
+
+/// The text with the repairs applied and the list of them, empty if it needed none; `separator` is
+/// how the table separates cells, see [`Schema::separator`](crate::schema::Schema::separator).
+pub fn repair(text: &str, separator: &Separator) -> (String, Vec<Fix>) {
+  let mut fixes = vec![];
+  let text = match text.strip_prefix('\u{FEFF}') {
+    Some(text) => {
+      fixes.push(Fix::Bom);
+      text
+    }
+    None => text,
+  };
+  let endings = text.matches('\r').count();
+  let text = match endings {
+    0 => text.to_string(),
+    _ => {
+      fixes.push(Fix::LineEndings(endings));
+      text.replace("\r\n", "\n").replace('\r', "\n")
+    }
+  };
+
+  let (header, body) = Header::parse(&text);
+  let offset = text[..text.len() - body.len()].matches('\n').count();
+  let mut repaired = header.to_string();
+  for (idx, line) in body.split_inclusive('\n').enumerate() {
+    let content = line.trim_end_matches('\n');
+    let ending = &line[content.len()..];
+    if let Some(separated) = separated(content, separator) {
+      fixes.push(Fix::Separator(offset + idx + 1));
+      repaired.push_str(&separated);
+      repaired.push_str(ending);
+    } else if parse_line(content).is_some_and(|entry| entry.word.trim().is_empty() || entry.code.trim().is_empty())
+      && !content.starts_with('#') {
+      fixes.push(Fix::Empty(offset + idx + 1, content.to_string()));
+    } else {
+      repaired.push_str(line);
+    }
+  }
+  (repaired, fixes)
+}
+
+/// An entry line the table's separator doesn't split, separated with it, if it's unambiguous where
+/// its cells are: tabs separate them, else spaces do as [`tab_separated`] tells.
+fn separated(line: &str, separator: &Separator) -> Option<String> {
+  if line.starts_with('#') || separator.parse_weighted(line).is_some() {
+    return None;
+  }
+  let tabbed = match line.contains('\t') {
+    true => line.to_string(),
+    false => tab_separated(line)?,
+  };
+  // empty cells are left for `Fix::Empty`
+  parse_line(&tabbed)
+    .filter(|entry| !entry.word.trim().is_empty() && !entry.code.trim().is_empty())
+    .map(|_| separator.restyle(&tabbed))
+}
+
+/// A line without tabs whose cells are separated by spaces, with tabs, if that's unambiguous:
+/// runs of several spaces separate cells, else single spaces do when there are two cells, or
+/// three the last of which is a weight.
+fn tab_separated(line: &str) -> Option<String> {
+  let (content, annotation) = split_annotation(line);
+  if line.starts_with('#') || content.contains('\t') || content.trim().is_empty() {
+    return None;
+  }
+  let content = content.trim();
+  let cells: Vec<_> = if content.contains("  ") {
+    content.split("  ").map(str::trim).filter(|cell| !cell.is_empty()).collect()
+  } else {
+    content.split(' ').collect()
+  };
+  let unambiguous = match cells.len() {
+    2 => true,
+    3 => cells[2].parse::<u64>().is_ok(),
+    _ => false,
+  };
+  if !unambiguous || cells.iter().any(|cell| cell.contains(' ')) {
+    return None;
+  }
+  let mut separated = cells.join("\t");
+  if annotation.is_some() {
+    separated.push('\t');
+    separated.push_str(&line[line.find('#').unwrap_or(line.len())..]);
+  }
+  Some(separated)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_repair() {
+    let text = "\u{FEFF}---\r\nname: xkjd6\r\n...\r\n我\tw\r\n我们  wi   5\r\n非 f # 自造\r\nhello world hw\r\n\tq\r\n去\t\r\n# 注释\r\n";
+    let (repaired, fixes) = repair(text, &Separator::Tab);
+    assert_eq!("---\nname: xkjd6\n...\n我\tw\n我们\twi\t5\n非\tf\t# 自造\nhello world hw\n# 注释\n", repaired);
+    assert_eq!(vec![
+      Fix::Bom,
+      Fix::LineEndings(10),
+      Fix::Separator(5),
+      Fix::Separator(6),
+      Fix::Empty(8, "\tq".to_string()),
+      Fix::Empty(9, "去\t".to_string()),
+    ], fixes);
+    assert_eq!((repaired.clone(), vec![]), repair(&repaired, &Separator::Tab));
+
+    // a table aligned with spaces stays so, only the lines it can't read are separated like it
+    let text = "我    w\n我们  wi  5\n非 f\n常\tc\n";
+    let (repaired, fixes) = repair(text, &Separator::Spaces(2));
+    assert_eq!("我    w\n我们  wi  5\n非  f\n常\tc\n", repaired);
+    assert_eq!(vec![Fix::Separator(3)], fixes);
+    let (repaired, _) = repair("非 f\n常\tc # 注\n", &Separator::Custom("|".to_string()));
+    assert_eq!("非|f\n常|c # 注\n", repaired);
+  }
+}
@@ -1,104 +1,249 @@
+use std::borrow::Cow;
+use std::sync::OnceLock;
 use std::ops::Range;
-use std::collections::HashMap;
-use crate::trie::Trie;
+use std::collections::hash_map::{Entry, HashMap};
+use std::collections::HashSet;
+use crate::convert::{Converter, Script};
+use crate::hint::SegmenterHint;
+use crate::progress::{Progress, Stage};
+use crate::trie::{Completion, SelectKeys, Trie};
 use crate::types::{Code, Word};
 
+/// How often `build_with_progress` reports.
+const PROGRESS_NODES: usize = 1 << 14;
+
 struct Info<'a> {
   full_code: Code,
   node: &'a Trie,
 }
 
-impl<'a> Info<'a> {
-  fn from(node: &'a Trie) -> Self {
-    Self {
-      full_code: node.full_code(),
-      node,
-    }
-  }
-}
-
+/// Maps each word to its shortest full code, built on the first reverse lookup.
 pub struct RevDict<'a> {
-  map: HashMap<Word, Info<'a>>,
+  map: OnceLock<HashMap<Word, Info<'a>>>,
   trie: &'a Trie,
+  conversion: Option<(&'a Converter, Script)>,
+  completion: Completion,
+  select_keys: SelectKeys,
+  known: Option<HashSet<Word>>,
+  hint: Option<&'a dyn SegmenterHint>,
 }
 
 impl<'a> RevDict<'a> {
   pub fn new(trie: &'a Trie) -> Self {
-    Self { map: HashMap::new(), trie }
+    Self { map: OnceLock::new(), trie, conversion: None, completion: Completion::ALL, select_keys: Default::default(), known: None, hint: None }
   }
 
-  fn get(&self, word: &str) -> Option<&Info<'_>> {
-    self.map.get(word)
+  /// Walks the trie once, see `Trie::walk`, reporting the nodes walked every `PROGRESS_NODES`.
+  fn build(trie: &'a Trie, mut progress: Option<&mut dyn FnMut(Progress)>) -> HashMap<Word, Info<'a>> {
+    let mut map: HashMap<Word, Info<'a>> = HashMap::new();
+    let total = progress.as_ref().map_or(0, |_| trie.nodes().count() as u64);
+    // ties go to the node met first
+    for (idx, (node, full_code)) in trie.walk().enumerate() {
+      if let Some(progress) = progress.as_mut().filter(|_| idx % PROGRESS_NODES == 0) {
+        progress(Progress::new(Stage::Index, idx as u64, total));
+      }
+      for word in node.words() {
+        match map.entry(word.clone()) {
+          Entry::Vacant(vacant) => {
+            vacant.insert(Info { full_code: full_code.clone(), node });
+          }
+          Entry::Occupied(mut occupied) => {
+            if full_code.len() < occupied.get().full_code.len() {
+              occupied.insert(Info { full_code: full_code.clone(), node });
+            }
+          }
+        }
+      }
+    }
+    if let Some(progress) = progress {
+      progress(Progress::new(Stage::Index, total, total));
+    }
+    map
   }
 
-  fn get_mut(&mut self, word: &str) -> Option<&mut Info<'a>> {
-    self.map.get_mut(word)
+  fn map(&self) -> &HashMap<Word, Info<'a>> {
+    self.map.get_or_init(|| Self::build(self.trie, None))
   }
 
-  fn insert(&mut self, word: Word, node: &'a Trie) {
-    self.map.insert(word, Info::from(node));
+  /// Builds the reverse lookup now rather than on the first one, reporting the progress.
+  pub fn build_with_progress(&self, mut progress: impl FnMut(Progress)) {
+    self.map.get_or_init(|| Self::build(self.trie, Some(&mut progress)));
   }
 
-  pub(crate) fn insert_if_shorter(&mut self, word: &str, node: &'a Trie) {
-    match self.get_mut(word) {
-      None => {
-        self.insert(word.to_string(), node);
-      }
-      Some(info) => {
-        if node.full_code_len() < info.full_code.len() {
-          info.full_code = node.full_code();
-          info.node = node;
-        }
-      }
+  /// Converts sentences to `script` before encoding them,
+  /// e.g. to encode a traditional corpus against a simplified dictionary.
+  pub fn convert_input(&mut self, converter: &'a Converter, script: Script) {
+    self.conversion = Some((converter, script));
+  }
+
+  /// The text in the script sentences are encoded in, see `convert_input`. Corpora are converted
+  /// before they're split by the characters of the dict.
+  pub fn convert<'s>(&self, text: &'s str) -> Cow<'s, str> {
+    match self.conversion {
+      Some((converter, script)) => Cow::Owned(converter.convert(text, script)),
+      None => Cow::Borrowed(text),
+    }
+  }
+
+  /// Whether the candidate lists include completions of longer codes, see `Trie::candidates`.
+  pub fn set_completion(&mut self, completion: impl Into<Completion>) {
+    self.completion = completion.into();
+  }
+
+  pub fn completion(&self) -> Completion {
+    self.completion
+  }
+
+  /// The keys committing candidates, of which the first one commits a code before typing one extending it.
+  pub fn set_select_keys(&mut self, select_keys: SelectKeys) {
+    self.select_keys = select_keys;
+  }
+
+  pub fn select_keys(&self) -> &SelectKeys {
+    &self.select_keys
+  }
+
+  /// Encodes sentences with these words only, like someone who hasn't learnt the others' codes yet.
+  /// They still take their places among the candidates.
+  pub fn restrict(&mut self, known: HashSet<Word>) {
+    self.known = Some(known);
+  }
+
+  /// Splits sentences where the hint allows or prefers, see `SegmenterHint`.
+  pub fn set_hint(&mut self, hint: &'a dyn SegmenterHint) {
+    self.hint = Some(hint);
+  }
+
+  /// The shortest full code of `word` and the node it's stored at.
+  pub fn lookup(&self, word: &str) -> Option<(&Code, &'a Trie)> {
+    self.map().get(word).map(|info| (&info.full_code, info.node))
+  }
+
+  pub fn words(&self) -> impl Iterator<Item=&Word> {
+    self.map().keys()
+  }
+
+  fn get(&self, word: &str) -> Option<&Info<'a>> {
+    if self.known.as_ref().is_some_and(|known| !known.contains(word)) {
+      return None;
+    }
+    // English words are typed in lowercase whatever their case in the text, like Rime's easy_en
+    match self.map().get(word) {
+      None if word.bytes().any(|byte| byte.is_ascii_uppercase()) => self.get(&word.to_ascii_lowercase()),
+      info => info,
     }
   }
 }
 
-impl RevDict<'_> {
+/// A word of a sentence encoded by `RevDict::segment`.
+pub struct Segment<'a> {
+  pub word: Word,
+  /// The keys typed for the word, starting with the commit key, a blank by default, if the previous
+  /// candidate has to be committed first.
+  pub code: Code,
+  pub node: &'a Trie,
+  /// The length of the commit key `code` starts with, 0 if none.
+  prefix_len: usize,
+}
+
+impl Segment<'_> {
+  /// The keys typed for the word itself, without the commit key.
+  pub fn word_code(&self) -> &str {
+    &self.code[self.prefix_len..]
+  }
+
+  /// Whether the word is committed as soon as its code is complete, see `Trie::eval`.
+  pub fn is_auto_commit(&self) -> bool {
+    self.node.words().len() == 1 && self.node.is_leaf()
+  }
+}
+
+impl<'a> RevDict<'a> {
   pub fn shortest(&self, sentence: &str) -> Result<Vec<Code>, String> {
+    let segments = self.segment(sentence)?;
+    let mut codes: Vec<_> = segments.iter().map(|segment| segment.code.clone()).collect();
+    if segments.last().is_some_and(|last| !last.is_auto_commit()) {
+      codes.extend(self.select_keys.commit_key().map(String::from));
+    }
+    Ok(codes)
+  }
+
+  /// Splits the sentence into the words with the shortest total code.
+  pub fn segment(&self, sentence: &str) -> Result<Vec<Segment<'a>>, String> {
+    self.segment_excluding(sentence, None)
+  }
+
+  /// Like `segment` as if `excluded` weren't in the dict, e.g. to type a phrase without its own entry.
+  pub fn segment_without(&self, sentence: &str, excluded: &str) -> Result<Vec<Segment<'a>>, String> {
+    self.segment_excluding(sentence, Some(excluded))
+  }
+
+  fn segment_excluding(&self, sentence: &str, excluded: Option<&str>) -> Result<Vec<Segment<'a>>, String> {
     /*
      * dp[i] = min { dp[j] + self[sentence[j..i]].length } for 0 <= j < i
      * */
 
+    let sentence = self.convert(sentence);
+    let sentence = sentence.as_ref();
+
     struct State<'a> {
       code: String,
+      prefix_len: usize,
       prev: usize,
       sum_len: usize,
       node: &'a Trie,
       word_range: Range<usize>,
     }
 
-    let mut dp = vec![State {
+    // None marks prefixes no sequence of words ends at
+    let mut dp = vec![Some(State {
       code: "".to_string(),
+      prefix_len: 0,
       prev: 0,
       sum_len: 0,
       node: self.trie,
       word_range: Default::default(),
-    }];
+    })];
 
     let char_indices: Vec<_> = sentence.char_indices().collect();
-    for (right_char_index, &(_, right_char)) in char_indices.iter().enumerate() {
+    let boundaries = self.hint.map(|hint| hint.boundaries(sentence));
+    for right_char_index in 0..char_indices.len() {
       let mut code = String::new();
+      let mut prefix_len = 0;
       let mut prev = 0;
       let mut sum_len = usize::MAX;
       let mut node_option = None;
-      let mut word_range = Default::default();
+      let mut best_range = Default::default();
       let next_byte_index = char_indices
         .get(right_char_index + 1)
         .map(|pair| pair.0)
         .unwrap_or(sentence.len());
       for left_char_index in 0..=right_char_index {
+        let Some(prev_state) = &dp[left_char_index] else { continue };
         let left_byte_index = char_indices[left_char_index].0;
-        word_range = left_byte_index..next_byte_index;
+        let word_range = left_byte_index..next_byte_index;
         let word = &sentence[word_range.clone()];
+        if excluded == Some(word) {
+          continue;
+        }
+        // the hint's keys only weigh on the choice, they aren't typed
+        let penalty = match (self.hint, &boundaries) {
+          (Some(hint), Some(boundaries)) => {
+            let inside = boundaries.partition_point(|&idx| idx <= word_range.start)..boundaries.partition_point(|&idx| idx < word_range.end);
+            match hint.penalty(inside.len()) {
+              Some(penalty) => penalty,
+              None => continue,
+            }
+          }
+          _ => 0,
+        };
 
         if let Some(Info { full_code: rev_code, node }) = self.get(word) {
-          let prev_state = &dp[left_char_index];
           let prefix_blank = {
             let prev_node = prev_state.node;
             let is_prev_candidate = {
               let prev_word = &sentence[prev_state.word_range.clone()];
-              let mut prev_candidates = prev_node.candidates();
+              let mut prev_candidates = prev_node.candidates(self.completion);
               if let Some(first_candidate) = prev_candidates.next() {
                 first_candidate == prev_word && prev_candidates.next().is_some()
               } else {
@@ -109,38 +254,48 @@ impl RevDict<'_> {
               && prev_node.children().any(|child| rev_code.starts_with(child.code()))
           };
 
+          // without a key committing the first candidate, the previous word can't be followed by this one
+          let prefix = match prefix_blank {
+            true => match self.select_keys.commit_key() {
+              Some(key) => key.to_string(),
+              None => continue,
+            },
+            false => String::new(),
+          };
           let prev_len = prev_state.sum_len;
-          let new_len = prev_len + rev_code.len() + if prefix_blank { 1 } else { 0 };
+          let new_len = prev_len + rev_code.len() + prefix.len() + penalty;
           if new_len < sum_len {
             sum_len = new_len;
             prev = left_char_index;
-            code = format!("{}{rev_code}", if prefix_blank { " " } else { "" });
+            code = format!("{prefix}{rev_code}");
+            prefix_len = prefix.len();
             node_option = Some(node);
+            best_range = word_range;
           }
         }
       }
-      if let Some(node) = node_option {
-        dp.push(State { code, prev, sum_len, node, word_range });
-      } else {
-        return Err(format!("can't generate the sentence from the dictionary, see '{right_char}' at {right_char_index}"));
-      }
+      dp.push(node_option.map(|node| State { code, prefix_len, prev, sum_len, node, word_range: best_range }));
     }
 
+    let Some(mut state) = dp.last().unwrap().as_ref() else {
+      let reachable = dp.iter().rposition(Option::is_some).unwrap();
+      let (_, ch) = char_indices[reachable];
+      return Err(format!("can't generate the sentence from the dictionary, see '{ch}' at {reachable}"));
+    };
+
     // collect
-    let mut codes = vec![];
-    let mut state = dp.last().unwrap();
-    if state.node.words().len() > 1 || !state.node.is_leaf() {
-      codes.push(" ".to_string());
-    }
-    loop {
-      codes.push(state.code.clone());
-      if state.prev == 0 {
-        break;
-      }
-      state = &dp[state.prev];
+    let mut segments = vec![];
+    while !state.word_range.is_empty() {
+      segments.push(Segment {
+        word: sentence[state.word_range.clone()].to_string(),
+        code: state.code.clone(),
+        node: state.node,
+        prefix_len: state.prefix_len,
+      });
+      state = dp[state.prev].as_ref().unwrap();
     }
-    codes.reverse();
-    Ok(codes)
+    segments.reverse();
+    Ok(segments)
   }
 }
 
@@ -148,13 +303,82 @@ impl RevDict<'_> {
 mod test {
   use super::*;
 
+  fn readme_trie() -> Box<Trie> {
+    let mut trie = Box::new(Trie::new());
+    for (code, word) in [
+      ("w", "我"), ("wi", "我们"), ("e", "是"), ("ekfw", "是非"), ("jpi", "常"), ("fio", "非常"),
+      ("fwjp", "非常"), ("xa", "喜欢"), ("xhn", "喜欢你"), ("xhni", "瞎胡闹"), ("n", "你"),
+      ("d", "的"), ("nui", "你的"), (",", "，"), (".", "。"),
+    ] {
+      trie.insert(code.to_string(), word.to_string());
+    }
+    trie
+  }
+
+  #[test]
+  fn test_lazy_build() {
+    let trie = readme_trie();
+    let dict = trie.rev_dict();
+    assert!(dict.map.get().is_none());
+    let (code, node) = dict.lookup("非常").unwrap();
+    assert_eq!("fio", code);
+    assert_eq!(node.full_code(), *code);
+    for word in dict.words() {
+      let (code, node) = dict.lookup(word).unwrap();
+      assert_eq!(node.full_code(), *code);
+    }
+  }
+
+  #[test]
+  fn test_shortest_readme() {
+    let mut trie = readme_trie();
+    trie.insert("i".to_string(), "一".to_string());
+    let dict = trie.rev_dict();
+    // "喜欢你/的" beats "喜欢/你的" when only the entries listed in the readme are loaded
+    assert_eq!(vec!["w", "e", "fio", "xhn", "d", "."], dict.shortest("我是非常喜欢你的。").unwrap());
+    // "wi" would select 我们 without the blank
+    assert_eq!(vec!["w", " i"], dict.shortest("我一").unwrap());
+
+    let mut dict = trie.rev_dict();
+    dict.set_select_keys(SelectKeys::new(vec![";".to_string(), "'".to_string()]).unwrap());
+    assert_eq!(vec!["w", ";i"], dict.shortest("我一").unwrap());
+    assert_eq!("i", dict.segment("我一").unwrap()[1].word_code());
+    assert_eq!(vec!["w", ";"], dict.shortest("我").unwrap());
+    dict.set_select_keys(SelectKeys::new(vec![]).unwrap());
+    assert!(dict.shortest("我一").is_err());
+
+    let segments = dict.segment("我是非常喜欢你的。").unwrap();
+    let words: Vec<_> = segments.iter().map(|segment| segment.word.as_str()).collect();
+    assert_eq!(vec!["我", "是", "非常", "喜欢你", "的", "。"], words);
+    assert!(segments[5].is_auto_commit());
+    assert!(!segments[0].is_auto_commit());
+
+    assert_eq!(
+      Err("can't generate the sentence from the dictionary, see '他' at 1".to_string()),
+      dict.shortest("我他")
+    );
+  }
+
+  #[test]
+  fn test_convert_input() {
+    let trie = readme_trie();
+    let converter = Converter::builtin();
+    let mut dict = trie.rev_dict();
+    assert!(dict.shortest("我們喜歡你").is_err());
+    dict.convert_input(&converter, Script::Simplified);
+    assert_eq!(vec!["wi", "xhn", " "], dict.shortest("我們喜歡你").unwrap());
+    assert_eq!("我们喜欢你", dict.convert("我們喜歡你"));
+    let stats = crate::corpus::analyze(&dict, ["我們 喜歡你"]);
+    assert_eq!((5, 0), (stats.chars, stats.unencoded));
+    let mut analyzer = crate::corpus::Analyzer::new(&dict);
+    assert_eq!("我们(wi)喜欢你(xhn)", analyzer.annotate("我們喜歡你"));
+    assert_eq!(stats.keystrokes, analyzer.stats.keystrokes);
+  }
+
   #[test]
   fn test_shortest() {
     let mut trie = Trie::new();
-    let mut path = home::home_dir().unwrap();
-    path.push(r"AppData\Roaming\Rime");
-
-    path.push("xkjd6.cizu.dict.yaml");
+    let mut path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/rime/xkjd6.cizu.dict.yaml");
     trie.load_xkjd_dict(&path).unwrap();
 
     path.set_file_name("xkjd6.danzi.dict.yaml");
@@ -168,7 +392,7 @@ mod test {
 
     assert_eq!("我爱读书", trie.eval("wlxhdjej "));
 
-    trie.check_links().unwrap();
+    trie.validate().unwrap();
 
     let dict = trie.rev_dict();
     assert_eq!(vec!["nau", "ms", " "], dict.shortest("你好吗").unwrap());
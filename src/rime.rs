@@ -0,0 +1,160 @@
+//! Deploying a schema with librime, the way the IME would, to catch what only Rime rejects.
+//!
+//! Needs the `librime` feature and librime installed where the linker finds it.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// What librime said while building a schema.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Deployment {
+  pub ok: bool,
+  /// Warning and error lines of librime's log, without their glog prefix.
+  pub messages: Vec<String>,
+}
+
+/// Where the build goes: not the Rime directory's own `build`, so that the IME keeps using the deployed one.
+pub struct Workspace {
+  /// The Rime user directory, read but not written.
+  pub user_dir: PathBuf,
+  pub staging_dir: PathBuf,
+  pub log_dir: PathBuf,
+}
+
+impl Workspace {
+  pub fn new(user_dir: impl Into<PathBuf>, scratch: impl AsRef<Path>) -> Self {
+    let scratch = scratch.as_ref();
+    Self { user_dir: user_dir.into(), staging_dir: scratch.join("build"), log_dir: scratch.join("log") }
+  }
+}
+
+/// Keeps the `W`/`E`/`F` lines of a glog file, e.g.
+/// `E20240101 12:00:00.000000  1234 dict_compiler.cc:120] missing code for 我` becomes
+/// `dict_compiler.cc:120] missing code for 我`.
+pub fn log_messages(log: &str) -> Vec<String> {
+  log
+    .lines()
+    .filter(|line| line.starts_with(['W', 'E', 'F']) && line[1..].starts_with(|c: char| c.is_ascii_digit()))
+    .map(|line| line.split_once(char::is_whitespace)
+      .map(|(_, rest)| rest.trim_start())
+      .and_then(|rest| rest.split_once(char::is_whitespace))
+      .and_then(|(_, rest)| rest.trim_start().split_once(char::is_whitespace))
+      .map_or(line, |(_, message)| message.trim_start())
+      .to_string())
+    .collect()
+}
+
+/// The warnings and errors of the librime logs in `dir`, oldest first.
+pub fn read_logs(dir: &Path) -> io::Result<Vec<String>> {
+  let mut messages = vec![];
+  let mut paths: Vec<_> = fs::read_dir(dir)?
+    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+    // glog writes one file per level, each with the lines of the levels above it: INFO has them all
+    .filter(|path| path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.contains(".log.INFO.")))
+    .collect();
+  paths.sort();
+  for path in paths {
+    messages.extend(log_messages(&fs::read_to_string(path)?));
+  }
+  Ok(messages)
+}
+
+#[cfg(feature = "librime")]
+mod ffi {
+  use std::os::raw::{c_char, c_int};
+
+  /// `RimeTraits` of `rime_api.h`.
+  #[repr(C)]
+  pub struct RimeTraits {
+    pub data_size: c_int,
+    pub shared_data_dir: *const c_char,
+    pub user_data_dir: *const c_char,
+    pub distribution_name: *const c_char,
+    pub distribution_code_name: *const c_char,
+    pub distribution_version: *const c_char,
+    pub app_name: *const c_char,
+    pub modules: *const *const c_char,
+    pub min_log_level: c_int,
+    pub log_dir: *const c_char,
+    pub prebuilt_data_dir: *const c_char,
+    pub staging_dir: *const c_char,
+  }
+
+  #[link(name = "rime")]
+  extern "C" {
+    pub fn RimeSetup(traits: *mut RimeTraits);
+    pub fn RimeDeployerInitialize(traits: *mut RimeTraits);
+    pub fn RimeDeploySchema(schema_file: *const c_char) -> c_int;
+    pub fn RimeFinalize();
+  }
+}
+
+/// Compiles `<schema>.schema.yaml` and its dicts into the workspace's staging directory.
+///
+/// librime only sets up once per process, so this is meant to be called once.
+#[cfg(feature = "librime")]
+pub fn deploy(workspace: &Workspace, schema: &str) -> io::Result<Deployment> {
+  use std::ffi::CString;
+  use std::ptr;
+
+  let c_path = |path: &Path| CString::new(path.to_string_lossy().into_owned())
+    .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e));
+  fs::create_dir_all(&workspace.staging_dir)?;
+  if workspace.log_dir.exists() {
+    fs::remove_dir_all(&workspace.log_dir)?;
+  }
+  fs::create_dir_all(&workspace.log_dir)?;
+
+  let user_dir = c_path(&workspace.user_dir)?;
+  let staging_dir = c_path(&workspace.staging_dir)?;
+  let log_dir = c_path(&workspace.log_dir)?;
+  let schema_file = c_path(&workspace.user_dir.join(format!("{schema}.schema.yaml")))?;
+  let app_name = c"rime.smart-dict";
+  let mut traits = ffi::RimeTraits {
+    // RIME_STRUCT_INIT: the size of the fields after `data_size`
+    data_size: (std::mem::size_of::<ffi::RimeTraits>() - std::mem::size_of::<std::os::raw::c_int>()) as _,
+    shared_data_dir: user_dir.as_ptr(),
+    user_data_dir: user_dir.as_ptr(),
+    distribution_name: ptr::null(),
+    distribution_code_name: ptr::null(),
+    distribution_version: ptr::null(),
+    app_name: app_name.as_ptr(),
+    modules: ptr::null(),
+    min_log_level: 0,
+    log_dir: log_dir.as_ptr(),
+    prebuilt_data_dir: user_dir.as_ptr(),
+    staging_dir: staging_dir.as_ptr(),
+  };
+  // SAFETY: the strings outlive the calls, and librime copies what it keeps
+  let ok = unsafe {
+    ffi::RimeSetup(&mut traits);
+    ffi::RimeDeployerInitialize(&mut traits);
+    let ok = ffi::RimeDeploySchema(schema_file.as_ptr()) != 0;
+    ffi::RimeFinalize();
+    ok
+  };
+  Ok(Deployment { ok, messages: read_logs(&workspace.log_dir)? })
+}
+
+#[cfg(not(feature = "librime"))]
+pub fn deploy(_workspace: &Workspace, _schema: &str) -> io::Result<Deployment> {
+  Err(io::Error::new(io::ErrorKind::Unsupported, "smart-dict was built without the librime feature"))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_log_messages() {
+    let log = "Log file created at: 2024/01/01 12:00:00\n\
+               I20240101 12:00:00.000000  1234 deployer.cc:10] deploying\n\
+               W20240101 12:00:00.000100  1234 dict_compiler.cc:90] duplicate entry 我\n\
+               E20240101 12:00:00.000200  1234 entry_collector.cc:120] invalid line 我们 wi x\n";
+    assert_eq!(
+      vec!["dict_compiler.cc:90] duplicate entry 我", "entry_collector.cc:120] invalid line 我们 wi x"],
+      log_messages(log)
+    );
+  }
+}
@@ -0,0 +1,69 @@
+//! File IO over whichever async runtime is enabled: `async-std` (default) or `tokio`.
+//! Everything else only relies on the runtime-agnostic `futures` IO traits.
+
+use std::future::Future;
+use std::io;
+use std::path::Path;
+use futures::future::Either;
+use futures::io::Cursor;
+use futures::{AsyncBufRead, AsyncWrite};
+use crate::archive;
+
+/// Opens a file, or reads it at once into memory if it's in an archive, see `archive`.
+pub async fn open(path: impl AsRef<Path>) -> io::Result<impl AsyncBufRead + Unpin> {
+  Ok(open_with_len(path).await?.0)
+}
+
+#[cfg(feature = "async-std")]
+pub async fn create(path: impl AsRef<Path>) -> io::Result<impl AsyncWrite + Unpin> {
+  let file = async_std::fs::File::create(path.as_ref()).await?;
+  Ok(async_std::io::BufWriter::new(file))
+}
+
+#[cfg(all(feature = "tokio", not(feature = "async-std")))]
+pub async fn create(path: impl AsRef<Path>) -> io::Result<impl AsyncWrite + Unpin> {
+  use tokio_util::compat::TokioAsyncWriteCompatExt;
+  let file = tokio::fs::File::create(path.as_ref()).await?;
+  Ok(futures::io::BufWriter::new(file.compat_write()))
+}
+
+/// Opens a file for reading along with its length, taken from the open handle,
+/// or the decompressed length of an archived file.
+#[cfg(feature = "async-std")]
+pub async fn open_with_len(path: impl AsRef<Path>) -> io::Result<(impl AsyncBufRead + Unpin, u64)> {
+  if archive::is_archived(path.as_ref()) {
+    let bytes = archive::read(path.as_ref())?;
+    let len = bytes.len() as u64;
+    return Ok((Either::Left(Cursor::new(bytes)), len));
+  }
+  let file = async_std::fs::File::open(path.as_ref()).await?;
+  let len = file.metadata().await?.len();
+  Ok((Either::Right(async_std::io::BufReader::new(file)), len))
+}
+
+#[cfg(all(feature = "tokio", not(feature = "async-std")))]
+pub async fn open_with_len(path: impl AsRef<Path>) -> io::Result<(impl AsyncBufRead + Unpin, u64)> {
+  use tokio_util::compat::TokioAsyncReadCompatExt;
+  if archive::is_archived(path.as_ref()) {
+    let bytes = archive::read(path.as_ref())?;
+    let len = bytes.len() as u64;
+    return Ok((Either::Left(Cursor::new(bytes)), len));
+  }
+  let file = tokio::fs::File::open(path.as_ref()).await?;
+  let len = file.metadata().await?.len();
+  Ok((Either::Right(futures::io::BufReader::new(file.compat())), len))
+}
+
+#[cfg(feature = "async-std")]
+pub fn block_on<T>(future: impl Future<Output=T>) -> T {
+  async_std::task::block_on(future)
+}
+
+#[cfg(all(feature = "tokio", not(feature = "async-std")))]
+pub fn block_on<T>(future: impl Future<Output=T>) -> T {
+  tokio::runtime::Builder::new_current_thread()
+    .enable_all()
+    .build()
+    .expect("can't start the tokio runtime")
+    .block_on(future)
+}
@@ -0,0 +1,254 @@
+//! Estimating the keystrokes per character of a corpus from a sample of its lines, to iterate
+//! quickly on huge corpora: encoding is what takes time, reading the whole file is cheap.
+//!
+//! Lines are stratified by length, since short chat lines and long paragraphs encode differently,
+//! and sampled in proportion to their stratum. The estimate is the combined ratio estimator, with
+//! a 95% confidence interval from its linearized variance.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+use crate::corpus::{for_each_line, Analyzer, CorpusStats};
+use crate::encoding::Encoding;
+use crate::rev_dict::RevDict;
+
+/// Upper bounds in characters of the strata but the last.
+const STRATA: [usize; 5] = [4, 8, 16, 32, 64];
+/// The normal quantile of a two-sided 95% interval.
+const Z95: f64 = 1.96;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SampleSize {
+  /// Each line with this probability, `1%`.
+  Fraction(f64),
+  /// This many lines.
+  Lines(u64),
+}
+
+impl FromStr for SampleSize {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let invalid = || format!("a sample is a percentage like 1% or a number of lines, not {s}");
+    match s.strip_suffix('%') {
+      Some(percent) => {
+        let percent: f64 = percent.trim().parse().map_err(|_| invalid())?;
+        (percent > 0.0 && percent <= 100.0).then_some(SampleSize::Fraction(percent / 100.0)).ok_or_else(invalid)
+      }
+      None => s.trim().parse().ok().filter(|&lines| lines > 0).map(SampleSize::Lines).ok_or_else(invalid),
+    }
+  }
+}
+
+/// SplitMix64, enough to pick lines reproducibly from a seed.
+struct Rng(u64);
+
+impl Rng {
+  fn next(&mut self) -> u64 {
+    self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = self.0;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+  }
+
+  /// Uniform in [0, 1).
+  fn unit(&mut self) -> f64 {
+    (self.next() >> 11) as f64 / (1u64 << 53) as f64
+  }
+}
+
+/// The lines of a stratum in the corpus and the sums over those sampled, `x` the characters
+/// encoded and `y` the keystrokes of a line.
+#[derive(Clone, Debug, Default)]
+struct Stratum {
+  lines: u64,
+  sampled: u64,
+  x: f64,
+  y: f64,
+  xx: f64,
+  yy: f64,
+  xy: f64,
+}
+
+impl Stratum {
+  fn add(&mut self, stats: &CorpusStats) {
+    let (x, y) = (stats.chars as f64, stats.keystrokes as f64);
+    self.sampled += 1;
+    self.x += x;
+    self.y += y;
+    self.xx += x * x;
+    self.yy += y * y;
+    self.xy += x * y;
+  }
+
+  /// The weight of each line sampled, the lines it stands for.
+  fn weight(&self) -> f64 {
+    self.lines as f64 / self.sampled as f64
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Estimate {
+  pub keys_per_char: f64,
+  /// The 95% confidence interval.
+  pub low: f64,
+  pub high: f64,
+  pub lines: u64,
+  pub sampled_lines: u64,
+  /// Of the lines sampled.
+  pub stats: CorpusStats,
+  pub decode_errors: u64,
+}
+
+fn stratum(line: &str) -> usize {
+  let len = line.chars().filter(|ch| !ch.is_whitespace()).count();
+  STRATA.iter().filter(|&&bound| len > bound).count()
+}
+
+/// Calls `f` with each line of the corpus not blank, and its malformed sequences.
+fn read_lines(path: &Path, encoding: Encoding, mut f: impl FnMut(&str, usize)) -> io::Result<()> {
+  let mut reader = BufReader::new(File::open(path)?);
+  if reader.fill_buf()?.starts_with(encoding.bom()) {
+    reader.consume(encoding.bom().len());
+  }
+  for_each_line(reader, encoding, |line, _, errors| {
+    if !line.trim().is_empty() {
+      f(line, errors);
+    }
+    Ok(())
+  })
+}
+
+/// Estimates the keystrokes per character of a corpus from a sample of its lines, picked from `seed`.
+///
+/// A number of lines takes a first pass counting the lines of each stratum, to sample exactly
+/// its share of each.
+pub fn estimate(rev_dict: &RevDict, path: impl AsRef<Path>, encoding: Encoding, size: SampleSize, seed: u64) -> io::Result<Estimate> {
+  let path = path.as_ref();
+  let mut strata = vec![Stratum::default(); STRATA.len() + 1];
+  // the lines left to pick from and to pick in each stratum, for a number of lines
+  let mut quotas = None;
+  if let SampleSize::Lines(size) = size {
+    let mut counts = vec![0u64; strata.len()];
+    read_lines(path, encoding, |line, _| counts[stratum(line)] += 1)?;
+    let total: u64 = counts.iter().sum();
+    let wanted: Vec<_> = counts.iter().map(|&count| match total {
+      0 => 0,
+      // at least two lines of each stratum for its variance
+      _ => ((size as f64 * count as f64 / total as f64).round() as u64).max(2).min(count),
+    }).collect();
+    quotas = Some(counts.into_iter().zip(wanted).collect::<Vec<_>>());
+  }
+
+  let mut rng = Rng(seed);
+  let mut analyzer = Analyzer::new(rev_dict);
+  let mut decode_errors = 0;
+  read_lines(path, encoding, |line, errors| {
+    decode_errors += errors as u64;
+    let idx = stratum(line);
+    strata[idx].lines += 1;
+    let picked = match (&mut quotas, size) {
+      // Knuth's selection sampling: exactly the quota, each line as likely
+      (Some(quotas), _) => {
+        let (left, wanted) = &mut quotas[idx];
+        let picked = rng.unit() * (*left as f64) < *wanted as f64;
+        *left -= 1;
+        if picked {
+          *wanted -= 1;
+        }
+        picked
+      }
+      (None, SampleSize::Fraction(fraction)) => rng.unit() < fraction,
+      (None, SampleSize::Lines(_)) => unreachable!("lines are sampled by quota"),
+    };
+    if picked {
+      let before = analyzer.stats.clone();
+      analyzer.add_line(line);
+      let mut stats = analyzer.stats.clone();
+      stats.chars -= before.chars;
+      stats.keystrokes -= before.keystrokes;
+      strata[idx].add(&stats);
+    }
+  })?;
+
+  let sampled: Vec<_> = strata.iter().filter(|stratum| stratum.sampled > 0).collect();
+  let x: f64 = sampled.iter().map(|stratum| stratum.weight() * stratum.x).sum();
+  let y: f64 = sampled.iter().map(|stratum| stratum.weight() * stratum.y).sum();
+  if x == 0.0 {
+    return Err(io::Error::new(io::ErrorKind::InvalidInput, "no text was sampled, nothing to estimate from"));
+  }
+  let ratio = y / x;
+  // Var(R) = Σ N_h² (1 - n_h/N_h) s²_h / n_h / X², s²_h the variance of y - R x in the stratum
+  let variance: f64 = sampled
+    .iter()
+    .filter(|stratum| stratum.sampled > 1)
+    .map(|stratum| {
+      let n = stratum.sampled as f64;
+      let d = stratum.y - ratio * stratum.x;
+      let dd = stratum.yy - 2.0 * ratio * stratum.xy + ratio * ratio * stratum.xx;
+      let s2 = ((dd - d * d / n) / (n - 1.0)).max(0.0);
+      let lines = stratum.lines as f64;
+      lines * lines * (1.0 - n / lines) * s2 / n
+    })
+    .sum::<f64>() / (x * x);
+  let margin = Z95 * variance.sqrt();
+  Ok(Estimate {
+    keys_per_char: ratio,
+    low: ratio - margin,
+    high: ratio + margin,
+    lines: strata.iter().map(|stratum| stratum.lines).sum(),
+    sampled_lines: strata.iter().map(|stratum| stratum.sampled).sum(),
+    stats: analyzer.stats,
+    decode_errors,
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::fs;
+  use crate::corpus;
+  use crate::trie::Trie;
+
+  #[test]
+  fn test_estimate() {
+    assert_eq!(Ok(SampleSize::Fraction(0.015)), "1.5%".parse());
+    assert_eq!(Ok(SampleSize::Lines(300)), "300".parse());
+    assert!("0%".parse::<SampleSize>().is_err());
+
+    let mut trie = Trie::new();
+    trie.insert("w".to_string(), "我".to_string());
+    trie.insert("wi".to_string(), "我们".to_string());
+    trie.insert("f".to_string(), "非".to_string());
+    trie.insert("cxy".to_string(), "常".to_string());
+    trie.insert("fio".to_string(), "非常".to_string());
+    let rev_dict = trie.rev_dict();
+    let lines: Vec<_> = (0..2000)
+      .map(|idx| match idx % 4 {
+        0 => "我们".to_string(),
+        1 => "非常非常我们非".to_string(),
+        2 => "常".repeat(20),
+        _ => "我们非常".repeat(10 + idx % 7),
+      })
+      .collect();
+    let tmp = tempfile::tempdir().unwrap();
+    let path = tmp.path().join("sample.txt");
+    fs::write(&path, lines.join("\n")).unwrap();
+    let exact = corpus::analyze(&rev_dict, &lines).keys_per_char();
+
+    let estimate = estimate(&rev_dict, &path, Encoding::Utf8, SampleSize::Lines(200), 7).unwrap();
+    assert_eq!(2000, estimate.lines);
+    assert!((199..=203).contains(&estimate.sampled_lines), "{}", estimate.sampled_lines);
+    assert!(estimate.low < estimate.keys_per_char && estimate.keys_per_char < estimate.high);
+    assert!((estimate.keys_per_char - exact).abs() < 0.05, "{} vs {exact}", estimate.keys_per_char);
+    // everything sampled is exact, without uncertainty
+    let full = super::estimate(&rev_dict, &path, Encoding::Utf8, SampleSize::Fraction(1.0), 7).unwrap();
+    assert!((full.keys_per_char - exact).abs() < 1e-9 && full.high - full.low < 1e-9);
+    // nothing to sample isn't an estimate of 0/0 keys per char
+    fs::write(&path, "\n \n").unwrap();
+    assert!(super::estimate(&rev_dict, &path, Encoding::Utf8, SampleSize::Lines(200), 7).is_err());
+  }
+}
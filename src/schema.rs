@@ -0,0 +1,449 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use crate::archive;
+use crate::blacklist::Blacklist;
+use crate::dedup::{Located, Source};
+use crate::dict::{self, LineParser, Separator, DICT_EXT, PLAIN_EXTS};
+use crate::normalize::Normalization;
+use crate::progress::{Progress, Stage};
+use crate::trie::{Duplicates, Entry, Trie};
+use crate::weights::Rescaling;
+use crate::yaml;
+
+/// A table of another translator than the schema's, like an English word list or symbols, with
+/// codes of its own: typed after `prefix`, and kept as they are, capitals included, whatever the
+/// normalization.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Secondary {
+  pub table: String,
+  pub prefix: String,
+}
+
+/// A mistake in the list of tables to load, which `Schema::tables` works around.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ImportIssue {
+  /// Listed more than once, in `import_tables` or also as an extra or secondary table.
+  Repeated(String),
+  /// The main dict is in its own `import_tables`.
+  SelfImport(String),
+  /// An imported table imports the main dict back, which Rime ignores like any nested import.
+  Circular(String),
+}
+
+impl Display for ImportIssue {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ImportIssue::Repeated(table) => write!(f, "{table} is listed more than once, loaded once"),
+      ImportIssue::SelfImport(table) => write!(f, "{table} imports itself, loaded once"),
+      ImportIssue::Circular(table) => write!(f, "{table} imports the main dict back, Rime ignores imports of imported tables"),
+    }
+  }
+}
+
+/// The main dict, then the tables after it in order, each once.
+fn plan_tables(main: String, rest: impl IntoIterator<Item=String>) -> (Vec<String>, Vec<ImportIssue>) {
+  let mut issues = vec![];
+  let mut tables = vec![main];
+  for table in rest {
+    if table == tables[0] {
+      issues.push(ImportIssue::SelfImport(table));
+    } else if tables.contains(&table) {
+      if !issues.contains(&ImportIssue::Repeated(table.clone())) {
+        issues.push(ImportIssue::Repeated(table));
+      }
+    } else {
+      tables.push(table);
+    }
+  }
+  (tables, issues)
+}
+
+/// A Rime schema installed in a user directory, e.g. `xkjd6` in `%APPDATA%\Rime`.
+#[derive(Clone, Debug)]
+pub struct Schema {
+  pub dir: PathBuf,
+  pub name: String,
+  /// Tables read after the imported ones although Rime doesn't know them, e.g. plain phrase lists.
+  pub extra_tables: Vec<String>,
+  /// Read after the extra tables, see `Secondary`.
+  pub secondary_tables: Vec<Secondary>,
+  pub normalization: Normalization,
+  /// What loading a word already on its code does, e.g. when two tables share entries.
+  pub duplicates: Duplicates,
+  /// Words left out when loading the tables.
+  pub blacklist: Blacklist,
+  /// The separators of the tables not detected from their lines, see `LineParser`.
+  pub separators: BTreeMap<String, Separator>,
+  /// Brings the weights of each table to one scale as it's loaded.
+  pub rescaling: Option<Rescaling>,
+  main_dict: OnceLock<String>,
+}
+
+impl Schema {
+  pub fn new(dir: impl Into<PathBuf>, name: &str) -> Self {
+    Self {
+      dir: dir.into(),
+      name: name.to_string(),
+      extra_tables: vec![],
+      secondary_tables: vec![],
+      normalization: Default::default(),
+      duplicates: Default::default(),
+      blacklist: Default::default(),
+      separators: BTreeMap::new(),
+      rescaling: None,
+      main_dict: OnceLock::new(),
+    }
+  }
+
+  /// The dict Rime compiles for the schema, found once, see `find_main_dict`.
+  pub fn main_dict(&self) -> String {
+    self.main_dict.get_or_init(|| self.find_main_dict()).clone()
+  }
+
+  /// The schema's `translator/dictionary`, patched by `<schema>.custom.yaml` if it is, else the
+  /// `<schema>*.dict.yaml` importing tables or with the shortest name, else `<schema>.extended`.
+  fn find_main_dict(&self) -> String {
+    let read = |file: String| archive::read_to_string(self.dir.join(file)).ok().map(|text| yaml::parse(&text));
+    let custom = read(format!("{}.custom.yaml", self.name))
+      .and_then(|doc| doc.path("patch")?.get("translator/dictionary")?.as_str().map(String::from));
+    let declared = custom.or_else(|| read(format!("{}.schema.yaml", self.name))
+      .and_then(|doc| doc.path("translator/dictionary")?.as_str().map(String::from)));
+    if let Some(dict) = declared.filter(|dict| archive::exists(&self.dict_path(dict))) {
+      return dict;
+    }
+
+    let legacy = format!("{}.extended", self.name);
+    if archive::exists(&self.dict_path(&legacy)) {
+      return legacy;
+    }
+    let suffix = format!(".{DICT_EXT}");
+    let mut dicts: Vec<_> = fs::read_dir(&self.dir)
+      .into_iter()
+      .flatten()
+      .filter_map(|entry| entry.ok()?.file_name().into_string().ok()?.strip_suffix(&suffix).map(String::from))
+      .filter(|dict| dict == &self.name || dict.starts_with(&format!("{}.", self.name)))
+      .collect();
+    dicts.sort_by_cached_key(|dict| {
+      let imports = dict::read_header(self.dict_path(dict)).is_ok_and(|header| !dict::import_tables(&header).is_empty());
+      (!imports, dict.len(), dict.clone())
+    });
+    dicts.into_iter().next().unwrap_or(legacy)
+  }
+
+  /// The file of a table: `<name>.dict.yaml`, else a plain `<name>.tsv` or `<name>.txt`,
+  /// else `name` itself when it's a file name, each possibly gzipped as `<file>.gz`.
+  /// `dir` can be a `.zip` bundle, see `archive`.
+  pub fn dict_path(&self, dict_name: &str) -> PathBuf {
+    let yaml = self.dir.join(format!("{dict_name}.{DICT_EXT}"));
+    [DICT_EXT].iter()
+      .chain(PLAIN_EXTS)
+      .map(|ext| self.dir.join(format!("{dict_name}.{ext}")))
+      .chain([self.dir.join(dict_name)])
+      .flat_map(|path| {
+        let mut gz = path.clone().into_os_string();
+        gz.push(".gz");
+        [path, gz.into()]
+      })
+      .find(|path| archive::exists(path))
+      .unwrap_or(yaml)
+  }
+
+  /// Names of the tables imported by the main dict, e.g. `xkjd6.cizu`.
+  pub fn import_tables(&self) -> io::Result<Vec<String>> {
+    let header = dict::read_header(self.dict_path(&self.main_dict()))?;
+    Ok(dict::import_tables(&header))
+  }
+
+  /// The main dict followed by its import tables and the extra ones, each once.
+  pub fn tables(&self) -> io::Result<Vec<String>> {
+    Ok(self.plan_tables(self.import_tables()?).0)
+  }
+
+  /// The tables in the order they're loaded, see `tables`, and the mistakes in their lists.
+  pub fn plan_tables(&self, import_tables: Vec<String>) -> (Vec<String>, Vec<ImportIssue>) {
+    let rest = import_tables
+      .into_iter()
+      .chain(self.extra_tables.iter().cloned())
+      .chain(self.secondary_tables.iter().map(|secondary| secondary.table.clone()));
+    plan_tables(self.main_dict(), rest)
+  }
+
+  /// The mistakes in the lists of tables, including imported tables importing the main dict back.
+  pub fn import_issues(&self) -> io::Result<Vec<ImportIssue>> {
+    let main = self.main_dict();
+    let (tables, mut issues) = self.plan_tables(self.import_tables()?);
+    for table in &tables[1..] {
+      // a missing or broken table is reported when it's loaded
+      let Ok(header) = dict::read_header(self.dict_path(table)) else { continue };
+      if dict::import_tables(&header).contains(&main) {
+        issues.push(ImportIssue::Circular(table.clone()));
+      }
+    }
+    Ok(issues)
+  }
+
+  /// Parses the lines of a table with its separator if given one, else detecting it.
+  pub fn line_parser(&self, table: &str) -> LineParser {
+    match self.separators.get(table) {
+      Some(separator) => LineParser::with_separator(separator.clone()),
+      None => LineParser::new(),
+    }
+  }
+
+  /// The separator of a table, given or that of its first entry, for writing lines like its own.
+  pub fn separator(&self, table: &str) -> io::Result<Separator> {
+    match self.separators.get(table) {
+      Some(separator) => Ok(separator.clone()),
+      None => dict::detect_separator(self.dict_path(table)),
+    }
+  }
+
+  /// The entry as loaded from the table: normalized, or with the prefix of a secondary table.
+  pub fn normalize(&self, table: &str, entry: Entry) -> Entry {
+    match self.secondary_tables.iter().find(|secondary| secondary.table == table) {
+      Some(secondary) => {
+        let normalization = Normalization { lowercase: false, ..self.normalization };
+        let Entry { word, code } = normalization.entry(entry);
+        Entry { word, code: format!("{}{code}", secondary.prefix) }
+      }
+      None => self.normalization.entry(entry),
+    }
+  }
+
+  pub fn load_trie(&self) -> io::Result<Box<Trie>> {
+    self.load_trie_with_progress(|_| {})
+  }
+
+  /// Like [`Self::load_trie`], reporting the bytes of the tables loaded after each.
+  pub fn load_trie_with_progress(&self, mut progress: impl FnMut(Progress)) -> io::Result<Box<Trie>> {
+    let mut trie = Box::new(Trie::new());
+    let tables = self.tables()?;
+    let mut loaded = Loaded::new(self, &tables);
+    for table in &tables {
+      self.load_table(&mut trie, table)?;
+      progress(loaded.next());
+    }
+    Ok(trie)
+  }
+
+  /// Loads a table into the trie, normalized and without the blacklisted words.
+  pub fn load_table(&self, trie: &mut Trie, table: &str) -> io::Result<()> {
+    self.insert_entries(trie, table, dict::read_weighted_entries_with(self.dict_path(table), self.line_parser(table))?);
+    Ok(())
+  }
+
+  /// Like [`Self::load_trie`], the tables in `patched` read from the text given there instead of
+  /// their files, e.g. to see what a patch would do without applying it.
+  pub fn load_trie_patched(&self, patched: &HashMap<String, String>) -> io::Result<Box<Trie>> {
+    let mut trie = Box::new(Trie::new());
+    for table in self.tables()? {
+      match patched.get(&table) {
+        Some(text) => {
+          let entries = dict::read_weighted_entries_from(text.as_bytes(), self.line_parser(&table))?;
+          self.insert_entries(&mut trie, &table, entries);
+        }
+        None => self.load_table(&mut trie, &table)?,
+      }
+    }
+    Ok(trie)
+  }
+
+  /// Loads a dict from outside the schema, like a pack to compare, the way its own tables are:
+  /// normalized and without the blacklisted words.
+  pub fn load_pack(&self, trie: &mut Trie, path: impl AsRef<Path>) -> io::Result<()> {
+    self.insert_entries(trie, "", dict::read_weighted_entries(path)?);
+    Ok(())
+  }
+
+  fn insert_entries(&self, trie: &mut Trie, table: &str, mut entries: Vec<(Entry, u64)>) {
+    if let Some(rescaling) = self.rescaling {
+      let weights: Vec<_> = entries.iter().map(|(_, weight)| *weight).collect();
+      for ((_, weight), rescaled) in entries.iter_mut().zip(rescaling.apply(&weights)) {
+        *weight = rescaled;
+      }
+    }
+    for (entry, weight) in entries {
+      let Entry { code, word } = self.normalize(table, entry);
+      if !self.blacklist.contains(&word) {
+        trie.insert_with(code, word, weight, self.duplicates);
+      }
+    }
+  }
+
+  /// Entries of all tables, each with the table and line it was read from.
+  pub fn located_entries(&self) -> io::Result<Vec<Located>> {
+    self.located_entries_patched(&HashMap::new())
+  }
+
+  /// Like [`Self::located_entries`], the tables in `patched` read from the text given there.
+  pub fn located_entries_patched(&self, patched: &HashMap<String, String>) -> io::Result<Vec<Located>> {
+    let mut entries = vec![];
+    for table in self.tables()? {
+      let annotated = match patched.get(&table) {
+        Some(text) => dict::read_annotated_entries_from(text.as_bytes(), self.line_parser(&table))?,
+        None => dict::read_annotated_entries_with(self.dict_path(&table), self.line_parser(&table))?,
+      };
+      for (line, entry, annotation) in annotated {
+        let entry = self.normalize(&table, entry);
+        entries.push(Located { entry, source: Source { table: table.clone(), line }, annotation });
+      }
+    }
+    Ok(entries)
+  }
+
+  #[cfg(any(feature = "async-std", feature = "tokio"))]
+  pub async fn import_tables_async(&self) -> io::Result<Vec<String>> {
+    let header = dict::read_header_async(crate::rt::open(self.dict_path(&self.main_dict())).await?).await?;
+    Ok(dict::import_tables(&header))
+  }
+
+  #[cfg(any(feature = "async-std", feature = "tokio"))]
+  pub async fn load_trie_async(&self) -> io::Result<Box<Trie>> {
+    self.load_trie_async_with_progress(|_| {}).await
+  }
+
+  /// Like [`Self::load_trie_async`], reporting the bytes of the tables loaded after each.
+  #[cfg(any(feature = "async-std", feature = "tokio"))]
+  pub async fn load_trie_async_with_progress(&self, mut progress: impl FnMut(Progress)) -> io::Result<Box<Trie>> {
+    let mut trie = Box::new(Trie::new());
+    let (tables, _) = self.plan_tables(self.import_tables_async().await?);
+    let mut loaded = Loaded::new(self, &tables);
+    for table in &tables {
+      self.load_table_async(&mut trie, table).await?;
+      progress(loaded.next());
+    }
+    Ok(trie)
+  }
+
+  #[cfg(any(feature = "async-std", feature = "tokio"))]
+  pub async fn load_table_async(&self, trie: &mut Trie, table: &str) -> io::Result<()> {
+    let reader = crate::rt::open(self.dict_path(table)).await?;
+    let entries = dict::read_weighted_entries_async_with(reader, self.line_parser(table)).await?;
+    self.insert_entries(trie, table, entries);
+    Ok(())
+  }
+}
+
+/// The sizes of the tables loaded so far, the progress of a [`Stage::Load`].
+struct Loaded {
+  sizes: Vec<u64>,
+  done: usize,
+}
+
+impl Loaded {
+  fn new(schema: &Schema, tables: &[String]) -> Self {
+    let sizes = tables.iter().map(|table| fs::metadata(schema.dict_path(table)).map_or(0, |meta| meta.len())).collect();
+    Self { sizes, done: 0 }
+  }
+
+  fn next(&mut self) -> Progress {
+    self.done += 1;
+    Progress::new(Stage::Load, self.sizes[..self.done].iter().sum(), self.sizes.iter().sum())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::collections::BTreeSet;
+  use super::*;
+
+  #[test]
+  fn test_main_dict() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let write = |file: &str, text: &str| fs::write(dir.join(file), text).unwrap();
+    write("xkjd6.dict.yaml", "---\nname: xkjd6\n...\n");
+    write("xkjd6.main.dict.yaml", "---\nname: xkjd6.main\nimport_tables:\n  - xkjd6.dict\n...\n");
+    write("xkjd6x.dict.yaml", "");
+    assert_eq!("xkjd6.main", Schema::new(dir, "xkjd6").main_dict());
+
+    write("xkjd6.schema.yaml", "translator:\n  dictionary: xkjd6\n");
+    assert_eq!("xkjd6", Schema::new(dir, "xkjd6").main_dict());
+    write("xkjd6.custom.yaml", "patch:\n  \"translator/dictionary\": xkjd6.main\n");
+    assert_eq!("xkjd6.main", Schema::new(dir, "xkjd6").main_dict());
+    assert_eq!("xkjd6x", Schema::new(dir, "xkjd6x").main_dict());
+    assert_eq!("other.extended", Schema::new(dir, "other").main_dict());
+  }
+
+  #[test]
+  fn test_load_progress() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let main = "---\nname: xkjd6.extended\nimport_tables:\n  - xkjd6.cizu\n...\n我\tw\n";
+    fs::write(dir.join("xkjd6.extended.dict.yaml"), main).unwrap();
+    fs::write(dir.join("xkjd6.cizu.dict.yaml"), "我们\twi\n").unwrap();
+    let mut reported = vec![];
+    let trie = Schema::new(dir, "xkjd6").load_trie_with_progress(|progress| reported.push(progress)).unwrap();
+    assert_eq!(2, trie.entries().count());
+    let total = (main.len() + "我们\twi\n".len()) as u64;
+    assert_eq!(vec![Progress::new(Stage::Load, main.len() as u64, total), Progress::new(Stage::Load, total, total)], reported);
+
+    let mut indexed = vec![];
+    trie.rev_dict().build_with_progress(|progress| indexed.push(progress));
+    assert!(indexed.last().is_some_and(Progress::is_done));
+  }
+
+  #[test]
+  fn test_import_issues() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    let main = "---\nname: xkjd6.extended\nimport_tables:\n  - xkjd6.cizu\n  - xkjd6.extended\n  - xkjd6.cizu\n  - xkjd6.user\n...\n我\tw\n";
+    fs::write(dir.join("xkjd6.extended.dict.yaml"), main).unwrap();
+    fs::write(dir.join("xkjd6.cizu.dict.yaml"), "我们\twi\n").unwrap();
+    fs::write(dir.join("xkjd6.user.dict.yaml"), "---\nimport_tables:\n  - xkjd6.extended\n...\n你\tn\n").unwrap();
+    let mut schema = Schema::new(dir, "xkjd6");
+    schema.extra_tables = vec!["xkjd6.user".to_string()];
+
+    assert_eq!(vec!["xkjd6.extended", "xkjd6.cizu", "xkjd6.user"], schema.tables().unwrap());
+    assert_eq!(vec![
+      ImportIssue::SelfImport("xkjd6.extended".to_string()),
+      ImportIssue::Repeated("xkjd6.cizu".to_string()),
+      ImportIssue::Repeated("xkjd6.user".to_string()),
+      ImportIssue::Circular("xkjd6.user".to_string()),
+    ], schema.import_issues().unwrap());
+    assert_eq!(3, schema.load_trie().unwrap().entries().count());
+    assert_eq!(3, schema.located_entries().unwrap().len());
+
+    let patched = HashMap::from([("xkjd6.cizu".to_string(), "我们\twi\n你们\tni\n".to_string())]);
+    assert_eq!(4, schema.load_trie_patched(&patched).unwrap().entries().count());
+    assert_eq!(4, schema.located_entries_patched(&patched).unwrap().len());
+    assert_eq!("我们\twi\n", fs::read_to_string(dir.join("xkjd6.cizu.dict.yaml")).unwrap());
+  }
+
+  #[test]
+  fn test_load_pack() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    fs::write(dir.join("pack.dict.yaml"), "---\nname: pack\n...\n我们\tWI\n他们\ttmi\n").unwrap();
+    let mut schema = Schema::new(dir, "xkjd6");
+    schema.normalization.lowercase = true;
+    schema.blacklist.add("他们").unwrap();
+    let mut trie = Trie::new();
+    schema.load_pack(&mut trie, dir.join("pack.dict.yaml")).unwrap();
+    assert_eq!(vec![("wi".to_string(), "我们".to_string())],
+               trie.entries().map(|entry| (entry.code, entry.word)).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn test_secondary() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path();
+    fs::write(dir.join("xkjd6.extended.dict.yaml"), "---\nname: xkjd6.extended\n...\n我\tW\n").unwrap();
+    fs::write(dir.join("easy_en.dict.yaml"), "---\nname: easy_en\n...\nhello\thello\niPhone\tiPhone\n").unwrap();
+    let mut schema = Schema::new(dir, "xkjd6");
+    schema.normalization.lowercase = true;
+    schema.secondary_tables = vec![Secondary { table: "easy_en".to_string(), prefix: "`".to_string() }];
+    assert_eq!(vec!["xkjd6.extended", "easy_en"], schema.tables().unwrap());
+    let trie = schema.load_trie().unwrap();
+    assert_eq!(vec!["`hello", "`iPhone", "w"], trie.entries().map(|entry| entry.code).collect::<BTreeSet<_>>().into_iter().collect::<Vec<_>>());
+
+    let rev_dict = trie.rev_dict();
+    assert_eq!(Ok(vec!["`hello".to_string()]), rev_dict.shortest("Hello"));
+    let stats = crate::corpus::analyze(&rev_dict, ["Hello 我"]);
+    assert_eq!((6, 0), (stats.chars, stats.unencoded));
+  }
+}
@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+use std::io;
+use crate::trie::Entry;
+use crate::yaml;
+
+/// A code shape reserved for words of some lengths, e.g. `SY` (声韵) for two-character words.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Shape {
+  /// One position per key: an uppercase class name, or the key itself.
+  pub pattern: String,
+  /// Lengths in characters of the words allowed on the shape.
+  pub lengths: Vec<usize>,
+}
+
+/// Short-code conventions of a schema like xkjd6's, read from `smart-dict.yaml`:
+///
+/// ```yaml
+/// conventions:
+///   classes:
+///     S: bcdfghjklmnpqrstwxyz
+///     Y: aeiou
+///   shapes:
+///     SY: 2
+///     SYY: 1,3
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Conventions {
+  pub classes: BTreeMap<char, String>,
+  pub shapes: Vec<Shape>,
+}
+
+fn invalid(msg: String) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl Conventions {
+  pub fn parse(value: &yaml::Value) -> io::Result<Self> {
+    let mut conventions = Self::default();
+    if let Some(classes) = value.get("classes") {
+      for (name, keys) in classes.entries() {
+        let mut chars = name.chars();
+        let class = chars.next()
+          .filter(|ch| ch.is_ascii_uppercase() && chars.next().is_none())
+          .ok_or_else(|| invalid(format!("class name {name} should be a single uppercase letter")))?;
+        let keys = keys.as_str().ok_or_else(|| invalid(format!("keys of class {name} should be a string")))?;
+        conventions.classes.insert(class, keys.to_string());
+      }
+    }
+    if let Some(shapes) = value.get("shapes") {
+      for (pattern, lengths) in shapes.entries() {
+        let lengths = lengths
+          .as_str()
+          .and_then(|lengths| lengths.split(',').map(|len| len.trim().parse().ok()).collect::<Option<Vec<_>>>())
+          .ok_or_else(|| invalid(format!("shape {pattern} should list word lengths like 1,3")))?;
+        if let Some(class) = pattern.chars().find(|ch| ch.is_ascii_uppercase() && !conventions.classes.contains_key(ch)) {
+          return Err(invalid(format!("shape {pattern} uses the undefined class {class}")));
+        }
+        conventions.shapes.push(Shape { pattern: pattern.clone(), lengths });
+      }
+    }
+    Ok(conventions)
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.shapes.is_empty()
+  }
+
+  pub fn matches(&self, shape: &Shape, code: &str) -> bool {
+    code.chars().count() == shape.pattern.chars().count()
+      && shape.pattern.chars().zip(code.chars()).all(|(expected, key)| {
+        match self.classes.get(&expected) {
+          Some(keys) => keys.contains(key),
+          None => expected == key,
+        }
+      })
+  }
+
+  /// The first reserved shape the entry's code has although its word is of another length.
+  pub fn violated(&self, entry: &Entry) -> Option<&Shape> {
+    let len = entry.word.chars().count();
+    self.shapes
+      .iter()
+      .find(|shape| self.matches(shape, &entry.code) && !shape.lengths.contains(&len))
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_violated() {
+    let doc = yaml::parse("conventions:\n  classes:\n    S: bdgw\n    Y: aeiou\n  shapes:\n    SY: 2\n    SYY: 1,3\n");
+    let conventions = Conventions::parse(doc.get("conventions").unwrap()).unwrap();
+    let entry = |code: &str, word: &str| Entry { code: code.to_string(), word: word.to_string() };
+
+    assert_eq!(None, conventions.violated(&entry("wi", "我们")));
+    assert_eq!("SY", conventions.violated(&entry("wo", "我")).unwrap().pattern);
+    assert_eq!(None, conventions.violated(&entry("woi", "我")));
+    assert_eq!("SYY", conventions.violated(&entry("woi", "我们")).unwrap().pattern);
+    assert_eq!(None, conventions.violated(&entry("wx", "我")));
+
+    let undefined = yaml::parse("shapes:\n  SX: 2\n");
+    assert!(Conventions::parse(&undefined).is_err());
+  }
+}
@@ -0,0 +1,126 @@
+//! Reports as tables, for spreadsheets: a workbook of one sheet per report with the `xlsx` feature.
+
+use std::io;
+use crate::collision::Collision;
+use crate::corpus::CorpusStats;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cell {
+  Text(String),
+  Number(f64),
+}
+
+impl From<&str> for Cell {
+  fn from(text: &str) -> Self {
+    Self::Text(text.to_string())
+  }
+}
+
+impl From<String> for Cell {
+  fn from(text: String) -> Self {
+    Self::Text(text)
+  }
+}
+
+impl From<f64> for Cell {
+  fn from(number: f64) -> Self {
+    Self::Number(number)
+  }
+}
+
+impl From<usize> for Cell {
+  fn from(number: usize) -> Self {
+    Self::Number(number as f64)
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sheet {
+  pub name: String,
+  pub header: Vec<String>,
+  pub rows: Vec<Vec<Cell>>,
+}
+
+impl Sheet {
+  pub fn new(name: &str, header: &[&str]) -> Self {
+    Self { name: name.to_string(), header: header.iter().map(|s| s.to_string()).collect(), rows: vec![] }
+  }
+
+  pub fn row(&mut self, cells: impl IntoIterator<Item=Cell>) {
+    self.rows.push(cells.into_iter().collect());
+  }
+}
+
+pub fn collisions_sheet(collisions: &[Collision]) -> Sheet {
+  let mut sheet = Sheet::new("collisions", &["code", "words", "count"]);
+  for collision in collisions {
+    sheet.row([collision.code.as_str().into(), collision.words.join(" ").into(), collision.words.len().into()]);
+  }
+  sheet
+}
+
+pub fn corpus_sheet(stats: &CorpusStats) -> Sheet {
+  let mut sheet = Sheet::new("corpus", &["metric", "value"]);
+  for (metric, value) in [
+    ("chars", stats.chars as f64),
+    ("keystrokes", stats.keystrokes as f64),
+    ("keys per char", stats.keys_per_char()),
+    ("auto-commit rate", stats.auto_commit_rate()),
+    ("coverage", stats.coverage()),
+  ] {
+    sheet.row([metric.into(), value.into()]);
+  }
+  sheet
+}
+
+/// The sheets in one workbook, as the bytes of an .xlsx file, the header row of each in bold.
+#[cfg(feature = "xlsx")]
+pub fn to_xlsx(sheets: &[Sheet]) -> io::Result<Vec<u8>> {
+  use rust_xlsxwriter::{Format, Workbook, XlsxError};
+
+  let error = |e: XlsxError| io::Error::other(e.to_string());
+  let mut workbook = Workbook::new();
+  let bold = Format::new().set_bold();
+  for sheet in sheets {
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name(&sheet.name).map_err(error)?;
+    for (col, title) in sheet.header.iter().enumerate() {
+      worksheet.write_string_with_format(0, col as u16, title, &bold).map_err(error)?;
+    }
+    for (row, cells) in sheet.rows.iter().enumerate() {
+      for (col, cell) in cells.iter().enumerate() {
+        let (row, col) = (row as u32 + 1, col as u16);
+        match cell {
+          Cell::Text(text) => worksheet.write_string(row, col, text),
+          Cell::Number(number) => worksheet.write_number(row, col, *number),
+        }.map_err(error)?;
+      }
+    }
+  }
+  workbook.save_to_buffer().map_err(error)
+}
+
+#[cfg(not(feature = "xlsx"))]
+pub fn to_xlsx(_sheets: &[Sheet]) -> io::Result<Vec<u8>> {
+  Err(io::Error::new(io::ErrorKind::Unsupported, "smart-dict was built without the xlsx feature"))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_sheets() {
+    let collisions = vec![Collision { code: "d".to_string(), words: vec!["的".to_string(), "地".to_string()] }];
+    let sheet = collisions_sheet(&collisions);
+    assert_eq!(vec!["code", "words", "count"], sheet.header);
+    assert_eq!(vec![vec![Cell::from("d"), Cell::from("的 地"), Cell::Number(2.0)]], sheet.rows);
+
+    let written = to_xlsx(&[sheet]);
+    if cfg!(feature = "xlsx") {
+      assert!(written.unwrap().starts_with(b"PK"));
+    } else {
+      assert_eq!(io::ErrorKind::Unsupported, written.unwrap_err().kind());
+    }
+  }
+}
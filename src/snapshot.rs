@@ -0,0 +1,115 @@
+//! Tries frozen for reading from any number of threads while the next version is prepared
+//! elsewhere: a `TrieSnapshot` is never changed once made, and a `Current` hands out the latest
+//! one without locking, the new one being swapped in whole once it's ready.
+
+use std::fmt::{Debug, Formatter};
+use std::ops::Deref;
+use std::sync::Arc;
+use arc_swap::ArcSwap;
+use crate::trie::Trie;
+
+/// A trie that can no longer change, cheap to clone and to send to other threads, see
+/// [`Trie::freeze`]. It reads like the trie it was made of.
+#[derive(Clone)]
+pub struct TrieSnapshot(Arc<Trie>);
+
+impl TrieSnapshot {
+  /// The root's children must point at it where it is, in the `Arc`.
+  pub(crate) fn new(trie: Arc<Trie>) -> Self {
+    Self(trie)
+  }
+
+  /// Whether both are the same snapshot rather than equal ones.
+  pub fn ptr_eq(&self, other: &Self) -> bool {
+    Arc::ptr_eq(&self.0, &other.0)
+  }
+
+  /// A trie of one's own to change, leaving the snapshot as it is.
+  pub fn thaw(&self) -> Box<Trie> {
+    self.0.to_boxed()
+  }
+}
+
+impl Deref for TrieSnapshot {
+  type Target = Trie;
+
+  fn deref(&self) -> &Trie {
+    &self.0
+  }
+}
+
+impl Debug for TrieSnapshot {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_tuple("TrieSnapshot").field(&self.0).finish()
+  }
+}
+
+/// The latest of a series of snapshots, e.g. the index of a server rebuilt on each reload. Readers
+/// take it without waiting for each other or for the writer, and keep what they took until they
+/// drop it, however many times it's replaced meanwhile.
+pub struct Current<T>(ArcSwap<T>);
+
+impl<T> Current<T> {
+  pub fn new(value: T) -> Self {
+    Self(ArcSwap::from_pointee(value))
+  }
+
+  pub fn load(&self) -> Arc<T> {
+    self.0.load_full()
+  }
+
+  /// Puts `value` in place of the current one, returning the latter.
+  pub fn replace(&self, value: T) -> Arc<T> {
+    self.0.swap(Arc::new(value))
+  }
+}
+
+impl<T: Debug> Debug for Current<T> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_tuple("Current").field(&*self.load()).finish()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::thread;
+  use super::*;
+
+  #[test]
+  fn test_snapshot() {
+    let build = |words: &[&str]| {
+      let mut trie = Trie::new();
+      for word in words {
+        trie.insert("wi".to_string(), word.to_string());
+        trie.insert("wiu".to_string(), format!("{word}的"));
+      }
+      trie.freeze()
+    };
+    let words = |trie: &Trie, code: &str| trie.node(code).map_or(vec![], |node| node.words().clone());
+    let first = build(&["我们"]);
+    assert_eq!(vec!["我们"], words(&first, "wi"));
+    assert_eq!(Some(first.node("wi").unwrap() as *const _), first.node("wiu").and_then(Trie::parent).map(|node| node as *const _));
+    assert!(first.ptr_eq(&first.clone()) && !first.ptr_eq(&build(&["我们"])));
+    let mut thawed = first.thaw();
+    thawed.insert("wi".to_string(), "为".to_string());
+    assert_eq!(vec!["我们"], words(&first, "wi"));
+
+    let current = Current::new(first);
+    thread::scope(|scope| {
+      for _ in 0..4 {
+        scope.spawn(|| {
+          for _ in 0..1000 {
+            // either version whole, never one halfway
+            let snapshot = current.load();
+            let found = words(&snapshot, "wi");
+            assert!(found == ["我们"] || found == ["我们", "咱们"], "{found:?}");
+            assert_eq!(found.len(), words(&snapshot, "wiu").len());
+          }
+        });
+      }
+      let old = current.replace(build(&["我们", "咱们"]));
+      assert_eq!(vec!["我们"], words(&old, "wi"));
+    });
+    assert_eq!(vec!["我们", "咱们"], words(&current.load(), "wi"));
+  }
+}
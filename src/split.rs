@@ -0,0 +1,108 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::ops::RangeInclusive;
+use regex::Regex;
+use crate::dict::LineParser;
+use crate::types::Word;
+
+/// Which entry lines go to a part.
+#[derive(Debug)]
+pub enum Predicate {
+  /// Characters in the word, `len:2` or `len:3-`.
+  Length(RangeInclusive<usize>),
+  /// The weight column, 0 when missing, `weight:100-999`.
+  Weight(RangeInclusive<u64>),
+  /// `match:<regex>` on the word.
+  Matches(Regex),
+  /// Words of an external category list.
+  Category(HashSet<Word>),
+}
+
+fn range<T: std::str::FromStr + Copy>(text: &str, max: T) -> Option<RangeInclusive<T>> {
+  match text.split_once('-') {
+    Some((start, "")) => Some(start.parse().ok()?..=max),
+    Some((start, end)) => Some(start.parse().ok()?..=end.parse().ok()?),
+    None => text.parse().ok().map(|n| n..=n),
+  }
+}
+
+impl Predicate {
+  /// Parses `len:<range>`, `weight:<range>` or `match:<regex>`;
+  /// categories are built by the caller from their list, see [`Predicate::Category`].
+  pub fn parse(text: &str) -> Result<Self, String> {
+    let invalid = || format!("invalid predicate {text:?}");
+    let (kind, arg) = text.split_once(':').ok_or_else(invalid)?;
+    match kind {
+      "len" => range(arg, usize::MAX).map(Self::Length).ok_or_else(invalid),
+      "weight" => range(arg, u64::MAX).map(Self::Weight).ok_or_else(invalid),
+      "match" => Regex::new(arg).map(Self::Matches).map_err(|e| format!("{}: {e}", invalid())),
+      _ => Err(invalid()),
+    }
+  }
+
+  pub fn matches(&self, word: &str, weight: u64) -> bool {
+    match self {
+      Self::Length(range) => range.contains(&word.chars().count()),
+      Self::Weight(range) => range.contains(&weight),
+      Self::Matches(regex) => regex.is_match(word),
+      Self::Category(words) => words.contains(word),
+    }
+  }
+}
+
+/// An output table, named `<source>.<suffix>`.
+#[derive(Debug)]
+pub struct Part {
+  pub suffix: String,
+  pub predicate: Predicate,
+}
+
+/// Distributes the entry lines to the first part they match, kept verbatim with their weights.
+/// The last group holds the lines no part matches; header and comment lines are dropped.
+pub fn split<'l>(lines: impl IntoIterator<Item=&'l str>, parts: &[Part]) -> Vec<Vec<&'l str>> {
+  let mut groups = vec![vec![]; parts.len() + 1];
+  // like `dict::read_entries`, skipping the header and taking the separator of the table
+  let mut parser = LineParser::new();
+  for line in lines {
+    let Some((entry, weight)) = parser.parse_weighted(line) else { continue };
+    let group = parts
+      .iter()
+      .position(|part| part.predicate.matches(&entry.word, weight))
+      .unwrap_or(parts.len());
+    groups[group].push(line);
+  }
+  groups
+}
+
+/// The `import_tables` a main dict needs instead of the split table.
+pub fn import_snippet<'a>(tables: impl IntoIterator<Item=&'a str>) -> String {
+  let mut snippet = "import_tables:\n".to_string();
+  for table in tables {
+    writeln!(snippet, "  - {table}").unwrap();
+  }
+  snippet
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_split() {
+    let text = "---\nname: test\nsort: by_weight\n...\n# 二字词\n我们\twi\t100\n喜欢\txa\n中华人民\tzhrm\t5\n的\td\t1000\n";
+    let parts = [
+      Part { suffix: "hot".to_string(), predicate: Predicate::parse("weight:100-").unwrap() },
+      Part { suffix: "two".to_string(), predicate: Predicate::parse("len:2").unwrap() },
+      Part { suffix: "zh".to_string(), predicate: Predicate::parse("match:^中").unwrap() },
+    ];
+    let groups = split(text.lines(), &parts);
+    assert_eq!(vec!["我们\twi\t100", "的\td\t1000"], groups[0]);
+    assert_eq!(vec!["喜欢\txa"], groups[1]);
+    assert_eq!(vec!["中华人民\tzhrm\t5"], groups[2]);
+    assert!(groups[3].is_empty());
+
+    assert_eq!("import_tables:\n  - test.hot\n", import_snippet(["test.hot"]));
+    assert!(Predicate::parse("len:x").is_err());
+    assert!(Predicate::parse("size:2").is_err());
+  }
+}
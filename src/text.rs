@@ -0,0 +1,56 @@
+//! Lengths of words as people see them rather than as bytes.
+
+/// Characters that attach to the previous one instead of starting a grapheme: combining marks,
+/// variation selectors, emoji skin tones, tag characters and the keycap mark.
+fn extends(ch: char) -> bool {
+  matches!(ch,
+    '\u{0300}'..='\u{036F}' | '\u{1AB0}'..='\u{1AFF}' | '\u{1DC0}'..='\u{1DFF}' | '\u{20D0}'..='\u{20FF}'
+    | '\u{3099}'..='\u{309A}' | '\u{FE00}'..='\u{FE0F}' | '\u{FE20}'..='\u{FE2F}'
+    | '\u{1F3FB}'..='\u{1F3FF}' | '\u{E0020}'..='\u{E007F}' | '\u{E0100}'..='\u{E01EF}')
+}
+
+fn is_regional_indicator(ch: char) -> bool {
+  matches!(ch, '\u{1F1E6}'..='\u{1F1FF}')
+}
+
+const ZWJ: char = '\u{200D}';
+
+/// Counts user-perceived characters, approximating Unicode's extended grapheme clusters well enough
+/// for dict words: a CJK character, `é` as `e` and a combining accent, `👍🏽`, `👨‍👩‍👧` and `🇨🇳` are one each.
+pub fn graphemes(text: &str) -> usize {
+  let mut count = 0;
+  let mut joined = false;
+  let mut lone_indicator = false;
+  for ch in text.chars() {
+    if extends(ch) {
+      continue;
+    }
+    if ch == ZWJ {
+      joined = true;
+      continue;
+    }
+    let pairs_flag = lone_indicator && is_regional_indicator(ch);
+    if !joined && !pairs_flag {
+      count += 1;
+    }
+    lone_indicator = is_regional_indicator(ch) && !pairs_flag;
+    joined = false;
+  }
+  count
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_graphemes() {
+    assert_eq!(2, graphemes("我们"));
+    assert_eq!(2, graphemes("e\u{301}a"));
+    assert_eq!(2, graphemes("赞👍🏽"));
+    assert_eq!(1, graphemes("👨\u{200D}👩\u{200D}👧"));
+    assert_eq!(2, graphemes("🇨🇳🇯🇵"));
+    assert_eq!(2, graphemes("🇨🇳🇯"));
+    assert_eq!(1, graphemes("1\u{FE0F}\u{20E3}"));
+  }
+}
@@ -1,15 +1,19 @@
-use std::collections::hash_map::{Keys, Values, ValuesMut};
-use std::collections::HashMap;
-use std::io::{BufRead, BufReader, Cursor};
+use std::collections::hash_map::{Values, ValuesMut};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
+use std::io::{Cursor, Write};
 use std::{io, mem};
-use std::fmt::{Debug, Formatter};
-use std::fs::File;
-use std::iter::{Chain, FlatMap};
+use std::fmt::{Debug, Display, Formatter};
 use std::ops::Index;
 use std::path::Path;
 use std::ptr::NonNull;
+use std::str::FromStr;
+use std::sync::Arc;
 use std::slice::Iter;
+use crate::dict;
+use crate::normalize::Normalization;
 use crate::rev_dict::RevDict;
+use crate::snapshot::TrieSnapshot;
 use crate::types::{Code, Word};
 
 struct CodeCursor(Cursor<Code>);
@@ -31,15 +35,6 @@ impl CodeCursor {
     &self.get_ref()[self.position()..]
   }
 
-  pub fn remaining_starts_with(&self, pat: &str) -> bool {
-    self.remaining().starts_with(pat)
-  }
-
-  pub fn get_char(&self, index: usize) -> Option<char> {
-    let pos = self.position();
-    self.get_ref().as_bytes().get(pos + index).map(|b| *b as char)
-  }
-
   pub fn remained_len(&self) -> usize {
     self.get_ref().len() - self.position()
   }
@@ -74,14 +69,35 @@ impl Index<usize> for CodeCursor {
   }
 }
 
+/// Nodes keep raw pointers to their parents, so the root must stay in place once entries are inserted.
+/// Box it when it has to be passed around.
 #[derive(Default)]
 pub struct Trie {
   code: Code,
   words: Vec<Word>,
+  /// The weight of each word, 0 unless given by the dict.
+  weights: Vec<u64>,
+  /// The highest weight in the subtree, which lets `Candidates` skip the lighter ones.
+  max_weight: u64,
   parent: Option<NonNull<Self>>,
-  links: HashMap<Code, Self>,
+  /// The children by the first byte of their codes, which siblings never share, so that a lookup
+  /// goes straight to the one child a code can continue with.
+  links: HashMap<u8, Self>,
 }
 
+// SAFETY: `parent` is only ever set to a node of the same tree, which holds its children in
+// `links`, so a trie owns every node its pointers reach and nothing outside it points into it:
+// sending it sends all it points at, like sending a tree of boxes. The pointers must be valid,
+// i.e. the root must not have moved since its children were linked, which `Box<Trie>` and
+// `freeze` see to, relinking them once the root is in place; that holds on any thread alike.
+unsafe impl Send for Trie {}
+// SAFETY: nothing is changed through `&Trie`, there's no interior mutability and `parent` only
+// hands out shared references. Every change, including those made through parent pointers in
+// `shrink_code`, starts from a `&mut` to a node, which is only reachable from a `&mut` to the root
+// (`child_mut` reborrows it), so it excludes every `&Trie` into the tree like it does for any
+// owned tree. A frozen `TrieSnapshot` gives out no `&mut` at all.
+unsafe impl Sync for Trie {}
+
 impl Trie {
   pub fn new() -> Self {
     Default::default()
@@ -91,15 +107,11 @@ impl Trie {
     self.parent.map(|p| unsafe { p.as_ref() })
   }
 
-  fn parent_mut(&self) -> Option<&mut Self> {
-    self.parent.map(|mut p| unsafe { p.as_mut() })
-  }
-
-  pub fn children(&self) -> Values<Code, Self> {
+  pub fn children(&self) -> Values<'_, u8, Self> {
     self.links.values()
   }
 
-  fn children_mut(&mut self) -> ValuesMut<Code, Self> {
+  fn children_mut(&mut self) -> ValuesMut<'_, u8, Self> {
     self.links.values_mut()
   }
 
@@ -119,24 +131,43 @@ impl Trie {
     &self.words
   }
 
-  pub fn edges(&self) -> Keys<Code, Self> {
-    self.links.keys()
+  pub fn weights(&self) -> &[u64] {
+    &self.weights
+  }
+
+  pub fn edges(&self) -> impl Iterator<Item=&Code> {
+    self.children().map(|child| &child.code)
   }
 
-  pub fn nodes(&self) -> Nodes {
+  pub fn nodes(&self) -> Nodes<'_> {
     Nodes::new(self)
   }
 
-  pub fn bubble(&self) -> Bubble {
+  /// Like `nodes`, paired with their full codes, each built from its parent's
+  /// instead of bubbling up to the root like `full_code` does.
+  pub fn walk(&self) -> Walk<'_> {
+    Walk::new(self)
+  }
+
+  pub fn bubble(&self) -> Bubble<'_> {
     Bubble::new(self)
   }
 
   pub fn child(&self, child_code: &str) -> Option<&Self> {
-    self.links.get(child_code)
+    self.links.get(child_code.as_bytes().first()?).filter(|child| child.code == child_code)
   }
 
   pub fn child_mut(&mut self, child_code: &str) -> Option<&mut Self> {
-    self.links.get_mut(child_code)
+    self.links.get_mut(child_code.as_bytes().first()?).filter(|child| child.code == child_code)
+  }
+
+  /// The child whose code `code` starts with.
+  fn child_within(&self, code: &str) -> Option<&Self> {
+    self.links.get(code.as_bytes().first()?).filter(|child| code.starts_with(&child.code[..]))
+  }
+
+  fn child_within_mut(&mut self, code: &str) -> Option<&mut Self> {
+    self.links.get_mut(code.as_bytes().first()?).filter(|child| code.starts_with(&child.code[..]))
   }
 
   fn set_half_parent_nonnull(&mut self, p_parent: NonNull<Self>) {
@@ -149,7 +180,7 @@ impl Trie {
 
   fn set_half_link(&mut self, child: Self) -> Option<Self> {
     let old_cap = self.links.capacity();
-    let ret = self.links.insert(child.code.clone(), child);
+    let ret = self.links.insert(child.code.as_bytes()[0], child);
     if self.links.capacity() > old_cap {
       for child in self.children_mut() {
         child.refresh_children();
@@ -171,8 +202,8 @@ impl Trie {
     mem::transmute(self.child_mut(&code).unwrap())
   }
 
-  fn del_half_link(&mut self, key: &Code) -> Option<Self> {
-    self.links.remove(key)
+  fn del_half_link(&mut self, code: &str) -> Option<Self> {
+    self.links.remove(code.as_bytes().first()?)
   }
 
   fn set_link(&mut self, child: Self) -> &mut Self {
@@ -183,8 +214,7 @@ impl Trie {
 
   pub fn is_ancestor_of(&self, other: &Self) -> bool {
     other.bubble()
-      .find(|&node| node as *const _ == self as *const _)
-      .is_some()
+      .any(|node| std::ptr::eq(node, self))
   }
 }
 
@@ -198,12 +228,44 @@ impl Trie {
     codes.into_iter().rev().collect()
   }
 
-  pub fn candidates(&self) -> Chain<Iter<Word>, FlatMap<Values<Code, Trie>, Iter<Word>, fn(&Trie) -> Iter<Word>>> {
-    let own_words = self.words.iter();
-    let children_words = self
-      .children()
-      .flat_map::<_, fn(&Trie) -> Iter<Word>>(|node| node.words.iter());
-    own_words.chain(children_words)
+  /// Candidates shown for the code ending at this node: its own words, followed by the words
+  /// of its descendants as far as `completion` goes, by descending weight then code length.
+  pub fn candidates(&self, completion: impl Into<Completion>) -> Candidates<'_> {
+    Candidates::new(self, completion.into())
+  }
+
+  /// Position of `word` in the candidate list of `code`, 0 for the first candidate.
+  pub fn candidate_index(&self, code: &str, word: &str, completion: impl Into<Completion>) -> Option<usize> {
+    self.node(code)?
+      .candidates(completion)
+      .position(|candidate| candidate == word)
+  }
+
+  /// The node whose full code is exactly `code`.
+  pub fn node(&self, code: &str) -> Option<&Self> {
+    let mut cursor = CodeCursor::new(code.to_string());
+    let node = self.deepest_full_code(&mut cursor);
+    if cursor.is_empty() {
+      Some(node)
+    } else {
+      None
+    }
+  }
+
+  /// The node whose candidates show after typing `keys`: the node of that code, or the one below
+  /// when `keys` stop in the middle of a compressed edge.
+  pub fn node_through(&self, keys: &str) -> Option<&Self> {
+    let mut cursor = CodeCursor::new(keys.to_string());
+    let node = self.deepest_full_code(&mut cursor);
+    if cursor.is_empty() {
+      return Some(node);
+    }
+    node.links.get(&cursor[0]).filter(|child| child.code.starts_with(cursor.remaining()))
+  }
+
+  /// Whether some code starts with `keys`.
+  fn is_code_prefix(&self, keys: &str) -> bool {
+    self.node_through(keys).is_some()
   }
 
   fn poll(&self, code: &mut CodeCursor) -> usize {
@@ -225,7 +287,8 @@ impl Trie {
   unsafe fn shrink_code(&mut self, new_len: usize) -> &mut Self {
     debug_assert!(new_len < self.code.len());
 
-    if let Some(parent) = self.parent_mut() {
+    if let Some(mut parent) = self.parent {
+      let parent = parent.as_mut();
       let mut this = parent.del_half_link(&self.code).unwrap();
 
       this.code.truncate(new_len);
@@ -240,12 +303,19 @@ impl Trie {
   }
 
   pub fn insert(&mut self, code: Code, word: Word) {
+    self.insert_weighted(code, word, 0);
+  }
+
+  pub fn insert_weighted(&mut self, code: Code, word: Word, weight: u64) {
+    self.raise_max_weight(&code, weight);
     unsafe {
       let mut code = CodeCursor::new(code);
       let (node, matched) = self.try_best_to_match_mut(&mut code);
       if code.is_empty() {
         if matched == node.code.len() {
-          node.words.push(word)
+          node.words.push(word);
+          node.weights.push(weight);
+          node.debug_validate();
         } else {
           // regard node as the new parent and construct a new child
           let child_code = node.code[matched..].to_string();
@@ -253,15 +323,19 @@ impl Trie {
           let new_node = Self {
             code: child_code,
             words: mem::replace(&mut node.words, vec![word]),
+            weights: mem::replace(&mut node.weights, vec![weight]),
+            max_weight: node.max_weight,
             links: mem::take(&mut node.links),
             parent: None,
           };
+          node.max_weight = node.max_weight.max(weight);
           let new_node = node.set_link(new_node);
 
           let p_new_node = NonNull::new_unchecked(new_node);
           for child in new_node.children_mut() {
             child.set_half_parent_nonnull(p_new_node);
           }
+          new_node.debug_validate();
         }
       } else {
         let remained_code = code.into_remained();
@@ -271,9 +345,12 @@ impl Trie {
           node.set_half_link(Self {
             code: remained_code,
             words: vec![word],
+            weights: vec![weight],
+            max_weight: weight,
             parent: Some(p_node),
             ..Default::default()
           });
+          node.debug_validate();
         } else {
           // regard node as the new parent and construct two new children
           let child_code = node.code[matched..].to_string();
@@ -281,9 +358,12 @@ impl Trie {
           let spawn_child = Self {
             code: child_code,
             words: mem::take(&mut node.words),
+            weights: mem::take(&mut node.weights),
+            max_weight: node.max_weight,
             links: mem::take(&mut node.links),
             parent: None,
           };
+          node.max_weight = node.max_weight.max(weight);
           let spawn_child = node.set_link(spawn_child);
 
           let p_spawn_child = NonNull::new_unchecked(spawn_child);
@@ -294,22 +374,103 @@ impl Trie {
           let new_child = Self {
             code: remained_code,
             words: vec![word],
+            weights: vec![weight],
+            max_weight: weight,
             parent: None,
             ..Default::default()
           };
-          node.set_link(new_child);
+          node.set_link(new_child).debug_validate();
         }
       }
     }
   }
 
+  /// Inserts like [`Trie::insert_weighted`] unless `word` is already on `code`, then it's up to `duplicates`.
+  pub fn insert_with(&mut self, code: Code, word: Word, weight: u64, duplicates: Duplicates) {
+    let existing = match duplicates {
+      Duplicates::Keep => None,
+      _ => self.node_mut(&code).and_then(|node| node.words.iter().position(|w| *w == word).map(|idx| (node, idx))),
+    };
+    let Some((node, idx)) = existing else {
+      return self.insert_weighted(code, word, weight);
+    };
+    match duplicates {
+      Duplicates::Keep | Duplicates::Ignore => {}
+      Duplicates::Replace => {
+        node.weights[idx] = weight;
+        self.refresh_max_weight(&code);
+      }
+      Duplicates::KeepHighest => {
+        node.weights[idx] = node.weights[idx].max(weight);
+        self.raise_max_weight(&code, weight);
+      }
+    }
+  }
+
+  /// Removes the words repeated on a node, keeping the first one with the highest of their weights.
+  /// Returns how many were removed.
+  pub fn dedup_words(&mut self) -> usize {
+    let mut removed = 0;
+    let mut seen: HashMap<Word, usize> = HashMap::new();
+    let (mut words, mut weights): (Vec<Word>, Vec<u64>) = (vec![], vec![]);
+    for (word, weight) in mem::take(&mut self.words).into_iter().zip(mem::take(&mut self.weights)) {
+      match seen.get(&word) {
+        Some(&idx) => {
+          weights[idx] = weights[idx].max(weight);
+          removed += 1;
+        }
+        None => {
+          seen.insert(word.clone(), words.len());
+          words.push(word);
+          weights.push(weight);
+        }
+      }
+    }
+    self.words = words;
+    self.weights = weights;
+    for child in self.children_mut() {
+      removed += child.dedup_words();
+    }
+    removed
+  }
+
+  fn node_mut(&mut self, code: &str) -> Option<&mut Self> {
+    let mut node = self;
+    let mut rest = code;
+    while !rest.is_empty() {
+      let child = node.child_within_mut(rest)?;
+      rest = &rest[child.code.len()..];
+      node = child;
+    }
+    Some(node)
+  }
+
+  /// Recomputes `max_weight` along the nodes on the way to `code`, after a weight went down.
+  fn refresh_max_weight(&mut self, code: &str) {
+    if let Some(child) = self.child_within_mut(code) {
+      child.refresh_max_weight(&code[child.code.len()..]);
+    }
+    self.max_weight = self.weights.iter().chain(self.children().map(|child| &child.max_weight)).copied().max().unwrap_or(0);
+  }
+
+  /// Raises `max_weight` along the existing nodes on the way to `code`, before inserting there;
+  /// the nodes `insert` creates or splits get theirs right away.
+  fn raise_max_weight(&mut self, code: &str, weight: u64) {
+    let mut node = self;
+    let mut rest = code;
+    loop {
+      node.max_weight = node.max_weight.max(weight);
+      let Some(child) = node.child_within_mut(rest) else { break };
+      rest = &rest[child.code.len()..];
+      node = child;
+    }
+  }
+
   fn deepest_full_code(&self, code: &mut CodeCursor) -> &Self {
     let mut node = self;
 
     loop {
-      let child = node
-        .children()
-        .find(|child| code.remaining_starts_with(&child.code));
+      let child = node.child_within(code.remaining());
 
       match child {
         None => break,
@@ -334,12 +495,7 @@ impl Trie {
       return (node, node.code.len());
     }
 
-    let ch = &code.remaining()[..1];
-    let child = node.child(ch)
-      .or(node
-        .children()
-        .find(|child| child.code.starts_with(ch))
-      );
+    let child = node.links.get(&code[0]);
 
     if let Some(child) = child {
       (child, child.poll(code))
@@ -352,104 +508,521 @@ impl Trie {
     let (node, len) = self.try_best_to_match(code);
     (NonNull::from(node).as_mut(), len)
   }
+}
+
+/// Which longer codes complete the one typed, like Rime's `translator/enable_completion`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Completion {
+  Off,
+  /// Codes up to this many keys longer.
+  Depth(usize),
+}
+
+impl Completion {
+  pub const ALL: Self = Self::Depth(usize::MAX);
+}
+
+impl Default for Completion {
+  fn default() -> Self {
+    Self::ALL
+  }
+}
+
+impl From<bool> for Completion {
+  fn from(enabled: bool) -> Self {
+    if enabled { Self::ALL } else { Self::Off }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct EvalOptions {
+  pub completion: Completion,
+  /// Applied to the typed keys, should match the one the dicts were loaded with.
+  pub normalization: Normalization,
+  /// Passes text that can't be keys through untouched, see [`Trie::trace`].
+  pub echo: bool,
+  /// Removes the last key of the composition, or the last committed character if there's none.
+  pub backspace: Option<char>,
+  /// Clears the composition.
+  pub escape: Option<char>,
+  pub select_keys: SelectKeys,
+}
+
+impl Default for EvalOptions {
+  fn default() -> Self {
+    Self {
+      completion: Completion::ALL,
+      normalization: Default::default(),
+      echo: false,
+      backspace: None,
+      escape: None,
+      select_keys: Default::default(),
+    }
+  }
+}
 
-  fn find_a_child_starts_with(&self, ch: char) -> Option<&Self> {
-    self.children().find(|child| child.code.starts_with(ch))
+/// Keys that pick a candidate instead of starting a code, `select_keys` in the config:
+/// the keys of the first candidate, then those of the second and so on.
+///
+/// By default blank and `1` pick the first candidate (空格选重), `'` and `2` the second (次选),
+/// and the other digits the rest, but some 乱序 variants use `;` or `z` instead of `'`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SelectKeys {
+  by_index: Vec<String>,
+}
+
+impl SelectKeys {
+  pub fn new(by_index: Vec<String>) -> Result<Self, String> {
+    let mut seen = HashSet::new();
+    if let Some(key) = by_index.iter().flat_map(|keys| keys.chars()).find(|&key| !seen.insert(key)) {
+      return Err(format!("{key:?} selects two candidates"));
+    }
+    Ok(Self { by_index })
+  }
+
+  /// The candidate the key picks, 0 for the first one.
+  pub fn index(&self, key: char) -> Option<usize> {
+    self.by_index.iter().position(|keys| keys.contains(key))
+  }
+
+  pub fn contains(&self, key: char) -> bool {
+    self.index(key).is_some()
+  }
+
+  /// The key typed to commit the first candidate when the next code would extend the current one.
+  pub fn commit_key(&self) -> Option<char> {
+    self.by_index.first().and_then(|keys| keys.chars().next())
+  }
+
+  /// How many candidates can be selected.
+  pub fn len(&self) -> usize {
+    self.by_index.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.by_index.is_empty()
+  }
+}
+
+impl Default for SelectKeys {
+  fn default() -> Self {
+    let by_index = [" 1", "'2", "3", "4", "5", "6", "7", "8", "9"];
+    Self { by_index: by_index.map(String::from).to_vec() }
+  }
+}
+
+/// What a step of [`Trie::trace`] did with the candidates of its node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+  /// The only candidate of a leaf went up without a key.
+  AutoCommit,
+  /// A key starting the next code committed the first candidate.
+  Commit,
+  /// A selection key picked the candidate at this index.
+  Select(usize),
+  /// A selection key past the candidates committed the first one and itself.
+  Overflow,
+  /// A key no word was typed with went up as is.
+  Literal,
+  /// The code ended, committing the first candidate.
+  End,
+  /// The keys ended only starting a code, composing with nothing to commit.
+  Compose,
+  /// Text that isn't keys went up untouched, in echo mode.
+  Echo,
+  /// Backspace removed the last key typed.
+  Backspace,
+  /// Backspace with nothing typed removed the last character committed.
+  Delete,
+  /// Escape dropped the keys typed.
+  Escape,
+}
+
+impl Display for Action {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Action::AutoCommit => write!(f, "auto-commit"),
+      Action::Commit => write!(f, "commit"),
+      Action::Select(index) => write!(f, "select {}", index + 1),
+      Action::Overflow => write!(f, "overflow"),
+      Action::Literal => write!(f, "literal"),
+      Action::End => write!(f, "end"),
+      Action::Compose => write!(f, "compose"),
+      Action::Echo => write!(f, "echo"),
+      Action::Backspace => write!(f, "backspace"),
+      Action::Delete => write!(f, "delete"),
+      Action::Escape => write!(f, "escape"),
+    }
   }
 }
 
+pub struct Step<'a> {
+  /// Keys consumed by this step.
+  pub keys: Code,
+  pub node: &'a Trie,
+  pub action: Action,
+  pub output: String,
+}
+
 impl Trie {
   pub fn eval(&self, code: &str) -> String {
-    let mut code = CodeCursor::new(code.to_string());
-    let mut output = Vec::new();
+    self.eval_with(code, &Default::default())
+  }
+
+  pub fn eval_with(&self, code: &str, options: &EvalOptions) -> String {
+    let mut output = String::new();
+    for step in self.trace(code, options) {
+      if step.action == Action::Delete {
+        output.pop();
+      }
+      output.push_str(&step.output);
+    }
+    output
+  }
+
+  /// Characters used by some code.
+  pub fn alphabet(&self) -> HashSet<char> {
+    self.nodes().flat_map(|node| node.code.chars()).collect()
+  }
+
+  /// Evaluates `code` step by step, recording what each step saw and did.
+  ///
+  /// With `echo`, `code` is rather a document: characters outside the alphabet, and selection keys
+  /// not following a code, are echoed untouched and commit whatever was being typed.
+  ///
+  /// The `backspace` and `escape` keys edit the composition, the keys typed since the last commit,
+  /// so that logs of real typing with its corrections can be replayed.
+  pub fn trace(&self, code: &str, options: &EvalOptions) -> Vec<Step<'_>> {
+    let keys = options.normalization.keys(code);
+    if options.backspace.is_none() && options.escape.is_none() {
+      return self.trace_unedited(keys, options);
+    }
+
+    let mut steps = vec![];
+    let mut pending = String::new();
+    for ch in keys.chars() {
+      let is_backspace = options.backspace == Some(ch);
+      if !is_backspace && options.escape != Some(ch) {
+        pending.push(ch);
+        continue;
+      }
+      // what the next key would commit anyway is committed, the rest is the composition
+      let mut traced = self.trace_unedited(pending.clone(), options);
+      let composition = self.pop_composition(&mut traced);
+      pending.drain(..pending.len() - composition.len());
+      steps.extend(traced);
+      let action = match is_backspace {
+        true if pending.pop().is_some() => Action::Backspace,
+        true => Action::Delete,
+        false => {
+          pending.clear();
+          Action::Escape
+        }
+      };
+      steps.push(Step { keys: ch.to_string(), node: self, action, output: String::new() });
+    }
+    steps.extend(self.trace_unedited(pending, options));
+    steps
+  }
+
+  /// Pops the steps of the keys still composing at the end of `steps`: the last code unless its word
+  /// went up by itself, or keys only starting one which went up as literals. Returns these keys.
+  fn pop_composition(&self, steps: &mut Vec<Step<'_>>) -> Code {
+    let mut composition = Code::new();
+    let is_composing = |step: &Step| match step.action {
+      Action::End => !(step.node.words.len() == 1 && step.node.is_leaf()),
+      action => action == Action::Compose,
+    };
+    if steps.last().is_some_and(is_composing) {
+      composition = steps.pop().unwrap().keys;
+    }
+    let literals = steps.iter().rev().take_while(|step| step.action == Action::Literal).count();
+    let keys_from = |start: usize| steps[start..].iter().map(|step| step.keys.as_str()).collect::<String>() + &composition;
+    match (steps.len() - literals..steps.len()).find(|&start| self.is_code_prefix(&keys_from(start))) {
+      Some(start) => {
+        let composition = keys_from(start);
+        steps.truncate(start);
+        composition
+      }
+      None => composition,
+    }
+  }
+
+  /// What shows after typing `keys`, see `trace`: the text committed so far and the keys still composing.
+  pub fn compose(&self, keys: &str, options: &EvalOptions) -> (String, Code) {
+    let mut steps = self.trace(keys, options);
+    let composition = self.pop_composition(&mut steps);
+    let mut output = String::new();
+    for step in steps {
+      if step.action == Action::Delete {
+        output.pop();
+      }
+      output.push_str(&step.output);
+    }
+    (output, composition)
+  }
+
+  fn trace_unedited(&self, keys: Code, options: &EvalOptions) -> Vec<Step<'_>> {
+    if !options.echo {
+      return self.trace_keys(keys, options);
+    }
+
+    let alphabet = self.alphabet();
+    let mut chunks: Vec<(bool, String)> = vec![];
+    for ch in keys.chars() {
+      let is_select_key = |ch| options.select_keys.contains(ch);
+      let is_keys = alphabet.contains(&ch) || is_select_key(ch) && chunks
+        .last()
+        .is_some_and(|(is_keys, chunk)| *is_keys && !chunk.ends_with(is_select_key));
+      match chunks.last_mut() {
+        Some((last_is_keys, chunk)) if *last_is_keys == is_keys => chunk.push(ch),
+        _ => chunks.push((is_keys, ch.to_string())),
+      }
+    }
+
+    let mut steps = vec![];
+    for (is_keys, chunk) in chunks {
+      if is_keys {
+        steps.extend(self.trace_keys(chunk, options));
+      } else {
+        steps.push(Step { keys: chunk.clone(), node: self, action: Action::Echo, output: chunk });
+      }
+    }
+    steps
+  }
+
+  fn trace_keys(&self, keys: Code, options: &EvalOptions) -> Vec<Step<'_>> {
+    let mut code = CodeCursor::new(keys);
+    let mut steps = Vec::new();
+    let step = |code: &CodeCursor, start: usize, node, action, output| Step {
+      keys: code.get_ref()[start..code.position()].to_string(),
+      node,
+      action,
+      output,
+    };
 
     loop {
+      let start = code.position();
       let node = self.deepest_full_code(&mut code);
-      let first_word = node.words.get(0).cloned();
+      let first_word = node.words.first().cloned();
       if code.is_empty() {
-        if let Some(word) = first_word {
-          output.push(word);
+        match first_word {
+          Some(first_word) => steps.push(step(&code, start, node, Action::End, first_word)),
+          // keys only starting codes end with nothing to commit, but they're still composing
+          None if code.position() > start => steps.push(step(&code, start, node, Action::Compose, String::new())),
+          None => {}
         }
         break;
       }
       let peeked = code[0] as char;
 
-      if first_word.is_none() {
-        output.push(String::from(code.shift() as char));
+      let Some(first_word) = first_word else {
+        let literal = String::from(code.shift() as char);
+        steps.push(step(&code, start, node, Action::Literal, literal));
         continue;
-      }
-      let first_word = first_word.unwrap();
+      };
       if node.words.len() == 1 && node.is_leaf() { // 唯一时自动上屏
-        output.push(first_word);
+        steps.push(step(&code, start, node, Action::AutoCommit, first_word));
         continue;
       }
-      let select = match peeked {
-        ' ' => 0, // 空格选重
-        '\'' => 1, // 次选
-        '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' =>
-          peeked as usize - b'1' as usize, // 数字键选重
-        _ => {
-          output.push(first_word);
-          continue;
-        }
+      let Some(select) = options.select_keys.index(peeked) else {
+        steps.push(step(&code, start, node, Action::Commit, first_word));
+        continue;
       };
 
-      if node as *const _ == self as *const _ { // no candidates
-        output.push(String::from(code.shift() as char));
+      if std::ptr::eq(node, self) { // no candidates
+        let literal = String::from(code.shift() as char);
+        steps.push(step(&code, start, node, Action::Literal, literal));
       } else {
-        let selected = node.candidates()
-          .skip(select)
-          .next();
-
-        if let Some(selected) = selected {
-          output.push(selected.clone());
-        } else {
-          output.push(first_word);
-          output.push(peeked.to_string());
-        }
-
+        let selected = node.candidates(options.completion).nth(select).cloned();
         code.shift();
+        let (action, output) = match selected {
+          Some(selected) => (Action::Select(select), selected),
+          None => (Action::Overflow, first_word + &peeked.to_string()),
+        };
+        steps.push(step(&code, start, node, action, output));
       }
     }
-    output.join("")
+    steps
+  }
+
+  pub fn entries(&self) -> impl Iterator<Item=Entry> + '_ {
+    self.walk().flat_map(|(node, code)| {
+      node.words.iter().map(move |word| Entry { code: code.clone(), word: word.clone() })
+    })
+  }
+
+  /// Like `entries`, in the order of their codes and then of the candidates, streamed from
+  /// a sorted walk rather than collected and sorted.
+  pub fn sorted_entries(&self) -> impl Iterator<Item=(Entry, u64)> + '_ {
+    Walk::sorted(self).flat_map(|(node, code)| {
+      node.words.iter().zip(&node.weights).map(move |(word, &weight)| (Entry { code: code.clone(), word: word.clone() }, weight))
+    })
+  }
+
+  /// Writes the entries as a table sorted by code, with their weights when they have some,
+  /// returning how many were written.
+  pub fn write_dict(&self, writer: &mut impl Write) -> io::Result<usize> {
+    let mut count = 0;
+    for (entry, weight) in self.sorted_entries() {
+      writeln!(writer, "{}", dict::format_line(&entry, Some(weight).filter(|&weight| weight > 0), None))?;
+      count += 1;
+    }
+    Ok(count)
   }
 
-  pub fn rev_dict(&self) -> RevDict {
-    let mut rev_dict = RevDict::new(self);
+  /// The trie made immutable, to be read from other threads while the next one is built, see
+  /// `snapshot`.
+  pub fn freeze(self) -> TrieSnapshot {
+    let mut root = Arc::new(self);
+    // the root moved, the children have to follow it; deeper nodes stay where they were
+    Arc::get_mut(&mut root).unwrap().refresh_children();
+    TrieSnapshot::new(root)
+  }
+
+  /// The reverse lookup of words, built lazily on its first use.
+  pub fn rev_dict(&self) -> RevDict<'_> {
+    RevDict::new(self)
+  }
+
+  /// The nodes of the subtree and roughly the heap bytes they hold: the capacity of their strings,
+  /// vectors and maps, a control byte per map slot, but not the allocator's overhead.
+  pub fn footprint(&self) -> Footprint {
+    let mut footprint = Footprint::default();
     for node in self.nodes() {
-      for word in &node.words {
-        rev_dict.insert_if_shorter(word, node);
+      footprint.nodes += 1;
+      footprint.heap_bytes += node.code.capacity()
+        + node.words.capacity() * size_of::<Word>()
+        + node.words.iter().map(String::capacity).sum::<usize>()
+        + node.weights.capacity() * size_of::<u64>()
+        + node.links.capacity() * (size_of::<(u8, Self)>() + 1);
+    }
+    footprint
+  }
+
+  /// Counts the nodes of the subtree by depth, children and words.
+  pub fn metrics(&self) -> Metrics {
+    let mut metrics = Metrics::default();
+    let (mut depth_sum, mut inner, mut children) = (0, 0, 0);
+    let mut stack = vec![(self, 0, 0)];
+    while let Some((node, depth, code_len)) = stack.pop() {
+      metrics.nodes += 1;
+      metrics.words += node.words.len();
+      metrics.max_depth = metrics.max_depth.max(depth);
+      metrics.max_code_len = metrics.max_code_len.max(code_len);
+      depth_sum += depth;
+      *metrics.depths.entry(depth).or_default() += 1;
+      *metrics.branching.entry(node.links.len()).or_default() += 1;
+      *metrics.words_per_node.entry(node.words.len()).or_default() += 1;
+      if !node.is_leaf() {
+        inner += 1;
+        children += node.links.len();
       }
+      stack.extend(node.children().map(|child| (child, depth + 1, code_len + child.code.len())));
     }
-    rev_dict
+    metrics.avg_depth = depth_sum as f64 / metrics.nodes as f64;
+    metrics.avg_branching = if inner == 0 { 0.0 } else { children as f64 / inner as f64 };
+    metrics
   }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Footprint {
+  pub nodes: usize,
+  pub heap_bytes: usize,
+}
+
+/// The shape of a trie, see [`Trie::metrics`].
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metrics {
+  pub nodes: usize,
+  pub words: usize,
+  /// Edges from the root to the deepest node.
+  pub max_depth: usize,
+  pub avg_depth: f64,
+  /// Keys of the longest code.
+  pub max_code_len: usize,
+  /// Nodes by depth.
+  pub depths: BTreeMap<usize, usize>,
+  /// Nodes by number of children, leaves included.
+  pub branching: BTreeMap<usize, usize>,
+  /// Children per node that has some.
+  pub avg_branching: f64,
+  /// Nodes by number of words, the empty ones included.
+  pub words_per_node: BTreeMap<usize, usize>,
+}
+
 impl Trie {
   pub fn load_xkjd_dict(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
-    self.extend(BufReader::new(
-      File::open(path.as_ref())?)
-      .lines()
-      .filter_map(|line| {
-        let mut line = line.unwrap();
-        if let Some(idx) = line.find('#') {
-          line.truncate(idx);
-        }
-        let mut cells = line.split('\t');
-        let word = cells.next().map(String::from)?;
-        let code = cells.next().map(String::from)?;
+    self.load_dict(path, &Normalization::default(), Duplicates::Keep)
+  }
 
-        Some(Entry { word, code })
-      }));
+  pub fn load_dict(&mut self, path: impl AsRef<Path>, normalization: &Normalization, duplicates: Duplicates) -> io::Result<()> {
+    for (entry, weight) in dict::read_weighted_entries(path)? {
+      let Entry { code, word } = normalization.entry(entry);
+      self.insert_with(code, word, weight, duplicates);
+    }
     Ok(())
   }
+
+  #[cfg(any(feature = "async-std", feature = "tokio"))]
+  pub async fn load_xkjd_dict_async(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+    self.load_dict_async(path, &Normalization::default(), Duplicates::Keep).await
+  }
+
+  #[cfg(any(feature = "async-std", feature = "tokio"))]
+  pub async fn load_dict_async(&mut self, path: impl AsRef<Path>, normalization: &Normalization, duplicates: Duplicates) -> io::Result<()> {
+    for (entry, weight) in dict::read_weighted_entries_async(crate::rt::open(path).await?).await? {
+      let Entry { code, word } = normalization.entry(entry);
+      self.insert_with(code, word, weight, duplicates);
+    }
+    Ok(())
+  }
+}
+
+/// What loading a word already on its code does, see [`Trie::insert_with`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Duplicates {
+  /// Adds it again, like Rime does.
+  #[default]
+  Keep,
+  Ignore,
+  /// Takes the new weight.
+  Replace,
+  /// Takes the higher weight.
+  KeepHighest,
+}
+
+impl FromStr for Duplicates {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    match s {
+      "keep" => Ok(Self::Keep),
+      "ignore" => Ok(Self::Ignore),
+      "replace" => Ok(Self::Replace),
+      "keep-highest" => Ok(Self::KeepHighest),
+      _ => Err(format!("duplicates should be keep, ignore, replace or keep-highest, not {s}")),
+    }
+  }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Entry {
   pub code: Code,
   pub word: Word,
 }
 
+/// As a line of a table, `word\tcode`.
+impl Display for Entry {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}\t{}", self.word, self.code)
+  }
+}
+
 impl Extend<Entry> for Trie {
   fn extend<T: IntoIterator<Item=Entry>>(&mut self, iter: T) {
     for Entry { code, word } in iter {
@@ -458,28 +1031,280 @@ impl Extend<Entry> for Trie {
   }
 }
 
-#[cfg(test)]
+impl Extend<(Entry, u64)> for Trie {
+  fn extend<T: IntoIterator<Item=(Entry, u64)>>(&mut self, iter: T) {
+    for (Entry { code, word }, weight) in iter {
+      self.insert_weighted(code, word, weight);
+    }
+  }
+}
+
 impl Trie {
-  pub(crate) fn check_links(&self) -> Result<(), &Self> {
-    for child in self.children() {
-      if child.parent().unwrap() as *const _ != self as *const _ {
-        return Err(child);
+  /// Copies the nodes, leaving the parent pointers to [`Trie::relink`].
+  fn clone_unlinked(&self) -> Self {
+    Self {
+      code: self.code.clone(),
+      words: self.words.clone(),
+      weights: self.weights.clone(),
+      max_weight: self.max_weight,
+      parent: None,
+      links: self.links
+        .iter()
+        .map(|(&key, child)| (key, child.clone_unlinked()))
+        .collect(),
+    }
+  }
+
+  /// A copy of the subtree as a trie of its own.
+  pub(crate) fn to_boxed(&self) -> Box<Self> {
+    let mut trie = Box::new(self.clone_unlinked());
+    trie.relink();
+    trie
+  }
+
+  /// Points the children of every node at it, once the nodes won't move anymore.
+  fn relink(&mut self) {
+    self.refresh_children();
+    for child in self.children_mut() {
+      child.relink();
+    }
+  }
+
+  /// Grafts the entries of `other` into this trie with their weights, after the words already on the same codes.
+  /// Entries present in both are kept once.
+  pub fn merge(&mut self, other: &Trie) {
+    for (node, code) in other.walk() {
+      for (word, &weight) in node.words.iter().zip(&node.weights) {
+        if !self.node(&code).is_some_and(|node| node.words.contains(word)) {
+          self.insert_weighted(code.clone(), word.clone(), weight);
+        }
+      }
+    }
+  }
+
+  /// Whether the word is on the code.
+  pub fn contains(&self, code: &str, word: &str) -> bool {
+    self.node(code).is_some_and(|node| node.words.iter().any(|candidate| candidate == word))
+  }
+
+  /// A new trie of the entries `keep` holds for, with their weights and in the same candidate order.
+  pub fn filtered(&self, mut keep: impl FnMut(&str, &str) -> bool) -> Box<Trie> {
+    let mut trie = Box::new(Trie::new());
+    for (node, code) in self.walk() {
+      for (word, &weight) in node.words.iter().zip(&node.weights) {
+        if keep(&code, word) {
+          trie.insert_weighted(code.clone(), word.clone(), weight);
+        }
+      }
+    }
+    trie
+  }
+
+  /// The entries also in `other`, by code and word, with the weights of this trie.
+  pub fn intersection(&self, other: &Trie) -> Box<Trie> {
+    self.filtered(|code, word| other.contains(code, word))
+  }
+
+  /// The entries not in `other`, e.g. those of a local schema missing upstream.
+  pub fn difference(&self, other: &Trie) -> Box<Trie> {
+    self.filtered(|code, word| !other.contains(code, word))
+  }
+
+  /// The entries in only one of the tries, those of this one first on a shared code.
+  pub fn symmetric_difference(&self, other: &Trie) -> Box<Trie> {
+    let mut trie = self.difference(other);
+    trie.merge(&other.difference(self));
+    trie
+  }
+}
+
+/// A deep copy, boxed for the same reason as [`FromIterator<Entry>`].
+impl Clone for Box<Trie> {
+  fn clone(&self) -> Self {
+    self.to_boxed()
+  }
+}
+
+/// Boxed since the root must not move once entries are inserted, see [`Trie`].
+impl FromIterator<Entry> for Box<Trie> {
+  fn from_iter<T: IntoIterator<Item=Entry>>(iter: T) -> Self {
+    let mut trie = Box::new(Trie::new());
+    trie.extend(iter);
+    trie
+  }
+}
+
+/// Something [`TrieBuilder`] takes: an [`Entry`], `(code, word)` or `(code, word, weight)`.
+pub trait IntoWeightedEntry {
+  fn into_weighted_entry(self) -> (Entry, u64);
+}
+
+impl IntoWeightedEntry for Entry {
+  fn into_weighted_entry(self) -> (Entry, u64) {
+    (self, 0)
+  }
+}
+
+impl<C: Into<Code>, W: Into<Word>> IntoWeightedEntry for (C, W) {
+  fn into_weighted_entry(self) -> (Entry, u64) {
+    (Entry { code: self.0.into(), word: self.1.into() }, 0)
+  }
+}
+
+impl<C: Into<Code>, W: Into<Word>> IntoWeightedEntry for (C, W, u64) {
+  fn into_weighted_entry(self) -> (Entry, u64) {
+    (Entry { code: self.0.into(), word: self.1.into() }, self.2)
+  }
+}
+
+/// Collects entries in memory and builds a boxed [`Trie`] from them.
+/// Like Rime, words sharing a code are ordered by descending weight, then by the order they were added.
+///
+/// ```
+/// use smart_dict::trie::TrieBuilder;
+///
+/// let trie = TrieBuilder::new()
+///   .entry(("n", "那", 10))
+///   .entry(("n", "你", 90))
+///   .entry(("ni", "你们"))
+///   .build();
+/// assert_eq!("你", trie.eval("n"));
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TrieBuilder {
+  entries: Vec<(Entry, u64)>,
+}
+
+impl TrieBuilder {
+  pub fn new() -> Self {
+    Default::default()
+  }
+
+  pub fn entry(mut self, entry: impl IntoWeightedEntry) -> Self {
+    self.entries.push(entry.into_weighted_entry());
+    self
+  }
+
+  pub fn build(mut self) -> Box<Trie> {
+    self.entries.sort_by_key(|(_, weight)| Reverse(*weight));
+    let mut trie = Box::new(Trie::new());
+    trie.extend(self.entries);
+    trie
+  }
+}
+
+impl<T: IntoWeightedEntry> Extend<T> for TrieBuilder {
+  fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+    self.entries.extend(iter.into_iter().map(IntoWeightedEntry::into_weighted_entry));
+  }
+}
+
+impl<T: IntoWeightedEntry> FromIterator<T> for TrieBuilder {
+  fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> Self {
+    let mut builder = Self::new();
+    builder.extend(iter);
+    builder
+  }
+}
+
+/// A broken invariant found by `Trie::validate`, identified by the full code of the offending node.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Invalid {
+  /// The parent pointer of the node doesn't point to the node owning it.
+  BrokenLink(Code),
+  /// The node is stored under a key other than the first byte of its code.
+  MismatchedKey(Code),
+  /// A non-root node with an empty code.
+  EmptyCode(Code),
+  /// A node without words that has fewer than two children, which insert never leaves behind.
+  Redundant(Code),
+  /// `max_weight` isn't the highest weight of the subtree, or words and weights don't pair up.
+  Weight(Code),
+}
+
+impl Display for Invalid {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Invalid::BrokenLink(code) => write!(f, "broken parent link at {code:?}"),
+      Invalid::MismatchedKey(code) => write!(f, "mismatched link key at {code:?}"),
+      Invalid::EmptyCode(code) => write!(f, "empty code at {code:?}"),
+      Invalid::Redundant(code) => write!(f, "redundant intermediate node at {code:?}"),
+      Invalid::Weight(code) => write!(f, "inconsistent weights at {code:?}"),
+    }
+  }
+}
+
+impl Trie {
+  /// Checks the structural invariants of the whole trie.
+  pub fn validate(&self) -> Result<(), Invalid> {
+    self.nodes().try_for_each(Self::validate_node)
+  }
+
+  /// Checks the invariants between a node and its direct children.
+  fn validate_node(&self) -> Result<(), Invalid> {
+    if !self.is_root() {
+      if self.code.is_empty() {
+        return Err(Invalid::EmptyCode(self.full_code()));
+      }
+      if self.words.is_empty() && self.links.len() < 2 {
+        return Err(Invalid::Redundant(self.full_code()));
+      }
+    }
+    let max_weight = self.weights.iter().chain(self.children().map(|child| &child.max_weight)).max();
+    if self.weights.len() != self.words.len() || max_weight.copied().unwrap_or(0) != self.max_weight {
+      return Err(Invalid::Weight(self.full_code()));
+    }
+    for (key, child) in &self.links {
+      if !child.parent.is_some_and(|p| std::ptr::eq(p.as_ptr(), self)) {
+        return Err(Invalid::BrokenLink(self.full_code() + &child.code));
+      }
+      if child.code.as_bytes().first() != Some(key) {
+        return Err(Invalid::MismatchedKey(self.full_code() + &child.code));
       }
-      child.check_links()?;
     }
     Ok(())
   }
+
+  fn debug_validate(&self) {
+    if cfg!(debug_assertions) {
+      let node = self.parent().unwrap_or(self);
+      if let Err(invalid) = node.validate_node().and_then(|_| {
+        node.children().try_for_each(Self::validate_node)
+      }) {
+        panic!("trie corrupted: {invalid}");
+      }
+    }
+  }
 }
 
+/// The node alone, its children only counted: a whole dict would be megabytes.
 impl Debug for Trie {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-    let parent = self.parent().unwrap();
-    write!(f, r"Trie {{
-    code: {:?},
-    words: {:?},
-    parent.code: {:?},
-    parent.words: {:?},
-}}", self.code(), self.words(), parent.code(), parent.words())
+    f.debug_struct("Trie")
+      .field("code", &self.code)
+      .field("full_code", &self.full_code())
+      .field("words", &self.words)
+      .field("weights", &self.weights)
+      .field("parent.code", &self.parent().map(|parent| &parent.code))
+      .field("children", &self.links.len())
+      .finish()
+  }
+}
+
+/// The node and all its descendants, children in the order of their codes.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Trie {
+  fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeStruct;
+
+    let mut children: Vec<_> = self.children().collect();
+    children.sort_unstable_by(|a, b| a.code.cmp(&b.code));
+    let mut node = serializer.serialize_struct("Trie", 4)?;
+    node.serialize_field("code", &self.code)?;
+    node.serialize_field("words", &self.words)?;
+    node.serialize_field("weights", &self.weights)?;
+    node.serialize_field("children", &children)?;
+    node.end()
   }
 }
 
@@ -503,6 +1328,95 @@ impl<'a> Iterator for Bubble<'a> {
   }
 }
 
+/// A node whose subtree is yet to be expanded, or one of its words.
+struct Pending<'a> {
+  /// The word's, or the highest of the subtree.
+  weight: u64,
+  /// Past the node the candidates are listed for.
+  code_len: usize,
+  node: &'a Trie,
+  word: Option<usize>,
+}
+
+impl Pending<'_> {
+  /// Heavier first, then shorter; a subtree is expanded before the words it may outrank.
+  fn key(&self) -> (u64, Reverse<usize>, bool, Reverse<&str>, Reverse<usize>) {
+    (self.weight, Reverse(self.code_len), self.word.is_none(), Reverse(&self.node.code), Reverse(self.word.unwrap_or(0)))
+  }
+}
+
+impl PartialEq for Pending<'_> {
+  fn eq(&self, other: &Self) -> bool {
+    self.cmp(other) == Ordering::Equal
+  }
+}
+
+impl Eq for Pending<'_> {}
+
+impl PartialOrd for Pending<'_> {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+impl Ord for Pending<'_> {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.key().cmp(&other.key())
+  }
+}
+
+/// Best-first over the subtree: a node is only expanded once its `max_weight` could outrank
+/// the words found so far, so the first candidates don't walk the whole subtree.
+pub struct Candidates<'a> {
+  words: Iter<'a, Word>,
+  pending: BinaryHeap<Pending<'a>>,
+  max_depth: usize,
+}
+
+impl<'a> Candidates<'a> {
+  pub fn new(node: &'a Trie, completion: Completion) -> Self {
+    let mut candidates = Self {
+      words: node.words.iter(),
+      pending: BinaryHeap::new(),
+      max_depth: 0,
+    };
+    if let Completion::Depth(max_depth) = completion {
+      candidates.max_depth = max_depth;
+      candidates.expand(node, 0);
+    }
+    candidates
+  }
+
+  fn expand(&mut self, node: &'a Trie, code_len: usize) {
+    for child in node.children() {
+      let code_len = code_len + child.code.len();
+      if code_len <= self.max_depth {
+        self.pending.push(Pending { weight: child.max_weight, code_len, node: child, word: None });
+      }
+    }
+  }
+}
+
+impl<'a> Iterator for Candidates<'a> {
+  type Item = &'a Word;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if let Some(word) = self.words.next() {
+      return Some(word);
+    }
+    loop {
+      let Pending { code_len, node, word, .. } = self.pending.pop()?;
+      if let Some(idx) = word {
+        return Some(&node.words[idx]);
+      }
+      for (idx, &weight) in node.weights.iter().enumerate() {
+        self.pending.push(Pending { weight, code_len, node, word: Some(idx) });
+      }
+      self.expand(node, code_len);
+    }
+  }
+}
+
 pub struct Nodes<'a> {
   stack: Vec<&'a Trie>,
 }
@@ -521,18 +1435,116 @@ impl<'a> Iterator for Nodes<'a> {
   fn next(&mut self) -> Option<Self::Item> {
     self.stack
       .pop()
-      .map(|node| {
+      .inspect(|node| {
         self.stack.extend(node.children());
-        node
       })
   }
 }
 
+pub struct Walk<'a> {
+  stack: Vec<(&'a Trie, Code)>,
+  sorted: bool,
+}
+
+impl<'a> Walk<'a> {
+  pub fn new(node: &'a Trie) -> Self {
+    Self {
+      stack: vec![(node, node.full_code())],
+      sorted: false,
+    }
+  }
+
+  /// Visits the nodes in the order of their codes, sorting the children of each node as it goes.
+  pub fn sorted(node: &'a Trie) -> Self {
+    Self { sorted: true, ..Self::new(node) }
+  }
+}
+
+impl<'a> Iterator for Walk<'a> {
+  type Item = (&'a Trie, Code);
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let (node, code) = self.stack.pop()?;
+    let start = self.stack.len();
+    self.stack.extend(node.children().map(|child| (child, code.clone() + &child.code)));
+    if self.sorted {
+      // popped from the end, so the smallest code goes last
+      self.stack[start..].sort_unstable_by(|a, b| b.1.cmp(&a.1));
+    }
+    Some((node, code))
+  }
+}
+
 #[cfg(test)]
 mod test {
   use std::collections::HashSet;
+  use proptest::collection::vec;
+  use proptest::prelude::*;
   use super::*;
 
+  #[test]
+  fn test_footprint() {
+    let mut trie = Trie::new();
+    let empty = trie.footprint();
+    assert_eq!(Footprint { nodes: 1, heap_bytes: 0 }, empty);
+    trie.insert("ab".to_string(), "你".to_string());
+    trie.insert("ac".to_string(), "好".to_string());
+    let footprint = trie.footprint();
+    // the root, `a` and its children `b` and `c`
+    assert_eq!(4, footprint.nodes);
+    assert!(footprint.heap_bytes >= 2 * size_of::<(Code, Trie)>() + "你好".len());
+  }
+
+  #[test]
+  fn test_debug() {
+    let trie = TrieBuilder::from_iter([("wi", "我们"), ("w", "我")]).build();
+    assert_eq!(r#"Trie { code: "", full_code: "", words: [], weights: [], parent.code: None, children: 1 }"#, format!("{trie:?}"));
+    assert!(format!("{:?}", trie.node("wi").unwrap()).contains(r#"full_code: "wi", words: ["我们"]"#));
+    assert_eq!("我们\twi", Entry { code: "wi".to_string(), word: "我们".to_string() }.to_string());
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn test_serialize() {
+    let trie = TrieBuilder::new().entry(("wi", "我们", 2)).entry(("w", "我", 0)).entry(("a", "啊", 0)).build();
+    assert_eq!(
+      r#"{"code":"","words":[],"weights":[],"children":[{"code":"a","words":["啊"],"weights":[0],"children":[]},{"code":"w","words":["我"],"weights":[0],"children":[{"code":"i","words":["我们"],"weights":[2],"children":[]}]}]}"#,
+      serde_json::to_string(&trie).unwrap()
+    );
+    assert_eq!(r#"{"code":"wi","word":"我们"}"#, serde_json::to_string(&Entry { code: "wi".to_string(), word: "我们".to_string() }).unwrap());
+  }
+
+  #[test]
+  fn test_write_dict() {
+    let trie = TrieBuilder::new()
+      .entry(("wi", "我们", 0))
+      .entry(("ab", "你", 3))
+      .entry(("a", "啊", 0))
+      .entry(("ab", "号", 0))
+      .entry(("abc", "好", 0))
+      .entry(("b", "不", 0))
+      .build();
+    let mut out = vec![];
+    assert_eq!(6, trie.write_dict(&mut out).unwrap());
+    assert_eq!("啊\ta\n你\tab\t3\n号\tab\n好\tabc\n不\tb\n我们\twi\n", String::from_utf8(out).unwrap());
+    let mut sorted: Vec<_> = trie.entries().collect();
+    sorted.sort_by(|a, b| a.code.cmp(&b.code));
+    assert_eq!(sorted, trie.sorted_entries().map(|(entry, _)| entry).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn test_metrics() {
+    let trie = TrieBuilder::from_iter([("ab", "你"), ("ac", "好"), ("ac", "号"), ("d", "的")]).build();
+    let metrics = trie.metrics();
+    // the root, `a` and `d` below it, `b` and `c` below `a`
+    assert_eq!((5, 4, 2, 2), (metrics.nodes, metrics.words, metrics.max_depth, metrics.max_code_len));
+    assert_eq!(6.0 / 5.0, metrics.avg_depth);
+    assert_eq!(BTreeMap::from([(0, 1), (1, 2), (2, 2)]), metrics.depths);
+    assert_eq!(BTreeMap::from([(0, 3), (2, 2)]), metrics.branching);
+    assert_eq!(2.0, metrics.avg_branching);
+    assert_eq!(BTreeMap::from([(0, 2), (1, 2), (2, 1)]), metrics.words_per_node);
+  }
+
   #[test]
   fn test_poll_short_code() {
     let trie = Trie {
@@ -618,7 +1630,7 @@ mod test {
     assert_eq!(child as *const _, descendant.parent().unwrap() as *const _);
     assert_eq!(0, descendant.children().count());
 
-    assert!(root.check_links().is_ok());
+    assert!(root.validate().is_ok());
   }
 
   #[test]
@@ -649,7 +1661,7 @@ mod test {
     assert_eq!(trie as *const _, child2.parent().unwrap() as *const _);
     assert_eq!(0, child2.children().count());
 
-    assert!(root.check_links().is_ok());
+    assert!(root.validate().is_ok());
   }
 
   #[test]
@@ -668,13 +1680,200 @@ mod test {
     );
   }
 
+  #[test]
+  fn test_candidates_completion() {
+    let mut root = Trie::new();
+    root.insert("n".to_string(), "你".to_string());
+    root.insert("nia".to_string(), "哪里".to_string());
+    root.insert("ni".to_string(), "你们".to_string());
+    root.insert("nib".to_string(), "你不".to_string());
+    root.insert("n".to_string(), "那".to_string());
+
+    let node = root.node("n").unwrap();
+    assert_eq!(vec!["你", "那"], node.candidates(false).collect::<Vec<_>>());
+    assert_eq!(vec!["你", "那", "你们", "哪里", "你不"], node.candidates(true).collect::<Vec<_>>());
+
+    assert_eq!(Some(1), root.candidate_index("n", "那", false));
+    assert_eq!(None, root.candidate_index("n", "你们", false));
+    assert_eq!(Some(2), root.candidate_index("n", "你们", true));
+    assert_eq!(None, root.candidate_index("nx", "你们", true));
+  }
+
+  #[test]
+  fn test_candidates_weight_and_depth() {
+    let trie = TrieBuilder::from_iter([
+      ("n", "你", 50), ("ni", "你们", 10), ("nia", "哪里", 80), ("nib", "你不", 10), ("niab", "拿", 90),
+    ]).build();
+    assert_eq!(Ok(()), trie.validate());
+    let node = trie.node("n").unwrap();
+    assert_eq!(vec!["你", "拿", "哪里", "你们", "你不"], node.candidates(true).collect::<Vec<_>>());
+    assert_eq!(vec!["你", "哪里", "你们", "你不"], node.candidates(Completion::Depth(2)).collect::<Vec<_>>());
+    assert_eq!(vec!["你", "你们"], node.candidates(Completion::Depth(1)).collect::<Vec<_>>());
+    assert_eq!(vec!["你"], node.candidates(Completion::Off).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn test_duplicates() {
+    let load = |duplicates| {
+      let mut trie = Trie::new();
+      for (code, word, weight) in [("n", "你", 50), ("ni", "你们", 10), ("n", "那", 20), ("n", "你", 30), ("n", "你", 80)] {
+        trie.insert_with(code.to_string(), word.to_string(), weight, duplicates);
+      }
+      assert_eq!(Ok(()), trie.validate());
+      let node = trie.node("n").unwrap();
+      node.words().iter().cloned().zip(node.weights().iter().copied()).collect::<Vec<_>>()
+    };
+    let weighted = |pairs: &[(&str, u64)]| pairs.iter().map(|&(word, weight)| (word.to_string(), weight)).collect::<Vec<_>>();
+    assert_eq!(weighted(&[("你", 50), ("那", 20), ("你", 30), ("你", 80)]), load(Duplicates::Keep));
+    assert_eq!(weighted(&[("你", 50), ("那", 20)]), load(Duplicates::Ignore));
+    assert_eq!(weighted(&[("你", 80), ("那", 20)]), load(Duplicates::Replace));
+    assert_eq!(weighted(&[("你", 80), ("那", 20)]), load(Duplicates::KeepHighest));
+
+    let mut trie = TrieBuilder::from_iter([("n", "你", 30), ("n", "那", 90), ("n", "你", 50), ("n", "那", 10)]).build();
+    trie.insert_with("n".to_string(), "那".to_string(), 5, Duplicates::Replace);
+    assert_eq!(Ok(()), trie.validate());
+    assert_eq!(2, trie.dedup_words());
+    assert_eq!(Ok(()), trie.validate());
+    assert_eq!(&vec!["那".to_string(), "你".to_string()], trie.node("n").unwrap().words());
+    assert_eq!(&[10, 50], trie.node("n").unwrap().weights());
+  }
+
+  #[test]
+  fn test_builder() {
+    let trie = TrieBuilder::from_iter([("n", "那", 1), ("n", "你", 9), ("ni", "你们", 0), ("n", "呢", 1)]).build();
+    assert_eq!(Ok(()), trie.validate());
+    assert_eq!(vec!["你", "那", "呢"], trie.node("n").unwrap().candidates(false).collect::<Vec<_>>());
+
+    let entries = vec![Entry { code: "w".to_string(), word: "我".to_string() }];
+    let collected: Box<Trie> = entries.clone().into_iter().collect();
+    assert_eq!(entries, collected.entries().collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn test_clone_and_merge() {
+    let base = TrieBuilder::from_iter([("n", "你"), ("ni", "你们"), ("nia", "哪里"), ("w", "我")]).build();
+    let mut overlay = base.clone();
+    drop(base);
+    assert_eq!(Ok(()), overlay.validate());
+    assert_eq!("nia", overlay.node("nia").unwrap().full_code());
+
+    let user = TrieBuilder::from_iter([("n", "那"), ("ni", "你们"), ("nib", "你不")]).build();
+    overlay.merge(&user);
+    assert_eq!(Ok(()), overlay.validate());
+    assert_eq!(vec!["你", "那"], overlay.node("n").unwrap().candidates(false).collect::<Vec<_>>());
+    assert_eq!(vec!["你们"], overlay.node("ni").unwrap().candidates(false).collect::<Vec<_>>());
+    assert_eq!(6, overlay.entries().count());
+  }
+
+  #[test]
+  fn test_set_operations() {
+    let local = TrieBuilder::from_iter([("n", "你", 9), ("n", "那", 5), ("ni", "你们", 0), ("nib", "你不", 0)]).build();
+    let upstream = TrieBuilder::from_iter([("n", "那", 7), ("n", "你", 3), ("ni", "你们", 0), ("nia", "哪里", 0)]).build();
+    let sorted = |trie: Box<Trie>| trie.sorted_entries().map(|(entry, weight)| format!("{} {} {weight}", entry.code, entry.word)).collect::<Vec<_>>();
+
+    assert!(local.contains("nib", "你不") && !local.contains("ni", "你不") && !local.contains("x", "你"));
+    assert_eq!(vec!["n 你 9", "n 那 5", "ni 你们 0"], sorted(local.intersection(&upstream)));
+    assert_eq!(vec!["nib 你不 0"], sorted(local.difference(&upstream)));
+    assert_eq!(vec!["nia 哪里 0", "nib 你不 0"], sorted(local.symmetric_difference(&upstream)));
+    assert_eq!(Ok(()), local.intersection(&upstream).validate());
+    assert_eq!(0, local.difference(&local).entries().count());
+  }
+
+  #[test]
+  fn test_trace() {
+    let mut root = Trie::new();
+    root.insert("w".to_string(), "我".to_string());
+    root.insert("n".to_string(), "你".to_string());
+    root.insert("n".to_string(), "那".to_string());
+    root.insert("ni".to_string(), "你们".to_string());
+
+    let steps = root.trace("w2nn9n", &Default::default());
+    let traced: Vec<_> = steps
+      .iter()
+      .map(|step| (step.keys.as_str(), step.node.full_code(), step.action, step.output.as_str()))
+      .collect();
+    assert_eq!(vec![
+      ("w", "w".to_string(), Action::AutoCommit, "我"),
+      ("2", "".to_string(), Action::Literal, "2"),
+      ("n", "n".to_string(), Action::Commit, "你"),
+      ("n9", "n".to_string(), Action::Overflow, "你9"),
+      ("n", "n".to_string(), Action::End, "你"),
+    ], traced);
+    assert_eq!("我2你你9你", root.eval("w2nn9n"));
+  }
+
+  #[test]
+  fn test_echo() {
+    let trie = TrieBuilder::from_iter([("w", "我"), ("n", "你"), ("n", "那"), ("ni", "你们"), ("xh", "喜欢")]).build();
+    let options = EvalOptions { echo: true, ..Default::default() };
+    // 你们 goes up by itself, so both blanks are echoed
+    assert_eq!("我喜欢你们  2024 年，OK\n那", trie.eval_with("wxhni  2024 年，OK\nn2", &options));
+    // the first blank selects 你, the second one is echoed
+    assert_eq!("喜欢你 2024", trie.eval_with("xhn  2024", &options));
+  }
+
+  #[test]
+  fn test_select_keys() {
+    let trie = TrieBuilder::from_iter([("w", "我"), ("n", "你"), ("n", "那"), ("ni", "你们")]).build();
+    assert_eq!("那那", trie.eval("n'n2"));
+    let select_keys = SelectKeys::new(vec![" 1".to_string(), ";2".to_string()]).unwrap();
+    let options = EvalOptions { select_keys, ..Default::default() };
+    // neither ' nor 3 select anything now, they commit 你 and go up as they are
+    assert_eq!("那你'你3", trie.eval_with("n;n'n3", &options));
+    assert!(SelectKeys::new(vec![" 1".to_string(), "1".to_string()]).is_err());
+  }
+
+  #[test]
+  fn test_edits() {
+    let trie = TrieBuilder::from_iter([("w", "我"), ("n", "你"), ("n", "那"), ("ni", "你们"), ("xh", "喜欢")]).build();
+    let options = EvalOptions { backspace: Some('<'), escape: Some('!'), ..Default::default() };
+    // n isn't committed yet, backspace drops it
+    assert_eq!("我", trie.eval_with("n<w", &options));
+    // 你们 went up by itself, backspace deletes a character like it would in the editor
+    assert_eq!("你", trie.eval_with("ni<", &options));
+    // 我 went up by itself, so backspace deletes it
+    assert_eq!("你", trie.eval_with("w<n", &options));
+    // the escape drops x but not 你, committed by the x
+    assert_eq!("你喜欢", trie.eval_with("nx!xh", &options));
+    let actions: Vec<_> = trie.trace("nx!x<", &options).iter().map(|step| step.action).collect();
+    assert_eq!(vec![Action::Commit, Action::Escape, Action::Backspace], actions);
+    // keys ending at a node without words are traced as still composing, not as an end with nothing to commit
+    let branched = TrieBuilder::from_iter([("n", "你"), ("xha", "吓"), ("xhb", "喜爱")]).build();
+    let traced: Vec<_> = branched.trace("nxh", &Default::default()).iter().map(|step| (step.keys.clone(), step.action)).collect();
+    assert_eq!(vec![("n".to_string(), Action::AutoCommit), ("xh".to_string(), Action::Compose)], traced);
+    assert_eq!(("你".to_string(), "xh".to_string()), branched.compose("nxh", &Default::default()));
+    // 喜欢 went up by itself too
+    assert_eq!("喜", trie.eval_with("xh<", &options));
+    // without the options, they're keys like any other
+    assert_eq!("我<你", trie.eval_with("w<n", &Default::default()));
+  }
+
+  proptest! {
+    #[test]
+    fn test_insert_round_trip(entries in vec(("[a-e]{1,5}", "[我你他们的]{1,3}", 0..100u64), 1..200)) {
+      let mut root = Trie::new();
+      for (code, word, weight) in &entries {
+        root.insert_weighted(code.clone(), word.clone(), *weight);
+      }
+
+      prop_assert_eq!(Ok(()), root.validate());
+      for (code, word, _) in &entries {
+        let node = root.node(code);
+        prop_assert!(node.is_some(), "{} not found", code);
+        prop_assert!(node.unwrap().words().contains(word));
+        prop_assert_eq!(code, &node.unwrap().full_code());
+      }
+      prop_assert_eq!(entries.len(), root.entries().count());
+      for (node, code) in root.walk() {
+        prop_assert_eq!(node.full_code(), code);
+      }
+    }
+  }
+
   #[test]
   fn test_load() {
     let mut trie = Trie::new();
-    let mut path = home::home_dir().unwrap();
-    path.push(r"AppData\Roaming\Rime");
-    path.push("xkjd6.cizu.dict.yaml");
-    trie.load_xkjd_dict(path).unwrap();
+    trie.load_xkjd_dict(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/rime/xkjd6.cizu.dict.yaml")).unwrap();
     assert_eq!("我爱读书", trie.eval("wlxhdjej "));
   }
 }
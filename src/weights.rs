@@ -0,0 +1,105 @@
+//! Weights of tables brought to one scale: tables from different sources weigh their words on
+//! scales of their own, say 0 to 100 against raw corpus counts, and once merged their candidates
+//! would order by source rather than by frequency.
+
+use std::str::FromStr;
+
+/// The weight of the heaviest word unless told otherwise.
+pub const DEFAULT_MAX: u64 = 10_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Method {
+  /// In proportion, the heaviest word of the table getting the maximum.
+  Linear,
+  /// By rank among the distinct weights of the table, evenly spread up to the maximum, for
+  /// tables whose weights are skewed or on no scale at all.
+  Rank,
+}
+
+/// How the weights of each table are rescaled, `linear` or `rank`, then `:<max>` if not 10000.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rescaling {
+  pub method: Method,
+  pub max: u64,
+}
+
+impl FromStr for Rescaling {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (method, max) = match s.split_once(':') {
+      Some((method, max)) => (method, max.trim().parse().map_err(|_| format!("{max} isn't a weight"))?),
+      None => (s, DEFAULT_MAX),
+    };
+    let method = match method.trim() {
+      "linear" => Method::Linear,
+      "rank" => Method::Rank,
+      other => return Err(format!("weights are rescaled linear or by rank, not {other}")),
+    };
+    Ok(Self { method, max })
+  }
+}
+
+impl Rescaling {
+  /// The weights of a table on the common scale. 0, a missing weight, stays 0.
+  pub fn apply(&self, weights: &[u64]) -> Vec<u64> {
+    let scale = |numerator: u64, denominator: u64| {
+      ((numerator as u128 * self.max as u128 + denominator as u128 / 2) / denominator as u128) as u64
+    };
+    match self.method {
+      Method::Linear => {
+        let top = weights.iter().copied().max().unwrap_or(0);
+        weights.iter().map(|&weight| if top == 0 { 0 } else { scale(weight, top) }).collect()
+      }
+      Method::Rank => {
+        let mut distinct: Vec<_> = weights.iter().copied().filter(|&weight| weight > 0).collect();
+        distinct.sort_unstable();
+        distinct.dedup();
+        let ranks = distinct.len() as u64;
+        weights
+          .iter()
+          .map(|&weight| match weight {
+            0 => 0,
+            weight => scale(distinct.partition_point(|&other| other < weight) as u64 + 1, ranks),
+          })
+          .collect()
+      }
+    }
+  }
+}
+
+/// The line with its weight `old`, the last number before the annotation, replaced by `new`;
+/// `None` if there's no such number.
+pub fn with_weight(line: &str, old: u64, new: u64) -> Option<String> {
+  let end = line.find('#').unwrap_or(line.len());
+  let old = old.to_string();
+  let is_digit = |idx: usize| line.as_bytes().get(idx).is_some_and(u8::is_ascii_digit);
+  let (start, _) = line[..end]
+    .rmatch_indices(&old)
+    .find(|&(start, _)| (start == 0 || !is_digit(start - 1)) && !is_digit(start + old.len()))?;
+  Some(format!("{}{new}{}", &line[..start], &line[start + old.len()..]))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_rescaling() {
+    assert_eq!(Ok(Rescaling { method: Method::Rank, max: 100 }), "rank:100".parse());
+    assert_eq!(Ok(Rescaling { method: Method::Linear, max: DEFAULT_MAX }), "linear".parse());
+    assert!("log".parse::<Rescaling>().is_err());
+
+    let linear = Rescaling { method: Method::Linear, max: 100 };
+    assert_eq!(vec![100, 50, 0, 13], linear.apply(&[8, 4, 0, 1]));
+    assert_eq!(vec![0, 0], linear.apply(&[0, 0]));
+    // raw counts and a 0–100 scale end up side by side
+    let rank = Rescaling { method: Method::Rank, max: 100 };
+    assert_eq!(vec![100, 75, 50, 25, 25, 0], rank.apply(&[1_000_000, 5_000, 30, 2, 2, 0]));
+    assert_eq!(rank.apply(&[100, 60, 1]), rank.apply(&[9_000, 120, 3]));
+
+    assert_eq!(Some("我们\twomf\t75\t# 10 次".to_string()), with_weight("我们\twomf\t10\t# 10 次", 10, 75));
+    assert_eq!(Some("a1  a1  9".to_string()), with_weight("a1  a1  1", 1, 9));
+    assert_eq!(None, with_weight("我们\twomf\t0100", 100, 9));
+  }
+}
@@ -0,0 +1,92 @@
+//! What adding an entry would do to the candidates around its code, before adding it.
+
+use crate::trie::{Completion, Entry, Trie};
+use crate::types::{Code, Word};
+
+/// A candidate shown lower once the entry is added.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pushed {
+  /// The code, or the prefix of it, whose candidates changed.
+  pub code: Code,
+  pub word: Word,
+  /// Positions before and after, 0 for the first candidate; `to` is `shown` if it's pushed off the list.
+  pub from: usize,
+  pub to: usize,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Impact {
+  /// Words already on the code.
+  pub occupants: Vec<Word>,
+  /// Position of the new word on its code.
+  pub index: Option<usize>,
+  pub pushed: Vec<Pushed>,
+  /// Codes whose only word went up by itself and no longer would, with that word.
+  pub broken_auto_commits: Vec<(Code, Word)>,
+}
+
+fn is_auto_commit(node: &Trie) -> bool {
+  node.words().len() == 1 && node.is_leaf()
+}
+
+/// Compares the candidates of the entry's code and its prefixes, where it shows as a completion,
+/// before and after it's added; only the first `shown` candidates of each count.
+pub fn impact(before: &Trie, after: &Trie, entry: &Entry, completion: Completion, shown: usize) -> Impact {
+  let mut impact = Impact {
+    occupants: before.node(&entry.code).map(|node| node.words().clone()).unwrap_or_default(),
+    index: after.candidate_index(&entry.code, &entry.word, completion),
+    ..Default::default()
+  };
+  let prefixes = entry.code.char_indices().map(|(idx, _)| idx).skip(1).chain([entry.code.len()]).map(|end| &entry.code[..end]);
+  for prefix in prefixes {
+    let candidates = |trie: &Trie| -> Vec<Word> {
+      trie.node_through(prefix)
+        .map(|node| node.candidates(completion).take(shown).cloned().collect())
+        .unwrap_or_default()
+    };
+    let after_candidates = candidates(after);
+    for (from, word) in candidates(before).into_iter().enumerate() {
+      let to = after_candidates.iter().position(|candidate| *candidate == word).unwrap_or(shown);
+      if to > from {
+        impact.pushed.push(Pushed { code: prefix.to_string(), word, from, to });
+      }
+    }
+
+    if let Some(node) = before.node(prefix).filter(|node| is_auto_commit(node)) {
+      if !after.node(prefix).is_some_and(is_auto_commit) {
+        impact.broken_auto_commits.push((prefix.to_string(), node.words()[0].clone()));
+      }
+    }
+  }
+  impact
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::trie::TrieBuilder;
+
+  #[test]
+  fn test_impact() {
+    let before = TrieBuilder::from_iter([("x", "想"), ("x", "小"), ("xh", "喜欢"), ("xhni", "瞎胡闹")]).build();
+    let entry = Entry { code: "xh".to_string(), word: "学会".to_string() };
+    let mut after = before.clone();
+    after.insert(entry.code.clone(), entry.word.clone());
+
+    let found = impact(&before, &after, &entry, Completion::ALL, 3);
+    assert_eq!(vec!["喜欢".to_string()], found.occupants);
+    assert_eq!(Some(1), found.index);
+    // 瞎胡闹 was the second candidate of xh as a completion, x shows the same first three
+    assert_eq!(vec![Pushed { code: "xh".to_string(), word: "瞎胡闹".to_string(), from: 1, to: 2 }], found.pushed);
+    assert!(found.broken_auto_commits.is_empty());
+
+    let entry = Entry { code: "xhnio".to_string(), word: "瞎胡闹哦".to_string() };
+    let mut after = before.clone();
+    after.insert(entry.code.clone(), entry.word.clone());
+    let found = impact(&before, &after, &entry, Completion::ALL, 3);
+    assert_eq!(vec![("xhni".to_string(), "瞎胡闹".to_string())], found.broken_auto_commits);
+    assert_eq!(Some(0), found.index);
+  }
+}
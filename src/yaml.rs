@@ -0,0 +1,181 @@
+//! A minimal YAML subset: nested block mappings, block sequences and plain/quoted scalars,
+//! which is all Rime schemas and our config use.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+  Scalar(String),
+  List(Vec<Value>),
+  Map(Vec<(String, Value)>),
+}
+
+impl Value {
+  pub fn get(&self, key: &str) -> Option<&Value> {
+    match self {
+      Value::Map(pairs) => pairs.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+      _ => None,
+    }
+  }
+
+  /// Looks up a slash-separated path such as `translator/dictionary`.
+  pub fn path(&self, path: &str) -> Option<&Value> {
+    path.split('/').try_fold(self, |value, key| value.get(key))
+  }
+
+  pub fn as_str(&self) -> Option<&str> {
+    match self {
+      Value::Scalar(s) => Some(s),
+      _ => None,
+    }
+  }
+
+  pub fn as_list(&self) -> &[Value] {
+    match self {
+      Value::List(items) => items,
+      _ => &[],
+    }
+  }
+
+  pub fn entries(&self) -> &[(String, Value)] {
+    match self {
+      Value::Map(pairs) => pairs,
+      _ => &[],
+    }
+  }
+}
+
+struct Line<'a> {
+  indent: usize,
+  text: &'a str,
+}
+
+pub(crate) fn strip_comment(line: &str) -> &str {
+  let mut quote = None;
+  for (i, ch) in line.char_indices() {
+    match (ch, quote) {
+      ('"' | '\'', None) => quote = Some(ch),
+      (c, Some(q)) if c == q => quote = None,
+      ('#', None) if i == 0 || line[..i].ends_with(char::is_whitespace) => return &line[..i],
+      _ => {}
+    }
+  }
+  line
+}
+
+fn scalar(text: &str) -> Value {
+  let text = text.trim();
+  let unquoted = text
+    .strip_prefix('"').and_then(|t| t.strip_suffix('"'))
+    .or_else(|| text.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')))
+    .unwrap_or(text);
+  Value::Scalar(unquoted.to_string())
+}
+
+fn split_key(text: &str) -> Option<(&str, &str)> {
+  let (key, rest) = text.split_once(':')?;
+  if rest.is_empty() || rest.starts_with(' ') {
+    Some((key.trim().trim_matches('"'), rest.trim()))
+  } else {
+    None
+  }
+}
+
+/// Parses the document up to the `...` end marker, so the header of a `dict.yaml` can be fed directly.
+pub fn parse(text: &str) -> Value {
+  let lines: Vec<_> = text
+    .lines()
+    .take_while(|line| line.trim_end() != "...")
+    .map(strip_comment)
+    .filter(|line| !line.trim().is_empty() && line.trim_end() != "---")
+    .map(|line| Line {
+      indent: line.len() - line.trim_start().len(),
+      text: line.trim(),
+    })
+    .collect();
+  let mut pos = 0;
+  parse_block(&lines, &mut pos, 0)
+}
+
+fn parse_block(lines: &[Line], pos: &mut usize, indent: usize) -> Value {
+  let Some(first) = lines.get(*pos) else {
+    return Value::Map(vec![]);
+  };
+  let indent = first.indent.max(indent);
+  if first.text.starts_with('-') {
+    let mut items = vec![];
+    while let Some(line) = lines.get(*pos) {
+      if line.indent != indent || !line.text.starts_with('-') {
+        break;
+      }
+      *pos += 1;
+      let item = line.text[1..].trim();
+      if item.is_empty() {
+        items.push(parse_block(lines, pos, indent + 1));
+      } else if let Some((key, value)) = split_key(item) {
+        // "- key: value" starts an inline mapping
+        let mut pairs = vec![(key.to_string(), scalar(value))];
+        if let Value::Map(rest) = parse_nested(lines, pos, indent) {
+          pairs.extend(rest);
+        }
+        items.push(Value::Map(pairs));
+      } else {
+        items.push(scalar(item));
+      }
+    }
+    Value::List(items)
+  } else {
+    let mut pairs = vec![];
+    while let Some(line) = lines.get(*pos) {
+      if line.indent != indent {
+        break;
+      }
+      let Some((key, value)) = split_key(line.text) else { break };
+      *pos += 1;
+      let value = if value.is_empty() {
+        parse_nested(lines, pos, indent)
+      } else {
+        scalar(value)
+      };
+      pairs.push((key.to_string(), value));
+    }
+    Value::Map(pairs)
+  }
+}
+
+fn parse_nested(lines: &[Line], pos: &mut usize, indent: usize) -> Value {
+  match lines.get(*pos) {
+    // sequences are allowed at the same indentation as their key
+    Some(next) if next.indent > indent || (next.indent == indent && next.text.starts_with('-')) =>
+      parse_block(lines, pos, next.indent),
+    _ => Value::Scalar(String::new()),
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_parse() {
+    let doc = parse(r#"
+# comment
+---
+name: "xkjd6.extended"
+version: "Q1"
+import_tables:
+  - xkjd6.danzi # 单字
+  - xkjd6.cizu
+translator:
+  dictionary: xkjd6
+  enable_completion: false
+...
+我	w
+"#);
+    assert_eq!(Some("xkjd6.extended"), doc.get("name").and_then(Value::as_str));
+    let tables: Vec<_> = doc.get("import_tables").unwrap().as_list().iter()
+      .filter_map(Value::as_str)
+      .collect();
+    assert_eq!(vec!["xkjd6.danzi", "xkjd6.cizu"], tables);
+    assert_eq!(Some("xkjd6"), doc.path("translator/dictionary").and_then(Value::as_str));
+    assert_eq!(Some("false"), doc.path("translator/enable_completion").and_then(Value::as_str));
+  }
+}
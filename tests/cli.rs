@@ -0,0 +1,230 @@
+//! The binary run on the sample schema under `tests/fixtures/rime`, each test on a copy of its own.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+fn fixture(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("smart-dict-cli-{name}-{}", std::process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+  for entry in fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/rime")).unwrap() {
+    let path = entry.unwrap().path();
+    fs::copy(&path, dir.join(path.file_name().unwrap())).unwrap();
+  }
+  dir
+}
+
+/// The exit code and stdout of the command, failing on an error message.
+fn run(dir: &Path, args: &[&str]) -> (i32, String) {
+  let output = Command::new(env!("CARGO_BIN_EXE_smart-dict"))
+    .args(args)
+    .arg("--dir")
+    .arg(dir)
+    .env("LANG", "en_US.UTF-8")
+    .output()
+    .unwrap();
+  let stderr = String::from_utf8_lossy(&output.stderr);
+  assert!(!stderr.contains("error"), "{args:?}: {stderr}");
+  (output.status.code().unwrap(), String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn test_eval_and_lookup() {
+  let dir = fixture("eval");
+  assert_eq!((0, "我爱读书\n".to_string()), run(&dir, &["eval", "wlxhdjej "]));
+  let (_, lookups) = run(&dir, &["lookup", "我爱", "码农"]);
+  assert_eq!(vec!["我爱\twlxh\t\tfalse", "码农\tmnxa\t\tfalse"], lookups.lines().collect::<Vec<_>>());
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_checks() {
+  let dir = fixture("checks");
+  let (code, json) = run(&dir, &["fmt", "--check"]);
+  assert_eq!(0, code, "{json}");
+  assert!(json.contains(r#""passed":true"#), "{json}");
+  let (code, json) = run(&dir, &["lint", "--check"]);
+  assert_eq!(0, code, "{json}");
+  assert!(json.contains(r#""errors":0"#), "{json}");
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_add_word_diff_apply() {
+  let base = fixture("base");
+  let dir = fixture("head");
+  let user = dir.join("xkjd6.user.dict.yaml");
+  let before = fs::read_to_string(&user).unwrap();
+  run(&dir, &["add-word", "测试", "ceui", "--table", "xkjd6.user", "--dry-run"]);
+  assert_eq!(before, fs::read_to_string(&user).unwrap());
+  run(&dir, &["add-word", "测试", "ceui", "--table", "xkjd6.user"]);
+  assert!(fs::read_to_string(&user).unwrap().ends_with("测试\tceui\n"));
+  assert_eq!((0, "测试\n".to_string()), run(&dir, &["eval", "ceui"]));
+
+  let (_, patch) = run(&dir, &["diff", "--base", base.to_str().unwrap()]);
+  assert_eq!("@ xkjd6.user\n+ 测试\tceui\n", patch);
+  let patch_path = base.join("user.patch");
+  let (_, preview) = run(&dir, &["diff", "--base", base.to_str().unwrap(), "--out", patch_path.to_str().unwrap(), "--dry-run"]);
+  assert!(preview.contains("+++ b/user.patch\n") && preview.contains("\n+@ xkjd6.user\n"), "{preview}");
+  assert!(!patch_path.exists());
+  fs::write(&patch_path, patch).unwrap();
+  run(&base, &["apply", patch_path.to_str().unwrap()]);
+  assert_eq!(fs::read_to_string(&user).unwrap(), fs::read_to_string(base.join("xkjd6.user.dict.yaml")).unwrap());
+  fs::remove_dir_all(base).unwrap();
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_ndjson_pages() {
+  let dir = fixture("ndjson");
+  let (_, all) = run(&dir, &["export", "--dict", "-", "--ndjson"]);
+  let (_, page) = run(&dir, &["export", "--dict", "-", "--ndjson", "--offset", "5", "--limit", "3"]);
+  assert_eq!(all.lines().skip(5).take(3).collect::<Vec<_>>(), page.lines().collect::<Vec<_>>());
+  assert!(page.starts_with(r#"{"code":"#), "{page}");
+
+  let (_, collisions) = run(&dir, &["collisions", "--ndjson"]);
+  assert!(collisions.lines().all(|line| line.starts_with('{') && line.ends_with('}')), "{collisions}");
+  let (_, rest) = run(&dir, &["collisions", "--ndjson", "--offset", "1"]);
+  assert_eq!(collisions.lines().skip(1).collect::<Vec<_>>(), rest.lines().collect::<Vec<_>>());
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_impact() {
+  let dir = fixture("impact");
+  let user = dir.join("xkjd6.user.dict.yaml");
+  let before = fs::read_to_string(&user).unwrap();
+  let patch_path = dir.join("user.patch");
+  fs::write(&patch_path, "@ xkjd6.user\n+ 测试\tceui\n+ 测验\twlxh\n").unwrap();
+  let corpus_path = dir.join("corpus.txt");
+  fs::write(&corpus_path, "我爱测试\n").unwrap();
+  let patch = patch_path.to_str().unwrap();
+
+  let (code, report) = run(&dir, &["impact", "--patch", patch, "--corpus", corpus_path.to_str().unwrap()]);
+  assert_eq!(0, code, "{report}");
+  assert!(report.contains("1 new, 0 resolved") && report.contains("    + wlxh\t"), "{report}");
+  let (code, json) = run(&dir, &["impact", "--patch", patch, "--check"]);
+  assert_eq!(1, code, "{json}");
+  assert!(json.contains(r#""new_collisions":1"#) && json.contains(r#""entries_change":2"#), "{json}");
+  assert_eq!(before, fs::read_to_string(&user).unwrap());
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_convert_corpus() {
+  let dir = fixture("convert");
+  let corpus_path = dir.join("traditional.txt");
+  fs::write(&corpus_path, "我愛讀書\n").unwrap();
+  let corpus = corpus_path.to_str().unwrap();
+  let (_, report) = run(&dir, &["corpus", "--corpus", corpus]);
+  assert!(report.contains("25.00% coverage"), "{report}");
+  let (_, report) = run(&dir, &["corpus", "--corpus", corpus, "--convert", "t2s"]);
+  assert!(report.contains("4 chars") && report.contains("100.00% coverage"), "{report}");
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_budget() {
+  let dir = fixture("budget");
+  fs::write(dir.join("smart-dict.yaml"), "budgets:\n  xkjd6.user: 1\n").unwrap();
+  let (code, json) = run(&dir, &["budget", "--check"]);
+  assert_eq!(1, code, "{json}");
+  assert!(json.contains("xkjd6.user has"), "{json}");
+  run(&dir, &["budget", "--save"]);
+  let (_, report) = run(&dir, &["budget"]);
+  assert!(report.lines().any(|line| line.starts_with("xkjd6.user") && line.contains("+0")), "{report}");
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_tree_normalized() {
+  let dir = fixture("tree");
+  fs::write(dir.join("smart-dict.yaml"), "normalize:\n  lowercase: true\n").unwrap();
+  let (code, tree) = run(&dir, &["tree", "WL"]);
+  assert_eq!(0, code, "{tree}");
+  assert!(tree.starts_with("wlxh") && tree.contains("我爱"), "{tree}");
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_migrate_spaced_user_table() {
+  let dir = fixture("migrate");
+  let user = dir.join("xkjd6.user.dict.yaml");
+  fs::write(&user, "---\nname: xkjd6.user\n...\n键道  jldz  10  # 常用\n码农  mnxa\n").unwrap();
+  let danzi = fs::read_to_string(dir.join("xkjd6.danzi.dict.yaml")).unwrap();
+  let (old, new) = (dir.join("old.txt"), dir.join("new.txt"));
+  fs::write(&old, &danzi).unwrap();
+  fs::write(&new, danzi.replace("道\tdzua", "道\tdxua")).unwrap();
+
+  let (code, report) = run(&dir, &["migrate", "--old", old.to_str().unwrap(), "--new", new.to_str().unwrap(), "--yes"]);
+  assert_eq!(0, code, "{report}");
+  assert!(report.contains("键道\tjldz → jldx"), "{report}");
+  assert_eq!("---\nname: xkjd6.user\n...\n键道  jldx  10  # 常用\n码农  mnxa\n", fs::read_to_string(&user).unwrap());
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_lint_fix_spaced_user_table() {
+  let dir = fixture("lint-fix");
+  let user = dir.join("xkjd6.user.dict.yaml");
+  fs::write(&user, "---\nname: xkjd6.user\n...\n键道  jmdz  10\n码农 mnxa\n").unwrap();
+  let (code, report) = run(&dir, &["lint", "--fix"]);
+  assert_eq!(0, code, "{report}");
+  assert!(report.starts_with("xkjd6.user:5: "), "{report}");
+  assert_eq!("---\nname: xkjd6.user\n...\n键道  jmdz  10\n码农  mnxa\n", fs::read_to_string(&user).unwrap());
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_apply_hunks_of_one_table() {
+  let dir = fixture("apply-hunks");
+  let patch_path = dir.join("user.patch");
+  fs::write(&patch_path, "@ xkjd6.user\n+ 测试\tceui\n@ xkjd6.user\n- 码农\tmnxa\n").unwrap();
+  let (code, report) = run(&dir, &["apply", patch_path.to_str().unwrap()]);
+  assert_eq!((0, "xkjd6.user\t2 changes\n"), (code, report.as_str()));
+  let user = fs::read_to_string(dir.join("xkjd6.user.dict.yaml")).unwrap();
+  assert!(user.ends_with("键道\tjmdz\n测试\tceui\n") && !user.contains("码农"), "{user}");
+  fs::remove_dir_all(dir).unwrap();
+}
+
+/// The answers of `serve` to the requests, sent on one connection.
+fn serve(dir: &Path, args: &[&str], requests: &[&str]) -> Vec<String> {
+  let mut server = Command::new(env!("CARGO_BIN_EXE_smart-dict"))
+    .args(["serve", "--addr", "127.0.0.1:0"])
+    .args(args)
+    .arg("--dir")
+    .arg(dir)
+    .env("LANG", "en_US.UTF-8")
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+  let mut listening = String::new();
+  BufReader::new(server.stderr.take().unwrap()).read_line(&mut listening).unwrap();
+  let addr = listening.strip_prefix("listening on ").and_then(|rest| rest.split(',').next()).unwrap_or_else(|| panic!("{listening}"));
+  let mut stream = TcpStream::connect(addr).unwrap();
+  for request in requests {
+    writeln!(stream, "{request}").unwrap();
+  }
+  let answers = BufReader::new(stream).lines().take(requests.len()).map(Result::unwrap).collect();
+  server.kill().unwrap();
+  server.wait().unwrap();
+  answers
+}
+
+#[test]
+fn test_serve_metrics() {
+  let dir = fixture("serve");
+  let requests = [":candidates jmdz", ":candidates jmdz", ":metrics"];
+  let answers = serve(&dir, &[], &requests);
+  assert_eq!("jmdz\t键道", answers[0]);
+  assert_eq!(answers[0], answers[1]);
+  assert!(answers[2].starts_with("lookups 2\tlocal_hits 1\tshared_hits 0\tmisses 1\t"), "{}", answers[2]);
+  // nothing cached at all
+  let answers = serve(&dir, &["--cache", "0"], &requests);
+  assert_eq!("jmdz\t键道", answers[1]);
+  assert!(answers[2].starts_with("lookups 2\tlocal_hits 0\tshared_hits 0\tmisses 2\t"), "{}", answers[2]);
+  fs::remove_dir_all(dir).unwrap();
+}